@@ -0,0 +1,149 @@
+use db::{establish_connection_pool, pending_migrations};
+use mev_lib::{config::Config, indexer::onchain_indexer::OnchainIndexer};
+
+use anyhow::{anyhow, Result};
+use sui_sdk::SuiClientBuilder;
+use tracing::{debug, error, info, instrument, trace, warn};
+
+/// Checks that the database is reachable and all migrations are applied.
+///
+pub async fn check_database(config: &Config) -> Result<()> {
+    let db_pool = establish_connection_pool(&config.database.database_url, 1, 0)?;
+
+    let pending = pending_migrations(&db_pool)?;
+    if !pending.is_empty() {
+        return Err(anyhow!("Pending migrations: {}", pending.join(", ")));
+    }
+
+    Ok(())
+}
+
+/// Checks that the RPC URL answers the latest checkpoint sequence number.
+///
+pub async fn check_rpc_url(rpc_url: &str) -> Result<()> {
+    let client = SuiClientBuilder::default().build(rpc_url).await?;
+
+    let seq_number = client
+        .read_api()
+        .get_latest_checkpoint_sequence_number()
+        .await?;
+    debug!("RPC {} latest checkpoint #{}", rpc_url, seq_number);
+
+    Ok(())
+}
+
+/// Checks that the checkpoint store serves the given checkpoint.
+///
+pub async fn check_remote_store(remote_store_url: &str, seq_number: u64) -> Result<()> {
+    OnchainIndexer::fetch_checkpoint(remote_store_url, seq_number).await?;
+
+    Ok(())
+}
+
+/// Runs every check and prints a pass/fail line per check.
+/// Fails if any of the checks failed.
+///
+pub async fn handle_doctor(config: &Config) -> Result<()> {
+    let network_config = config
+        .networks
+        .get(&config.run_mode)
+        .ok_or_else(|| anyhow!("No network config for run mode {}", config.run_mode))?;
+
+    let mut results = vec![(
+        "database connection and migrations".to_string(),
+        check_database(config).await,
+    )];
+
+    for rpc_url in network_config.all_rpc_urls() {
+        let result = check_rpc_url(&rpc_url).await;
+        results.push((format!("RPC {}", rpc_url), result));
+    }
+
+    results.push((
+        format!("checkpoint store {}", network_config.remote_store_url),
+        check_remote_store(
+            &network_config.remote_store_url,
+            config.indexer.start_checkpoint_number,
+        )
+        .await,
+    ));
+
+    let (lines, result) = check_report(&results);
+    for line in lines {
+        println!("{}", line);
+    }
+
+    result
+}
+
+/// Pass/fail line of each check, and an error counting the failed checks if any failed.
+///
+fn check_report(results: &[(String, Result<()>)]) -> (Vec<String>, Result<()>) {
+    let mut failed = 0;
+    let lines = results
+        .iter()
+        .map(|(check, result)| match result {
+            Ok(_) => format!("[PASS] {}", check),
+            Err(e) => {
+                failed += 1;
+                format!("[FAIL] {}: {}", check, e)
+            }
+        })
+        .collect();
+
+    if failed > 0 {
+        return (
+            lines,
+            Err(anyhow!("{} of {} checks failed", failed, results.len())),
+        );
+    }
+
+    (lines, Ok(()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn passing_checks_report_a_pass_line_each() {
+        let results = vec![
+            ("database".to_string(), Ok(())),
+            ("RPC https://rpc".to_string(), Ok(())),
+        ];
+
+        let (lines, result) = check_report(&results);
+
+        assert_eq!(lines, vec!["[PASS] database", "[PASS] RPC https://rpc"]);
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn failed_checks_report_their_error_and_fail() {
+        let results = vec![
+            (
+                "database".to_string(),
+                Err(anyhow!(
+                    "Pending migrations: 2025-07-28-031204_canonicalize_coin_types"
+                )),
+            ),
+            ("RPC https://rpc".to_string(), Ok(())),
+            (
+                "checkpoint store https://store".to_string(),
+                Err(anyhow!("timeout")),
+            ),
+        ];
+
+        let (lines, result) = check_report(&results);
+
+        assert_eq!(
+            lines,
+            vec![
+                "[FAIL] database: Pending migrations: 2025-07-28-031204_canonicalize_coin_types",
+                "[PASS] RPC https://rpc",
+                "[FAIL] checkpoint store https://store: timeout",
+            ]
+        );
+        assert_eq!(result.unwrap_err().to_string(), "2 of 3 checks failed");
+    }
+}
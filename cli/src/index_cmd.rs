@@ -1,7 +1,8 @@
 use mev_lib::{
     indexer::{onchain_indexer::OnchainIndexer, registry::EventProcessorRegistry},
     service::registry::ServiceRegistry,
-    utils,
+    types,
+    utils::{self, rpc_pool::RpcPool},
 };
 
 use anyhow::Result;
@@ -10,10 +11,11 @@ use std::{str::FromStr, sync::Arc};
 use sui_sdk::{
     rpc_types::{CheckpointId, EventFilter, SuiTransactionBlockResponseOptions},
     types::{
+        base_types::ObjectID,
         digests::{Digest, TransactionDigest},
         messages_checkpoint::CheckpointSequenceNumber,
+        Identifier,
     },
-    SuiClient,
 };
 use tracing::{debug, error, info, instrument, trace, warn};
 
@@ -31,22 +33,106 @@ pub enum IndexCommands {
         digest: String,
     },
 
+    #[command(about = "Get the latest events emitted by a Move module")]
+    ModuleEvents {
+        #[arg(long)]
+        package: String,
+        #[arg(long)]
+        module: String,
+        #[arg(long, default_value_t = 10)]
+        limit: u64,
+        #[arg(long, default_value_t = false)]
+        dry_run: bool,
+    },
+
     #[command(about = "Get checkpoint details")]
     CheckpointDetails {
         #[arg(long)]
         checkpoint: u64,
     },
+
+    #[command(about = "Reprocess a range of checkpoints")]
+    BackfillRange {
+        #[arg(long)]
+        start: u64,
+        #[arg(long)]
+        end: u64,
+        #[arg(long, default_value_t = false)]
+        force: bool,
+    },
+
+    #[command(about = "Print a stored pool with its coins and ticks, compared to chain")]
+    PoolDetails {
+        #[arg(long)]
+        pool_id: String,
+        #[arg(long, default_value_t = 10)]
+        tick_window: usize,
+        #[arg(long, default_value_t = false)]
+        refresh: bool,
+    },
+
+    #[command(about = "Print the health factor of a borrower from indexed positions")]
+    HealthFactor {
+        #[arg(long)]
+        platform: String,
+        #[arg(long)]
+        address: String,
+    },
+
+    #[command(about = "Print a borrower portfolio from indexed positions as JSON")]
+    ExportBorrower {
+        #[arg(long)]
+        platform: String,
+        #[arg(long)]
+        address: String,
+    },
+
+    #[command(about = "List the shared objects cached in the database")]
+    SharedObjects,
+
+    #[command(about = "Drop a cached shared object so it is fetched from chain again")]
+    InvalidateSharedObject {
+        #[arg(long)]
+        object_id: String,
+    },
+
+    #[command(about = "Print the persisted indexer metrics as JSON")]
+    Metrics {
+        #[arg(long, default_value_t = false)]
+        latest: bool,
+        #[arg(long)]
+        from_seq: Option<u64>,
+        #[arg(long)]
+        to_seq: Option<u64>,
+    },
+
+    #[command(about = "List the coins without price or with a stale price")]
+    PriceCoverage {
+        #[arg(long, default_value_t = 3600)]
+        max_age_secs: u64,
+    },
+
+    #[command(about = "Rebuild a borrower portfolio from on-chain data")]
+    ResyncBorrower {
+        #[arg(long)]
+        platform: String,
+        #[arg(long)]
+        address: String,
+    },
 }
 
 //handlers
-pub async fn handle_query_events(client: Arc<SuiClient>, digest: &str) -> Result<()> {
+pub async fn handle_query_events(rpc_pool: Arc<RpcPool>, digest: &str) -> Result<()> {
     let tx_digest = TransactionDigest::from_str(digest)
         .map_err(|_| anyhow::anyhow!("Failed to parse transaction digest: {}", digest))?;
 
-    let query = EventFilter::Transaction(tx_digest);
-    let events = client
-        .event_api()
-        .query_events(query, None, None, false)
+    let events = rpc_pool
+        .call(|client| async move {
+            client
+                .event_api()
+                .query_events(EventFilter::Transaction(tx_digest), None, None, false)
+                .await
+        })
         .await?;
 
     for event in events.data {
@@ -57,16 +143,340 @@ pub async fn handle_query_events(client: Arc<SuiClient>, digest: &str) -> Result
     Ok(())
 }
 
+/// Builds the filter of the events emitted by a module of a package.
+///
+pub fn module_events_filter(package: &str, module: &str) -> Result<EventFilter> {
+    let package = ObjectID::from_str(package)
+        .map_err(|e| anyhow::anyhow!("Failed to parse package ID {}: {}", package, e))?;
+    let module = Identifier::new(module)
+        .map_err(|e| anyhow::anyhow!("Invalid module name {}: {}", module, e))?;
+
+    Ok(EventFilter::MoveModule { package, module })
+}
+
+/// Prints the latest `limit` events of the module, newest first,
+/// and processes them with their event processor unless `dry_run` is set.
+/// Processing failures are logged and do not stop the others.
+///
+pub async fn handle_query_module_events(
+    rpc_pool: Arc<RpcPool>,
+    event_processor_registry: Arc<EventProcessorRegistry>,
+    package: &str,
+    module: &str,
+    limit: u64,
+    dry_run: bool,
+) -> Result<()> {
+    let query = module_events_filter(package, module)?;
+
+    let mut events = vec![];
+    let mut cursor = None;
+    while (events.len() as u64) < limit {
+        let page_size = (limit - events.len() as u64) as usize;
+        let page = rpc_pool
+            .call(|client| {
+                let query = query.clone();
+                async move {
+                    client
+                        .event_api()
+                        .query_events(query, cursor, Some(page_size), true)
+                        .await
+                }
+            })
+            .await?;
+
+        events.extend(page.data);
+        if !page.has_next_page {
+            break;
+        }
+        cursor = page.next_cursor;
+    }
+
+    for event in events {
+        let tx_digest = event.id.tx_digest.to_string();
+        info!("Event type {:?} in tx {}", event.type_, tx_digest);
+        info!("Event data {:?}", event.parsed_json);
+
+        if dry_run {
+            continue;
+        }
+
+        if let Err(e) = event_processor_registry
+            .process_tx_event(event, &tx_digest)
+            .await
+        {
+            error!("Failed to process event of tx {}: {}", tx_digest, e);
+        }
+    }
+
+    Ok(())
+}
+
 pub async fn handle_process_tx(onchain_indexer: Arc<OnchainIndexer>, digest: &str) -> Result<()> {
     onchain_indexer.process_tx_events(digest).await
 }
 
-pub async fn handle_query_checkpoint(client: Arc<SuiClient>, checkpoint: u64) -> Result<()> {
+pub async fn handle_query_checkpoint(rpc_pool: Arc<RpcPool>, checkpoint: u64) -> Result<()> {
     let checkpoint_seq_num: CheckpointSequenceNumber = checkpoint;
-    let checkpoint_id = CheckpointId::from(checkpoint_seq_num);
 
-    let checkpoint = client.read_api().get_checkpoint(checkpoint_id).await?;
+    let checkpoint = rpc_pool
+        .call(|client| async move {
+            client
+                .read_api()
+                .get_checkpoint(CheckpointId::from(checkpoint_seq_num))
+                .await
+        })
+        .await?;
 
     info!("Checkpoint {:?}", checkpoint);
     Ok(())
 }
+
+pub async fn handle_backfill_range(
+    onchain_indexer: Arc<OnchainIndexer>,
+    start: u64,
+    end: u64,
+    force: bool,
+) -> Result<()> {
+    onchain_indexer
+        .reprocess_checkpoint_range(start, end, force)
+        .await
+}
+
+pub async fn handle_resync_borrower(
+    service_registry: Arc<ServiceRegistry>,
+    platform: &str,
+    address: &str,
+) -> Result<()> {
+    service_registry.resync_borrower(platform, address).await
+}
+
+pub async fn handle_health_factor(
+    service_registry: Arc<ServiceRegistry>,
+    platform: &str,
+    address: &str,
+) -> Result<()> {
+    let db_lending_service = &service_registry.db_lending_service;
+
+    let (user_deposits, user_borrows) =
+        db_lending_service.find_borrower_portfolio_from_db(platform, address)?;
+
+    if user_deposits.is_empty() && user_borrows.is_empty() {
+        warn!("Borrower {} has no positions on {}", address, platform);
+        return Ok(());
+    }
+
+    let markets = service_registry
+        .get_lending_service(platform)?
+        .fetch_lending_markets()
+        .await?;
+
+    let result = db_lending_service.calc_health_factor(&markets, &user_deposits, &user_borrows)?;
+
+    warn!(
+        "Borrower {} on {}: collateral ${}, debt ${}, health factor {}",
+        address,
+        platform,
+        result.total_collateral_usd.round_dp(2),
+        result.total_debt_usd.round_dp(2),
+        if result.total_debt_usd.is_zero() {
+            "inf".to_string()
+        } else {
+            result.health_factor.round_dp(4).to_string()
+        }
+    );
+
+    Ok(())
+}
+
+pub async fn handle_export_borrower(
+    service_registry: Arc<ServiceRegistry>,
+    platform: &str,
+    address: &str,
+) -> Result<()> {
+    let portfolio = service_registry
+        .db_lending_service
+        .export_borrower_portfolio(platform, address)
+        .await?;
+
+    println!("{:#}", portfolio);
+
+    Ok(())
+}
+
+pub async fn handle_list_shared_objects(service_registry: Arc<ServiceRegistry>) -> Result<()> {
+    let shared_objects = service_registry
+        .db_lending_service
+        .find_all_shared_objects()?;
+
+    for shared_object in &shared_objects {
+        warn!(
+            "Shared object {}: initial shared version {}, cached at {:?}",
+            shared_object.object_id, shared_object.initial_shared_version, shared_object.cached_at
+        );
+    }
+    warn!("{} shared objects cached", shared_objects.len());
+
+    Ok(())
+}
+
+pub async fn handle_invalidate_shared_object(
+    service_registry: Arc<ServiceRegistry>,
+    object_id: &str,
+) -> Result<()> {
+    let deleted = service_registry
+        .db_lending_service
+        .invalidate_shared_object(object_id)?;
+
+    if deleted == 0 {
+        warn!("Shared object {} is not cached", object_id);
+    } else {
+        warn!("Invalidated shared object {}", object_id);
+    }
+
+    Ok(())
+}
+
+pub async fn handle_price_coverage(
+    service_registry: Arc<ServiceRegistry>,
+    max_age_secs: u64,
+) -> Result<()> {
+    let db_lending_service = &service_registry.db_lending_service;
+
+    let missing_coins = db_lending_service.find_coins_without_price()?;
+    for coin in &missing_coins {
+        warn!("Missing price: {} ({:?})", coin.coin_type, coin.symbol);
+    }
+
+    let stale_coins = db_lending_service.find_coins_with_stale_price(max_age_secs)?;
+    for coin in &stale_coins {
+        let latest_updated_at = [
+            coin.pyth_latest_updated_at,
+            coin.hermes_latest_updated_at,
+            coin.supra_latest_updated_at,
+            coin.switchboard_latest_updated_at,
+        ]
+        .into_iter()
+        .flatten()
+        .max();
+
+        warn!(
+            "Stale price: {} ({:?}), updated at {:?}",
+            coin.coin_type, coin.symbol, latest_updated_at
+        );
+    }
+
+    warn!(
+        "{} coins without price, {} coins with a price older than {}s",
+        missing_coins.len(),
+        stale_coins.len(),
+        max_age_secs
+    );
+
+    Ok(())
+}
+
+/// Prints the stored pool, its coins and up to `tick_window` initialized ticks
+/// on each side of its current tick, with the fields which differ from chain.
+/// With `refresh`, the on-chain pool is saved first.
+///
+pub async fn handle_pool_details(
+    service_registry: Arc<ServiceRegistry>,
+    pool_id: &str,
+    tick_window: usize,
+    refresh: bool,
+) -> Result<()> {
+    let db_pool_service = &service_registry.db_pool_service;
+
+    let (stored_pool, _) = db_pool_service.find_pool_from_db(pool_id, None).await?;
+    let dex_service = service_registry.get_dex_service(&stored_pool.exchange)?;
+    let (diff, onchain_pool) = db_pool_service
+        .verify_pool_against_chain(pool_id, Arc::clone(&dex_service))
+        .await?;
+    if diff.is_empty() {
+        warn!("Pool {} matches chain", pool_id);
+    } else {
+        for field in &diff.fields {
+            warn!(
+                "Drift of {}: stored {:?}, onchain {:?}, delta {:?}",
+                field.field, field.stored, field.onchain, field.delta
+            );
+        }
+    }
+
+    if refresh {
+        // the pool fetched for the diff
+        db_pool_service.save_pool_to_db(onchain_pool).await?;
+        warn!("Refreshed pool {} from chain", pool_id);
+    }
+
+    let (pool, coins) = db_pool_service.find_pool_from_db(pool_id, None).await?;
+    warn!("Pool {:#?}", pool);
+    for coin in &coins {
+        warn!(
+            "Coin {} ({:?}), {} decimals",
+            coin.coin_type, coin.symbol, coin.decimals
+        );
+    }
+
+    if let Some(current_tick_index) = pool.current_tick_index {
+        let ticks = db_pool_service
+            .find_ticks_around(pool_id, current_tick_index, tick_window)
+            .await?;
+
+        for tick in &ticks {
+            warn!(
+                "Tick {}{}: liquidity net {:?}, gross {:?}",
+                tick.tick_index,
+                if tick.tick_index == current_tick_index {
+                    " (current)"
+                } else {
+                    ""
+                },
+                tick.liquidity_net,
+                tick.liquidity_gross
+            );
+        }
+        warn!(
+            "{} ticks around current tick {}",
+            ticks.len(),
+            current_tick_index
+        );
+    }
+
+    Ok(())
+}
+
+/// Prints the latest persisted metric, or the ones of a checkpoint range, as JSON,
+/// to export the indexer stats without database access.
+///
+pub async fn handle_metrics(
+    service_registry: Arc<ServiceRegistry>,
+    latest: bool,
+    from_seq: Option<u64>,
+    to_seq: Option<u64>,
+) -> Result<()> {
+    let db_lending_service = &service_registry.db_lending_service;
+
+    let json = match (latest, from_seq) {
+        (true, _) => {
+            let metric = db_lending_service
+                .find_latest_seq_number()?
+                .map(types::Metric::from);
+            serde_json::to_string_pretty(&metric)?
+        }
+        (false, Some(from_seq)) => {
+            let metrics: Vec<types::Metric> = db_lending_service
+                .find_metrics_between(from_seq, to_seq.unwrap_or(u64::MAX))?
+                .into_iter()
+                .map(types::Metric::from)
+                .collect();
+            serde_json::to_string_pretty(&metrics)?
+        }
+        (false, None) => return Err(anyhow::anyhow!("Either --latest or --from-seq is required")),
+    };
+
+    // plain stdout, so the output can be piped
+    println!("{}", json);
+
+    Ok(())
+}
@@ -1,15 +1,18 @@
 use db::models::pool::{self, NewPool, Pool, UpdatePool};
 use db::repositories::{
-    borrower::BorrowerRepositoryImpl, coin::CoinRepositoryImpl, metric::MetricRepositoryImpl,
+    borrower::BorrowerRepositoryImpl, coin::CoinRepositoryImpl,
+    failed_event::FailedEventRepositoryImpl, metric::MetricRepositoryImpl,
     pool::PoolRepositoryImpl, pool_tick::PoolTickRepositoryImpl,
-    shared_object::SharedObjectRepositoryImpl, user_borrow::UserBorrowRepositoryImpl,
-    user_deposit::UserDepositRepositoryImpl, BorrowerRepository, CoinRepository, MetricRepository,
-    PoolRepository, PoolTickRepository, SharedObjectRepository, UserBorrowRepository,
+    shared_object::SharedObjectRepositoryImpl, swap_event::SwapEventRepositoryImpl,
+    user_borrow::UserBorrowRepositoryImpl, user_deposit::UserDepositRepositoryImpl,
+    BorrowerRepository, CoinRepository, FailedEventRepository, MetricRepository, PoolRepository,
+    PoolTickRepository, SharedObjectRepository, SwapEventRepository, UserBorrowRepository,
     UserDepositRepository,
 };
-use db::{establish_connection_pool, run_migrations};
+use db::{establish_connection_pool_with_retry, run_migrations};
 use mev_lib::{
     config::Config,
+    constant,
     indexer::{onchain_indexer::OnchainIndexer, registry::EventProcessorRegistry},
     service::{
         db_service::{lending::LendingService, pool::PoolService},
@@ -17,9 +20,10 @@ use mev_lib::{
         registry::ServiceRegistry,
     },
     types::Borrower,
-    utils::{self, ptb::PTBHelper},
+    utils::{self, ptb::PTBHelper, rate_limiter::RpcRateLimiter, rpc_pool::RpcPool},
 };
 
+mod doctor_cmd;
 mod index_cmd;
 
 use index_cmd::IndexCommands;
@@ -28,12 +32,12 @@ use anyhow::{anyhow, Result};
 use clap::{Parser, Subcommand};
 use std::sync::{atomic::AtomicU64, Arc, Mutex};
 use sui_sdk::types::base_types::SuiAddress;
-use sui_sdk::{SuiClient, SuiClientBuilder};
-use tokio::sync::{mpsc, RwLock};
+use tokio::{
+    sync::{mpsc, RwLock},
+    time::Duration,
+};
 use tracing::{debug, error, info, instrument, trace, warn, Level};
-use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::util::SubscriberInitExt;
-use tracing_subscriber::{fmt, EnvFilter};
 
 #[derive(Parser)]
 #[command(name = "indexer-cli")]
@@ -51,26 +55,32 @@ enum Commands {
         #[command(subcommand)]
         command: IndexCommands,
     },
+
+    #[command(about = "Check the configuration, database, RPC and checkpoint store")]
+    Doctor,
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let config = Arc::new(Config::load_toml()?);
 
-    let log_level = utils::convert_log_level_to_tracing_level(&config.log_level);
-    let filter = EnvFilter::from_default_env().add_directive(log_level.into());
-
-    tracing_subscriber::registry()
-        .with(fmt::layer())
-        .with(filter)
-        .try_init()?;
+    utils::build_subscriber(&config.log_level, &config.log_format).try_init()?;
 
     warn!("Starting mev-cli...");
 
-    let db_conn = establish_connection_pool(
+    let args = Cli::parse();
+
+    // run before connecting, the setup below bails on the first failure and applies migrations
+    if let Commands::Doctor = args.command {
+        return doctor_cmd::handle_doctor(&config).await;
+    }
+
+    let db_conn = establish_connection_pool_with_retry(
         &config.database.database_url,
         config.database.db_connection_pool_max_size,
         config.database.db_connection_pool_idle_size,
+        config.database.db_connection_max_retries,
+        Duration::from_millis(config.database.db_connection_retry_delay_ms),
     )?;
     warn!("Connected to database {}", &config.database.database_url);
 
@@ -101,24 +111,47 @@ async fn main() -> Result<()> {
     let shared_object_repo: Arc<dyn SharedObjectRepository + Send + Sync> =
         Arc::new(SharedObjectRepositoryImpl::new(db_conn.clone()));
 
+    let failed_event_repo: Arc<dyn FailedEventRepository + Send + Sync> =
+        Arc::new(FailedEventRepositoryImpl::new(db_conn.clone()));
+    let swap_event_repo: Arc<dyn SwapEventRepository + Send + Sync> =
+        Arc::new(SwapEventRepositoryImpl::new(db_conn.clone()));
+
     let network_config = config.networks.get(&config.run_mode).unwrap();
 
-    let sui_client = Arc::new(
-        SuiClientBuilder::default()
-            .build(network_config.rpc_url.clone())
-            .await?,
+    let rpc_urls = network_config.all_rpc_urls();
+    // one limiter shared by every RPC call of the process
+    let rpc_limiter = Arc::new(RpcRateLimiter::new(
+        config.rpc_max_concurrent,
+        config.rpc_qps,
+    ));
+    let rpc_pool = Arc::new(
+        utils::retry_with_backoff(
+            || {
+                RpcPool::new(
+                    &rpc_urls,
+                    config.indexer.rpc_max_retries,
+                    Duration::from_millis(constant::RPC_RETRY_BASE_DELAY_MS),
+                    Arc::clone(&rpc_limiter),
+                )
+            },
+            config.indexer.rpc_max_retries,
+            Duration::from_millis(constant::RPC_RETRY_BASE_DELAY_MS),
+        )
+        .await?,
     );
     warn!(
         "Sui client initialized with RPC URL: {}",
-        network_config.rpc_url,
+        rpc_pool.rpc_url()
     );
 
     // register services
     let db_pool_service = Arc::new(PoolService::new(
         Arc::clone(&config),
+        Arc::clone(&rpc_pool),
         Arc::clone(&pool_repo),
         Arc::clone(&coin_repo),
         Arc::clone(&pool_tick_repo),
+        Arc::clone(&swap_event_repo),
     ));
 
     let db_lending_service = Arc::new(LendingService::new(
@@ -129,17 +162,19 @@ async fn main() -> Result<()> {
         Arc::clone(&borrower_repo),
         Arc::clone(&metric_repo),
         Arc::clone(&shared_object_repo),
+        Arc::clone(&failed_event_repo),
     ));
 
     let ptb_helper = Arc::new(PTBHelper::new(
-        Arc::clone(&sui_client),
+        Arc::clone(&config),
+        Arc::clone(&rpc_pool),
         Arc::clone(&db_pool_service),
         Arc::clone(&db_lending_service),
     ));
 
     let service_registry = Arc::new(ServiceRegistry::new(
         Arc::clone(&config),
-        Arc::clone(&sui_client),
+        Arc::clone(&rpc_pool),
         Arc::clone(&coin_repo),
         Arc::clone(&pool_repo),
         Arc::clone(&db_pool_service),
@@ -150,7 +185,7 @@ async fn main() -> Result<()> {
     // event-processor registry
     let event_processor_registry = Arc::new(EventProcessorRegistry::new(
         Arc::clone(&config),
-        Arc::clone(&sui_client),
+        Arc::clone(&rpc_pool),
         Arc::clone(&pool_repo),
         Arc::clone(&coin_repo),
         Arc::clone(&db_pool_service),
@@ -161,35 +196,139 @@ async fn main() -> Result<()> {
     // onchain indexer
     let latest_timestamp_ms = Arc::new(AtomicU64::new(0));
 
-    let onchain_indexer = Arc::new(OnchainIndexer::new(
+    let onchain_indexer = OnchainIndexer::new(
         Arc::clone(&config),
-        Arc::clone(&sui_client),
+        Arc::clone(&rpc_pool),
         Arc::clone(&db_pool_service),
         Arc::clone(&db_lending_service),
         Arc::clone(&service_registry),
         Arc::clone(&event_processor_registry),
         Arc::clone(&latest_timestamp_ms),
-    ));
+        None,
+    );
+    let onchain_indexer = Arc::new(if config.indexer.transactional_checkpoints {
+        onchain_indexer.with_checkpoint_transactions(db_conn.clone())
+    } else {
+        onchain_indexer
+    });
 
-    let args = Cli::parse();
     match args.command {
         Commands::Index { command } => match command {
             IndexCommands::TxEvents { digest } => {
                 info!("Querying events for transaction: {}", digest);
 
-                index_cmd::handle_query_events(Arc::clone(&sui_client), &digest).await?;
+                index_cmd::handle_query_events(Arc::clone(&rpc_pool), &digest).await?;
             }
             IndexCommands::TxProcess { digest } => {
                 info!("Process tx events: {}", digest);
 
                 index_cmd::handle_process_tx(Arc::clone(&onchain_indexer), &digest).await?;
             }
+            IndexCommands::ModuleEvents {
+                package,
+                module,
+                limit,
+                dry_run,
+            } => {
+                info!("Querying events of module {}::{}", package, module);
+
+                index_cmd::handle_query_module_events(
+                    Arc::clone(&rpc_pool),
+                    Arc::clone(&event_processor_registry),
+                    &package,
+                    &module,
+                    limit,
+                    dry_run,
+                )
+                .await?;
+            }
             IndexCommands::CheckpointDetails { checkpoint } => {
                 info!("Querying checkpoint details: {}", checkpoint);
 
-                index_cmd::handle_query_checkpoint(Arc::clone(&sui_client), checkpoint).await?;
+                index_cmd::handle_query_checkpoint(Arc::clone(&rpc_pool), checkpoint).await?;
+            }
+            IndexCommands::BackfillRange { start, end, force } => {
+                info!("Backfill checkpoints from {} to {}", start, end);
+
+                index_cmd::handle_backfill_range(Arc::clone(&onchain_indexer), start, end, force)
+                    .await?;
+            }
+            IndexCommands::PoolDetails {
+                pool_id,
+                tick_window,
+                refresh,
+            } => {
+                info!("Pool details: {}", pool_id);
+
+                index_cmd::handle_pool_details(
+                    Arc::clone(&service_registry),
+                    &pool_id,
+                    tick_window,
+                    refresh,
+                )
+                .await?;
+            }
+            IndexCommands::HealthFactor { platform, address } => {
+                info!(
+                    "Health factor of borrower {} on platform {}",
+                    address, platform
+                );
+
+                index_cmd::handle_health_factor(Arc::clone(&service_registry), &platform, &address)
+                    .await?;
+            }
+            IndexCommands::ExportBorrower { platform, address } => {
+                info!("Export borrower {} on platform {}", address, platform);
+
+                index_cmd::handle_export_borrower(
+                    Arc::clone(&service_registry),
+                    &platform,
+                    &address,
+                )
+                .await?;
+            }
+            IndexCommands::SharedObjects => {
+                info!("List cached shared objects");
+
+                index_cmd::handle_list_shared_objects(Arc::clone(&service_registry)).await?;
+            }
+            IndexCommands::InvalidateSharedObject { object_id } => {
+                info!("Invalidate shared object {}", object_id);
+
+                index_cmd::handle_invalidate_shared_object(
+                    Arc::clone(&service_registry),
+                    &object_id,
+                )
+                .await?;
+            }
+            IndexCommands::Metrics {
+                latest,
+                from_seq,
+                to_seq,
+            } => {
+                info!("Exporting metrics");
+
+                index_cmd::handle_metrics(Arc::clone(&service_registry), latest, from_seq, to_seq)
+                    .await?;
+            }
+            IndexCommands::PriceCoverage { max_age_secs } => {
+                info!("Checking price coverage, max age {}s", max_age_secs);
+
+                index_cmd::handle_price_coverage(Arc::clone(&service_registry), max_age_secs)
+                    .await?;
+            }
+            IndexCommands::ResyncBorrower { platform, address } => {
+                info!("Resync borrower {} on platform {}", address, platform);
+
+                index_cmd::handle_resync_borrower(
+                    Arc::clone(&service_registry),
+                    &platform,
+                    &address,
+                )
+                .await?;
             }
         },
+        Commands::Doctor => unreachable!("handled before the setup"),
     }
 
     Ok(())
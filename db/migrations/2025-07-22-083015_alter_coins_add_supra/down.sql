@@ -0,0 +1,7 @@
+-- This file should undo anything in `up.sql`
+
+DROP INDEX IF EXISTS idx_coins_supra_pair_id;
+
+ALTER TABLE coins
+    DROP COLUMN IF EXISTS supra_pair_id,
+    DROP COLUMN IF EXISTS supra_latest_updated_at;
@@ -0,0 +1,7 @@
+-- Your SQL goes here
+
+ALTER TABLE coins
+    ADD COLUMN IF NOT EXISTS supra_pair_id INTEGER,
+    ADD COLUMN IF NOT EXISTS supra_latest_updated_at TIMESTAMP;
+
+CREATE INDEX IF NOT EXISTS idx_coins_supra_pair_id ON coins(supra_pair_id);
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+
+ALTER TABLE shared_objects
+    DROP COLUMN IF EXISTS cached_at;
@@ -0,0 +1,4 @@
+-- Your SQL goes here
+
+ALTER TABLE shared_objects
+    ADD COLUMN IF NOT EXISTS cached_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP;
@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+
+DROP INDEX IF EXISTS idx_failed_events_checkpoint_seq_number;
+
+DROP TABLE IF EXISTS failed_events;
@@ -0,0 +1,12 @@
+-- Your SQL goes here
+
+CREATE TABLE IF NOT EXISTS failed_events (
+    id SERIAL PRIMARY KEY,
+    event_type VARCHAR(512) NOT NULL,
+    tx_digest VARCHAR(64) NOT NULL,
+    checkpoint_seq_number BIGINT NOT NULL,
+    error TEXT NOT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_failed_events_checkpoint_seq_number ON failed_events(checkpoint_seq_number);
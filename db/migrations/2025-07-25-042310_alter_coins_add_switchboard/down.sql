@@ -0,0 +1,7 @@
+-- This file should undo anything in `up.sql`
+
+DROP INDEX IF EXISTS idx_coins_switchboard_aggregator_id;
+
+ALTER TABLE coins
+    DROP COLUMN IF EXISTS switchboard_aggregator_id,
+    DROP COLUMN IF EXISTS switchboard_latest_updated_at;
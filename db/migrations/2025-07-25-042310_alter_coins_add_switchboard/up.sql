@@ -0,0 +1,7 @@
+-- Your SQL goes here
+
+ALTER TABLE coins
+    ADD COLUMN IF NOT EXISTS switchboard_aggregator_id VARCHAR(66),
+    ADD COLUMN IF NOT EXISTS switchboard_latest_updated_at TIMESTAMP;
+
+CREATE INDEX IF NOT EXISTS idx_coins_switchboard_aggregator_id ON coins(switchboard_aggregator_id);
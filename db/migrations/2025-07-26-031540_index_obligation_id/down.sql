@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+
+DROP INDEX IF EXISTS idx_borrowers_platform_obligation_id;
+DROP INDEX IF EXISTS idx_user_deposits_platform_obligation_id;
+DROP INDEX IF EXISTS idx_user_borrows_platform_obligation_id;
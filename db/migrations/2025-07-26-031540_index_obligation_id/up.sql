@@ -0,0 +1,5 @@
+-- Your SQL goes here
+
+CREATE INDEX IF NOT EXISTS idx_user_borrows_platform_obligation_id ON user_borrows(platform, obligation_id);
+CREATE INDEX IF NOT EXISTS idx_user_deposits_platform_obligation_id ON user_deposits(platform, obligation_id);
+CREATE INDEX IF NOT EXISTS idx_borrowers_platform_obligation_id ON borrowers(platform, obligation_id);
@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+
+DROP INDEX IF EXISTS idx_swap_events_tx_digest;
+DROP INDEX IF EXISTS idx_swap_events_pool_id;
+
+DROP TABLE IF EXISTS swap_events;
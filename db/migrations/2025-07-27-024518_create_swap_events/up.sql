@@ -0,0 +1,16 @@
+-- Your SQL goes here
+
+CREATE TABLE IF NOT EXISTS swap_events (
+    id SERIAL PRIMARY KEY,
+    exchange VARCHAR(64) NOT NULL,
+    pool_id VARCHAR(66) NOT NULL,
+    a_to_b BOOLEAN NOT NULL,
+    amount_in VARCHAR(64) NOT NULL,
+    amount_out VARCHAR(64) NOT NULL,
+    fee_amount VARCHAR(64),
+    tx_digest VARCHAR(64) NOT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_swap_events_pool_id ON swap_events(pool_id);
+CREATE INDEX IF NOT EXISTS idx_swap_events_tx_digest ON swap_events(tx_digest);
@@ -0,0 +1,16 @@
+-- This file should undo anything in `up.sql`
+
+-- restore the coins changed or removed by up.sql, and the coins of the pools, as they were
+DELETE FROM coins
+WHERE id IN (SELECT id FROM coins_before_canonicalization);
+
+INSERT INTO coins
+SELECT * FROM coins_before_canonicalization;
+
+UPDATE pools
+SET coins = backup.coins
+FROM pools_before_canonicalization backup
+WHERE pools.id = backup.id;
+
+DROP TABLE coins_before_canonicalization;
+DROP TABLE pools_before_canonicalization;
@@ -0,0 +1,63 @@
+-- Your SQL goes here
+
+-- mirrors utils::canonicalize_coin_type: every type, the ones nested in generic arguments included,
+-- becomes 0x2::sui::SUI for SUI, otherwise its package address padded to 64 hex characters,
+-- and the whitespace is removed, e.g. `0xa::lp::LP<0x2::sui::SUI, 0xb::usdc::USDC>`
+-- becomes `0x0..0a::lp::LP<0x2::sui::SUI,0x0..0b::usdc::USDC>`
+CREATE OR REPLACE FUNCTION canonicalize_coin_type(coin_type TEXT)
+RETURNS TEXT AS $$
+DECLARE
+    canonical TEXT := '';
+    rest TEXT := regexp_replace(coin_type, '\s', '', 'g');
+    parts TEXT[];
+    address TEXT;
+BEGIN
+    LOOP
+        -- text before the next type, its address, module and name, and the text after it
+        parts := regexp_match(rest, '^(.*?)\m(?:0x)?([0-9a-fA-F]+)(::\w+::\w+)(\W.*)?$');
+        EXIT WHEN parts IS NULL;
+
+        address := ltrim(parts[2], '0');
+        IF address = '2' AND parts[3] = '::sui::SUI' THEN
+            canonical := canonical || parts[1] || '0x2::sui::SUI';
+        ELSE
+            canonical := canonical || parts[1] || '0x' || lpad(address, 64, '0') || parts[3];
+        END IF;
+        rest := coalesce(parts[4], '');
+    END LOOP;
+
+    RETURN canonical || rest;
+END;
+$$ LANGUAGE plpgsql IMMUTABLE;
+
+-- the rows changed below are kept as they were, for down.sql to restore them
+CREATE TABLE coins_before_canonicalization AS
+SELECT * FROM coins
+WHERE coin_type <> canonicalize_coin_type(coin_type);
+
+CREATE TABLE pools_before_canonicalization AS
+SELECT id, coins FROM pools
+WHERE coins <> '';
+
+-- keep one row per canonical coin type, preferring the one already in canonical form
+DELETE FROM coins c
+WHERE c.coin_type <> canonicalize_coin_type(c.coin_type)
+    AND EXISTS (
+        SELECT 1 FROM coins other
+        WHERE other.id <> c.id
+            AND canonicalize_coin_type(other.coin_type) = canonicalize_coin_type(c.coin_type)
+            AND (other.coin_type = canonicalize_coin_type(other.coin_type) OR other.id < c.id)
+    );
+
+UPDATE coins
+SET coin_type = canonicalize_coin_type(coin_type)
+WHERE coin_type <> canonicalize_coin_type(coin_type);
+
+UPDATE pools
+SET coins = (
+    SELECT string_agg(canonicalize_coin_type(t.coin_type), ',' ORDER BY t.ord)
+    FROM unnest(string_to_array(pools.coins, ',')) WITH ORDINALITY AS t(coin_type, ord)
+)
+WHERE coins <> '';
+
+DROP FUNCTION canonicalize_coin_type(TEXT);
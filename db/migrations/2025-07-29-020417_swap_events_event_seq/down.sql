@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+
+DROP INDEX IF EXISTS idx_swap_events_tx_digest_event_seq;
+CREATE INDEX IF NOT EXISTS idx_swap_events_tx_digest ON swap_events(tx_digest);
+
+ALTER TABLE swap_events DROP COLUMN IF EXISTS event_seq;
@@ -0,0 +1,18 @@
+-- Your SQL goes here
+
+ALTER TABLE swap_events ADD COLUMN IF NOT EXISTS event_seq BIGINT NOT NULL DEFAULT 0;
+
+-- the swaps recorded so far have no event seq, number them in insertion order within their tx
+UPDATE swap_events AS s
+SET event_seq = numbered.event_seq
+FROM (
+    SELECT id, ROW_NUMBER() OVER (PARTITION BY tx_digest ORDER BY id) - 1 AS event_seq
+    FROM swap_events
+) AS numbered
+WHERE s.id = numbered.id;
+
+ALTER TABLE swap_events ALTER COLUMN event_seq DROP DEFAULT;
+
+-- a swap is recorded once, however many times its checkpoint or tx is processed
+DROP INDEX IF EXISTS idx_swap_events_tx_digest;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_swap_events_tx_digest_event_seq ON swap_events(tx_digest, event_seq);
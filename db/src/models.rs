@@ -1,8 +1,10 @@
 pub mod borrower;
 pub mod coin;
+pub mod failed_event;
 pub mod metric;
 pub mod pool;
 pub mod pool_tick;
 pub mod shared_object;
+pub mod swap_event;
 pub mod user_borrow;
 pub mod user_deposit;
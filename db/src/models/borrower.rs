@@ -2,7 +2,7 @@ use crate::schema::borrowers;
 use chrono::NaiveDateTime;
 use diesel::prelude::*;
 
-#[derive(Queryable, Selectable, Debug, Clone)]
+#[derive(Queryable, QueryableByName, Selectable, Debug, Clone)]
 #[diesel(table_name = borrowers)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
 pub struct Borrower {
@@ -27,6 +27,10 @@ pub struct Coin {
     pub hermes_price: Option<String>,
     pub hermes_latest_updated_at: Option<NaiveDateTime>,
     pub vaa: Option<String>,
+    pub supra_pair_id: Option<i32>,
+    pub supra_latest_updated_at: Option<NaiveDateTime>,
+    pub switchboard_aggregator_id: Option<String>,
+    pub switchboard_latest_updated_at: Option<NaiveDateTime>,
 }
 
 #[derive(Insertable, Debug)]
@@ -50,6 +54,10 @@ pub struct NewCoin {
     pub hermes_price: Option<String>,
     pub hermes_latest_updated_at: Option<NaiveDateTime>,
     pub vaa: Option<String>,
+    pub supra_pair_id: Option<i32>,
+    pub supra_latest_updated_at: Option<NaiveDateTime>,
+    pub switchboard_aggregator_id: Option<String>,
+    pub switchboard_latest_updated_at: Option<NaiveDateTime>,
 }
 
 #[derive(AsChangeset, Debug)]
@@ -73,4 +81,8 @@ pub struct UpdateCoin {
     pub hermes_price: Option<String>,
     pub hermes_latest_updated_at: Option<NaiveDateTime>,
     pub vaa: Option<String>,
+    pub supra_pair_id: Option<i32>,
+    pub supra_latest_updated_at: Option<NaiveDateTime>,
+    pub switchboard_aggregator_id: Option<String>,
+    pub switchboard_latest_updated_at: Option<NaiveDateTime>,
 }
@@ -0,0 +1,25 @@
+use crate::schema::failed_events;
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+
+#[derive(Queryable, Selectable, Debug, Clone)]
+#[diesel(table_name = failed_events)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct FailedEvent {
+    pub id: i32,
+    pub event_type: String,
+    pub tx_digest: String,
+    pub checkpoint_seq_number: i64,
+    pub error: String,
+    pub created_at: Option<NaiveDateTime>,
+}
+
+#[derive(Insertable, Debug, Clone)]
+#[diesel(table_name = failed_events)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct NewFailedEvent {
+    pub event_type: String,
+    pub tx_digest: String,
+    pub checkpoint_seq_number: i64,
+    pub error: String,
+}
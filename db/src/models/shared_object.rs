@@ -11,6 +11,7 @@ pub struct SharedObject {
     pub initial_shared_version: i64,
     pub created_at: Option<NaiveDateTime>,
     pub updated_at: Option<NaiveDateTime>,
+    pub cached_at: Option<NaiveDateTime>,
 }
 
 #[derive(Insertable, Debug, Clone)]
@@ -19,6 +20,7 @@ pub struct SharedObject {
 pub struct NewSharedObject {
     pub object_id: String,
     pub initial_shared_version: i64,
+    pub cached_at: Option<NaiveDateTime>,
 }
 
 #[derive(AsChangeset, Debug, Clone)]
@@ -27,4 +29,5 @@ pub struct NewSharedObject {
 pub struct UpdateSharedObject {
     pub object_id: Option<String>,
     pub initial_shared_version: Option<i64>,
+    pub cached_at: Option<NaiveDateTime>,
 }
@@ -0,0 +1,36 @@
+use crate::schema::swap_events;
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+
+#[derive(Queryable, Selectable, Debug, Clone)]
+#[diesel(table_name = swap_events)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct SwapEvent {
+    pub id: i32,
+    pub exchange: String,
+    pub pool_id: String,
+    pub a_to_b: bool,
+    pub amount_in: String,
+    pub amount_out: String,
+    pub fee_amount: Option<String>,
+    pub tx_digest: String,
+    pub created_at: Option<NaiveDateTime>,
+    pub event_seq: i64,
+}
+
+/// Swap normalized across exchanges,
+/// each DEX processor maps its own swap event fields into it.
+/// Identified by the tx digest and the sequence number of the event in the tx.
+#[derive(Insertable, Debug, Clone)]
+#[diesel(table_name = swap_events)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct NewSwapEvent {
+    pub exchange: String,
+    pub pool_id: String,
+    pub a_to_b: bool,
+    pub amount_in: String,
+    pub amount_out: String,
+    pub fee_amount: Option<String>,
+    pub tx_digest: String,
+    pub event_seq: i64,
+}
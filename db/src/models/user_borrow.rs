@@ -102,7 +102,7 @@ pub struct UserBorrowWithCoinInfo {
     pub supply_index: Option<String>,
 }
 
-#[derive(Queryable, Debug)]
+#[derive(Queryable, QueryableByName, Debug)]
 pub struct UserBorrowDistinct {
     #[diesel(sql_type = Text)]
     pub platform: String,
@@ -1,19 +1,23 @@
 pub mod borrower;
 pub mod coin;
+pub mod failed_event;
 pub mod metric;
 pub mod pool;
 pub mod pool_tick;
 pub mod shared_object;
+pub mod swap_event;
 pub mod user_borrow;
 pub mod user_deposit;
 
 use crate::models::{
     borrower::{Borrower, NewBorrower, UpdateBorrower},
     coin::{Coin, NewCoin, UpdateCoin},
+    failed_event::{FailedEvent, NewFailedEvent},
     metric::{Metric, NewMetric, UpdateMetric},
     pool::{NewPool, Pool, UpdatePool},
     pool_tick::{NewPoolTick, PoolTick, UpdatePoolTick},
     shared_object::{NewSharedObject, SharedObject, UpdateSharedObject},
+    swap_event::{NewSwapEvent, SwapEvent},
     user_borrow::{
         NewUserBorrow, UpdateUserBorrow, UserBorrow, UserBorrowCoin, UserBorrowDistinct,
         UserBorrowWithCoinInfo,
@@ -23,166 +27,253 @@ use crate::models::{
         UserDepositWithCoinInfo,
     },
 };
+use crate::RepoResult;
 
-use diesel::prelude::*;
+use chrono::NaiveDateTime;
 
 pub trait PoolRepository {
-    fn create(&self, pool: &NewPool) -> QueryResult<Pool>;
-    fn update(&self, id: i32, pool: &UpdatePool) -> QueryResult<Pool>;
-    fn delete(&self, id: i32) -> QueryResult<bool>;
-    fn find_by_id(&self, id: i32) -> QueryResult<Pool>;
-    fn find_by_address(&self, address: &str) -> QueryResult<Pool>;
-    fn find_all(&self) -> QueryResult<Vec<Pool>>;
+    fn create(&self, pool: &NewPool) -> RepoResult<Pool>;
+    fn update(&self, id: i32, pool: &UpdatePool) -> RepoResult<Pool>;
+    fn delete(&self, id: i32) -> RepoResult<bool>;
+    fn find_by_id(&self, id: i32) -> RepoResult<Pool>;
+    fn find_by_address(&self, address: &str) -> RepoResult<Pool>;
+    fn find_by_addresses(&self, addresses: &[&str]) -> RepoResult<Vec<Pool>>;
+    fn find_by_coins_containing(&self, coin_a: &str, coin_b: &str) -> RepoResult<Vec<Pool>>;
+    fn upsert(&self, new_pool: &NewPool, update_pool: &UpdatePool) -> RepoResult<Pool>;
+    fn find_all(&self) -> RepoResult<Vec<Pool>>;
+    fn find_by_exchange_paged(
+        &self,
+        exchange: &str,
+        offset: i64,
+        limit: i64,
+    ) -> RepoResult<Vec<Pool>>;
+    fn count_by_exchange(&self, exchange: &str) -> RepoResult<i64>;
 }
 
 pub trait CoinRepository {
-    fn create(&self, coin: &NewCoin) -> QueryResult<Coin>;
-    fn update(&self, id: i32, coin: &UpdateCoin) -> QueryResult<Coin>;
-    fn delete(&self, id: i32) -> QueryResult<bool>;
-    fn find_by_id(&self, id: i32) -> QueryResult<Coin>;
-    fn find_all(&self) -> QueryResult<Vec<Coin>>;
-    fn find_by_coin_type(&self, coin_type: &str) -> QueryResult<Coin>;
-    fn find_by_pyth_feed_id(&self, pyth_feed_id: &str) -> QueryResult<Vec<Coin>>;
-    fn find_by_navi_asset_id(&self, asset_id: i32) -> QueryResult<Coin>;
-    fn find_all_pyth_feed_ids(&self) -> QueryResult<Vec<String>>;
+    fn create(&self, coin: &NewCoin) -> RepoResult<Coin>;
+    fn update(&self, id: i32, coin: &UpdateCoin) -> RepoResult<Coin>;
+    fn force_update_decimals(&self, id: i32, decimals: i32) -> RepoResult<Coin>;
+    fn upsert(&self, new_coin: &NewCoin, update_coin: &UpdateCoin) -> RepoResult<Coin>;
+    fn update_prices_batch(&self, updates: &[(i32, UpdateCoin)]) -> RepoResult<usize>;
+    fn delete(&self, id: i32) -> RepoResult<bool>;
+    fn find_by_id(&self, id: i32) -> RepoResult<Coin>;
+    fn find_all(&self) -> RepoResult<Vec<Coin>>;
+    fn find_by_coin_type(&self, coin_type: &str) -> RepoResult<Coin>;
+    fn find_by_coin_types(&self, coin_types: &[String]) -> RepoResult<Vec<Coin>>;
+    fn find_by_pyth_feed_id(&self, pyth_feed_id: &str) -> RepoResult<Vec<Coin>>;
+    fn find_by_supra_pair_id(&self, supra_pair_id: i32) -> RepoResult<Vec<Coin>>;
+    fn find_by_switchboard_aggregator_id(&self, aggregator_id: &str) -> RepoResult<Vec<Coin>>;
+    fn find_by_navi_asset_id(&self, asset_id: i32) -> RepoResult<Coin>;
+    fn find_all_pyth_feed_ids(&self) -> RepoResult<Vec<String>>;
+    fn find_coins_without_price(&self) -> RepoResult<Vec<Coin>>;
+    fn find_coins_with_stale_price(&self, older_than: NaiveDateTime) -> RepoResult<Vec<Coin>>;
 }
 
 pub trait UserBorrowRepository {
-    fn create(&self, user_borrow: &NewUserBorrow) -> QueryResult<UserBorrow>;
-    fn update(&self, id: i32, user_borrow: &UpdateUserBorrow) -> QueryResult<UserBorrow>;
-    fn delete(&self, id: i32) -> QueryResult<bool>;
-    fn find_by_id(&self, id: i32) -> QueryResult<UserBorrow>;
-    fn find_all(&self) -> QueryResult<Vec<UserBorrow>>;
+    fn create(&self, user_borrow: &NewUserBorrow) -> RepoResult<UserBorrow>;
+    fn update(&self, id: i32, user_borrow: &UpdateUserBorrow) -> RepoResult<UserBorrow>;
+    fn delete(&self, id: i32) -> RepoResult<bool>;
+    fn find_by_id(&self, id: i32) -> RepoResult<UserBorrow>;
+    fn find_all(&self) -> RepoResult<Vec<UserBorrow>>;
 
-    fn delete_by_platform_and_address(&self, platform: &str, address: &str) -> QueryResult<bool>;
+    fn delete_by_platform_and_address(&self, platform: &str, address: &str) -> RepoResult<bool>;
 
     fn find_by_platform_and_address(
         &self,
         platform: &str,
         address: &str,
-    ) -> QueryResult<Vec<UserBorrow>>;
+    ) -> RepoResult<Vec<UserBorrow>>;
 
     fn find_by_platform_and_address_with_coin_info(
         &self,
         platform: &str,
         address: &str,
-    ) -> QueryResult<Vec<UserBorrowWithCoinInfo>>;
+    ) -> RepoResult<Vec<UserBorrowWithCoinInfo>>;
+
+    fn find_distinct_platform_and_address(&self) -> RepoResult<Vec<UserBorrowDistinct>>;
 
-    fn find_distinct_platform_and_address(&self) -> QueryResult<Vec<UserBorrowDistinct>>;
+    fn iter_distinct_platform_and_address(
+        &self,
+        batch_size: i64,
+    ) -> Box<dyn Iterator<Item = RepoResult<Vec<UserBorrowDistinct>>> + Send + '_>;
 
     fn find_coins_by_platform_and_address(
         &self,
         platform: &str,
         address: &str,
-    ) -> QueryResult<Vec<UserBorrowCoin>>;
+    ) -> RepoResult<Vec<UserBorrowCoin>>;
 
     fn delete_by_platform_and_address_and_obligation_id(
         &self,
         platform: &str,
         address: &str,
         obligation_id: &str,
-    ) -> QueryResult<bool>;
+    ) -> RepoResult<bool>;
 
     fn find_by_platform_and_address_and_coin_type(
         &self,
         platform: &str,
         address: &str,
         coin_type: &str,
-    ) -> QueryResult<UserBorrow>;
+    ) -> RepoResult<UserBorrow>;
 
     fn find_by_platform_and_obligation_id(
         &self,
         platform: &str,
         obligation_id: &str,
-    ) -> QueryResult<UserBorrow>;
+    ) -> RepoResult<UserBorrow>;
 }
 
 pub trait UserDepositRepository {
-    fn create(&self, user_deposit: &NewUserDeposit) -> QueryResult<UserDeposit>;
-    fn update(&self, id: i32, user_deposit: &UpdateUserDeposit) -> QueryResult<UserDeposit>;
-    fn delete(&self, id: i32) -> QueryResult<bool>;
-    fn find_by_id(&self, id: i32) -> QueryResult<UserDeposit>;
-    fn find_all(&self) -> QueryResult<Vec<UserDeposit>>;
+    fn create(&self, user_deposit: &NewUserDeposit) -> RepoResult<UserDeposit>;
+    fn update(&self, id: i32, user_deposit: &UpdateUserDeposit) -> RepoResult<UserDeposit>;
+    fn delete(&self, id: i32) -> RepoResult<bool>;
+    fn find_by_id(&self, id: i32) -> RepoResult<UserDeposit>;
+    fn find_all(&self) -> RepoResult<Vec<UserDeposit>>;
 
-    fn delete_by_platform_and_address(&self, platform: &str, address: &str) -> QueryResult<bool>;
+    fn delete_by_platform_and_address(&self, platform: &str, address: &str) -> RepoResult<bool>;
 
     fn find_by_platform_and_address(
         &self,
         platform: &str,
         address: &str,
-    ) -> QueryResult<Vec<UserDeposit>>;
+    ) -> RepoResult<Vec<UserDeposit>>;
 
     fn find_by_platform_and_address_and_coin_type(
         &self,
         platform: &str,
         address: &str,
         coin_type: &str,
-    ) -> QueryResult<UserDeposit>;
+    ) -> RepoResult<UserDeposit>;
 
     fn find_by_platform_and_address_with_coin_info(
         &self,
         platform: &str,
         address: &str,
-    ) -> QueryResult<Vec<UserDepositWithCoinInfo>>;
+    ) -> RepoResult<Vec<UserDepositWithCoinInfo>>;
 
     fn delete_by_platform_and_address_and_obligation_id(
         &self,
         platform: &str,
         address: &str,
         obligation_id: &str,
-    ) -> QueryResult<bool>;
+    ) -> RepoResult<bool>;
 
-    fn find_distinct_platform_and_address(&self) -> QueryResult<Vec<UserDepositDistinct>>;
+    fn find_distinct_platform_and_address(&self) -> RepoResult<Vec<UserDepositDistinct>>;
 }
 
 pub trait PoolTickRepository {
-    fn create(&self, pool_tick: &NewPoolTick) -> QueryResult<PoolTick>;
-    fn update(&self, id: i32, pool_tick: &UpdatePoolTick) -> QueryResult<PoolTick>;
-    fn delete(&self, id: i32) -> QueryResult<bool>;
-    fn find_by_id(&self, id: i32) -> QueryResult<PoolTick>;
-    fn find_all(&self) -> QueryResult<Vec<PoolTick>>;
+    fn create(&self, pool_tick: &NewPoolTick) -> RepoResult<PoolTick>;
+    fn update(&self, id: i32, pool_tick: &UpdatePoolTick) -> RepoResult<PoolTick>;
+    fn delete(&self, id: i32) -> RepoResult<bool>;
+    fn find_by_id(&self, id: i32) -> RepoResult<PoolTick>;
+    fn find_all(&self) -> RepoResult<Vec<PoolTick>>;
     fn find_by_address_and_tick_index(
         &self,
         address: &str,
         tick_index: i32,
-    ) -> QueryResult<PoolTick>;
-    fn find_by_address(&self, address: &str) -> QueryResult<Vec<PoolTick>>;
+    ) -> RepoResult<PoolTick>;
+    fn find_by_address(&self, address: &str) -> RepoResult<Vec<PoolTick>>;
     fn find_lower_tick_for_address(
         &self,
         address: &str,
         tick_index: i32,
-    ) -> QueryResult<Option<PoolTick>>;
+    ) -> RepoResult<Option<PoolTick>>;
 
     fn find_higher_tick_for_address(
         &self,
         address: &str,
         tick_index: i32,
-    ) -> QueryResult<Option<PoolTick>>;
+    ) -> RepoResult<Option<PoolTick>>;
+
+    fn find_lower_ticks_for_address(
+        &self,
+        address: &str,
+        tick_index: i32,
+        limit: i64,
+    ) -> RepoResult<Vec<PoolTick>>;
+
+    fn find_higher_ticks_for_address(
+        &self,
+        address: &str,
+        tick_index: i32,
+        limit: i64,
+    ) -> RepoResult<Vec<PoolTick>>;
+
+    fn upsert_batch(&self, pool_ticks: &[NewPoolTick]) -> RepoResult<Vec<PoolTick>>;
+    fn add_liquidity_deltas(&self, pool_ticks: &[NewPoolTick]) -> RepoResult<Vec<PoolTick>>;
+
+    fn delete_ticks_outside_range(
+        &self,
+        address: &str,
+        lower_tick_index: i32,
+        upper_tick_index: i32,
+    ) -> RepoResult<usize>;
 }
 
 pub trait MetricRepository {
-    fn create(&self, metric: &NewMetric) -> QueryResult<Metric>;
-    fn update(&self, id: i32, metric: &UpdateMetric) -> QueryResult<Metric>;
-    fn delete(&self, id: i32) -> QueryResult<bool>;
-    fn find_by_id(&self, id: i32) -> QueryResult<Metric>;
-    fn find_latest_seq_number(&self) -> QueryResult<Option<Metric>>;
+    fn create(&self, metric: &NewMetric) -> RepoResult<Metric>;
+    fn update(&self, id: i32, metric: &UpdateMetric) -> RepoResult<Metric>;
+    fn delete(&self, id: i32) -> RepoResult<bool>;
+    fn find_by_id(&self, id: i32) -> RepoResult<Metric>;
+    fn find_latest_seq_number(&self) -> RepoResult<Option<Metric>>;
+    fn find_metrics_between(&self, start_seq: i32, end_seq: i32) -> RepoResult<Vec<Metric>>;
+    fn avg_lagging_between(&self, start_seq: i32, end_seq: i32) -> RepoResult<Option<f32>>;
 }
 
 pub trait BorrowerRepository {
-    fn create(&self, borrower: &NewBorrower) -> QueryResult<Borrower>;
-    fn update(&self, id: i32, borrower: &UpdateBorrower) -> QueryResult<Borrower>;
-    fn delete(&self, id: i32) -> QueryResult<bool>;
-    fn find_by_id(&self, id: i32) -> QueryResult<Borrower>;
-    fn find_all(&self) -> QueryResult<Vec<Borrower>>;
-    fn find_by_platform_and_address(&self, platform: &str, address: &str) -> QueryResult<Borrower>;
-    fn find_all_by_status(&self, status: i32) -> QueryResult<Vec<Borrower>>;
+    fn create(&self, borrower: &NewBorrower) -> RepoResult<Borrower>;
+    fn update(&self, id: i32, borrower: &UpdateBorrower) -> RepoResult<Borrower>;
+    fn delete(&self, id: i32) -> RepoResult<bool>;
+    fn find_by_id(&self, id: i32) -> RepoResult<Borrower>;
+    fn find_all(&self) -> RepoResult<Vec<Borrower>>;
+    fn find_by_platform_and_address(&self, platform: &str, address: &str) -> RepoResult<Borrower>;
+    fn find_by_platform_and_obligation_id(
+        &self,
+        platform: &str,
+        obligation_id: &str,
+    ) -> RepoResult<Borrower>;
+    fn find_all_by_status(&self, status: i32) -> RepoResult<Vec<Borrower>>;
+    fn find_stale(&self, platform: &str, older_than: NaiveDateTime) -> RepoResult<Vec<Borrower>>;
+    fn save_with_positions(
+        &self,
+        new_borrower: &NewBorrower,
+        new_borrows: &[NewUserBorrow],
+        new_deposits: &[NewUserDeposit],
+    ) -> RepoResult<Borrower>;
+    fn replace_with_positions(
+        &self,
+        new_borrower: &NewBorrower,
+        new_borrows: &[NewUserBorrow],
+        new_deposits: &[NewUserDeposit],
+    ) -> RepoResult<Borrower>;
 }
 
 pub trait SharedObjectRepository {
-    fn create(&self, shared_object: &NewSharedObject) -> QueryResult<SharedObject>;
-    fn update(&self, id: i32, shared_object: &UpdateSharedObject) -> QueryResult<SharedObject>;
-    fn delete(&self, id: i32) -> QueryResult<bool>;
-    fn find_by_id(&self, id: i32) -> QueryResult<SharedObject>;
-    fn find_by_object_id(&self, object_id: &str) -> QueryResult<SharedObject>;
-    fn find_all(&self) -> QueryResult<Vec<SharedObject>>;
+    fn create(&self, shared_object: &NewSharedObject) -> RepoResult<SharedObject>;
+    fn update(&self, id: i32, shared_object: &UpdateSharedObject) -> RepoResult<SharedObject>;
+    fn delete(&self, id: i32) -> RepoResult<bool>;
+    fn find_by_id(&self, id: i32) -> RepoResult<SharedObject>;
+    fn find_by_object_id(&self, object_id: &str) -> RepoResult<SharedObject>;
+    fn find_all(&self) -> RepoResult<Vec<SharedObject>>;
+    fn delete_by_object_id(&self, object_id: &str) -> RepoResult<usize>;
+}
+
+pub trait FailedEventRepository {
+    fn create(&self, failed_event: &NewFailedEvent) -> RepoResult<FailedEvent>;
+    fn delete(&self, id: i32) -> RepoResult<bool>;
+    fn find_by_id(&self, id: i32) -> RepoResult<FailedEvent>;
+    fn find_by_checkpoint_seq_number(&self, seq_number: i64) -> RepoResult<Vec<FailedEvent>>;
+}
+
+pub trait SwapEventRepository {
+    fn create(&self, swap_event: &NewSwapEvent) -> RepoResult<SwapEvent>;
+    /// Inserts the swaps not recorded yet, identified by tx digest and event seq.
+    /// Returns the number of swaps inserted.
+    fn create_batch(&self, swap_events: &[NewSwapEvent]) -> RepoResult<usize>;
+    fn delete(&self, id: i32) -> RepoResult<bool>;
+    fn find_by_id(&self, id: i32) -> RepoResult<SwapEvent>;
+    fn find_by_pool_id(&self, pool_id: &str) -> RepoResult<Vec<SwapEvent>>;
+    fn find_by_tx_digest(&self, tx_digest: &str) -> RepoResult<Vec<SwapEvent>>;
 }
@@ -1,8 +1,15 @@
-use crate::models::borrower::{Borrower, NewBorrower, UpdateBorrower};
+use crate::models::{
+    borrower::{Borrower, NewBorrower, UpdateBorrower},
+    user_borrow::{NewUserBorrow, UpdateUserBorrow},
+    user_deposit::{NewUserDeposit, UpdateUserDeposit},
+};
 use crate::repositories::BorrowerRepository;
-use crate::DbPool;
+use crate::{DbPool, RepoError, RepoResult};
 
+use chrono::NaiveDateTime;
 use diesel::prelude::*;
+use diesel::sql_query;
+use diesel::sql_types::*;
 
 pub struct BorrowerRepositoryImpl {
     db_pool: DbPool,
@@ -15,107 +22,462 @@ impl BorrowerRepositoryImpl {
 }
 
 impl BorrowerRepository for BorrowerRepositoryImpl {
-    fn create(&self, new_borrower: &NewBorrower) -> QueryResult<Borrower> {
+    fn create(&self, new_borrower: &NewBorrower) -> RepoResult<Borrower> {
         use crate::schema::borrowers::dsl::*;
-        let mut conn = self.db_pool.get().map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::UnableToSendCommand,
-                Box::new(e.to_string()),
-            )
-        })?;
+        let mut conn = crate::connection(&self.db_pool)?;
 
         diesel::insert_into(borrowers)
             .values(new_borrower)
-            .get_result(&mut conn)
+            .get_result(&mut *conn)
+            .map_err(RepoError::from)
     }
 
-    fn update(&self, borrower_id: i32, update_borrower: &UpdateBorrower) -> QueryResult<Borrower> {
+    fn update(&self, borrower_id: i32, update_borrower: &UpdateBorrower) -> RepoResult<Borrower> {
         use crate::schema::borrowers::dsl::*;
-        let mut conn = self.db_pool.get().map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::UnableToSendCommand,
-                Box::new(e.to_string()),
-            )
-        })?;
+        let mut conn = crate::connection(&self.db_pool)?;
 
         diesel::update(borrowers.find(borrower_id))
             .set(update_borrower)
-            .get_result(&mut conn)
+            .get_result(&mut *conn)
+            .map_err(RepoError::from)
     }
 
-    fn delete(&self, borrower_id: i32) -> QueryResult<bool> {
+    fn delete(&self, borrower_id: i32) -> RepoResult<bool> {
         use crate::schema::borrowers::dsl::*;
-        let mut conn = self.db_pool.get().map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::UnableToSendCommand,
-                Box::new(e.to_string()),
-            )
-        })?;
+        let mut conn = crate::connection(&self.db_pool)?;
 
-        let deleted_rows = diesel::delete(borrowers.find(borrower_id)).execute(&mut conn)?;
+        let deleted_rows = diesel::delete(borrowers.find(borrower_id)).execute(&mut *conn)?;
         Ok(deleted_rows > 0)
     }
 
-    fn find_by_id(&self, borrower_id: i32) -> QueryResult<Borrower> {
+    fn find_by_id(&self, borrower_id: i32) -> RepoResult<Borrower> {
         use crate::schema::borrowers::dsl::*;
-        let mut conn = self.db_pool.get().map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::UnableToSendCommand,
-                Box::new(e.to_string()),
-            )
-        })?;
+        let mut conn = crate::connection(&self.db_pool)?;
 
-        borrowers.find(borrower_id).get_result(&mut conn)
+        borrowers
+            .find(borrower_id)
+            .get_result(&mut *conn)
+            .map_err(RepoError::from)
     }
 
-    fn find_all(&self) -> QueryResult<Vec<Borrower>> {
+    fn find_all(&self) -> RepoResult<Vec<Borrower>> {
         use crate::schema::borrowers::dsl::*;
-        let mut conn = self.db_pool.get().map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::UnableToSendCommand,
-                Box::new(e.to_string()),
-            )
-        })?;
+        let mut conn = crate::connection(&self.db_pool)?;
 
-        borrowers.load(&mut conn)
+        borrowers.load(&mut *conn).map_err(RepoError::from)
     }
 
     fn find_by_platform_and_address(
         &self,
         platform_val: &str,
         address_val: &str,
-    ) -> QueryResult<Borrower> {
+    ) -> RepoResult<Borrower> {
         use crate::schema::borrowers::dsl::*;
-        let mut conn = self.db_pool.get().map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::UnableToSendCommand,
-                Box::new(e.to_string()),
-            )
-        })?;
+        let mut conn = crate::connection(&self.db_pool)?;
 
         borrowers
             .filter(platform.eq(platform_val))
             .filter(borrower.eq(address_val))
-            .first(&mut conn)
+            .first(&mut *conn)
+            .map_err(RepoError::from)
     }
 
-    fn find_all_by_status(&self, status_val: i32) -> QueryResult<Vec<Borrower>> {
+    fn find_by_platform_and_obligation_id(
+        &self,
+        platform_val: &str,
+        obligation_id_val: &str,
+    ) -> RepoResult<Borrower> {
         use crate::schema::borrowers::dsl::*;
-        let mut conn = self.db_pool.get().map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::UnableToSendCommand,
-                Box::new(e.to_string()),
-            )
-        })?;
+        let mut conn = crate::connection(&self.db_pool)?;
+
+        borrowers
+            .filter(platform.eq(platform_val))
+            .filter(obligation_id.eq(obligation_id_val))
+            .first(&mut *conn)
+            .map_err(RepoError::from)
+    }
+
+    fn find_all_by_status(&self, status_val: i32) -> RepoResult<Vec<Borrower>> {
+        use crate::schema::borrowers::dsl::*;
+        let mut conn = crate::connection(&self.db_pool)?;
 
         borrowers
             .filter(status.eq(status_val))
-            .load(&mut conn)
-            .map_err(|e| {
-                diesel::result::Error::DatabaseError(
-                    diesel::result::DatabaseErrorKind::UnableToSendCommand,
-                    Box::new(e.to_string()),
+            .load(&mut *conn)
+            .map_err(RepoError::from)
+    }
+
+    fn find_stale(
+        &self,
+        platform_str: &str,
+        older_than: NaiveDateTime,
+    ) -> RepoResult<Vec<Borrower>> {
+        let mut conn = crate::connection(&self.db_pool)?;
+
+        // the portfolio update time is the latest update of the borrows and deposits,
+        // borrowers without any position fall back to their own update time
+        sql_query(
+            "SELECT b.id, b.platform, b.borrower, b.obligation_id, b.status, b.created_at, b.updated_at
+             FROM borrowers b
+             LEFT JOIN (
+                 SELECT borrower, MAX(updated_at) AS updated_at
+                 FROM user_borrows
+                 WHERE platform = $1
+                 GROUP BY borrower
+             ) ub ON ub.borrower = b.borrower
+             LEFT JOIN (
+                 SELECT borrower, MAX(updated_at) AS updated_at
+                 FROM user_deposits
+                 WHERE platform = $1
+                 GROUP BY borrower
+             ) ud ON ud.borrower = b.borrower
+             WHERE b.platform = $1
+               AND COALESCE(GREATEST(ub.updated_at, ud.updated_at), b.updated_at) < $2
+             ORDER BY b.id",
+        )
+        .bind::<Text, _>(platform_str)
+        .bind::<Timestamp, _>(older_than)
+        .load(&mut *conn)
+            .map_err(RepoError::from)
+    }
+
+    /// Upserts the borrower with its borrows and deposits in a single transaction,
+    /// so a failure on any of them leaves none of them saved.
+    /// Positions are matched by (platform, borrower, coin type).
+    ///
+    fn save_with_positions(
+        &self,
+        new_borrower: &NewBorrower,
+        new_borrows: &[NewUserBorrow],
+        new_deposits: &[NewUserDeposit],
+    ) -> RepoResult<Borrower> {
+        crate::with_retry(&self.db_pool, |conn| {
+            conn.transaction(|conn| {
+                upsert_with_positions(conn, new_borrower, new_borrows, new_deposits)
+            })
+        })
+    }
+
+    /// Same as `save_with_positions`, but the stored borrows and deposits
+    /// of the borrower are deleted first in the same transaction,
+    /// so the positions not given are dropped.
+    ///
+    fn replace_with_positions(
+        &self,
+        new_borrower: &NewBorrower,
+        new_borrows: &[NewUserBorrow],
+        new_deposits: &[NewUserDeposit],
+    ) -> RepoResult<Borrower> {
+        use crate::schema::{user_borrows, user_deposits};
+
+        crate::with_retry(&self.db_pool, |conn| {
+            conn.transaction(|conn| {
+                diesel::delete(
+                    user_borrows::table
+                        .filter(user_borrows::platform.eq(&new_borrower.platform))
+                        .filter(user_borrows::borrower.eq(&new_borrower.borrower)),
+                )
+                .execute(conn)?;
+
+                diesel::delete(
+                    user_deposits::table
+                        .filter(user_deposits::platform.eq(&new_borrower.platform))
+                        .filter(user_deposits::borrower.eq(&new_borrower.borrower)),
                 )
+                .execute(conn)?;
+
+                upsert_with_positions(conn, new_borrower, new_borrows, new_deposits)
             })
+        })
+    }
+}
+
+fn upsert_with_positions(
+    conn: &mut PgConnection,
+    new_borrower: &NewBorrower,
+    new_borrows: &[NewUserBorrow],
+    new_deposits: &[NewUserDeposit],
+) -> QueryResult<Borrower> {
+    use crate::schema::{borrowers, user_borrows, user_deposits};
+
+    let saved_borrower = diesel::insert_into(borrowers::table)
+        .values(new_borrower)
+        .on_conflict((borrowers::platform, borrowers::borrower))
+        .do_update()
+        .set(&UpdateBorrower {
+            platform: None,
+            borrower: None,
+            obligation_id: new_borrower.obligation_id.clone(),
+            status: Some(new_borrower.status),
+        })
+        .get_result(conn)?;
+
+    for new_borrow in new_borrows {
+        diesel::insert_into(user_borrows::table)
+            .values(new_borrow)
+            .on_conflict((
+                user_borrows::platform,
+                user_borrows::borrower,
+                user_borrows::coin_type,
+            ))
+            .do_update()
+            .set(&UpdateUserBorrow {
+                platform: None,
+                borrower: None,
+                coin_type: None,
+                amount: Some(new_borrow.amount.clone()),
+                obligation_id: new_borrow.obligation_id.clone(),
+                debt_borrow_index: new_borrow.debt_borrow_index.clone(),
+            })
+            .execute(conn)?;
+    }
+
+    for new_deposit in new_deposits {
+        diesel::insert_into(user_deposits::table)
+            .values(new_deposit)
+            .on_conflict((
+                user_deposits::platform,
+                user_deposits::borrower,
+                user_deposits::coin_type,
+            ))
+            .do_update()
+            .set(&UpdateUserDeposit {
+                platform: None,
+                borrower: None,
+                coin_type: None,
+                amount: Some(new_deposit.amount.clone()),
+                obligation_id: new_deposit.obligation_id.clone(),
+            })
+            .execute(conn)?;
+    }
+
+    Ok(saved_borrower)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repositories::{
+        user_borrow::UserBorrowRepositoryImpl, user_deposit::UserDepositRepositoryImpl,
+        UserBorrowRepository, UserDepositRepository,
+    };
+
+    fn new_borrower(status: i32) -> NewBorrower {
+        NewBorrower {
+            platform: "navi".to_string(),
+            borrower: "0xborrower".to_string(),
+            obligation_id: None,
+            status,
+        }
+    }
+
+    fn new_borrow(coin_type: &str, amount: &str) -> NewUserBorrow {
+        NewUserBorrow {
+            platform: "navi".to_string(),
+            borrower: "0xborrower".to_string(),
+            coin_type: coin_type.to_string(),
+            amount: amount.to_string(),
+            obligation_id: None,
+            debt_borrow_index: None,
+        }
+    }
+
+    fn new_deposit(coin_type: &str, amount: &str) -> NewUserDeposit {
+        NewUserDeposit {
+            platform: "navi".to_string(),
+            borrower: "0xborrower".to_string(),
+            coin_type: coin_type.to_string(),
+            amount: amount.to_string(),
+            obligation_id: None,
+        }
+    }
+
+    #[test]
+    fn replace_with_positions_drops_the_positions_not_given() {
+        let Some(db_pool) = crate::test_db_pool() else {
+            return;
+        };
+        let repo = BorrowerRepositoryImpl::new(db_pool.clone());
+        let borrow_repo = UserBorrowRepositoryImpl::new(db_pool.clone());
+        let deposit_repo = UserDepositRepositoryImpl::new(db_pool);
+
+        repo.save_with_positions(
+            &new_borrower(0),
+            &[
+                new_borrow("0x2::sui::SUI", "10"),
+                new_borrow("0xusdc", "20"),
+            ],
+            &[new_deposit("0x2::sui::SUI", "100")],
+        )
+        .unwrap();
+
+        let saved = repo
+            .replace_with_positions(
+                &new_borrower(1),
+                &[new_borrow("0xusdc", "25")],
+                &[new_deposit("0xusdt", "50")],
+            )
+            .unwrap();
+
+        assert_eq!(saved.status, 1);
+        let borrows = borrow_repo
+            .find_by_platform_and_address("navi", "0xborrower")
+            .unwrap();
+        assert_eq!(
+            borrows
+                .iter()
+                .map(|b| (b.coin_type.as_str(), b.amount.as_str()))
+                .collect::<Vec<_>>(),
+            vec![("0xusdc", "25")]
+        );
+        let deposits = deposit_repo
+            .find_by_platform_and_address("navi", "0xborrower")
+            .unwrap();
+        assert_eq!(
+            deposits
+                .iter()
+                .map(|d| (d.coin_type.as_str(), d.amount.as_str()))
+                .collect::<Vec<_>>(),
+            vec![("0xusdt", "50")]
+        );
+    }
+
+    #[test]
+    fn save_with_positions_keeps_the_positions_not_given() {
+        let Some(db_pool) = crate::test_db_pool() else {
+            return;
+        };
+        let repo = BorrowerRepositoryImpl::new(db_pool.clone());
+        let borrow_repo = UserBorrowRepositoryImpl::new(db_pool);
+
+        repo.save_with_positions(&new_borrower(0), &[new_borrow("0xusdc", "20")], &[])
+            .unwrap();
+        repo.save_with_positions(&new_borrower(0), &[new_borrow("0xusdt", "5")], &[])
+            .unwrap();
+
+        assert_eq!(
+            borrow_repo
+                .find_by_platform_and_address("navi", "0xborrower")
+                .unwrap()
+                .len(),
+            2
+        );
+    }
+
+    #[test]
+    fn find_by_platform_and_obligation_id_returns_the_borrower_of_the_obligation() {
+        let Some(db_pool) = crate::test_db_pool() else {
+            return;
+        };
+        let repo = BorrowerRepositoryImpl::new(db_pool);
+
+        for (platform, borrower, obligation_id) in [
+            ("scallop", "0xborrower", Some("0xobligation")),
+            ("scallop", "0xother", Some("0xother_obligation")),
+            ("suilend", "0xsuilend", Some("0xobligation")),
+            ("scallop", "0xpending", None),
+        ] {
+            repo.create(&NewBorrower {
+                platform: platform.to_string(),
+                borrower: borrower.to_string(),
+                obligation_id: obligation_id.map(str::to_string),
+                status: 0,
+            })
+            .unwrap();
+        }
+
+        let found = repo
+            .find_by_platform_and_obligation_id("scallop", "0xobligation")
+            .unwrap();
+        assert_eq!(found.borrower, "0xborrower");
+        assert_eq!(found.platform, "scallop");
+
+        let found = repo
+            .find_by_platform_and_obligation_id("suilend", "0xobligation")
+            .unwrap();
+        assert_eq!(found.borrower, "0xsuilend");
+
+        assert!(matches!(
+            repo.find_by_platform_and_obligation_id("navi", "0xobligation"),
+            Err(RepoError::NotFound)
+        ));
+    }
+
+    fn at(year: i32, month: u32, day: u32) -> NaiveDateTime {
+        chrono::NaiveDate::from_ymd_opt(year, month, day)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap()
+    }
+
+    /// Inserts the borrower of the platform, last updated at `updated_at`.
+    /// The update triggers always set the current time, so the time is only set on insert.
+    fn insert_borrower_at(
+        db_pool: &DbPool,
+        platform: &str,
+        borrower: &str,
+        updated_at: NaiveDateTime,
+    ) {
+        let mut conn = crate::connection(db_pool).unwrap();
+        sql_query("INSERT INTO borrowers (platform, borrower, updated_at) VALUES ($1, $2, $3)")
+            .bind::<Text, _>(platform)
+            .bind::<Text, _>(borrower)
+            .bind::<Timestamp, _>(updated_at)
+            .execute(&mut *conn)
+            .unwrap();
+    }
+
+    /// Same as `insert_borrower_at` for a position in `table`, user_borrows or user_deposits.
+    fn insert_position_at(
+        db_pool: &DbPool,
+        table: &str,
+        platform: &str,
+        borrower: &str,
+        updated_at: NaiveDateTime,
+    ) {
+        let mut conn = crate::connection(db_pool).unwrap();
+        sql_query(format!(
+            "INSERT INTO {table} (platform, borrower, coin_type, amount, updated_at)
+             VALUES ($1, $2, '0x2::sui::SUI', '1', $3)"
+        ))
+        .bind::<Text, _>(platform)
+        .bind::<Text, _>(borrower)
+        .bind::<Timestamp, _>(updated_at)
+        .execute(&mut *conn)
+        .unwrap();
+    }
+
+    #[test]
+    fn find_stale_compares_the_latest_update_of_the_portfolio() {
+        let Some(db_pool) = crate::test_db_pool() else {
+            return;
+        };
+        let repo = BorrowerRepositoryImpl::new(db_pool.clone());
+        let (old, cutoff, recent) = (at(2024, 6, 1), at(2025, 1, 1), at(2025, 2, 1));
+
+        // without positions the borrower update time is used
+        insert_borrower_at(&db_pool, "navi", "0xidle", old);
+        insert_borrower_at(&db_pool, "navi", "0xcutoff", cutoff);
+        // with positions the latest of them is used, whatever the borrower update time
+        insert_borrower_at(&db_pool, "navi", "0xborrowing", old);
+        insert_position_at(&db_pool, "user_borrows", "navi", "0xborrowing", recent);
+        insert_borrower_at(&db_pool, "navi", "0xdepositing", old);
+        insert_position_at(&db_pool, "user_borrows", "navi", "0xdepositing", old);
+        insert_position_at(&db_pool, "user_deposits", "navi", "0xdepositing", recent);
+        insert_borrower_at(&db_pool, "navi", "0xold_positions", recent);
+        insert_position_at(&db_pool, "user_borrows", "navi", "0xold_positions", old);
+        // the other platforms are ignored, their positions included
+        insert_borrower_at(&db_pool, "scallop", "0xscallop", old);
+        insert_position_at(&db_pool, "user_borrows", "scallop", "0xidle", recent);
+
+        let stale = repo
+            .find_stale("navi", cutoff)
+            .unwrap()
+            .into_iter()
+            .map(|b| b.borrower)
+            .collect::<Vec<_>>();
+
+        // the cutoff is exclusive
+        assert_eq!(stale, vec!["0xidle", "0xold_positions"]);
     }
 }
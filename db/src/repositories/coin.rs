@@ -1,7 +1,8 @@
 use crate::models::coin::{Coin, NewCoin, UpdateCoin};
 use crate::repositories::CoinRepository;
-use crate::DbPool;
+use crate::{DbPool, RepoError, RepoResult};
 
+use chrono::NaiveDateTime;
 use diesel::prelude::*;
 
 pub struct CoinRepositoryImpl {
@@ -15,128 +16,361 @@ impl CoinRepositoryImpl {
 }
 
 impl CoinRepository for CoinRepositoryImpl {
-    fn create(&self, coin: &NewCoin) -> QueryResult<Coin> {
+    fn create(&self, coin: &NewCoin) -> RepoResult<Coin> {
         use crate::schema::coins::dsl::*;
-        let mut conn = self.db_pool.get().map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::UnableToSendCommand,
-                Box::new(e.to_string()),
-            )
-        })?;
+        let mut conn = crate::connection(&self.db_pool)?;
 
         diesel::insert_into(coins)
             .values(coin)
-            .get_result(&mut conn)
+            .get_result(&mut *conn)
+            .map_err(RepoError::from)
     }
 
-    fn update(&self, coin_id: i32, coin: &UpdateCoin) -> QueryResult<Coin> {
+    fn update(&self, coin_id: i32, coin: &UpdateCoin) -> RepoResult<Coin> {
         use crate::schema::coins::dsl::*;
-        let mut conn = self.db_pool.get().map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::UnableToSendCommand,
-                Box::new(e.to_string()),
-            )
-        })?;
+        let mut conn = crate::connection(&self.db_pool)?;
 
         diesel::update(coins.find(coin_id))
             .set(coin)
-            .get_result(&mut conn)
+            .get_result(&mut *conn)
+            .map_err(RepoError::from)
     }
 
-    fn delete(&self, coin_id: i32) -> QueryResult<bool> {
+    /// Inserts the coin, or applies `update_coin` if its type already exists,
+    /// in a single statement so that concurrent saves can't race.
+    ///
+    fn upsert(&self, new_coin: &NewCoin, update_coin: &UpdateCoin) -> RepoResult<Coin> {
         use crate::schema::coins::dsl::*;
-        let mut conn = self.db_pool.get().map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::UnableToSendCommand,
-                Box::new(e.to_string()),
-            )
-        })?;
 
-        let deleted_rows = diesel::delete(coins.find(coin_id)).execute(&mut conn)?;
+        crate::with_retry(&self.db_pool, |conn| {
+            diesel::insert_into(coins)
+                .values(new_coin)
+                .on_conflict(coin_type)
+                .do_update()
+                .set(update_coin)
+                .get_result(conn)
+        })
+    }
+
+    /// Overwrites the decimals of a coin,
+    /// which are otherwise never updated once the coin is created.
+    ///
+    fn force_update_decimals(&self, coin_id: i32, decimals_val: i32) -> RepoResult<Coin> {
+        use crate::schema::coins::dsl::*;
+        let mut conn = crate::connection(&self.db_pool)?;
+
+        diesel::update(coins.find(coin_id))
+            .set(decimals.eq(decimals_val))
+            .get_result(&mut *conn)
+            .map_err(RepoError::from)
+    }
+
+    /// Applies all updates in a single transaction.
+    /// If any coin is missing or any update fails, nothing is written.
+    ///
+    fn update_prices_batch(&self, updates: &[(i32, UpdateCoin)]) -> RepoResult<usize> {
+        use crate::schema::coins::dsl::*;
+
+        crate::with_retry(&self.db_pool, |conn| {
+            conn.transaction(|conn| {
+                let mut updated_rows = 0;
+
+                for (coin_id, coin) in updates {
+                    let rows = diesel::update(coins.find(coin_id))
+                        .set(coin)
+                        .execute(conn)?;
+
+                    if rows == 0 {
+                        return Err(diesel::result::Error::NotFound);
+                    }
+
+                    updated_rows += rows;
+                }
+
+                Ok(updated_rows)
+            })
+        })
+    }
+
+    fn delete(&self, coin_id: i32) -> RepoResult<bool> {
+        use crate::schema::coins::dsl::*;
+        let mut conn = crate::connection(&self.db_pool)?;
+
+        let deleted_rows = diesel::delete(coins.find(coin_id)).execute(&mut *conn)?;
         Ok(deleted_rows > 0)
     }
 
-    fn find_by_id(&self, coin_id: i32) -> QueryResult<Coin> {
+    fn find_by_id(&self, coin_id: i32) -> RepoResult<Coin> {
         use crate::schema::coins::dsl::*;
-        let mut conn = self.db_pool.get().map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::UnableToSendCommand,
-                Box::new(e.to_string()),
-            )
-        })?;
+        let mut conn = crate::connection(&self.db_pool)?;
 
-        coins.find(coin_id).get_result(&mut conn)
+        coins
+            .find(coin_id)
+            .get_result(&mut *conn)
+            .map_err(RepoError::from)
     }
 
-    fn find_all(&self) -> QueryResult<Vec<Coin>> {
+    fn find_all(&self) -> RepoResult<Vec<Coin>> {
         use crate::schema::coins::dsl::*;
-        let mut conn = self.db_pool.get().map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::UnableToSendCommand,
-                Box::new(e.to_string()),
-            )
-        })?;
+        let mut conn = crate::connection(&self.db_pool)?;
 
-        coins.load(&mut conn)
+        coins.load(&mut *conn).map_err(RepoError::from)
     }
 
-    fn find_by_coin_type(&self, coin_type_str: &str) -> QueryResult<Coin> {
+    fn find_by_coin_type(&self, coin_type_str: &str) -> RepoResult<Coin> {
         use crate::schema::coins::dsl::*;
-        let mut conn = self.db_pool.get().map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::UnableToSendCommand,
-                Box::new(e.to_string()),
-            )
-        })?;
+        let mut conn = crate::connection(&self.db_pool)?;
 
         coins
             .filter(coin_type.eq(coin_type_str))
             .limit(1)
-            .get_result(&mut conn)
+            .get_result(&mut *conn)
+            .map_err(RepoError::from)
     }
 
-    fn find_by_pyth_feed_id(&self, feed_id: &str) -> QueryResult<Vec<Coin>> {
+    fn find_by_coin_types(&self, coin_types: &[String]) -> RepoResult<Vec<Coin>> {
         use crate::schema::coins::dsl::*;
-        let mut conn = self.db_pool.get().map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::UnableToSendCommand,
-                Box::new(e.to_string()),
-            )
-        })?;
+        let mut conn = crate::connection(&self.db_pool)?;
 
-        coins.filter(pyth_feed_id.eq(feed_id)).load(&mut conn)
+        coins
+            .filter(coin_type.eq_any(coin_types))
+            .load(&mut *conn)
+            .map_err(RepoError::from)
     }
 
-    fn find_by_navi_asset_id(&self, asset_id: i32) -> QueryResult<Coin> {
+    fn find_by_pyth_feed_id(&self, feed_id: &str) -> RepoResult<Vec<Coin>> {
         use crate::schema::coins::dsl::*;
-        let mut conn = self.db_pool.get().map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::UnableToSendCommand,
-                Box::new(e.to_string()),
-            )
-        })?;
+        let mut conn = crate::connection(&self.db_pool)?;
+
+        coins
+            .filter(pyth_feed_id.eq(feed_id))
+            .load(&mut *conn)
+            .map_err(RepoError::from)
+    }
+
+    fn find_by_supra_pair_id(&self, pair_id: i32) -> RepoResult<Vec<Coin>> {
+        use crate::schema::coins::dsl::*;
+        let mut conn = crate::connection(&self.db_pool)?;
+
+        coins
+            .filter(supra_pair_id.eq(pair_id))
+            .load(&mut *conn)
+            .map_err(RepoError::from)
+    }
+
+    fn find_by_switchboard_aggregator_id(&self, aggregator_id: &str) -> RepoResult<Vec<Coin>> {
+        use crate::schema::coins::dsl::*;
+        let mut conn = crate::connection(&self.db_pool)?;
+
+        coins
+            .filter(switchboard_aggregator_id.eq(aggregator_id))
+            .load(&mut *conn)
+            .map_err(RepoError::from)
+    }
+
+    fn find_by_navi_asset_id(&self, asset_id: i32) -> RepoResult<Coin> {
+        use crate::schema::coins::dsl::*;
+        let mut conn = crate::connection(&self.db_pool)?;
 
         coins
             .filter(navi_asset_id.eq(asset_id))
             .limit(1)
-            .get_result(&mut conn)
+            .get_result(&mut *conn)
+            .map_err(RepoError::from)
     }
 
-    fn find_all_pyth_feed_ids(&self) -> QueryResult<Vec<String>> {
+    fn find_all_pyth_feed_ids(&self) -> RepoResult<Vec<String>> {
         use crate::schema::coins::dsl::*;
-        let mut conn = self.db_pool.get().map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::UnableToSendCommand,
-                Box::new(e.to_string()),
-            )
-        })?;
+        let mut conn = crate::connection(&self.db_pool)?;
 
         let results: Vec<Option<String>> = coins
             .select(pyth_feed_id)
             .filter(pyth_feed_id.is_not_null())
             .distinct()
-            .load(&mut conn)?;
+            .load(&mut *conn)?;
 
         Ok(results.into_iter().flatten().collect())
     }
+
+    /// Coins without any price, from Pyth, Hermes, Supra or Switchboard.
+    ///
+    fn find_coins_without_price(&self) -> RepoResult<Vec<Coin>> {
+        use crate::schema::coins::dsl::*;
+        let mut conn = crate::connection(&self.db_pool)?;
+
+        coins
+            .filter(price_pyth.is_null())
+            .filter(hermes_price.is_null())
+            .filter(price_supra.is_null())
+            .filter(price_switchboard.is_null())
+            .order(coin_type.asc())
+            .load(&mut *conn)
+            .map_err(RepoError::from)
+    }
+
+    /// Coins with a price, but none of them updated since `older_than`.
+    ///
+    fn find_coins_with_stale_price(&self, older_than: NaiveDateTime) -> RepoResult<Vec<Coin>> {
+        use crate::schema::coins::dsl::*;
+        let mut conn = crate::connection(&self.db_pool)?;
+
+        coins
+            .filter(
+                price_pyth
+                    .is_not_null()
+                    .or(hermes_price.is_not_null())
+                    .or(price_supra.is_not_null())
+                    .or(price_switchboard.is_not_null()),
+            )
+            .filter(
+                pyth_latest_updated_at
+                    .is_null()
+                    .or(pyth_latest_updated_at.lt(older_than)),
+            )
+            .filter(
+                hermes_latest_updated_at
+                    .is_null()
+                    .or(hermes_latest_updated_at.lt(older_than)),
+            )
+            .filter(
+                supra_latest_updated_at
+                    .is_null()
+                    .or(supra_latest_updated_at.lt(older_than)),
+            )
+            .filter(
+                switchboard_latest_updated_at
+                    .is_null()
+                    .or(switchboard_latest_updated_at.lt(older_than)),
+            )
+            .order(coin_type.asc())
+            .load(&mut *conn)
+            .map_err(RepoError::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_coin(coin_type_val: &str) -> NewCoin {
+        NewCoin {
+            coin_type: coin_type_val.to_string(),
+            decimals: 9,
+            name: None,
+            symbol: None,
+            price_pyth: None,
+            price_supra: None,
+            price_switchboard: None,
+            pyth_feed_id: None,
+            pyth_info_object_id: None,
+            pyth_latest_updated_at: None,
+            pyth_ema_price: None,
+            pyth_decimals: None,
+            navi_asset_id: None,
+            navi_oracle_id: None,
+            navi_feed_id: None,
+            hermes_price: None,
+            hermes_latest_updated_at: None,
+            vaa: None,
+            supra_pair_id: None,
+            supra_latest_updated_at: None,
+            switchboard_aggregator_id: None,
+            switchboard_latest_updated_at: None,
+        }
+    }
+
+    fn supra_price(price: &str) -> UpdateCoin {
+        UpdateCoin {
+            coin_type: None,
+            decimals: None,
+            name: None,
+            symbol: None,
+            price_pyth: None,
+            price_supra: Some(price.to_string()),
+            price_switchboard: None,
+            pyth_feed_id: None,
+            pyth_info_object_id: None,
+            pyth_latest_updated_at: None,
+            pyth_ema_price: None,
+            pyth_decimals: None,
+            navi_asset_id: None,
+            navi_oracle_id: None,
+            navi_feed_id: None,
+            hermes_price: None,
+            hermes_latest_updated_at: None,
+            vaa: None,
+            supra_pair_id: None,
+            supra_latest_updated_at: None,
+            switchboard_aggregator_id: None,
+            switchboard_latest_updated_at: None,
+        }
+    }
+
+    #[test]
+    fn find_by_coin_types_skips_the_missing_coins() {
+        let Some(db_pool) = crate::test_db_pool() else {
+            return;
+        };
+        let repo = CoinRepositoryImpl::new(db_pool);
+        let coin_a = repo.create(&new_coin("0xtest::a::A")).unwrap();
+        let coin_b = repo.create(&new_coin("0xtest::b::B")).unwrap();
+
+        let mut found = repo
+            .find_by_coin_types(&[
+                "0xtest::b::B".to_string(),
+                "0xtest::missing::MISSING".to_string(),
+                "0xtest::a::A".to_string(),
+            ])
+            .unwrap()
+            .into_iter()
+            .map(|c| c.id)
+            .collect::<Vec<_>>();
+        found.sort();
+
+        // the order is up to the database, callers match the coins by type
+        assert_eq!(found, vec![coin_a.id, coin_b.id]);
+    }
+
+    #[test]
+    fn update_prices_batch_updates_every_coin() {
+        let Some(db_pool) = crate::test_db_pool() else {
+            return;
+        };
+        let repo = CoinRepositoryImpl::new(db_pool);
+        let coin_a = repo.create(&new_coin("0xtest::a::A")).unwrap();
+        let coin_b = repo.create(&new_coin("0xtest::b::B")).unwrap();
+
+        let updated = repo
+            .update_prices_batch(&[
+                (coin_a.id, supra_price("1.5")),
+                (coin_b.id, supra_price("2.5")),
+            ])
+            .unwrap();
+
+        assert_eq!(updated, 2);
+        assert_eq!(
+            repo.find_by_id(coin_a.id).unwrap().price_supra.as_deref(),
+            Some("1.5")
+        );
+        assert_eq!(
+            repo.find_by_id(coin_b.id).unwrap().price_supra.as_deref(),
+            Some("2.5")
+        );
+    }
+
+    #[test]
+    fn update_prices_batch_with_a_missing_coin_updates_none() {
+        let Some(db_pool) = crate::test_db_pool() else {
+            return;
+        };
+        let repo = CoinRepositoryImpl::new(db_pool);
+        let coin_a = repo.create(&new_coin("0xtest::a::A")).unwrap();
+
+        // the missing coin comes after the update of coin A, which must be rolled back
+        let result =
+            repo.update_prices_batch(&[(coin_a.id, supra_price("1.5")), (-1, supra_price("2.5"))]);
+
+        assert!(matches!(result, Err(RepoError::NotFound)));
+        assert_eq!(repo.find_by_id(coin_a.id).unwrap().price_supra, None);
+    }
 }
@@ -0,0 +1,56 @@
+use crate::models::failed_event::{FailedEvent, NewFailedEvent};
+use crate::repositories::FailedEventRepository;
+use crate::{DbPool, RepoError, RepoResult};
+
+use diesel::prelude::*;
+
+pub struct FailedEventRepositoryImpl {
+    db_pool: DbPool,
+}
+
+impl FailedEventRepositoryImpl {
+    pub fn new(db_pool: DbPool) -> Self {
+        FailedEventRepositoryImpl { db_pool }
+    }
+}
+
+impl FailedEventRepository for FailedEventRepositoryImpl {
+    fn create(&self, new_failed_event: &NewFailedEvent) -> RepoResult<FailedEvent> {
+        use crate::schema::failed_events::dsl::*;
+        let mut conn = crate::connection(&self.db_pool)?;
+
+        diesel::insert_into(failed_events)
+            .values(new_failed_event)
+            .get_result(&mut *conn)
+            .map_err(RepoError::from)
+    }
+
+    fn delete(&self, id_val: i32) -> RepoResult<bool> {
+        use crate::schema::failed_events::dsl::*;
+        let mut conn = crate::connection(&self.db_pool)?;
+
+        let deleted_rows = diesel::delete(failed_events.find(id_val)).execute(&mut *conn)?;
+        Ok(deleted_rows > 0)
+    }
+
+    fn find_by_id(&self, id_val: i32) -> RepoResult<FailedEvent> {
+        use crate::schema::failed_events::dsl::*;
+        let mut conn = crate::connection(&self.db_pool)?;
+
+        failed_events
+            .find(id_val)
+            .get_result(&mut *conn)
+            .map_err(RepoError::from)
+    }
+
+    fn find_by_checkpoint_seq_number(&self, seq_number_val: i64) -> RepoResult<Vec<FailedEvent>> {
+        use crate::schema::failed_events::dsl::*;
+        let mut conn = crate::connection(&self.db_pool)?;
+
+        failed_events
+            .filter(checkpoint_seq_number.eq(seq_number_val))
+            .order(id.asc())
+            .load(&mut *conn)
+            .map_err(RepoError::from)
+    }
+}
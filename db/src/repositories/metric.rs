@@ -1,6 +1,6 @@
 use crate::models::metric::{Metric, NewMetric, UpdateMetric};
 use crate::repositories::MetricRepository;
-use crate::DbPool;
+use crate::{DbPool, RepoError, RepoResult};
 
 use diesel::prelude::*;
 
@@ -15,77 +15,76 @@ impl MetricRepositoryImpl {
 }
 
 impl MetricRepository for MetricRepositoryImpl {
-    fn create(&self, metric: &NewMetric) -> QueryResult<Metric> {
+    fn create(&self, metric: &NewMetric) -> RepoResult<Metric> {
         use crate::schema::metrics::dsl::*;
-        let mut conn = self.db_pool.get().map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::UnableToSendCommand,
-                Box::new(e.to_string()),
-            )
-        })?;
+        let mut conn = crate::connection(&self.db_pool)?;
 
         diesel::insert_into(metrics)
             .values(metric)
-            .get_result(&mut conn)
+            .get_result(&mut *conn)
+            .map_err(RepoError::from)
     }
 
-    fn update(&self, metric_id: i32, metric: &UpdateMetric) -> QueryResult<Metric> {
+    fn update(&self, metric_id: i32, metric: &UpdateMetric) -> RepoResult<Metric> {
         use crate::schema::metrics::dsl::*;
-        let mut conn = self.db_pool.get().map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::UnableToSendCommand,
-                Box::new(e.to_string()),
-            )
-        })?;
+        let mut conn = crate::connection(&self.db_pool)?;
 
         diesel::update(metrics.find(metric_id))
             .set(metric)
-            .get_result(&mut conn)
+            .get_result(&mut *conn)
+            .map_err(RepoError::from)
     }
 
-    fn delete(&self, metric_id: i32) -> QueryResult<bool> {
+    fn delete(&self, metric_id: i32) -> RepoResult<bool> {
         use crate::schema::metrics::dsl::*;
-        let mut conn = self.db_pool.get().map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::UnableToSendCommand,
-                Box::new(e.to_string()),
-            )
-        })?;
-
-        let deleted_rows = diesel::delete(metrics.find(metric_id)).execute(&mut conn)?;
+        let mut conn = crate::connection(&self.db_pool)?;
+
+        let deleted_rows = diesel::delete(metrics.find(metric_id)).execute(&mut *conn)?;
         Ok(deleted_rows > 0)
     }
 
-    fn find_by_id(&self, metric_id: i32) -> QueryResult<Metric> {
+    fn find_by_id(&self, metric_id: i32) -> RepoResult<Metric> {
         use crate::schema::metrics::dsl::*;
-        let mut conn = self.db_pool.get().map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::UnableToSendCommand,
-                Box::new(e.to_string()),
-            )
-        })?;
-
-        metrics.find(metric_id).get_result(&mut conn)
+        let mut conn = crate::connection(&self.db_pool)?;
+
+        metrics
+            .find(metric_id)
+            .get_result(&mut *conn)
+            .map_err(RepoError::from)
     }
 
-    fn find_latest_seq_number(&self) -> QueryResult<Option<Metric>> {
+    fn find_latest_seq_number(&self) -> RepoResult<Option<Metric>> {
         use crate::schema::metrics::dsl::*;
-        let mut conn = self.db_pool.get().map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::UnableToSendCommand,
-                Box::new(e.to_string()),
-            )
-        })?;
+        let mut conn = crate::connection(&self.db_pool)?;
 
         metrics
             .order(latest_seq_number.desc())
-            .first::<Metric>(&mut conn)
+            .first::<Metric>(&mut *conn)
             .optional()
-            .map_err(|e| {
-                diesel::result::Error::DatabaseError(
-                    diesel::result::DatabaseErrorKind::UnableToSendCommand,
-                    Box::new(e.to_string()),
-                )
-            })
+            .map_err(RepoError::from)
+    }
+
+    fn find_metrics_between(&self, start_seq: i32, end_seq: i32) -> RepoResult<Vec<Metric>> {
+        use crate::schema::metrics::dsl::*;
+        let mut conn = crate::connection(&self.db_pool)?;
+
+        metrics
+            .filter(latest_seq_number.between(start_seq, end_seq))
+            .order(latest_seq_number.asc())
+            .load(&mut *conn)
+            .map_err(RepoError::from)
+    }
+
+    fn avg_lagging_between(&self, start_seq: i32, end_seq: i32) -> RepoResult<Option<f32>> {
+        use crate::schema::metrics::dsl::*;
+        let mut conn = crate::connection(&self.db_pool)?;
+
+        // avg of a REAL column is a DOUBLE PRECISION, NULL when no row is in the range
+        let avg_value: Option<f64> = metrics
+            .filter(latest_seq_number.between(start_seq, end_seq))
+            .select(diesel::dsl::avg(avg_lagging))
+            .first(&mut *conn)?;
+
+        Ok(avg_value.map(|v| v as f32))
     }
 }
@@ -1,6 +1,6 @@
 use crate::models::pool::{NewPool, Pool, UpdatePool};
 use crate::repositories::PoolRepository;
-use crate::DbPool;
+use crate::{DbPool, RepoError, RepoResult};
 
 use diesel::prelude::*;
 use diesel::sql_query;
@@ -17,83 +17,244 @@ impl PoolRepositoryImpl {
 }
 
 impl PoolRepository for PoolRepositoryImpl {
-    fn create(&self, pool: &NewPool) -> QueryResult<Pool> {
+    fn create(&self, pool: &NewPool) -> RepoResult<Pool> {
         use crate::schema::pools::dsl::*;
-        let mut conn = self.db_pool.get().map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::UnableToSendCommand,
-                Box::new(e.to_string()),
-            )
-        })?;
+        let mut conn = crate::connection(&self.db_pool)?;
 
         diesel::insert_into(pools)
             .values(pool)
-            .get_result(&mut conn)
+            .get_result(&mut *conn)
+            .map_err(RepoError::from)
     }
 
-    fn update(&self, pool_id: i32, pool: &UpdatePool) -> QueryResult<Pool> {
+    fn update(&self, pool_id: i32, pool: &UpdatePool) -> RepoResult<Pool> {
         use crate::schema::pools::dsl::*;
-        let mut conn = self.db_pool.get().map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::UnableToSendCommand,
-                Box::new(e.to_string()),
-            )
-        })?;
+        let mut conn = crate::connection(&self.db_pool)?;
 
         diesel::update(pools.find(pool_id))
             .set(pool)
-            .get_result(&mut conn)
+            .get_result(&mut *conn)
+            .map_err(RepoError::from)
     }
 
-    fn delete(&self, pool_id: i32) -> QueryResult<bool> {
+    fn delete(&self, pool_id: i32) -> RepoResult<bool> {
         use crate::schema::pools::dsl::*;
-        let mut conn = self.db_pool.get().map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::UnableToSendCommand,
-                Box::new(e.to_string()),
-            )
-        })?;
+        let mut conn = crate::connection(&self.db_pool)?;
 
-        let deleted_rows = diesel::delete(pools.find(pool_id)).execute(&mut conn)?;
+        let deleted_rows = diesel::delete(pools.find(pool_id)).execute(&mut *conn)?;
         Ok(deleted_rows > 0)
     }
 
-    fn find_by_id(&self, pool_id: i32) -> QueryResult<Pool> {
+    fn find_by_id(&self, pool_id: i32) -> RepoResult<Pool> {
         use crate::schema::pools::dsl::*;
-        let mut conn = self.db_pool.get().map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::UnableToSendCommand,
-                Box::new(e.to_string()),
-            )
-        })?;
+        let mut conn = crate::connection(&self.db_pool)?;
 
-        pools.find(pool_id).get_result(&mut conn)
+        pools
+            .find(pool_id)
+            .get_result(&mut *conn)
+            .map_err(RepoError::from)
     }
 
-    fn find_by_address(&self, pool_address: &str) -> QueryResult<Pool> {
+    fn find_by_address(&self, pool_address: &str) -> RepoResult<Pool> {
         use crate::schema::pools::dsl::*;
-        let mut conn = self.db_pool.get().map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::UnableToSendCommand,
-                Box::new(e.to_string()),
-            )
-        })?;
+        let mut conn = crate::connection(&self.db_pool)?;
 
         pools
             .filter(address.eq(pool_address))
             .limit(1)
-            .get_result(&mut conn)
+            .get_result(&mut *conn)
+            .map_err(RepoError::from)
     }
 
-    fn find_all(&self) -> QueryResult<Vec<Pool>> {
+    fn find_by_addresses(&self, pool_addresses: &[&str]) -> RepoResult<Vec<Pool>> {
         use crate::schema::pools::dsl::*;
-        let mut conn = self.db_pool.get().map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::UnableToSendCommand,
-                Box::new(e.to_string()),
+        let mut conn = crate::connection(&self.db_pool)?;
+
+        pools
+            .filter(address.eq_any(pool_addresses))
+            .load(&mut *conn)
+            .map_err(RepoError::from)
+    }
+
+    fn find_by_coins_containing(&self, coin_a: &str, coin_b: &str) -> RepoResult<Vec<Pool>> {
+        use crate::schema::pools::dsl::*;
+        let mut conn = crate::connection(&self.db_pool)?;
+
+        // `coins` is a comma-joined list, wrap it and the needles in commas so
+        // a coin type never matches as a prefix or suffix of another one.
+        // strpos is used instead of LIKE since coin types may contain `_`.
+        pools
+            .filter(
+                diesel::dsl::sql::<Bool>("strpos(',' || coins || ',', ")
+                    .bind::<Text, _>(format!(",{},", coin_a))
+                    .sql(") > 0 AND strpos(',' || coins || ',', ")
+                    .bind::<Text, _>(format!(",{},", coin_b))
+                    .sql(") > 0"),
             )
-        })?;
+            .load(&mut *conn)
+            .map_err(RepoError::from)
+    }
+
+    /// Inserts the pool, or applies `update_pool` if its address already exists,
+    /// in a single statement so that concurrent saves can't race.
+    ///
+    fn upsert(&self, new_pool: &NewPool, update_pool: &UpdatePool) -> RepoResult<Pool> {
+        use crate::schema::pools::dsl::*;
+
+        crate::with_retry(&self.db_pool, |conn| {
+            diesel::insert_into(pools)
+                .values(new_pool)
+                .on_conflict(address)
+                .do_update()
+                .set(update_pool)
+                .get_result(conn)
+        })
+    }
+
+    fn find_all(&self) -> RepoResult<Vec<Pool>> {
+        use crate::schema::pools::dsl::*;
+        let mut conn = crate::connection(&self.db_pool)?;
+
+        pools.load(&mut *conn).map_err(RepoError::from)
+    }
+
+    /// Pools of the exchange ordered by ID,
+    /// so that consecutive pages don't overlap.
+    ///
+    fn find_by_exchange_paged(
+        &self,
+        exchange_str: &str,
+        offset_val: i64,
+        limit_val: i64,
+    ) -> RepoResult<Vec<Pool>> {
+        use crate::schema::pools::dsl::*;
+        let mut conn = crate::connection(&self.db_pool)?;
+
+        pools
+            .filter(exchange.eq(exchange_str))
+            .order(id.asc())
+            .offset(offset_val)
+            .limit(limit_val)
+            .load(&mut *conn)
+            .map_err(RepoError::from)
+    }
+
+    fn count_by_exchange(&self, exchange_str: &str) -> RepoResult<i64> {
+        use crate::schema::pools::dsl::*;
+        let mut conn = crate::connection(&self.db_pool)?;
+
+        pools
+            .filter(exchange.eq(exchange_str))
+            .count()
+            .get_result(&mut *conn)
+            .map_err(RepoError::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_pool(pool_address: &str) -> NewPool {
+        NewPool {
+            exchange: "test_exchange".to_string(),
+            address: pool_address.to_string(),
+            liquidity: Some("1000".to_string()),
+            current_sqrt_price: None,
+            tick_spacing: None,
+            fee_rate: Some(3_000),
+            is_pause: Some(false),
+            coins: "0x2::sui::SUI,0xusdc".to_string(),
+            coin_amounts: None,
+            weights: None,
+            fees_swap_in: None,
+            fees_swap_out: None,
+            current_tick_index: None,
+            pool_type: None,
+        }
+    }
+
+    fn update_pool(liquidity_val: &str) -> UpdatePool {
+        UpdatePool {
+            exchange: None,
+            address: None,
+            liquidity: Some(liquidity_val.to_string()),
+            current_sqrt_price: None,
+            tick_spacing: None,
+            fee_rate: None,
+            is_pause: None,
+            coins: None,
+            coin_amounts: None,
+            weights: None,
+            fees_swap_in: None,
+            fees_swap_out: None,
+            current_tick_index: None,
+            pool_type: None,
+        }
+    }
+
+    #[test]
+    fn concurrent_upserts_of_a_pool_save_one_row() {
+        let Some(db_pool) = crate::test_db_pool() else {
+            return;
+        };
+        let repo = PoolRepositoryImpl::new(db_pool);
+
+        // the saves race to the pool connection, each one must find or insert the same row
+        let results = std::thread::scope(|scope| {
+            let handles = (0..4)
+                .map(|i| {
+                    let repo = &repo;
+                    scope.spawn(move || {
+                        repo.upsert(&new_pool("0xpool"), &update_pool(&i.to_string()))
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .collect::<Vec<_>>()
+        });
+
+        for result in results {
+            assert_eq!(result.unwrap().address, "0xpool");
+        }
+        assert_eq!(repo.count_by_exchange("test_exchange").unwrap(), 1);
+        assert_eq!(repo.find_by_addresses(&["0xpool"]).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn pages_of_an_exchange_do_not_overlap() {
+        let Some(db_pool) = crate::test_db_pool() else {
+            return;
+        };
+        let repo = PoolRepositoryImpl::new(db_pool);
+        let addresses = (0..5).map(|i| format!("0xpool{}", i)).collect::<Vec<_>>();
+        for address in &addresses {
+            repo.create(&new_pool(address)).unwrap();
+        }
+        repo.create(&NewPool {
+            exchange: "other_exchange".to_string(),
+            ..new_pool("0xother")
+        })
+        .unwrap();
+
+        let count = repo.count_by_exchange("test_exchange").unwrap();
+        let mut paged = vec![];
+        for offset in (0..count).step_by(2) {
+            let page = repo
+                .find_by_exchange_paged("test_exchange", offset, 2)
+                .unwrap();
+            assert!(page.len() <= 2);
+            paged.extend(page.into_iter().map(|pool| pool.address));
+        }
 
-        pools.load(&mut conn)
+        assert_eq!(count, 5);
+        assert_eq!(paged, addresses);
+        assert!(repo
+            .find_by_exchange_paged("test_exchange", count, 2)
+            .unwrap()
+            .is_empty());
     }
 }
@@ -1,8 +1,10 @@
 use crate::models::pool_tick::{NewPoolTick, PoolTick, UpdatePoolTick};
 use crate::repositories::PoolTickRepository;
-use crate::DbPool;
+use crate::{DbPool, RepoError, RepoResult};
 
+use diesel::dsl::sql;
 use diesel::prelude::*;
+use diesel::sql_types::{Nullable, Text};
 
 pub struct PoolTickRepositoryImpl {
     db_pool: DbPool,
@@ -15,138 +17,298 @@ impl PoolTickRepositoryImpl {
 }
 
 impl PoolTickRepository for PoolTickRepositoryImpl {
-    fn create(&self, pool_tick: &NewPoolTick) -> QueryResult<PoolTick> {
+    fn create(&self, pool_tick: &NewPoolTick) -> RepoResult<PoolTick> {
         use crate::schema::pool_ticks::dsl::*;
-        let mut conn = self.db_pool.get().map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::UnableToSendCommand,
-                Box::new(e.to_string()),
-            )
-        })?;
+        let mut conn = crate::connection(&self.db_pool)?;
 
         diesel::insert_into(pool_ticks)
             .values(pool_tick)
-            .get_result(&mut conn)
+            .get_result(&mut *conn)
+            .map_err(RepoError::from)
     }
 
-    fn update(&self, pool_tick_id: i32, pool_tick: &UpdatePoolTick) -> QueryResult<PoolTick> {
+    fn update(&self, pool_tick_id: i32, pool_tick: &UpdatePoolTick) -> RepoResult<PoolTick> {
         use crate::schema::pool_ticks::dsl::*;
-        let mut conn = self.db_pool.get().map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::UnableToSendCommand,
-                Box::new(e.to_string()),
-            )
-        })?;
+        let mut conn = crate::connection(&self.db_pool)?;
 
         diesel::update(pool_ticks.find(pool_tick_id))
             .set(pool_tick)
-            .get_result(&mut conn)
+            .get_result(&mut *conn)
+            .map_err(RepoError::from)
     }
 
-    fn delete(&self, pool_tick_id: i32) -> QueryResult<bool> {
+    fn delete(&self, pool_tick_id: i32) -> RepoResult<bool> {
         use crate::schema::pool_ticks::dsl::*;
-        let mut conn = self.db_pool.get().map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::UnableToSendCommand,
-                Box::new(e.to_string()),
-            )
-        })?;
-
-        let deleted_rows = diesel::delete(pool_ticks.find(pool_tick_id)).execute(&mut conn)?;
+        let mut conn = crate::connection(&self.db_pool)?;
+
+        let deleted_rows = diesel::delete(pool_ticks.find(pool_tick_id)).execute(&mut *conn)?;
         Ok(deleted_rows > 0)
     }
 
-    fn find_by_id(&self, pool_tick_id: i32) -> QueryResult<PoolTick> {
+    fn find_by_id(&self, pool_tick_id: i32) -> RepoResult<PoolTick> {
         use crate::schema::pool_ticks::dsl::*;
-        let mut conn = self.db_pool.get().map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::UnableToSendCommand,
-                Box::new(e.to_string()),
-            )
-        })?;
+        let mut conn = crate::connection(&self.db_pool)?;
 
-        pool_ticks.find(pool_tick_id).get_result(&mut conn)
+        pool_ticks
+            .find(pool_tick_id)
+            .get_result(&mut *conn)
+            .map_err(RepoError::from)
     }
 
-    fn find_all(&self) -> QueryResult<Vec<PoolTick>> {
+    fn find_all(&self) -> RepoResult<Vec<PoolTick>> {
         use crate::schema::pool_ticks::dsl::*;
-        let mut conn = self.db_pool.get().map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::UnableToSendCommand,
-                Box::new(e.to_string()),
-            )
-        })?;
+        let mut conn = crate::connection(&self.db_pool)?;
 
-        pool_ticks.load(&mut conn)
+        pool_ticks.load(&mut *conn).map_err(RepoError::from)
     }
 
     fn find_by_address_and_tick_index(
         &self,
         pool_address: &str,
         pool_tick_index: i32,
-    ) -> QueryResult<PoolTick> {
+    ) -> RepoResult<PoolTick> {
         use crate::schema::pool_ticks::dsl::*;
-        let mut conn = self.db_pool.get().map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::UnableToSendCommand,
-                Box::new(e.to_string()),
-            )
-        })?;
+        let mut conn = crate::connection(&self.db_pool)?;
         pool_ticks
             .filter(address.eq(pool_address).and(tick_index.eq(pool_tick_index)))
             .limit(1)
-            .get_result(&mut conn)
+            .get_result(&mut *conn)
+            .map_err(RepoError::from)
     }
 
-    fn find_by_address(&self, pool_address: &str) -> QueryResult<Vec<PoolTick>> {
+    fn find_by_address(&self, pool_address: &str) -> RepoResult<Vec<PoolTick>> {
         use crate::schema::pool_ticks::dsl::*;
-        let mut conn = self.db_pool.get().map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::UnableToSendCommand,
-                Box::new(e.to_string()),
-            )
-        })?;
+        let mut conn = crate::connection(&self.db_pool)?;
 
-        pool_ticks.filter(address.eq(pool_address)).load(&mut conn)
+        pool_ticks
+            .filter(address.eq(pool_address))
+            .load(&mut *conn)
+            .map_err(RepoError::from)
     }
 
     fn find_lower_tick_for_address(
         &self,
         address_str: &str,
         tick_index_val: i32,
-    ) -> QueryResult<Option<PoolTick>> {
+    ) -> RepoResult<Option<PoolTick>> {
         use crate::schema::pool_ticks::dsl::*;
-        let mut conn = self.db_pool.get().map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::UnableToSendCommand,
-                Box::new(e.to_string()),
-            )
-        })?;
+        let mut conn = crate::connection(&self.db_pool)?;
 
         pool_ticks
             .filter(address.eq(address_str).and(tick_index.lt(tick_index_val)))
             .order(tick_index.desc())
-            .first::<PoolTick>(&mut conn)
+            .first::<PoolTick>(&mut *conn)
             .optional()
+            .map_err(RepoError::from)
     }
 
     fn find_higher_tick_for_address(
         &self,
         address_str: &str,
         tick_index_val: i32,
-    ) -> QueryResult<Option<PoolTick>> {
+    ) -> RepoResult<Option<PoolTick>> {
         use crate::schema::pool_ticks::dsl::*;
-        let mut conn = self.db_pool.get().map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::UnableToSendCommand,
-                Box::new(e.to_string()),
-            )
-        })?;
+        let mut conn = crate::connection(&self.db_pool)?;
 
         pool_ticks
             .filter(address.eq(address_str).and(tick_index.gt(tick_index_val)))
             .order(tick_index.asc())
-            .first::<PoolTick>(&mut conn)
+            .first::<PoolTick>(&mut *conn)
             .optional()
+            .map_err(RepoError::from)
+    }
+
+    /// Finds up to `limit` ticks below the given tick index, in descending order.
+    ///
+    fn find_lower_ticks_for_address(
+        &self,
+        address_str: &str,
+        tick_index_val: i32,
+        limit_val: i64,
+    ) -> RepoResult<Vec<PoolTick>> {
+        use crate::schema::pool_ticks::dsl::*;
+        let mut conn = crate::connection(&self.db_pool)?;
+
+        pool_ticks
+            .filter(address.eq(address_str).and(tick_index.lt(tick_index_val)))
+            .order(tick_index.desc())
+            .limit(limit_val)
+            .load::<PoolTick>(&mut *conn)
+            .map_err(RepoError::from)
+    }
+
+    /// Finds up to `limit` ticks above the given tick index, in ascending order.
+    ///
+    fn find_higher_ticks_for_address(
+        &self,
+        address_str: &str,
+        tick_index_val: i32,
+        limit_val: i64,
+    ) -> RepoResult<Vec<PoolTick>> {
+        use crate::schema::pool_ticks::dsl::*;
+        let mut conn = crate::connection(&self.db_pool)?;
+
+        pool_ticks
+            .filter(address.eq(address_str).and(tick_index.gt(tick_index_val)))
+            .order(tick_index.asc())
+            .limit(limit_val)
+            .load::<PoolTick>(&mut *conn)
+            .map_err(RepoError::from)
+    }
+
+    /// Inserts or updates multiple ticks in a single statement.
+    /// Conflicts on (address, tick_index) keep the existing liquidity values
+    /// unless the new row provides them.
+    ///
+    fn upsert_batch(&self, new_pool_ticks: &[NewPoolTick]) -> RepoResult<Vec<PoolTick>> {
+        use crate::schema::pool_ticks::dsl::*;
+
+        if new_pool_ticks.is_empty() {
+            return Ok(vec![]);
+        }
+
+        crate::with_retry(&self.db_pool, |conn| {
+            diesel::insert_into(pool_ticks)
+                .values(new_pool_ticks)
+                .on_conflict((address, tick_index))
+                .do_update()
+                .set((
+                    liquidity_net.eq(sql::<Nullable<Text>>(
+                        "COALESCE(excluded.liquidity_net, pool_ticks.liquidity_net)",
+                    )),
+                    liquidity_gross.eq(sql::<Nullable<Text>>(
+                        "COALESCE(excluded.liquidity_gross, pool_ticks.liquidity_gross)",
+                    )),
+                ))
+                .get_results(conn)
+        })
+    }
+
+    /// The liquidity values of the given ticks are deltas added to the stored ones,
+    /// missing ticks start from zero.
+    /// The net liquidity is stored as the bits of a Move `I128` like every other tick write,
+    /// so its deltas are bits too and are added modulo 2^128.
+    /// The gross liquidity delta is signed decimal text, the sum never goes below zero.
+    ///
+    fn add_liquidity_deltas(&self, new_pool_ticks: &[NewPoolTick]) -> RepoResult<Vec<PoolTick>> {
+        use crate::schema::pool_ticks::dsl::*;
+
+        if new_pool_ticks.is_empty() {
+            return Ok(vec![]);
+        }
+
+        crate::with_retry(&self.db_pool, |conn| {
+            diesel::insert_into(pool_ticks)
+                .values(new_pool_ticks)
+                .on_conflict((address, tick_index))
+                .do_update()
+                .set((
+                    liquidity_net.eq(sql::<Nullable<Text>>(
+                        "MOD(COALESCE(pool_ticks.liquidity_net, '0')::numeric \
+                         + COALESCE(excluded.liquidity_net, '0')::numeric, \
+                         340282366920938463463374607431768211456)::text",
+                    )),
+                    liquidity_gross.eq(sql::<Nullable<Text>>(
+                        "GREATEST(COALESCE(pool_ticks.liquidity_gross, '0')::numeric \
+                         + COALESCE(excluded.liquidity_gross, '0')::numeric, 0)::text",
+                    )),
+                ))
+                .get_results(conn)
+        })
+    }
+
+    fn delete_ticks_outside_range(
+        &self,
+        address_str: &str,
+        lower_tick_index: i32,
+        upper_tick_index: i32,
+    ) -> RepoResult<usize> {
+        use crate::schema::pool_ticks::dsl::*;
+        let mut conn = crate::connection(&self.db_pool)?;
+
+        diesel::delete(
+            pool_ticks.filter(address.eq(address_str)).filter(
+                tick_index
+                    .lt(lower_tick_index)
+                    .or(tick_index.gt(upper_tick_index)),
+            ),
+        )
+        .execute(&mut *conn)
+        .map_err(RepoError::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn delta(tick_index: i32, liquidity_net: i128, liquidity_gross: i128) -> NewPoolTick {
+        NewPoolTick {
+            address: "0xpool".to_string(),
+            tick_index,
+            liquidity_net: Some((liquidity_net as u128).to_string()),
+            liquidity_gross: Some(liquidity_gross.to_string()),
+        }
+    }
+
+    fn liquidity(repo: &PoolTickRepositoryImpl, tick_index: i32) -> (i128, String) {
+        let tick = repo
+            .find_by_address_and_tick_index("0xpool", tick_index)
+            .unwrap();
+        let net = tick.liquidity_net.unwrap().parse::<u128>().unwrap() as i128;
+
+        (net, tick.liquidity_gross.unwrap())
+    }
+
+    #[test]
+    fn add_liquidity_deltas_accumulates_net_liquidity_bits() {
+        let Some(db_pool) = crate::test_db_pool() else {
+            return;
+        };
+        let repo = PoolTickRepositoryImpl::new(db_pool);
+
+        // add 1000 over [-10, 10], add 500 over [0, 10], remove 1000 over [-10, 10]
+        for (lower, upper, liquidity) in [(-10, 10, 1000), (0, 10, 500), (-10, 10, -1000)] {
+            repo.add_liquidity_deltas(&[
+                delta(lower, liquidity, liquidity),
+                delta(upper, -liquidity, liquidity),
+            ])
+            .unwrap();
+        }
+
+        assert_eq!(liquidity(&repo, -10), (0, "0".to_string()));
+        assert_eq!(liquidity(&repo, 0), (500, "500".to_string()));
+        assert_eq!(liquidity(&repo, 10), (-500, "500".to_string()));
+    }
+
+    #[test]
+    fn delete_ticks_outside_range_keeps_the_bounds() {
+        let Some(db_pool) = crate::test_db_pool() else {
+            return;
+        };
+        let repo = PoolTickRepositoryImpl::new(db_pool);
+        let mut deltas = [-20, -10, 0, 10, 20]
+            .into_iter()
+            .map(|tick_index| delta(tick_index, 1, 1))
+            .collect::<Vec<_>>();
+        deltas.push(NewPoolTick {
+            address: "0xother".to_string(),
+            ..delta(100, 1, 1)
+        });
+        repo.add_liquidity_deltas(&deltas).unwrap();
+
+        let deleted = repo.delete_ticks_outside_range("0xpool", -10, 10).unwrap();
+
+        assert_eq!(deleted, 2);
+        let mut kept = repo
+            .find_by_address("0xpool")
+            .unwrap()
+            .into_iter()
+            .map(|tick| tick.tick_index)
+            .collect::<Vec<_>>();
+        kept.sort();
+        assert_eq!(kept, vec![-10, 0, 10]);
+        // the ticks of the other pools are left alone
+        assert_eq!(repo.find_by_address("0xother").unwrap().len(), 1);
     }
 }
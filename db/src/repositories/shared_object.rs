@@ -1,6 +1,6 @@
 use crate::models::shared_object::{NewSharedObject, SharedObject, UpdateSharedObject};
 use crate::repositories::SharedObjectRepository;
-use crate::DbPool;
+use crate::{DbPool, RepoError, RepoResult};
 
 use diesel::prelude::*;
 
@@ -15,86 +15,71 @@ impl SharedObjectRepositoryImpl {
 }
 
 impl SharedObjectRepository for SharedObjectRepositoryImpl {
-    fn create(&self, new_shared_object: &NewSharedObject) -> QueryResult<SharedObject> {
+    fn create(&self, new_shared_object: &NewSharedObject) -> RepoResult<SharedObject> {
         use crate::schema::shared_objects::dsl::*;
-        let mut conn = self.db_pool.get().map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::UnableToSendCommand,
-                Box::new(e.to_string()),
-            )
-        })?;
+        let mut conn = crate::connection(&self.db_pool)?;
 
         diesel::insert_into(shared_objects)
             .values(new_shared_object)
-            .get_result(&mut conn)
+            .get_result(&mut *conn)
+            .map_err(RepoError::from)
     }
 
     fn update(
         &self,
         id_val: i32,
         update_shared_object: &UpdateSharedObject,
-    ) -> QueryResult<SharedObject> {
+    ) -> RepoResult<SharedObject> {
         use crate::schema::shared_objects::dsl::*;
-        let mut conn = self.db_pool.get().map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::UnableToSendCommand,
-                Box::new(e.to_string()),
-            )
-        })?;
+        let mut conn = crate::connection(&self.db_pool)?;
 
         diesel::update(shared_objects.find(id_val))
             .set(update_shared_object)
-            .get_result(&mut conn)
+            .get_result(&mut *conn)
+            .map_err(RepoError::from)
     }
 
-    fn delete(&self, id_val: i32) -> QueryResult<bool> {
+    fn delete(&self, id_val: i32) -> RepoResult<bool> {
         use crate::schema::shared_objects::dsl::*;
-        let mut conn = self.db_pool.get().map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::UnableToSendCommand,
-                Box::new(e.to_string()),
-            )
-        })?;
-
-        let deleted_rows = diesel::delete(shared_objects.find(id_val)).execute(&mut conn)?;
+        let mut conn = crate::connection(&self.db_pool)?;
+
+        let deleted_rows = diesel::delete(shared_objects.find(id_val)).execute(&mut *conn)?;
         Ok(deleted_rows > 0)
     }
 
-    fn find_by_id(&self, id_val: i32) -> QueryResult<SharedObject> {
+    fn find_by_id(&self, id_val: i32) -> RepoResult<SharedObject> {
         use crate::schema::shared_objects::dsl::*;
-        let mut conn = self.db_pool.get().map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::UnableToSendCommand,
-                Box::new(e.to_string()),
-            )
-        })?;
-
-        shared_objects.find(id_val).get_result(&mut conn)
+        let mut conn = crate::connection(&self.db_pool)?;
+
+        shared_objects
+            .find(id_val)
+            .get_result(&mut *conn)
+            .map_err(RepoError::from)
     }
 
-    fn find_by_object_id(&self, object_id_val: &str) -> QueryResult<SharedObject> {
+    fn find_by_object_id(&self, object_id_val: &str) -> RepoResult<SharedObject> {
         use crate::schema::shared_objects::dsl::*;
-        let mut conn = self.db_pool.get().map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::UnableToSendCommand,
-                Box::new(e.to_string()),
-            )
-        })?;
+        let mut conn = crate::connection(&self.db_pool)?;
 
         shared_objects
             .filter(object_id.eq(object_id_val))
-            .first(&mut conn)
+            .first(&mut *conn)
+            .map_err(RepoError::from)
+    }
+
+    fn find_all(&self) -> RepoResult<Vec<SharedObject>> {
+        use crate::schema::shared_objects::dsl::*;
+        let mut conn = crate::connection(&self.db_pool)?;
+
+        shared_objects.load(&mut *conn).map_err(RepoError::from)
     }
 
-    fn find_all(&self) -> QueryResult<Vec<SharedObject>> {
+    fn delete_by_object_id(&self, object_id_val: &str) -> RepoResult<usize> {
         use crate::schema::shared_objects::dsl::*;
-        let mut conn = self.db_pool.get().map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::UnableToSendCommand,
-                Box::new(e.to_string()),
-            )
-        })?;
-
-        shared_objects.load(&mut conn)
+        let mut conn = crate::connection(&self.db_pool)?;
+
+        diesel::delete(shared_objects.filter(object_id.eq(object_id_val)))
+            .execute(&mut *conn)
+            .map_err(RepoError::from)
     }
 }
@@ -0,0 +1,161 @@
+use crate::models::swap_event::{NewSwapEvent, SwapEvent};
+use crate::repositories::SwapEventRepository;
+use crate::{DbPool, RepoError, RepoResult};
+
+use diesel::prelude::*;
+
+pub struct SwapEventRepositoryImpl {
+    db_pool: DbPool,
+}
+
+impl SwapEventRepositoryImpl {
+    pub fn new(db_pool: DbPool) -> Self {
+        SwapEventRepositoryImpl { db_pool }
+    }
+}
+
+impl SwapEventRepository for SwapEventRepositoryImpl {
+    fn create(&self, new_swap_event: &NewSwapEvent) -> RepoResult<SwapEvent> {
+        use crate::schema::swap_events::dsl::*;
+        let mut conn = crate::connection(&self.db_pool)?;
+
+        diesel::insert_into(swap_events)
+            .values(new_swap_event)
+            .get_result(&mut *conn)
+            .map_err(RepoError::from)
+    }
+
+    fn create_batch(&self, new_swap_events: &[NewSwapEvent]) -> RepoResult<usize> {
+        use crate::schema::swap_events::dsl::*;
+        if new_swap_events.is_empty() {
+            return Ok(0);
+        }
+
+        let mut conn = crate::connection(&self.db_pool)?;
+
+        diesel::insert_into(swap_events)
+            .values(new_swap_events)
+            .on_conflict((tx_digest, event_seq))
+            .do_nothing()
+            .execute(&mut *conn)
+            .map_err(RepoError::from)
+    }
+
+    fn delete(&self, id_val: i32) -> RepoResult<bool> {
+        use crate::schema::swap_events::dsl::*;
+        let mut conn = crate::connection(&self.db_pool)?;
+
+        let deleted_rows = diesel::delete(swap_events.find(id_val)).execute(&mut *conn)?;
+        Ok(deleted_rows > 0)
+    }
+
+    fn find_by_id(&self, id_val: i32) -> RepoResult<SwapEvent> {
+        use crate::schema::swap_events::dsl::*;
+        let mut conn = crate::connection(&self.db_pool)?;
+
+        swap_events
+            .find(id_val)
+            .get_result(&mut *conn)
+            .map_err(RepoError::from)
+    }
+
+    fn find_by_pool_id(&self, pool_id_val: &str) -> RepoResult<Vec<SwapEvent>> {
+        use crate::schema::swap_events::dsl::*;
+        let mut conn = crate::connection(&self.db_pool)?;
+
+        swap_events
+            .filter(pool_id.eq(pool_id_val))
+            .order(id.asc())
+            .load(&mut *conn)
+            .map_err(RepoError::from)
+    }
+
+    fn find_by_tx_digest(&self, tx_digest_val: &str) -> RepoResult<Vec<SwapEvent>> {
+        use crate::schema::swap_events::dsl::*;
+        let mut conn = crate::connection(&self.db_pool)?;
+
+        swap_events
+            .filter(tx_digest.eq(tx_digest_val))
+            .order(id.asc())
+            .load(&mut *conn)
+            .map_err(RepoError::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_swap_event(tx_digest: &str, event_seq: i64) -> NewSwapEvent {
+        NewSwapEvent {
+            exchange: "cetus".to_string(),
+            pool_id: format!("0xpool_{}", tx_digest),
+            a_to_b: true,
+            amount_in: "1000".to_string(),
+            amount_out: "990".to_string(),
+            fee_amount: Some("3".to_string()),
+            tx_digest: tx_digest.to_string(),
+            event_seq,
+        }
+    }
+
+    #[test]
+    fn create_batch_records_every_swap_of_a_tx() {
+        let Some(db_pool) = crate::test_db_pool() else {
+            return;
+        };
+        let repo = SwapEventRepositoryImpl::new(db_pool);
+
+        let inserted = repo
+            .create_batch(&[new_swap_event("tx_a", 0), new_swap_event("tx_a", 1)])
+            .unwrap();
+
+        assert_eq!(inserted, 2);
+        let swaps = repo.find_by_tx_digest("tx_a").unwrap();
+        assert_eq!(
+            swaps.iter().map(|s| s.event_seq).collect::<Vec<_>>(),
+            vec![0, 1]
+        );
+        assert_eq!(repo.find_by_pool_id("0xpool_tx_a").unwrap().len(), 2);
+    }
+
+    #[test]
+    fn create_batch_skips_recorded_swaps() {
+        let Some(db_pool) = crate::test_db_pool() else {
+            return;
+        };
+        let repo = SwapEventRepositoryImpl::new(db_pool);
+
+        repo.create_batch(&[new_swap_event("tx_b", 0)]).unwrap();
+        let inserted = repo
+            .create_batch(&[new_swap_event("tx_b", 0), new_swap_event("tx_b", 1)])
+            .unwrap();
+
+        assert_eq!(inserted, 1);
+        assert_eq!(repo.find_by_tx_digest("tx_b").unwrap().len(), 2);
+    }
+
+    #[test]
+    fn create_batch_without_swaps() {
+        // no database needed, nothing is sent
+        let manager = diesel::r2d2::ConnectionManager::<PgConnection>::new("postgres://unused");
+        let db_pool = diesel::r2d2::Pool::builder().build_unchecked(manager);
+        let repo = SwapEventRepositoryImpl::new(db_pool);
+
+        assert_eq!(repo.create_batch(&[]).unwrap(), 0);
+    }
+
+    #[test]
+    fn create_and_delete() {
+        let Some(db_pool) = crate::test_db_pool() else {
+            return;
+        };
+        let repo = SwapEventRepositoryImpl::new(db_pool);
+
+        let swap = repo.create(&new_swap_event("tx_c", 3)).unwrap();
+        assert_eq!(repo.find_by_id(swap.id).unwrap().event_seq, 3);
+
+        assert!(repo.delete(swap.id).unwrap());
+        assert!(matches!(repo.find_by_id(swap.id), Err(RepoError::NotFound)));
+    }
+}
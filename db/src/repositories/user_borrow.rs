@@ -3,7 +3,7 @@ use crate::models::user_borrow::{
     UserBorrowWithCoinInfo,
 };
 use crate::repositories::{UserBorrowRepository, UserDepositRepository};
-use crate::DbPool;
+use crate::{DbPool, RepoError, RepoResult};
 
 use diesel::prelude::*;
 use diesel::sql_query;
@@ -17,97 +17,140 @@ impl UserBorrowRepositoryImpl {
     pub fn new(db_pool: DbPool) -> Self {
         UserBorrowRepositoryImpl { db_pool }
     }
+
+    /// Distinct (platform, borrower, obligation_id) rows after the cursor,
+    /// ordered so that the last row of a batch is the cursor of the next one.
+    /// A missing obligation ID sorts as an empty string.
+    fn find_distinct_platform_and_address_after(
+        &self,
+        cursor: &(String, String, String),
+        batch_size: i64,
+    ) -> RepoResult<Vec<UserBorrowDistinct>> {
+        let mut conn = crate::connection(&self.db_pool)?;
+
+        sql_query(
+            "SELECT DISTINCT platform, borrower, obligation_id
+             FROM user_borrows
+             WHERE (platform, borrower, COALESCE(obligation_id, '')) > ($1, $2, $3)
+             ORDER BY platform, borrower, obligation_id NULLS FIRST
+             LIMIT $4",
+        )
+        .bind::<Text, _>(&cursor.0)
+        .bind::<Text, _>(&cursor.1)
+        .bind::<Text, _>(&cursor.2)
+        .bind::<BigInt, _>(batch_size)
+        .load(&mut *conn)
+        .map_err(RepoError::from)
+    }
+}
+
+/// Batches of distinct borrowers, each batch is loaded when the iterator advances.
+pub struct DistinctUserBorrowBatches<'a> {
+    repo: &'a UserBorrowRepositoryImpl,
+    batch_size: i64,
+    cursor: (String, String, String),
+    done: bool,
+}
+
+impl Iterator for DistinctUserBorrowBatches<'_> {
+    type Item = RepoResult<Vec<UserBorrowDistinct>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let batch = match self
+            .repo
+            .find_distinct_platform_and_address_after(&self.cursor, self.batch_size)
+        {
+            Ok(batch) => batch,
+            Err(e) => {
+                self.done = true;
+                return Some(Err(e));
+            }
+        };
+
+        match batch.last() {
+            Some(last) if batch.len() as i64 == self.batch_size => {
+                self.cursor = (
+                    last.platform.clone(),
+                    last.borrower.clone(),
+                    last.obligation_id.clone().unwrap_or_default(),
+                );
+            }
+            Some(_) => self.done = true,
+            None => return None,
+        }
+
+        Some(Ok(batch))
+    }
 }
 
 impl UserBorrowRepository for UserBorrowRepositoryImpl {
-    fn create(&self, user_borrow: &NewUserBorrow) -> QueryResult<UserBorrow> {
+    fn create(&self, user_borrow: &NewUserBorrow) -> RepoResult<UserBorrow> {
         use crate::schema::user_borrows::dsl::*;
-        let mut conn = self.db_pool.get().map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::UnableToSendCommand,
-                Box::new(e.to_string()),
-            )
-        })?;
+        let mut conn = crate::connection(&self.db_pool)?;
 
         diesel::insert_into(user_borrows)
             .values(user_borrow)
-            .get_result(&mut conn)
+            .get_result(&mut *conn)
+            .map_err(RepoError::from)
     }
 
     fn update(
         &self,
         user_borrow_id: i32,
         user_borrow: &UpdateUserBorrow,
-    ) -> QueryResult<UserBorrow> {
+    ) -> RepoResult<UserBorrow> {
         use crate::schema::user_borrows::dsl::*;
-        let mut conn = self.db_pool.get().map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::UnableToSendCommand,
-                Box::new(e.to_string()),
-            )
-        })?;
+        let mut conn = crate::connection(&self.db_pool)?;
 
         diesel::update(user_borrows.find(user_borrow_id))
             .set(user_borrow)
-            .get_result(&mut conn)
+            .get_result(&mut *conn)
+            .map_err(RepoError::from)
     }
 
-    fn delete(&self, user_borrow_id: i32) -> QueryResult<bool> {
+    fn delete(&self, user_borrow_id: i32) -> RepoResult<bool> {
         use crate::schema::user_borrows::dsl::*;
-        let mut conn = self.db_pool.get().map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::UnableToSendCommand,
-                Box::new(e.to_string()),
-            )
-        })?;
-
-        let deleted_rows = diesel::delete(user_borrows.find(user_borrow_id)).execute(&mut conn)?;
+        let mut conn = crate::connection(&self.db_pool)?;
+
+        let deleted_rows = diesel::delete(user_borrows.find(user_borrow_id)).execute(&mut *conn)?;
         Ok(deleted_rows > 0)
     }
 
-    fn find_by_id(&self, user_borrow_id: i32) -> QueryResult<UserBorrow> {
+    fn find_by_id(&self, user_borrow_id: i32) -> RepoResult<UserBorrow> {
         use crate::schema::user_borrows::dsl::*;
-        let mut conn = self.db_pool.get().map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::UnableToSendCommand,
-                Box::new(e.to_string()),
-            )
-        })?;
-
-        user_borrows.find(user_borrow_id).get_result(&mut conn)
+        let mut conn = crate::connection(&self.db_pool)?;
+
+        user_borrows
+            .find(user_borrow_id)
+            .get_result(&mut *conn)
+            .map_err(RepoError::from)
     }
 
-    fn find_all(&self) -> QueryResult<Vec<UserBorrow>> {
+    fn find_all(&self) -> RepoResult<Vec<UserBorrow>> {
         use crate::schema::user_borrows::dsl::*;
-        let mut conn = self.db_pool.get().map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::UnableToSendCommand,
-                Box::new(e.to_string()),
-            )
-        })?;
-
-        user_borrows.load(&mut conn)
+        let mut conn = crate::connection(&self.db_pool)?;
+
+        user_borrows.load(&mut *conn).map_err(RepoError::from)
     }
 
     fn delete_by_platform_and_address(
         &self,
         platform_name: &str,
         address_str: &str,
-    ) -> QueryResult<bool> {
+    ) -> RepoResult<bool> {
         use crate::schema::user_borrows::dsl::*;
-        let mut conn = self.db_pool.get().map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::UnableToSendCommand,
-                Box::new(e.to_string()),
-            )
-        })?;
+        let mut conn = crate::connection(&self.db_pool)?;
 
         let deleted_rows = diesel::delete(
             user_borrows
                 .filter(platform.eq(platform_name))
                 .filter(borrower.eq(address_str)),
         )
-        .execute(&mut conn)?;
+        .execute(&mut *conn)?;
 
         Ok(deleted_rows > 0)
     }
@@ -116,31 +159,22 @@ impl UserBorrowRepository for UserBorrowRepositoryImpl {
         &self,
         platform_str: &str,
         address_str: &str,
-    ) -> QueryResult<Vec<UserBorrow>> {
+    ) -> RepoResult<Vec<UserBorrow>> {
         use crate::schema::user_borrows::dsl::*;
-        let mut conn = self.db_pool.get().map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::UnableToSendCommand,
-                Box::new(e.to_string()),
-            )
-        })?;
+        let mut conn = crate::connection(&self.db_pool)?;
         user_borrows
             .filter(platform.eq(platform_str))
             .filter(borrower.eq(address_str))
-            .load(&mut conn)
+            .load(&mut *conn)
+            .map_err(RepoError::from)
     }
 
     fn find_by_platform_and_address_with_coin_info(
         &self,
         platform_str: &str,
         borrower_str: &str,
-    ) -> QueryResult<Vec<UserBorrowWithCoinInfo>> {
-        let mut conn = self.db_pool.get().map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::UnableToSendCommand,
-                Box::new(e.to_string()),
-            )
-        })?;
+    ) -> RepoResult<Vec<UserBorrowWithCoinInfo>> {
+        let mut conn = crate::connection(&self.db_pool)?;
 
         sql_query(
             "SELECT ub.platform, ub.borrower, ub.coin_type, ub.amount, 
@@ -154,35 +188,40 @@ impl UserBorrowRepository for UserBorrowRepositoryImpl {
         )
         .bind::<Text, _>(platform_str)
         .bind::<Text, _>(borrower_str)
-        .load(&mut conn)
+        .load(&mut *conn)
+            .map_err(RepoError::from)
     }
 
-    fn find_distinct_platform_and_address(&self) -> QueryResult<Vec<UserBorrowDistinct>> {
+    fn find_distinct_platform_and_address(&self) -> RepoResult<Vec<UserBorrowDistinct>> {
         use crate::schema::user_borrows::dsl::*;
-        let mut conn = self.db_pool.get().map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::UnableToSendCommand,
-                Box::new(e.to_string()),
-            )
-        })?;
+        let mut conn = crate::connection(&self.db_pool)?;
 
         user_borrows
             .select((platform, borrower, obligation_id))
             .distinct()
-            .load(&mut conn)
+            .load(&mut *conn)
+            .map_err(RepoError::from)
+    }
+
+    fn iter_distinct_platform_and_address(
+        &self,
+        batch_size: i64,
+    ) -> Box<dyn Iterator<Item = RepoResult<Vec<UserBorrowDistinct>>> + Send + '_> {
+        // platforms are never empty, so the empty cursor precedes every row
+        Box::new(DistinctUserBorrowBatches {
+            repo: self,
+            batch_size: batch_size.max(1),
+            cursor: (String::new(), String::new(), String::new()),
+            done: false,
+        })
     }
 
     fn find_coins_by_platform_and_address(
         &self,
         platform: &str,
         address: &str,
-    ) -> QueryResult<Vec<UserBorrowCoin>> {
-        let mut conn = self.db_pool.get().map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::UnableToSendCommand,
-                Box::new(e.to_string()),
-            )
-        })?;
+    ) -> RepoResult<Vec<UserBorrowCoin>> {
+        let mut conn = crate::connection(&self.db_pool)?;
         sql_query(
             "SELECT DISTINCT ub.coin_type
              FROM user_borrows ub
@@ -190,7 +229,8 @@ impl UserBorrowRepository for UserBorrowRepositoryImpl {
         )
         .bind::<Text, _>(platform)
         .bind::<Text, _>(address)
-        .load(&mut conn)
+        .load(&mut *conn)
+        .map_err(RepoError::from)
     }
 
     fn delete_by_platform_and_address_and_obligation_id(
@@ -198,14 +238,9 @@ impl UserBorrowRepository for UserBorrowRepositoryImpl {
         platform_str: &str,
         address_str: &str,
         obligation_id_str: &str,
-    ) -> QueryResult<bool> {
+    ) -> RepoResult<bool> {
         use crate::schema::user_borrows::dsl::*;
-        let mut conn = self.db_pool.get().map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::UnableToSendCommand,
-                Box::new(e.to_string()),
-            )
-        })?;
+        let mut conn = crate::connection(&self.db_pool)?;
 
         let deleted_rows = diesel::delete(
             user_borrows
@@ -213,7 +248,7 @@ impl UserBorrowRepository for UserBorrowRepositoryImpl {
                 .filter(borrower.eq(address_str))
                 .filter(obligation_id.eq(obligation_id_str)),
         )
-        .execute(&mut conn)?;
+        .execute(&mut *conn)?;
 
         Ok(deleted_rows > 0)
     }
@@ -223,38 +258,30 @@ impl UserBorrowRepository for UserBorrowRepositoryImpl {
         platform_str: &str,
         address_str: &str,
         coin_type_str: &str,
-    ) -> QueryResult<UserBorrow> {
+    ) -> RepoResult<UserBorrow> {
         use crate::schema::user_borrows::dsl::*;
-        let mut conn = self.db_pool.get().map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::UnableToSendCommand,
-                Box::new(e.to_string()),
-            )
-        })?;
+        let mut conn = crate::connection(&self.db_pool)?;
 
         user_borrows
             .filter(platform.eq(platform_str))
             .filter(borrower.eq(address_str))
             .filter(coin_type.eq(coin_type_str))
-            .first(&mut conn)
+            .first(&mut *conn)
+            .map_err(RepoError::from)
     }
 
     fn find_by_platform_and_obligation_id(
         &self,
         platform_str: &str,
         obligation_id_str: &str,
-    ) -> QueryResult<UserBorrow> {
+    ) -> RepoResult<UserBorrow> {
         use crate::schema::user_borrows::dsl::*;
-        let mut conn = self.db_pool.get().map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::UnableToSendCommand,
-                Box::new(e.to_string()),
-            )
-        })?;
+        let mut conn = crate::connection(&self.db_pool)?;
 
         user_borrows
             .filter(platform.eq(platform_str))
             .filter(obligation_id.eq(obligation_id_str))
-            .first(&mut conn)
+            .first(&mut *conn)
+            .map_err(RepoError::from)
     }
 }
@@ -2,7 +2,7 @@ use crate::models::user_deposit::{
     NewUserDeposit, UpdateUserDeposit, UserDeposit, UserDepositDistinct, UserDepositWithCoinInfo,
 };
 use crate::repositories::UserDepositRepository;
-use crate::DbPool;
+use crate::{DbPool, RepoError, RepoResult};
 
 use diesel::prelude::*;
 use diesel::sql_query;
@@ -19,95 +19,70 @@ impl UserDepositRepositoryImpl {
 }
 
 impl UserDepositRepository for UserDepositRepositoryImpl {
-    fn create(&self, user_deposit: &NewUserDeposit) -> QueryResult<UserDeposit> {
+    fn create(&self, user_deposit: &NewUserDeposit) -> RepoResult<UserDeposit> {
         use crate::schema::user_deposits::dsl::*;
-        let mut conn = self.db_pool.get().map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::UnableToSendCommand,
-                Box::new(e.to_string()),
-            )
-        })?;
+        let mut conn = crate::connection(&self.db_pool)?;
 
         diesel::insert_into(user_deposits)
             .values(user_deposit)
-            .get_result(&mut conn)
+            .get_result(&mut *conn)
+            .map_err(RepoError::from)
     }
 
     fn update(
         &self,
         user_deposit_id: i32,
         user_deposit: &UpdateUserDeposit,
-    ) -> QueryResult<UserDeposit> {
+    ) -> RepoResult<UserDeposit> {
         use crate::schema::user_deposits::dsl::*;
-        let mut conn = self.db_pool.get().map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::UnableToSendCommand,
-                Box::new(e.to_string()),
-            )
-        })?;
+        let mut conn = crate::connection(&self.db_pool)?;
 
         diesel::update(user_deposits.find(user_deposit_id))
             .set(user_deposit)
-            .get_result(&mut conn)
+            .get_result(&mut *conn)
+            .map_err(RepoError::from)
     }
 
-    fn delete(&self, user_deposit_id: i32) -> QueryResult<bool> {
+    fn delete(&self, user_deposit_id: i32) -> RepoResult<bool> {
         use crate::schema::user_deposits::dsl::*;
-        let mut conn = self.db_pool.get().map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::UnableToSendCommand,
-                Box::new(e.to_string()),
-            )
-        })?;
+        let mut conn = crate::connection(&self.db_pool)?;
 
         let deleted_rows =
-            diesel::delete(user_deposits.find(user_deposit_id)).execute(&mut conn)?;
+            diesel::delete(user_deposits.find(user_deposit_id)).execute(&mut *conn)?;
         Ok(deleted_rows > 0)
     }
 
-    fn find_by_id(&self, user_deposit_id: i32) -> QueryResult<UserDeposit> {
+    fn find_by_id(&self, user_deposit_id: i32) -> RepoResult<UserDeposit> {
         use crate::schema::user_deposits::dsl::*;
-        let mut conn = self.db_pool.get().map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::UnableToSendCommand,
-                Box::new(e.to_string()),
-            )
-        })?;
-
-        user_deposits.find(user_deposit_id).get_result(&mut conn)
+        let mut conn = crate::connection(&self.db_pool)?;
+
+        user_deposits
+            .find(user_deposit_id)
+            .get_result(&mut *conn)
+            .map_err(RepoError::from)
     }
 
-    fn find_all(&self) -> QueryResult<Vec<UserDeposit>> {
+    fn find_all(&self) -> RepoResult<Vec<UserDeposit>> {
         use crate::schema::user_deposits::dsl::*;
-        let mut conn = self.db_pool.get().map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::UnableToSendCommand,
-                Box::new(e.to_string()),
-            )
-        })?;
-
-        user_deposits.load(&mut conn)
+        let mut conn = crate::connection(&self.db_pool)?;
+
+        user_deposits.load(&mut *conn).map_err(RepoError::from)
     }
 
     fn delete_by_platform_and_address(
         &self,
         platform_name: &str,
         address: &str,
-    ) -> QueryResult<bool> {
+    ) -> RepoResult<bool> {
         use crate::schema::user_deposits::dsl::*;
-        let mut conn = self.db_pool.get().map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::UnableToSendCommand,
-                Box::new(e.to_string()),
-            )
-        })?;
+        let mut conn = crate::connection(&self.db_pool)?;
 
         let deleted_rows = diesel::delete(
             user_deposits
                 .filter(platform.eq(platform_name))
                 .filter(borrower.eq(address)),
         )
-        .execute(&mut conn)?;
+        .execute(&mut *conn)?;
 
         Ok(deleted_rows > 0)
     }
@@ -116,18 +91,14 @@ impl UserDepositRepository for UserDepositRepositoryImpl {
         &self,
         platform_str: &str,
         borrower_str: &str,
-    ) -> QueryResult<Vec<UserDeposit>> {
+    ) -> RepoResult<Vec<UserDeposit>> {
         use crate::schema::user_deposits::dsl::*;
-        let mut conn = self.db_pool.get().map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::UnableToSendCommand,
-                Box::new(e.to_string()),
-            )
-        })?;
+        let mut conn = crate::connection(&self.db_pool)?;
         user_deposits
             .filter(platform.eq(platform_str))
             .filter(borrower.eq(borrower_str))
-            .load(&mut conn)
+            .load(&mut *conn)
+            .map_err(RepoError::from)
     }
 
     fn find_by_platform_and_address_and_coin_type(
@@ -135,33 +106,24 @@ impl UserDepositRepository for UserDepositRepositoryImpl {
         platform_str: &str,
         address_str: &str,
         coin_type_str: &str,
-    ) -> QueryResult<UserDeposit> {
+    ) -> RepoResult<UserDeposit> {
         use crate::schema::user_deposits::dsl::*;
-        let mut conn = self.db_pool.get().map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::UnableToSendCommand,
-                Box::new(e.to_string()),
-            )
-        })?;
+        let mut conn = crate::connection(&self.db_pool)?;
 
         user_deposits
             .filter(platform.eq(platform_str))
             .filter(borrower.eq(address_str))
             .filter(coin_type.eq(coin_type_str))
-            .first(&mut conn)
+            .first(&mut *conn)
+            .map_err(RepoError::from)
     }
 
     fn find_by_platform_and_address_with_coin_info(
         &self,
         platform_str: &str,
         borrower_str: &str,
-    ) -> QueryResult<Vec<UserDepositWithCoinInfo>> {
-        let mut conn = self.db_pool.get().map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::UnableToSendCommand,
-                Box::new(e.to_string()),
-            )
-        })?;
+    ) -> RepoResult<Vec<UserDepositWithCoinInfo>> {
+        let mut conn = crate::connection(&self.db_pool)?;
 
         sql_query(
             "SELECT ud.platform, ud.borrower, ud.coin_type, ud.amount, 
@@ -175,7 +137,8 @@ impl UserDepositRepository for UserDepositRepositoryImpl {
         )
         .bind::<Text, _>(platform_str)
         .bind::<Text, _>(borrower_str)
-        .load(&mut conn)
+        .load(&mut *conn)
+            .map_err(RepoError::from)
     }
 
     fn delete_by_platform_and_address_and_obligation_id(
@@ -183,14 +146,9 @@ impl UserDepositRepository for UserDepositRepositoryImpl {
         platform_str: &str,
         borrower_str: &str,
         obligation_id_str: &str,
-    ) -> QueryResult<bool> {
+    ) -> RepoResult<bool> {
         use crate::schema::user_deposits::dsl::*;
-        let mut conn = self.db_pool.get().map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::UnableToSendCommand,
-                Box::new(e.to_string()),
-            )
-        })?;
+        let mut conn = crate::connection(&self.db_pool)?;
 
         let deleted_rows = diesel::delete(
             user_deposits
@@ -198,23 +156,19 @@ impl UserDepositRepository for UserDepositRepositoryImpl {
                 .filter(borrower.eq(borrower_str))
                 .filter(obligation_id.eq(obligation_id_str)),
         )
-        .execute(&mut conn)?;
+        .execute(&mut *conn)?;
 
         Ok(deleted_rows > 0)
     }
 
-    fn find_distinct_platform_and_address(&self) -> QueryResult<Vec<UserDepositDistinct>> {
+    fn find_distinct_platform_and_address(&self) -> RepoResult<Vec<UserDepositDistinct>> {
         use crate::schema::user_deposits::dsl::*;
-        let mut conn = self.db_pool.get().map_err(|e| {
-            diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::UnableToSendCommand,
-                Box::new(e.to_string()),
-            )
-        })?;
+        let mut conn = crate::connection(&self.db_pool)?;
 
         user_deposits
             .select((platform, borrower, obligation_id))
             .distinct()
-            .load(&mut conn)
+            .load(&mut *conn)
+            .map_err(RepoError::from)
     }
 }
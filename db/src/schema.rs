@@ -49,6 +49,24 @@ diesel::table! {
         hermes_price -> Nullable<Varchar>,
         hermes_latest_updated_at -> Nullable<Timestamp>,
         vaa -> Nullable<Text>,
+        supra_pair_id -> Nullable<Int4>,
+        supra_latest_updated_at -> Nullable<Timestamp>,
+        #[max_length = 66]
+        switchboard_aggregator_id -> Nullable<Varchar>,
+        switchboard_latest_updated_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    failed_events (id) {
+        id -> Int4,
+        #[max_length = 512]
+        event_type -> Varchar,
+        #[max_length = 64]
+        tx_digest -> Varchar,
+        checkpoint_seq_number -> Int8,
+        error -> Text,
+        created_at -> Nullable<Timestamp>,
     }
 }
 
@@ -123,6 +141,28 @@ diesel::table! {
         initial_shared_version -> Int8,
         created_at -> Nullable<Timestamp>,
         updated_at -> Nullable<Timestamp>,
+        cached_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    swap_events (id) {
+        id -> Int4,
+        #[max_length = 64]
+        exchange -> Varchar,
+        #[max_length = 66]
+        pool_id -> Varchar,
+        a_to_b -> Bool,
+        #[max_length = 64]
+        amount_in -> Varchar,
+        #[max_length = 64]
+        amount_out -> Varchar,
+        #[max_length = 64]
+        fee_amount -> Nullable<Varchar>,
+        #[max_length = 64]
+        tx_digest -> Varchar,
+        created_at -> Nullable<Timestamp>,
+        event_seq -> Int8,
     }
 }
 
@@ -167,10 +207,12 @@ diesel::table! {
 diesel::allow_tables_to_appear_in_same_query!(
     borrowers,
     coins,
+    failed_events,
     metrics,
     pool_ticks,
     pools,
     shared_objects,
+    swap_events,
     user_borrows,
     user_deposits,
 );
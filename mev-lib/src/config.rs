@@ -1,4 +1,4 @@
-use crate::types::FlashloanPool;
+use crate::{constant, types::FlashloanPool};
 
 use anyhow::Result;
 use dotenv::dotenv;
@@ -82,6 +82,8 @@ pub struct MomentumConfig {
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct NaviConfig {
+    #[serde(default = "default_lending_enabled")]
+    pub enabled: bool,
     pub api_endpoint: String,
     pub package_id: String,
     pub storage_id: String,
@@ -93,8 +95,14 @@ pub struct NaviConfig {
     pub oracle_package_id: String,
 }
 
+fn default_lending_enabled() -> bool {
+    true
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct SuilendConfig {
+    #[serde(default = "default_lending_enabled")]
+    pub enabled: bool,
     pub package_id: String,
     pub lending_market_id: String,
     pub lending_market_object_type: String,
@@ -103,6 +111,8 @@ pub struct SuilendConfig {
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ScallopConfig {
+    #[serde(default = "default_lending_enabled")]
+    pub enabled: bool,
     pub api_endpoint: String,
     pub package_id: String,
     pub versioned_id: String,
@@ -114,6 +124,12 @@ pub struct ScallopConfig {
     pub xoracle_pyth_package_id: String,
     pub xoracle_pyth_state_id: String,
     pub xoracle_pyth_registry_id: String,
+    #[serde(default = "default_asset_fetch_concurrency")]
+    pub asset_fetch_concurrency: usize,
+}
+
+fn default_asset_fetch_concurrency() -> usize {
+    10
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -127,11 +143,181 @@ pub struct PythConfig {
     pub price_identifier_type_tag: String,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SupraConfig {
+    pub package_id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SwitchboardConfig {
+    pub package_id: String,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct IndexerConfig {
     pub dev_mode: bool,
+    #[serde(default = "default_dev_mode_checkpoint_count")]
+    pub dev_mode_checkpoint_count: u64,
     pub start_checkpoint_number: u64,
     pub indexer_worker_count: usize,
+    #[serde(default)]
+    pub strict_ordering: bool,
+    #[serde(default)]
+    pub transactional_checkpoints: bool,
+    pub indexer_progress_filepath: Option<String>,
+    #[serde(default = "default_event_concurrency")]
+    pub event_concurrency: usize,
+    pub min_event_concurrency: Option<usize>,
+    pub max_event_concurrency: Option<usize>,
+    #[serde(default = "default_lagging_ms_threshold")]
+    pub lagging_ms_threshold: u64,
+    pub healthz_lagging_ms_threshold: Option<u64>,
+    #[serde(default = "default_rpc_max_retries")]
+    pub rpc_max_retries: u32,
+    pub metrics_listen_address: Option<String>,
+    pub metrics_flush_interval_secs: Option<u64>,
+    #[serde(default)]
+    pub record_failed_events: bool,
+    #[serde(default)]
+    pub use_event_subscription: bool,
+    #[serde(default)]
+    pub dedup_window_checkpoints: u64,
+    #[serde(default)]
+    pub no_dedup_event_types: Vec<String>,
+    #[serde(default = "default_coin_metadata_fetch_concurrency")]
+    pub coin_metadata_fetch_concurrency: usize,
+    pub alert_webhook_url: Option<String>,
+    #[serde(default)]
+    pub remote_store_options: HashMap<String, String>,
+}
+
+fn default_coin_metadata_fetch_concurrency() -> usize {
+    8
+}
+
+/// Concurrency of the event processing before it was tuned.
+///
+fn default_event_concurrency() -> usize {
+    10
+}
+
+fn default_lagging_ms_threshold() -> u64 {
+    60_000
+}
+
+fn default_rpc_max_retries() -> u32 {
+    3
+}
+
+impl IndexerConfig {
+    /// Number of checkpoint workers, a single one when `strict_ordering` is set
+    /// so checkpoints are processed in chain order, at the cost of throughput.
+    ///
+    pub fn worker_count(&self) -> usize {
+        if self.strict_ordering {
+            1
+        } else {
+            self.indexer_worker_count
+        }
+    }
+
+    /// Bounds of the tuned event concurrency, `event_concurrency` when not set,
+    /// so the concurrency stays fixed unless a range is configured.
+    ///
+    pub fn event_concurrency_range(&self) -> (usize, usize) {
+        (
+            self.min_event_concurrency.unwrap_or(self.event_concurrency),
+            self.max_event_concurrency.unwrap_or(self.event_concurrency),
+        )
+    }
+
+    /// Lagging above which `/healthz` reports the indexer unhealthy,
+    /// `healthz_lagging_ms_threshold` if set, otherwise `lagging_ms_threshold`.
+    ///
+    pub fn healthz_threshold_ms(&self) -> u64 {
+        self.healthz_lagging_ms_threshold
+            .unwrap_or(self.lagging_ms_threshold)
+    }
+
+    /// Access options of the remote checkpoint store as expected by the checkpoint reader,
+    /// sorted by key.
+    ///
+    pub fn remote_store_options(&self) -> Vec<(String, String)> {
+        let mut options = self
+            .remote_store_options
+            .iter()
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect::<Vec<_>>();
+        options.sort();
+        options
+    }
+}
+
+fn default_log_format() -> String {
+    "text".to_string()
+}
+
+fn default_rpc_timeout_ms() -> u64 {
+    10_000
+}
+
+fn default_dev_mode_checkpoint_count() -> u64 {
+    1
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LiquidationConfig {
+    #[serde(default = "default_max_price_impact_bps")]
+    pub max_price_impact_bps: u64,
+    #[serde(default = "default_gas_budget_multiplier_bps")]
+    pub gas_budget_multiplier_bps: u64,
+    #[serde(default)]
+    pub min_gas_reserve: u64,
+    #[serde(default = "default_owned_objects_page_size")]
+    pub owned_objects_page_size: usize,
+    pub owned_objects_max_count: Option<usize>,
+}
+
+impl Default for LiquidationConfig {
+    fn default() -> Self {
+        LiquidationConfig {
+            max_price_impact_bps: default_max_price_impact_bps(),
+            gas_budget_multiplier_bps: default_gas_budget_multiplier_bps(),
+            min_gas_reserve: 0,
+            owned_objects_page_size: default_owned_objects_page_size(),
+            owned_objects_max_count: None,
+        }
+    }
+}
+
+fn default_max_price_impact_bps() -> u64 {
+    100
+}
+
+fn default_gas_budget_multiplier_bps() -> u64 {
+    15_000
+}
+
+fn default_owned_objects_page_size() -> usize {
+    50
+}
+
+/// Static metadata of a common coin, e.g. a stablecoin,
+/// used instead of fetching it from RPC.
+///
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct KnownCoin {
+    pub coin_type: String,
+    pub decimals: u8,
+    pub name: Option<String>,
+    pub symbol: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PoolTickPruningConfig {
+    pub enabled: bool,
+    pub window: i32,
+    pub interval_secs: u64,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -139,27 +325,68 @@ pub struct DatabaseConfig {
     pub database_url: String,
     pub db_connection_pool_max_size: usize,
     pub db_connection_pool_idle_size: usize,
+    #[serde(default = "default_db_connection_max_retries")]
+    pub db_connection_max_retries: u32,
+    #[serde(default = "default_db_connection_retry_delay_ms")]
+    pub db_connection_retry_delay_ms: u64,
+}
+
+fn default_db_connection_max_retries() -> u32 {
+    5
+}
+
+fn default_db_connection_retry_delay_ms() -> u64 {
+    1_000
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct NetworkConfig {
     pub rpc_url: String,
+    #[serde(default)]
+    pub rpc_urls: Vec<String>,
     pub remote_store_url: String,
+    pub ws_url: Option<String>,
+}
+
+impl NetworkConfig {
+    /// RPC URLs in failover order,
+    /// `rpc_url` first followed by the fallback `rpc_urls`.
+    ///
+    pub fn all_rpc_urls(&self) -> Vec<String> {
+        let mut urls = vec![self.rpc_url.clone()];
+        for url in &self.rpc_urls {
+            if !urls.contains(url) {
+                urls.push(url.clone());
+            }
+        }
+        urls
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Config {
     // global
     pub log_level: String,
+    #[serde(default = "default_log_format")]
+    pub log_format: String,
     pub run_mode: String,
 
     pub arbitrage_enabled: bool,
     pub liquidation_enabled: bool,
     pub onchain_indexer_enabled: bool,
+    #[serde(default)]
+    pub shared_object_cache_ttl_secs: u64,
+    #[serde(default = "default_rpc_timeout_ms")]
+    pub rpc_timeout_ms: u64,
+    pub rpc_max_concurrent: Option<usize>,
+    pub rpc_qps: Option<u32>,
 
     pub database: DatabaseConfig,
     pub networks: HashMap<String, NetworkConfig>,
     pub indexer: IndexerConfig,
+    #[serde(default)]
+    pub liquidation: LiquidationConfig,
+    pub pool_tick_pruning: Option<PoolTickPruningConfig>,
 
     // dexes
     pub cetus: CetusConfig,
@@ -178,6 +405,11 @@ pub struct Config {
 
     // oracles
     pub pyth: PythConfig,
+    pub supra: Option<SupraConfig>,
+    pub switchboard: Option<SwitchboardConfig>,
+
+    #[serde(default)]
+    pub known_coins: Vec<KnownCoin>,
 }
 
 impl Config {
@@ -186,4 +418,283 @@ impl Config {
         let config: Config = toml::from_str(&config_str)?;
         Ok(config)
     }
+
+    /// Human readable summary of the enabled subsystems and their targets.
+    /// Disabled subsystems are left out.
+    ///
+    pub fn summary(&self) -> String {
+        let mut lines = vec![format!("run mode: {}", self.run_mode)];
+
+        if let Some(network) = self.networks.get(&self.run_mode) {
+            lines.push(format!("rpc: {}", network.all_rpc_urls().join(", ")));
+            if self.onchain_indexer_enabled {
+                if self.indexer.use_event_subscription {
+                    lines.push(format!(
+                        "event source: {}",
+                        network.ws_url.as_deref().unwrap_or("none")
+                    ));
+                } else {
+                    lines.push(format!("checkpoint source: {}", network.remote_store_url));
+                }
+            }
+        }
+
+        if self.onchain_indexer_enabled {
+            lines.push(format!(
+                "onchain indexer: enabled, dev mode {}, workers {}, strict ordering {}, transactional checkpoints {}",
+                self.indexer.dev_mode,
+                self.indexer.worker_count(),
+                self.indexer.strict_ordering,
+                self.indexer.transactional_checkpoints
+            ));
+        }
+
+        if self.arbitrage_enabled {
+            lines.push(format!(
+                "arbitrage: enabled, exchanges [{}]",
+                [
+                    constant::CETUS_EXCHANGE,
+                    constant::BLUEFIN_EXCHANGE,
+                    constant::TURBOS_EXCHANGE,
+                    constant::MOMENTUM_EXCHANGE,
+                    constant::AFTERMATH_EXCHANGE,
+                    constant::FLOWX_EXCHANGE,
+                    constant::BLUEMOVE_EXCHANGE,
+                    constant::OBRIC_EXCHANGE,
+                    constant::KRIYA_EXCHANGE,
+                ]
+                .join(", ")
+            ));
+        }
+
+        if self.liquidation_enabled {
+            lines.push(format!(
+                "liquidation: enabled, platforms [{}]",
+                [
+                    (constant::NAVI_LENDING, self.navi.enabled),
+                    (constant::SCALLOP_LENDING, self.scallop.enabled),
+                    (constant::SUILEND_LENDING, self.suilend.enabled),
+                ]
+                .into_iter()
+                .filter(|(_, enabled)| *enabled)
+                .map(|(platform, _)| platform)
+                .collect::<Vec<_>>()
+                .join(", ")
+            ));
+        }
+
+        // Supra and Switchboard prices are indexed only when configured
+        lines.push(format!(
+            "oracles: [{}]",
+            [
+                (constant::PYTH_ORACLE, true),
+                (constant::SUPRA_ORACLE, self.supra.is_some()),
+                (constant::SWITCHBOARD_ORACLE, self.switchboard.is_some()),
+            ]
+            .into_iter()
+            .filter(|(_, enabled)| *enabled)
+            .map(|(oracle, _)| oracle)
+            .collect::<Vec<_>>()
+            .join(", ")
+        ));
+
+        lines.join("\n")
+    }
+}
+
+/// Config of `config.toml.example`, its placeholders replaced with `false` and `1`,
+/// for the tests needing a whole config.
+///
+#[cfg(test)]
+pub(crate) fn test_config() -> Config {
+    parse_example_config(include_str!("../../config.toml.example"))
+}
+
+#[cfg(test)]
+fn parse_example_config(example: &str) -> Config {
+    let example = regex::Regex::new(r"= BOOL\b")
+        .unwrap()
+        .replace_all(example, "= false");
+    let example = regex::Regex::new(r"= [A-Z_]+_NUMBER\b")
+        .unwrap()
+        .replace_all(&example, "= 1");
+
+    toml::from_str(&example).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn minimal_indexer_config() -> IndexerConfig {
+        toml::from_str(
+            r#"
+            dev_mode = false
+            start_checkpoint_number = 0
+            indexer_worker_count = 4
+            "#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn event_concurrency_defaults_keep_the_tuning_off() {
+        let config = minimal_indexer_config();
+
+        assert_eq!(config.event_concurrency, 10);
+        assert_eq!(config.event_concurrency_range(), (10, 10));
+        assert_eq!(config.lagging_ms_threshold, 60_000);
+    }
+
+    #[test]
+    fn rpc_max_retries_default() {
+        assert_eq!(minimal_indexer_config().rpc_max_retries, 3);
+    }
+
+    #[test]
+    fn event_concurrency_range_defaults_to_the_concurrency() {
+        let mut config = minimal_indexer_config();
+        config.event_concurrency = 20;
+        config.max_event_concurrency = Some(40);
+
+        assert_eq!(config.event_concurrency_range(), (20, 40));
+    }
+
+    #[test]
+    fn shared_object_cache_disabled_by_default() {
+        let example = include_str!("../../config.toml.example")
+            .lines()
+            .filter(|line| !line.starts_with("shared_object_cache_ttl_secs"))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        assert_eq!(test_config().shared_object_cache_ttl_secs, 1);
+        assert_eq!(
+            parse_example_config(&example).shared_object_cache_ttl_secs,
+            0
+        );
+    }
+
+    #[test]
+    fn liquidation_section_defaults() {
+        // the whole section is optional
+        let mut in_section = false;
+        let example = include_str!("../../config.toml.example")
+            .lines()
+            .filter(|line| {
+                if line.starts_with('[') {
+                    in_section = *line == "[liquidation]";
+                }
+                !in_section
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        let config = parse_example_config(&example);
+
+        assert_eq!(config.liquidation.max_price_impact_bps, 100);
+        assert_eq!(config.liquidation.gas_budget_multiplier_bps, 15_000);
+        assert_eq!(config.liquidation.owned_objects_page_size, 50);
+
+        // as well as the price impact guard within it
+        let example = include_str!("../../config.toml.example")
+            .replace("max_price_impact_bps = MAX_PRICE_IMPACT_BPS_NUMBER", "");
+        assert_eq!(
+            parse_example_config(&example)
+                .liquidation
+                .max_price_impact_bps,
+            100
+        );
+    }
+
+    /// Example config without the given section.
+    fn example_without_section(section: &str) -> Config {
+        let mut in_section = false;
+        let example = include_str!("../../config.toml.example")
+            .lines()
+            .filter(|line| {
+                if line.starts_with('[') {
+                    in_section = *line == section;
+                }
+                !in_section
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        parse_example_config(&example)
+    }
+
+    #[test]
+    fn supra_section_is_optional() {
+        assert!(test_config().supra.is_some());
+        assert!(example_without_section("[supra]").supra.is_none());
+    }
+
+    #[test]
+    fn switchboard_section_is_optional() {
+        assert!(test_config().switchboard.is_some());
+        assert!(example_without_section("[switchboard]")
+            .switchboard
+            .is_none());
+    }
+
+    fn mainnet_config() -> Config {
+        let mut config = test_config();
+        config.run_mode = "mainnet".to_string();
+        config
+    }
+
+    #[test]
+    fn summary_includes_the_enabled_subsystems() {
+        let mut config = mainnet_config();
+        config.onchain_indexer_enabled = true;
+        config.liquidation_enabled = true;
+        config.navi.enabled = true;
+        config.suilend.enabled = true;
+
+        let summary = config.summary();
+
+        assert!(summary.contains("run mode: mainnet"));
+        assert!(summary.contains("rpc: https://fullnode.mainnet.sui.io:443"));
+        assert!(summary.contains("checkpoint source: https://checkpoints.mainnet.sui.io"));
+        assert!(summary.contains("onchain indexer: enabled"));
+        assert!(summary.contains(&format!(
+            "liquidation: enabled, platforms [{}, {}]",
+            constant::NAVI_LENDING,
+            constant::SUILEND_LENDING
+        )));
+        assert!(!summary.contains(constant::SCALLOP_LENDING));
+        assert!(!summary.contains("arbitrage"));
+    }
+
+    #[test]
+    fn summary_omits_the_disabled_subsystems() {
+        let mut config = mainnet_config();
+        config.arbitrage_enabled = true;
+
+        let summary = config.summary();
+
+        assert!(summary.contains("arbitrage: enabled, exchanges ["));
+        assert!(summary.contains(constant::KRIYA_EXCHANGE));
+        assert!(!summary.contains("onchain indexer"));
+        assert!(!summary.contains("checkpoint source"));
+        assert!(!summary.contains("liquidation"));
+    }
+
+    #[test]
+    fn summary_lists_only_the_configured_oracles() {
+        let summary = mainnet_config().summary();
+        assert!(summary.contains(&format!(
+            "oracles: [{}, {}, {}]",
+            constant::PYTH_ORACLE,
+            constant::SUPRA_ORACLE,
+            constant::SWITCHBOARD_ORACLE
+        )));
+
+        let summary = example_without_section("[supra]").summary();
+        assert!(summary.contains(&format!(
+            "oracles: [{}, {}]",
+            constant::PYTH_ORACLE,
+            constant::SWITCHBOARD_ORACLE
+        )));
+    }
 }
@@ -40,12 +40,43 @@ pub const FLOWX_MODIFY_LIQUIDITY_EVENT: &str =
 pub const BLUEMOVE_SWAP_EVENT: &str =
     "0xb24b6789e088b876afabca733bed2299fbc9e2d6369be4d1acfa17d8145454d9::swap::Swap_Event";
 
+pub const BLUEMOVE_ADD_LIQUIDITY_EVENT: &str =
+    "0xb24b6789e088b876afabca733bed2299fbc9e2d6369be4d1acfa17d8145454d9::swap::Add_Liquidity_Pool";
+
+pub const BLUEMOVE_REMOVE_LIQUIDITY_EVENT: &str =
+    "0xb24b6789e088b876afabca733bed2299fbc9e2d6369be4d1acfa17d8145454d9::swap::Remove_Liquidity_Pool";
+
 pub const AFTERMATH_SWAP_EVENT: &str =
     "0xc4049b2d1cc0f6e017fda8260e4377cecd236bd7f56a54fee120816e72e2e0dd::events::SwapEventV2";
 
+pub const AFTERMATH_ADD_LIQUIDITY_EVENT: &str =
+    "0xc4049b2d1cc0f6e017fda8260e4377cecd236bd7f56a54fee120816e72e2e0dd::events::DepositEventV2";
+
+pub const AFTERMATH_REMOVE_LIQUIDITY_EVENT: &str =
+    "0xc4049b2d1cc0f6e017fda8260e4377cecd236bd7f56a54fee120816e72e2e0dd::events::WithdrawEventV2";
+
 pub const OBRIC_SWAP_EVENT: &str =
     "0x200e762fa2c49f3dc150813038fbf22fd4f894ac6f23ebe1085c62f2ef97f1ca::obric::ObricSwapEvent";
 
+pub const KRIYA_SWAP_EVENT: &str =
+    "0xa0eba10b173538c8fecca1dff298e488402cc9ff374f8a12ca7758eebe830b66::spot_dex::SwapEvent";
+
+pub const KRIYA_ADD_LIQUIDITY_EVENT: &str =
+    "0xa0eba10b173538c8fecca1dff298e488402cc9ff374f8a12ca7758eebe830b66::spot_dex::LiquidityAddedEvent";
+
+pub const KRIYA_REMOVE_LIQUIDITY_EVENT: &str =
+    "0xa0eba10b173538c8fecca1dff298e488402cc9ff374f8a12ca7758eebe830b66::spot_dex::LiquidityRemovedEvent";
+
+// pool fee rates are stored in millionths
+pub const FEE_RATE_DENOMINATOR: u64 = 1_000_000;
+
+// FEE_SCALING of the Kriya spot_dex module, pool fee percents are divided by it
+pub const KRIYA_FEE_SCALING: u64 = 1_000_000;
+// ticks fetched per query when simulating a CLMM swap
+pub const SWAP_SIMULATION_TICK_BATCH: usize = 32;
+// pools loaded per page when refreshing all pools of an exchange
+pub const POOL_REFRESH_PAGE_SIZE: i64 = 100;
+
 // navi events
 pub const NAVI_BORROW_EVENT: &str =
     "0xd899cf7d2b5db716bd2cf55599fb0d5ee38a3061e7b6bb6eebf73fa5bc4c81ca::lending::BorrowEvent";
@@ -83,6 +114,10 @@ pub const SCALLOP_LIQUIDATE_EVENT_V2: &str =
     "0x6e641f0dca8aedab3101d047e96439178f16301bf0b57fe8745086ff1195eb3e::liquidate::LiquidateEventV2";
 
 // oracles
+// module::name of the Supra price update event, the package ID comes from config
+pub const SUPRA_UPDATE_PRICE_EVENT: &str = "SupraSValueFeed::SCCProcessedEvent";
+// module::name of the Switchboard aggregator update event, the package ID comes from config
+pub const SWITCHBOARD_UPDATE_PRICE_EVENT: &str = "aggregator_submit_result_action::AggregatorUpdated";
 pub const PYTH_UPDATE_PRICE_EVENT: &str = "0x8d97f1cd6ac663735be08d1d2b6d02a159e711586461306ce60a2b7a6a565a9e::event::PriceFeedUpdateEvent";
 
 // coin types
@@ -104,6 +139,7 @@ pub const MOMENTUM_EXCHANGE: &str = "momentum";
 pub const FLOWX_EXCHANGE: &str = "flowx";
 pub const BLUEMOVE_EXCHANGE: &str = "bluemove";
 pub const OBRIC_EXCHANGE: &str = "obric";
+pub const KRIYA_EXCHANGE: &str = "kriya";
 
 // lending names
 pub const NAVI_LENDING: &str = "navi";
@@ -112,6 +148,8 @@ pub const SUILEND_LENDING: &str = "suilend";
 
 // oracles names
 pub const PYTH_ORACLE: &str = "pyth";
+pub const SUPRA_ORACLE: &str = "supra";
+pub const SWITCHBOARD_ORACLE: &str = "switchboard";
 
 // pyth
 pub const PYTH_PRICE_UPDATE_MESSAGE_TYPE: &str = "price_update";
@@ -124,3 +162,16 @@ pub const PROCESSING_STATUS: i32 = 1;
 pub const SUCCEED_STATUS: i32 = 2;
 pub const FAILED_STATUS: i32 = -1;
 pub const ABNORMAL_STATUS: i32 = -2;
+
+// indexer
+pub const EVENT_CONCURRENCY_TUNING_INTERVAL: u64 = 10;
+pub const EVENT_CONCURRENCY_STEP: u64 = 2;
+pub const RPC_RETRY_BASE_DELAY_MS: u64 = 200;
+pub const EVENT_SUBSCRIPTION_BASE_DELAY_MS: u64 = 500;
+pub const EVENT_SUBSCRIPTION_MAX_DELAY_MS: u64 = 30_000;
+pub const DEX_POOLS_FETCH_CONCURRENCY: usize = 8;
+// lagging alerts are repeated after this interval, doubled on each alert up to the max factor
+pub const LAGGING_ALERT_BASE_INTERVAL_MS: u64 = 60_000;
+pub const LAGGING_ALERT_MAX_BACKOFF_FACTOR: u64 = 6;
+// sender -> obligation ID lookups are reused by the events of the same checkpoint
+pub const OBLIGATION_ID_CACHE_TTL_MS: u64 = 2_000;
@@ -16,6 +16,7 @@ use crate::{
 use db::models::{
     coin::{Coin, NewCoin, UpdateCoin},
     pool::{self, NewPool, Pool, UpdatePool},
+    swap_event::NewSwapEvent,
 };
 use db::repositories::{
     CoinRepository, PoolRepository, UserBorrowRepository, UserDepositRepository,
@@ -46,6 +47,37 @@ pub enum OnchainEvent {
     VoidEvent, // this is used to indicate that the event should not be processed
 }
 
+impl OnchainEvent {
+    /// Returns the exchange, lending platform or oracle which emitted the event.
+    /// Returns `None` for void events.
+    ///
+    pub fn source(&self) -> Option<&str> {
+        match self {
+            OnchainEvent::DEXSwap(e) => Some(&e.exchange),
+            OnchainEvent::DEXLiquidity(e) => Some(&e.exchange),
+            OnchainEvent::LendingDeposit(e) => Some(&e.platform),
+            OnchainEvent::LendingWithdraw(e) => Some(&e.platform),
+            OnchainEvent::LendingBorrow(e) => Some(&e.platform),
+            OnchainEvent::LendingRepay(e) => Some(&e.platform),
+            OnchainEvent::LendingLiquidate(e) => Some(&e.platform),
+            OnchainEvent::LendingIndexUpdated(e) => Some(&e.platform),
+            OnchainEvent::OraclePrice(e) => Some(&e.oracle),
+            OnchainEvent::VoidEvent => None,
+        }
+    }
+
+    /// Returns the pool changed by a DEX event.
+    /// Returns `None` for any other event.
+    ///
+    pub fn pool_id(&self) -> Option<&str> {
+        match self {
+            OnchainEvent::DEXSwap(e) => Some(&e.pool_id),
+            OnchainEvent::DEXLiquidity(e) => Some(&e.pool_id),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DEXSwapEvent {
     pub exchange: String,
@@ -104,4 +136,51 @@ pub trait EventProcessor: Display {
     /// By identifying the event, we can select to process only the latest event,
     /// ignoring all the previous events occured on the same entity (pool, obligation, price feed)
     fn get_event_id(&self, event_type: &str, event: &Event) -> Result<String>;
+
+    /// Whether an event of this type can be skipped when the same event ID
+    /// was processed within the last `indexer.dedup_window_checkpoints` checkpoints.
+    /// Only for events which refresh a state read on-chain, e.g. pool swaps;
+    /// lending events are never skipped since every update matters.
+    fn is_dedupable(&self, _event_type: &str) -> bool {
+        false
+    }
+
+    /// Decodes the swap of a DEX swap event in checkpoint data for volume analytics.
+    /// Called for every event before the deduplication, so no swap is missed.
+    /// Returns `None` for the other event types.
+    fn decode_swap_event(
+        &self,
+        _event_type: &str,
+        _event: &Event,
+        _tx_digest: &str,
+        _event_seq: u64,
+    ) -> Result<Option<NewSwapEvent>> {
+        Ok(None)
+    }
+
+    /// Same as `decode_swap_event` for an event in transaction data.
+    fn decode_tx_swap_event(
+        &self,
+        _event_type: &str,
+        _data: &Value,
+        _tx_digest: &str,
+        _event_seq: u64,
+    ) -> Result<Option<NewSwapEvent>> {
+        Ok(None)
+    }
+}
+
+/// Event of the type with BCS `contents`, as found in checkpoint data.
+///
+#[cfg(test)]
+pub(crate) fn test_event(event_type: &str, contents: Vec<u8>) -> Event {
+    let type_ = sui_types::parse_sui_struct_tag(event_type).unwrap();
+
+    Event {
+        package_id: sui_types::base_types::ObjectID::from(type_.address),
+        transaction_module: type_.module.clone(),
+        sender: sui_types::base_types::SuiAddress::ZERO,
+        type_,
+        contents,
+    }
 }
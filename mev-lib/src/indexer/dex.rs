@@ -3,6 +3,7 @@ pub mod bluefin;
 pub mod bluemove;
 pub mod cetus;
 pub mod flowx;
+pub mod kriya;
 pub mod momentum;
 pub mod obric;
 pub mod turbos;
@@ -3,7 +3,7 @@ use crate::{
     indexer::{self, EventProcessor, OnchainEvent},
     service::db_service::pool::PoolService,
     service::dex::DEXService,
-    utils,
+    utils::{self, rpc_pool::RpcPool},
 };
 use db::models::{
     coin::{Coin, NewCoin, UpdateCoin},
@@ -18,7 +18,6 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::fmt::{Debug, Display};
 use std::sync::Arc;
-use sui_sdk::SuiClient;
 use sui_types::{
     base_types::{ObjectID, SuiAddress},
     event::Event,
@@ -37,9 +36,32 @@ struct SwapEventV2 {
     amounts_out: Vec<u64>,
     reserves: Vec<u64>,
 }
+
+#[derive(Debug, Deserialize, Serialize)]
+struct DepositEventV2 {
+    pool_id: ObjectID,
+    issuer: SuiAddress,
+    referrer: Option<SuiAddress>,
+    types: Vec<String>,
+    deposits: Vec<u64>,
+    lp_coins_minted: u64,
+    reserves: Vec<u64>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct WithdrawEventV2 {
+    pool_id: ObjectID,
+    issuer: SuiAddress,
+    referrer: Option<SuiAddress>,
+    types: Vec<String>,
+    withdrawn: Vec<u64>,
+    lp_coins_burned: u64,
+    reserves: Vec<u64>,
+}
+
 pub struct Aftermath {
     exchange: String,
-    sui_client: Arc<SuiClient>,
+    rpc_pool: Arc<RpcPool>,
     pool_repo: Arc<dyn PoolRepository + Send + Sync>,
     coin_repo: Arc<dyn CoinRepository + Send + Sync>,
     pool_service: Arc<PoolService>,
@@ -48,7 +70,7 @@ pub struct Aftermath {
 
 impl Aftermath {
     pub fn new(
-        sui_client: Arc<SuiClient>,
+        rpc_pool: Arc<RpcPool>,
         pool_repo: Arc<dyn PoolRepository + Send + Sync>,
         coin_repo: Arc<dyn CoinRepository + Send + Sync>,
         pool_service: Arc<PoolService>,
@@ -56,7 +78,7 @@ impl Aftermath {
     ) -> Self {
         Aftermath {
             exchange: constant::AFTERMATH_EXCHANGE.to_string(),
-            sui_client,
+            rpc_pool,
             pool_repo,
             coin_repo,
             pool_service,
@@ -84,7 +106,9 @@ impl EventProcessor for Aftermath {
         tx_digest: &str,
     ) -> Result<()> {
         match event_type {
-            constant::AFTERMATH_SWAP_EVENT => {
+            constant::AFTERMATH_SWAP_EVENT
+            | constant::AFTERMATH_ADD_LIQUIDITY_EVENT
+            | constant::AFTERMATH_REMOVE_LIQUIDITY_EVENT => {
                 let pool_id = data
                     .get("pool_id")
                     .ok_or_else(|| anyhow!("Missing pool field in event data"))?
@@ -120,6 +144,18 @@ impl EventProcessor for Aftermath {
                     pool_id: pool_id.clone(),
                 }))
             }
+            constant::AFTERMATH_ADD_LIQUIDITY_EVENT
+            | constant::AFTERMATH_REMOVE_LIQUIDITY_EVENT => {
+                info!("Processing Onchain liquidity event: {:?}", event);
+                let pool_id = self.extract_pool_id_from_event(&event)?;
+
+                self.process_pool(&pool_id).await?;
+
+                Ok(OnchainEvent::DEXLiquidity(indexer::DEXLiquidityEvent {
+                    exchange: self.exchange.clone(),
+                    pool_id,
+                }))
+            }
             _ => Err(anyhow!("Unknown event type: {}", event_type)),
         }
     }
@@ -132,6 +168,10 @@ impl EventProcessor for Aftermath {
 
         Ok(format!("{}_{}_{}", &self.exchange, event_type, &pool_id))
     }
+
+    fn is_dedupable(&self, event_type: &str) -> bool {
+        event_type == constant::AFTERMATH_SWAP_EVENT
+    }
 }
 
 impl Aftermath {
@@ -152,6 +192,16 @@ impl Aftermath {
                 info!("Swap event data: {:?}", data);
                 data.pool_id.to_string()
             }
+            constant::AFTERMATH_ADD_LIQUIDITY_EVENT => {
+                let data = bcs::from_bytes::<DepositEventV2>(&event.contents)?;
+                info!("Deposit event data: {:?}", data);
+                data.pool_id.to_string()
+            }
+            constant::AFTERMATH_REMOVE_LIQUIDITY_EVENT => {
+                let data = bcs::from_bytes::<WithdrawEventV2>(&event.contents)?;
+                info!("Withdraw event data: {:?}", data);
+                data.pool_id.to_string()
+            }
             _ => {
                 return Err(anyhow!("Unknown event type: {}", event_type));
             }
@@ -160,3 +210,116 @@ impl Aftermath {
         Ok(pool_id)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::db_service::mock::{MockDEXService, MockDb};
+
+    const POOL_ID: &str = "0x97aae7a80abb29c9feabbe7075028550230401ffe7fb745757d3c28a30437408";
+    const SUI: &str = "0000000000000000000000000000000000000000000000000000000000000002::sui::SUI";
+    const USDC: &str =
+        "dba34672e30cb065b1f93e3ab55318768fd6fef66c15942c9f7cb846e2f900e7::usdc::USDC";
+
+    fn aftermath(db: &Arc<MockDb>) -> Aftermath {
+        Aftermath::new(
+            Arc::new(RpcPool::without_clients()),
+            Arc::clone(db) as Arc<dyn PoolRepository + Send + Sync>,
+            Arc::clone(db) as Arc<dyn CoinRepository + Send + Sync>,
+            Arc::new(db.pool_service(crate::config::test_config())),
+            Arc::new(MockDEXService::default()),
+        )
+    }
+
+    /// BCS of a deposit or withdraw event of `POOL_ID` in SUI and USDC, without referrer:
+    /// both events have the same layout.
+    fn liquidity_event_bytes(amounts: [u64; 2], lp_coins: u64) -> Vec<u8> {
+        let mut bytes = ObjectID::from_hex_literal(POOL_ID).unwrap().to_vec();
+        bytes.extend(SuiAddress::from_bytes([1u8; 32]).unwrap().to_vec());
+        bytes.extend(bcs::to_bytes(&None::<SuiAddress>).unwrap());
+        bytes.extend(bcs::to_bytes(&vec![SUI, USDC]).unwrap());
+        bytes.extend(bcs::to_bytes(&amounts.to_vec()).unwrap());
+        bytes.extend(lp_coins.to_le_bytes());
+        bytes.extend(bcs::to_bytes(&vec![5_000_000_000_000u64, 20_000_000_000]).unwrap());
+
+        bytes
+    }
+
+    #[test]
+    fn deposit_event_decodes_from_bcs() {
+        let data = bcs::from_bytes::<DepositEventV2>(&liquidity_event_bytes(
+            [1_000_000_000, 4_000_000],
+            2_000_000,
+        ))
+        .unwrap();
+
+        assert_eq!(data.pool_id, ObjectID::from_hex_literal(POOL_ID).unwrap());
+        assert_eq!(data.issuer, SuiAddress::from_bytes([1u8; 32]).unwrap());
+        assert_eq!(data.referrer, None);
+        assert_eq!(data.types, vec![SUI, USDC]);
+        assert_eq!(data.deposits, vec![1_000_000_000, 4_000_000]);
+        assert_eq!(data.lp_coins_minted, 2_000_000);
+        assert_eq!(data.reserves, vec![5_000_000_000_000, 20_000_000_000]);
+    }
+
+    #[test]
+    fn withdraw_event_decodes_from_bcs() {
+        let data = bcs::from_bytes::<WithdrawEventV2>(&liquidity_event_bytes(
+            [500_000_000, 2_000_000],
+            1_000_000,
+        ))
+        .unwrap();
+
+        assert_eq!(data.pool_id, ObjectID::from_hex_literal(POOL_ID).unwrap());
+        assert_eq!(data.types, vec![SUI, USDC]);
+        assert_eq!(data.withdrawn, vec![500_000_000, 2_000_000]);
+        assert_eq!(data.lp_coins_burned, 1_000_000);
+    }
+
+    #[test]
+    fn referrer_is_decoded_when_set() {
+        let mut bytes = ObjectID::from_hex_literal(POOL_ID).unwrap().to_vec();
+        bytes.extend(SuiAddress::from_bytes([1u8; 32]).unwrap().to_vec());
+        bytes.extend(bcs::to_bytes(&Some(SuiAddress::from_bytes([2u8; 32]).unwrap())).unwrap());
+        // the fields after the pool, the issuer and the empty referrer
+        bytes.extend(liquidity_event_bytes([1, 1], 1)[64 + 1..].iter());
+
+        let data = bcs::from_bytes::<DepositEventV2>(&bytes).unwrap();
+
+        assert_eq!(
+            data.referrer,
+            Some(SuiAddress::from_bytes([2u8; 32]).unwrap())
+        );
+        assert_eq!(data.lp_coins_minted, 1);
+    }
+
+    #[test]
+    fn pool_id_is_extracted_from_liquidity_events() {
+        let db = Arc::new(MockDb::default());
+        let aftermath = aftermath(&db);
+
+        for event_type in [
+            constant::AFTERMATH_ADD_LIQUIDITY_EVENT,
+            constant::AFTERMATH_REMOVE_LIQUIDITY_EVENT,
+        ] {
+            let event =
+                indexer::test_event(event_type, liquidity_event_bytes([1_000, 4_000], 2_000));
+
+            assert_eq!(
+                aftermath.extract_pool_id_from_event(&event).unwrap(),
+                POOL_ID
+            );
+        }
+    }
+
+    #[test]
+    fn truncated_liquidity_event_is_an_error() {
+        let db = Arc::new(MockDb::default());
+        let aftermath = aftermath(&db);
+        let mut bytes = liquidity_event_bytes([1_000, 4_000], 2_000);
+        bytes.truncate(bytes.len() - 1);
+        let event = indexer::test_event(constant::AFTERMATH_ADD_LIQUIDITY_EVENT, bytes);
+
+        assert!(aftermath.extract_pool_id_from_event(&event).is_err());
+    }
+}
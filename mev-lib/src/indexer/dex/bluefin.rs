@@ -3,7 +3,7 @@ use crate::{
     indexer::{self, EventProcessor, OnchainEvent},
     service::{db_service::pool::PoolService, dex::DEXService},
     types::{I128Json, I128, I32},
-    utils::tick_math,
+    utils::{rpc_pool::RpcPool, tick_math},
 };
 use db::models::{
     coin::{Coin, NewCoin, UpdateCoin},
@@ -21,7 +21,6 @@ use serde_with::{serde_as, DisplayFromStr};
 use std::fmt::{Debug, Display};
 use std::str::FromStr;
 use std::sync::Arc;
-use sui_sdk::SuiClient;
 use sui_types::object::{MoveObject, Object};
 use sui_types::{base_types::ObjectID, event::Event};
 use tokio::time::{Duration, Instant};
@@ -65,7 +64,7 @@ struct PoolTickUpdate {
 
 pub struct Bluefin {
     exchange: String,
-    sui_client: Arc<SuiClient>,
+    rpc_pool: Arc<RpcPool>,
     pool_repo: Arc<dyn PoolRepository + Send + Sync>,
     coin_repo: Arc<dyn CoinRepository + Send + Sync>,
     pool_service: Arc<PoolService>,
@@ -74,7 +73,7 @@ pub struct Bluefin {
 
 impl Bluefin {
     pub fn new(
-        sui_client: Arc<SuiClient>,
+        rpc_pool: Arc<RpcPool>,
         pool_repo: Arc<dyn PoolRepository + Send + Sync>,
         coin_repo: Arc<dyn CoinRepository + Send + Sync>,
         pool_service: Arc<PoolService>,
@@ -82,7 +81,7 @@ impl Bluefin {
     ) -> Self {
         Bluefin {
             exchange: constant::BLUEFIN_EXCHANGE.to_string(),
-            sui_client,
+            rpc_pool,
             pool_repo,
             coin_repo,
             pool_service,
@@ -169,6 +168,8 @@ impl EventProcessor for Bluefin {
 
     /// Returns a unique event ID based on the exchange name, event type, and pool ID.
     /// This ID can be used to identify events uniquely across txs in the checkpoint.
+    /// Tick updates are also keyed by the tick index,
+    /// so the updates of different ticks of a pool are all processed.
     fn get_event_id(&self, event_type: &str, event: &Event) -> Result<String> {
         match event_type {
             constant::BLUEFIN_SWAP_EVENT => {
@@ -190,6 +191,10 @@ impl EventProcessor for Bluefin {
             _ => Err(anyhow!("Unknown event type: {}", event_type)),
         }
     }
+
+    fn is_dedupable(&self, event_type: &str) -> bool {
+        event_type == constant::BLUEFIN_SWAP_EVENT
+    }
 }
 
 impl Bluefin {
@@ -238,3 +243,98 @@ impl Bluefin {
         Ok(pool_id)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::db_service::mock::{MockDEXService, MockDb};
+
+    const POOL_ID: &str = "0x3b585786b13af1d8ea067ab37101b6513a05d2f90cfe60e8b1d9e1b46a63c4fa";
+
+    fn bluefin(db: &Arc<MockDb>) -> Bluefin {
+        Bluefin::new(
+            Arc::new(RpcPool::without_clients()),
+            Arc::clone(db) as Arc<dyn PoolRepository + Send + Sync>,
+            Arc::clone(db) as Arc<dyn CoinRepository + Send + Sync>,
+            Arc::new(db.pool_service(crate::config::test_config())),
+            Arc::new(MockDEXService::default()),
+        )
+    }
+
+    /// `events::PoolTickUpdate` of `POOL_ID`, fields in declaration order.
+    fn tick_updated_event_bytes(
+        tick_index: i32,
+        liquidity_gross: u128,
+        liquidity_net: i128,
+    ) -> Vec<u8> {
+        let mut bytes = ObjectID::from_hex_literal(POOL_ID).unwrap().to_vec();
+        bytes.extend_from_slice(&(tick_index as u32).to_le_bytes());
+        bytes.extend_from_slice(&liquidity_gross.to_le_bytes());
+        bytes.extend_from_slice(&(liquidity_net as u128).to_le_bytes());
+
+        bytes
+    }
+
+    #[test]
+    fn tick_updated_event_decodes_from_bcs() {
+        let data =
+            bcs::from_bytes::<PoolTickUpdate>(&tick_updated_event_bytes(-10, 1_500, -500)).unwrap();
+
+        assert_eq!(data.pool, ObjectID::from_hex_literal(POOL_ID).unwrap());
+        assert_eq!(tick_math::i32_from_u32(data.index.bits).unwrap(), -10);
+        assert_eq!(data.liquidity_gross, 1_500);
+        assert_eq!(data.liquidity_net.bits as i128, -500);
+    }
+
+    #[tokio::test]
+    async fn tick_updated_event_saves_the_tick_liquidity() {
+        let db = Arc::new(MockDb::default());
+        let bluefin = bluefin(&db);
+
+        for (tick_index, liquidity_gross, liquidity_net) in [(-10, 1_500, 1_000), (10, 1_500, -500)]
+        {
+            let event = indexer::test_event(
+                constant::BLUEFIN_TICK_UPDATED_EVENT,
+                tick_updated_event_bytes(tick_index, liquidity_gross, liquidity_net),
+            );
+
+            bluefin
+                .process_raw_event(constant::BLUEFIN_TICK_UPDATED_EVENT, "", event, "tx")
+                .await
+                .unwrap();
+        }
+
+        let pool_ticks = db.pool_ticks.lock().unwrap();
+        assert_eq!(pool_ticks.len(), 2);
+        for (pool_tick, (tick_index, liquidity_net)) in
+            pool_ticks.iter().zip([(-10, 1_000), (10, -500)])
+        {
+            assert_eq!(pool_tick.address, POOL_ID);
+            assert_eq!(pool_tick.tick_index, tick_index);
+            assert_eq!(pool_tick.liquidity_gross.as_deref(), Some("1500"));
+            assert_eq!(
+                tick_math::liquidity_net_from_bits(pool_tick.liquidity_net.as_deref().unwrap())
+                    .unwrap(),
+                Decimal::from(liquidity_net)
+            );
+        }
+    }
+
+    #[test]
+    fn tick_updates_of_different_ticks_have_different_ids() {
+        let db = Arc::new(MockDb::default());
+        let bluefin = bluefin(&db);
+        let event_id = |tick_index: i32| {
+            let event = indexer::test_event(
+                constant::BLUEFIN_TICK_UPDATED_EVENT,
+                tick_updated_event_bytes(tick_index, 1_500, -500),
+            );
+            bluefin
+                .get_event_id(constant::BLUEFIN_TICK_UPDATED_EVENT, &event)
+                .unwrap()
+        };
+
+        assert_ne!(event_id(-10), event_id(10));
+        assert_eq!(event_id(10), event_id(10));
+    }
+}
@@ -2,6 +2,7 @@ use crate::{
     constant,
     indexer::{self, EventProcessor, OnchainEvent},
     service::{db_service::pool::PoolService, dex::DEXService},
+    utils::rpc_pool::RpcPool,
 };
 use db::models::{
     coin::{Coin, NewCoin, UpdateCoin},
@@ -17,7 +18,6 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::fmt::{Debug, Display};
 use std::sync::Arc;
-use sui_sdk::SuiClient;
 use sui_types::{
     base_types::{ObjectID, SuiAddress},
     event::Event,
@@ -41,7 +41,7 @@ struct SwapEvent {
 
 pub struct Bluemove {
     exchange: String,
-    sui_client: Arc<SuiClient>,
+    rpc_pool: Arc<RpcPool>,
     pool_repo: Arc<dyn PoolRepository + Send + Sync>,
     coin_repo: Arc<dyn CoinRepository + Send + Sync>,
     pool_service: Arc<PoolService>,
@@ -50,7 +50,7 @@ pub struct Bluemove {
 
 impl Bluemove {
     pub fn new(
-        sui_client: Arc<SuiClient>,
+        rpc_pool: Arc<RpcPool>,
         pool_repo: Arc<dyn PoolRepository + Send + Sync>,
         coin_repo: Arc<dyn CoinRepository + Send + Sync>,
         pool_service: Arc<PoolService>,
@@ -58,7 +58,7 @@ impl Bluemove {
     ) -> Self {
         Bluemove {
             exchange: constant::BLUEMOVE_EXCHANGE.to_string(),
-            sui_client,
+            rpc_pool,
             pool_repo,
             coin_repo,
             pool_service,
@@ -83,7 +83,9 @@ impl EventProcessor for Bluemove {
         tx_digest: &str,
     ) -> Result<()> {
         match event_type {
-            constant::BLUEMOVE_SWAP_EVENT => {
+            constant::BLUEMOVE_SWAP_EVENT
+            | constant::BLUEMOVE_ADD_LIQUIDITY_EVENT
+            | constant::BLUEMOVE_REMOVE_LIQUIDITY_EVENT => {
                 let pool_id = data
                     .get("pool_id")
                     .ok_or(anyhow!("Missing pool field in event data"))?
@@ -114,6 +116,17 @@ impl EventProcessor for Bluemove {
                     pool_id: pool_id.clone(),
                 }))
             }
+            constant::BLUEMOVE_ADD_LIQUIDITY_EVENT | constant::BLUEMOVE_REMOVE_LIQUIDITY_EVENT => {
+                info!("Processing liquidity event: {:?}", event);
+                let pool_id = self.extract_pool_id_from_event(event_type, &event)?;
+                // the reserves changed, refresh the pool coin amounts
+                self.process_pool(&pool_id).await?;
+
+                Ok(OnchainEvent::DEXLiquidity(indexer::DEXLiquidityEvent {
+                    exchange: self.exchange.clone(),
+                    pool_id,
+                }))
+            }
             _ => Err(anyhow!("Unknown event type: {}", event_type)),
         }
     }
@@ -124,6 +137,10 @@ impl EventProcessor for Bluemove {
 
         Ok(format!("{}_{}_{}", &self.exchange, &event_type, &pool_id))
     }
+
+    fn is_dedupable(&self, event_type: &str) -> bool {
+        event_type == constant::BLUEMOVE_SWAP_EVENT
+    }
 }
 
 impl Bluemove {
@@ -142,6 +159,13 @@ impl Bluemove {
                 info!("Swap event data: {:?}", data);
                 data.pool_id.to_string()
             }
+            // the pool ID is the leading field of the liquidity events, the only one needed
+            constant::BLUEMOVE_ADD_LIQUIDITY_EVENT | constant::BLUEMOVE_REMOVE_LIQUIDITY_EVENT => {
+                let pool_id_bytes = event.contents.get(..ObjectID::LENGTH).ok_or_else(|| {
+                    anyhow!("Liquidity event too short: {} bytes", event.contents.len())
+                })?;
+                bcs::from_bytes::<ObjectID>(pool_id_bytes)?.to_string()
+            }
             _ => {
                 return Err(anyhow!("Unknown event type: {}", event_type));
             }
@@ -150,3 +174,107 @@ impl Bluemove {
         Ok(pool_id)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::db_service::mock::{MockDEXService, MockDb};
+
+    const POOL_ID: &str = "0x0a2e9b4bd5bb5d2bd82c8ad44a5b0c03b4f3f7d2d1eb34f8d5f6b8e9a3c7d1f5";
+    const SUI: &str = "0000000000000000000000000000000000000000000000000000000000000002::sui::SUI";
+    const USDC: &str =
+        "dba34672e30cb065b1f93e3ab55318768fd6fef66c15942c9f7cb846e2f900e7::usdc::USDC";
+
+    fn bluemove(db: &Arc<MockDb>) -> Bluemove {
+        Bluemove::new(
+            Arc::new(RpcPool::without_clients()),
+            Arc::clone(db) as Arc<dyn PoolRepository + Send + Sync>,
+            Arc::clone(db) as Arc<dyn CoinRepository + Send + Sync>,
+            Arc::new(db.pool_service(crate::config::test_config())),
+            Arc::new(MockDEXService::default()),
+        )
+    }
+
+    /// BCS of an add or remove liquidity event of `POOL_ID` in SUI and USDC:
+    /// the pool, the user, the coin types, the amounts and the LP balance.
+    fn liquidity_event_bytes(amounts: [u64; 2], lsp_balance: u64) -> Vec<u8> {
+        let mut bytes = ObjectID::from_hex_literal(POOL_ID).unwrap().to_vec();
+        bytes.extend(SuiAddress::from_bytes([1u8; 32]).unwrap().to_vec());
+        bytes.extend(bcs::to_bytes(SUI).unwrap());
+        bytes.extend(bcs::to_bytes(USDC).unwrap());
+        for value in [amounts[0], amounts[1], lsp_balance] {
+            bytes.extend(value.to_le_bytes());
+        }
+
+        bytes
+    }
+
+    fn swap_event_bytes() -> Vec<u8> {
+        let mut bytes = ObjectID::from_hex_literal(POOL_ID).unwrap().to_vec();
+        bytes.extend(SuiAddress::from_bytes([1u8; 32]).unwrap().to_vec());
+        for (token, amount) in [
+            (SUI, 1_000_000_000u64),
+            (USDC, 0),
+            (SUI, 0),
+            (USDC, 3_990_000),
+        ] {
+            bytes.extend(bcs::to_bytes(token).unwrap());
+            bytes.extend(amount.to_le_bytes());
+        }
+
+        bytes
+    }
+
+    #[test]
+    fn swap_event_decodes_from_bcs() {
+        let data = bcs::from_bytes::<SwapEvent>(&swap_event_bytes()).unwrap();
+
+        assert_eq!(data.pool_id, ObjectID::from_hex_literal(POOL_ID).unwrap());
+        assert_eq!(data.user, SuiAddress::from_bytes([1u8; 32]).unwrap());
+        assert_eq!(data.token_x_in, SUI);
+        assert_eq!(data.amount_x_in, 1_000_000_000);
+        assert_eq!(data.token_y_out, USDC);
+        assert_eq!(data.amount_y_out, 3_990_000);
+    }
+
+    #[test]
+    fn pool_id_is_extracted_from_liquidity_events() {
+        let db = Arc::new(MockDb::default());
+        let bluemove = bluemove(&db);
+
+        for event_type in [
+            constant::BLUEMOVE_ADD_LIQUIDITY_EVENT,
+            constant::BLUEMOVE_REMOVE_LIQUIDITY_EVENT,
+        ] {
+            let event = indexer::test_event(
+                event_type,
+                liquidity_event_bytes([1_000_000_000, 4_000_000], 2_000_000),
+            );
+
+            assert_eq!(
+                bluemove
+                    .extract_pool_id_from_event(event_type, &event)
+                    .unwrap(),
+                POOL_ID
+            );
+            assert_eq!(
+                bluemove.get_event_id(event_type, &event).unwrap(),
+                format!("bluemove_{}_{}", event_type, POOL_ID)
+            );
+        }
+    }
+
+    #[test]
+    fn liquidity_event_shorter_than_a_pool_id_is_an_error() {
+        let db = Arc::new(MockDb::default());
+        let bluemove = bluemove(&db);
+        let event = indexer::test_event(
+            constant::BLUEMOVE_ADD_LIQUIDITY_EVENT,
+            liquidity_event_bytes([1, 1], 1)[..ObjectID::LENGTH - 1].to_vec(),
+        );
+
+        assert!(bluemove
+            .extract_pool_id_from_event(constant::BLUEMOVE_ADD_LIQUIDITY_EVENT, &event)
+            .is_err());
+    }
+}
@@ -3,12 +3,13 @@ use crate::{
     indexer::{self, EventProcessor, OnchainEvent},
     service::{db_service::pool::PoolService, dex::DEXService},
     types::{I128Json, I128, I32},
-    utils::tick_math,
+    utils::{self, rpc_pool::RpcPool, tick_math},
 };
 use db::models::{
     coin::{Coin, NewCoin, UpdateCoin},
     pool::{NewPool, Pool, UpdatePool},
     pool_tick::{NewPoolTick, PoolTick, UpdatePoolTick},
+    swap_event::NewSwapEvent,
 };
 use db::repositories::{CoinRepository, PoolRepository};
 
@@ -21,7 +22,6 @@ use serde_json::{self, Value};
 use serde_with::{serde_as, DisplayFromStr};
 use std::fmt::{Debug, Display};
 use std::sync::Arc;
-use sui_sdk::SuiClient;
 use sui_types::object::{MoveObject, Object};
 use sui_types::{base_types::ObjectID, event::Event};
 use tokio::time::{Duration, Instant};
@@ -43,6 +43,19 @@ struct SwapEvent {
     steps: u64,              // 8 bytes
 }
 
+#[serde_as]
+#[derive(Debug, Deserialize, Serialize)]
+struct SwapEventJson {
+    atob: bool,
+    pool: ObjectID,
+    #[serde_as(as = "DisplayFromStr")]
+    amount_in: u64,
+    #[serde_as(as = "DisplayFromStr")]
+    amount_out: u64,
+    #[serde_as(as = "DisplayFromStr")]
+    fee_amount: u64,
+}
+
 #[serde_as]
 #[derive(Debug, Deserialize, Serialize)]
 struct AddLiquidityEventJson {
@@ -103,7 +116,7 @@ struct RemoveLiquidityEvent {
 
 pub struct Cetus {
     exchange: String,
-    sui_client: Arc<SuiClient>,
+    rpc_pool: Arc<RpcPool>,
     pool_repo: Arc<dyn PoolRepository + Send + Sync>,
     coin_repo: Arc<dyn CoinRepository + Send + Sync>,
     pool_service: Arc<PoolService>,
@@ -112,7 +125,7 @@ pub struct Cetus {
 
 impl Cetus {
     pub fn new(
-        sui_client: Arc<SuiClient>,
+        rpc_pool: Arc<RpcPool>,
         pool_repo: Arc<dyn PoolRepository + Send + Sync>,
         coin_repo: Arc<dyn CoinRepository + Send + Sync>,
         pool_service: Arc<PoolService>,
@@ -120,7 +133,7 @@ impl Cetus {
     ) -> Self {
         Cetus {
             exchange: constant::CETUS_EXCHANGE.to_string(),
-            sui_client,
+            rpc_pool,
             pool_repo,
             coin_repo,
             pool_service,
@@ -147,13 +160,9 @@ impl EventProcessor for Cetus {
         match event_type {
             constant::CETUS_SWAP_EVENT => {
                 info!("Processing swap event: {}", data);
-                let pool_id = data
-                    .get("pool")
-                    .ok_or(anyhow!("Missing pool field in event data"))?
-                    .as_str()
-                    .ok_or(anyhow!("Pool field is not a string in event data"))?;
+                let event: SwapEventJson = serde_json::from_value(data.clone())?;
 
-                self.process_pool(pool_id).await?;
+                self.process_pool(&event.pool.to_string()).await?;
                 Ok(())
             }
             constant::CETUS_ADD_LIQUIDITY_EVENT => {
@@ -204,7 +213,30 @@ impl EventProcessor for Cetus {
         match event_type {
             constant::CETUS_SWAP_EVENT => {
                 info!("Processing raw swap event: {:?}", event);
-                let pool_id = self.extract_pool_id_from_event(&event)?;
+                let data = match bcs::from_bytes::<SwapEvent>(&event.contents) {
+                    Ok(data) => data,
+                    Err(e) => {
+                        // the pool state can still be refreshed
+                        warn!(
+                            "Failed to decode swap event of tx {}, falling back to RPC: {}",
+                            tx_digest, e
+                        );
+                        let pool_id = utils::extract_pool_id_fallback(
+                            &self.rpc_pool,
+                            tx_digest,
+                            &event,
+                            "pool",
+                        )
+                        .await?;
+                        self.process_pool(&pool_id).await?;
+
+                        return Ok(OnchainEvent::DEXSwap(indexer::DEXSwapEvent {
+                            exchange: self.exchange.clone(),
+                            pool_id,
+                        }));
+                    }
+                };
+                let pool_id = data.pool.to_string();
                 let pool = self.process_pool(&pool_id).await?;
 
                 Ok(OnchainEvent::DEXSwap(indexer::DEXSwapEvent {
@@ -241,14 +273,88 @@ impl EventProcessor for Cetus {
     fn get_event_id(&self, event_type: &str, event: &Event) -> Result<String> {
         match event_type {
             constant::CETUS_SWAP_EVENT => {
-                let pool_id = self.extract_pool_id_from_event(event)?;
+                let pool_id = self.extract_pool_id_from_event(event).unwrap_or_else(|e| {
+                    warn!("Failed to decode swap event, keying it by contents: {}", e);
+                    utils::undecoded_event_key(event)
+                });
 
                 Ok(format!("{}_{}_{}", &self.exchange, event_type, pool_id))
             }
+            // liquidity changes are applied as deltas to the ticks, so every occurrence is kept
+            constant::CETUS_ADD_LIQUIDITY_EVENT => {
+                let data = bcs::from_bytes::<AddLiquidityEvent>(&event.contents)?;
+                Ok(format!(
+                    "{}_{}_{}_{}",
+                    &self.exchange,
+                    event_type,
+                    data.pool,
+                    utils::event_contents_hash(event)
+                ))
+            }
+            constant::CETUS_REMOVE_LIQUIDITY_EVENT => {
+                let data = bcs::from_bytes::<RemoveLiquidityEvent>(&event.contents)?;
+                Ok(format!(
+                    "{}_{}_{}_{}",
+                    &self.exchange,
+                    event_type,
+                    data.pool,
+                    utils::event_contents_hash(event)
+                ))
+            }
 
             _ => Err(anyhow!("Unknown event type: {}", event_type)),
         }
     }
+
+    fn is_dedupable(&self, event_type: &str) -> bool {
+        event_type == constant::CETUS_SWAP_EVENT
+    }
+
+    fn decode_swap_event(
+        &self,
+        event_type: &str,
+        event: &Event,
+        tx_digest: &str,
+        event_seq: u64,
+    ) -> Result<Option<NewSwapEvent>> {
+        if event_type != constant::CETUS_SWAP_EVENT {
+            return Ok(None);
+        }
+
+        let data = bcs::from_bytes::<SwapEvent>(&event.contents)?;
+        Ok(Some(Self::new_swap_event(
+            data.pool,
+            data.atob,
+            data.amount_in,
+            data.amount_out,
+            data.fee_amount,
+            tx_digest,
+            event_seq,
+        )))
+    }
+
+    fn decode_tx_swap_event(
+        &self,
+        event_type: &str,
+        data: &Value,
+        tx_digest: &str,
+        event_seq: u64,
+    ) -> Result<Option<NewSwapEvent>> {
+        if event_type != constant::CETUS_SWAP_EVENT {
+            return Ok(None);
+        }
+
+        let event: SwapEventJson = serde_json::from_value(data.clone())?;
+        Ok(Some(Self::new_swap_event(
+            event.pool,
+            event.atob,
+            event.amount_in,
+            event.amount_out,
+            event.fee_amount,
+            tx_digest,
+            event_seq,
+        )))
+    }
 }
 
 impl Cetus {
@@ -279,43 +385,58 @@ impl Cetus {
         Ok(pool)
     }
 
-    async fn process_add_liquidity_event(&self, event: &AddLiquidityEvent) -> Result<()> {
-        let ticks = vec![event.tick_lower.bits, event.tick_upper.bits];
-
-        for tick in ticks {
-            let pool_tick = PoolTick {
-                id: 0, // ID will be auto-generated by the database
-                address: event.pool.to_string(),
-                tick_index: tick_math::i32_from_u32(tick)?,
-                liquidity_gross: None,
-                liquidity_net: None,
-                created_at: None,
-                updated_at: None,
-            };
-
-            self.pool_service.save_pool_tick_to_db(&pool_tick).await?;
+    /// Normalizes a swap for volume analytics.
+    ///
+    fn new_swap_event(
+        pool_id: ObjectID,
+        a_to_b: bool,
+        amount_in: u64,
+        amount_out: u64,
+        fee_amount: u64,
+        tx_digest: &str,
+        event_seq: u64,
+    ) -> NewSwapEvent {
+        NewSwapEvent {
+            exchange: constant::CETUS_EXCHANGE.to_string(),
+            pool_id: pool_id.to_string(),
+            a_to_b,
+            amount_in: amount_in.to_string(),
+            amount_out: amount_out.to_string(),
+            fee_amount: Some(fee_amount.to_string()),
+            tx_digest: tx_digest.to_string(),
+            event_seq: event_seq as i64,
         }
+    }
 
-        Ok(())
+    /// Adds the position liquidity to its boundary ticks.
+    ///
+    async fn process_add_liquidity_event(&self, event: &AddLiquidityEvent) -> Result<()> {
+        let liquidity_delta = i128::try_from(event.liquidity)
+            .map_err(|e| anyhow!("Liquidity {} overflows: {}", event.liquidity, e))?;
+
+        self.pool_service
+            .apply_liquidity_delta_to_ticks(
+                &event.pool.to_string(),
+                tick_math::i32_from_u32(event.tick_lower.bits)?,
+                tick_math::i32_from_u32(event.tick_upper.bits)?,
+                liquidity_delta,
+            )
+            .await
     }
 
+    /// Removes the position liquidity from its boundary ticks.
+    ///
     async fn process_remove_liquidity_event(&self, event: &RemoveLiquidityEvent) -> Result<()> {
-        let ticks = vec![event.tick_lower.bits, event.tick_upper.bits];
-
-        for tick in ticks {
-            let pool_tick = PoolTick {
-                id: 0, // ID will be auto-generated by the database
-                address: event.pool.to_string(),
-                tick_index: tick_math::i32_from_u32(tick)?,
-                liquidity_gross: None,
-                liquidity_net: None,
-                created_at: None, // Created at will be set by the database
-                updated_at: None, // Updated at will be set by the database
-            };
-
-            self.pool_service.save_pool_tick_to_db(&pool_tick).await?;
-        }
-
-        Ok(())
+        let liquidity_delta = i128::try_from(event.liquidity)
+            .map_err(|e| anyhow!("Liquidity {} overflows: {}", event.liquidity, e))?;
+
+        self.pool_service
+            .apply_liquidity_delta_to_ticks(
+                &event.pool.to_string(),
+                tick_math::i32_from_u32(event.tick_lower.bits)?,
+                tick_math::i32_from_u32(event.tick_upper.bits)?,
+                -liquidity_delta,
+            )
+            .await
     }
 }
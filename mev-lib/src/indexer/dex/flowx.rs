@@ -3,7 +3,7 @@ use crate::{
     indexer::{self, EventProcessor, OnchainEvent},
     service::{db_service::pool::PoolService, dex::DEXService},
     types::{I128Json, I128, I32},
-    utils::tick_math,
+    utils::{rpc_pool::RpcPool, tick_math},
 };
 use db::models::{
     coin::{Coin, NewCoin, UpdateCoin},
@@ -21,7 +21,6 @@ use serde_json::Value;
 use serde_with::{serde_as, DisplayFromStr};
 use std::fmt::{Debug, Display};
 use std::sync::Arc;
-use sui_sdk::SuiClient;
 use sui_types::base_types::{ObjectID, SuiAddress};
 use sui_types::event::{self, Event};
 use tokio::time::{Duration, Instant};
@@ -70,7 +69,7 @@ struct ModifyLiquidity {
 
 pub struct FlowX {
     exchange: String,
-    sui_client: Arc<SuiClient>,
+    rpc_pool: Arc<RpcPool>,
     pool_repo: Arc<dyn PoolRepository + Send + Sync>,
     coin_repo: Arc<dyn CoinRepository + Send + Sync>,
     pool_service: Arc<PoolService>,
@@ -79,7 +78,7 @@ pub struct FlowX {
 
 impl FlowX {
     pub fn new(
-        sui_client: Arc<SuiClient>,
+        rpc_pool: Arc<RpcPool>,
         pool_repo: Arc<dyn PoolRepository + Send + Sync>,
         coin_repo: Arc<dyn CoinRepository + Send + Sync>,
         pool_service: Arc<PoolService>,
@@ -87,7 +86,7 @@ impl FlowX {
     ) -> Self {
         FlowX {
             exchange: constant::FLOWX_EXCHANGE.to_string(),
-            sui_client,
+            rpc_pool,
             pool_repo,
             coin_repo,
             pool_service,
@@ -192,6 +191,10 @@ impl EventProcessor for FlowX {
             _ => Err(anyhow!("Unknown event type: {}", event_type)),
         }
     }
+
+    fn is_dedupable(&self, event_type: &str) -> bool {
+        event_type == constant::FLOWX_SWAP_EVENT
+    }
 }
 
 impl FlowX {
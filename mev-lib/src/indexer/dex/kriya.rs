@@ -0,0 +1,237 @@
+use crate::{
+    constant,
+    indexer::{self, EventProcessor, OnchainEvent},
+    service::{db_service::pool::PoolService, dex::DEXService},
+    utils::rpc_pool::RpcPool,
+};
+use db::repositories::{CoinRepository, PoolRepository};
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt::{Debug, Display};
+use std::sync::Arc;
+use sui_types::{
+    base_types::{ObjectID, SuiAddress},
+    event::Event,
+};
+use tracing::{debug, error, info, instrument, trace, warn};
+
+#[derive(Debug, Deserialize, Serialize)]
+struct SwapEvent {
+    pool_id: ObjectID,
+    user: SuiAddress,
+    reserve_x: u64,
+    reserve_y: u64,
+    amount_in: u64,
+    amount_out: u64,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct LiquidityAddedEvent {
+    pool_id: ObjectID,
+    liquidity_provider: SuiAddress,
+    amount_x: u64,
+    amount_y: u64,
+    lsp_minted: u64,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct LiquidityRemovedEvent {
+    pool_id: ObjectID,
+    liquidity_provider: SuiAddress,
+    amount_x: u64,
+    amount_y: u64,
+    lsp_burned: u64,
+}
+
+pub struct Kriya {
+    exchange: String,
+    rpc_pool: Arc<RpcPool>,
+    pool_repo: Arc<dyn PoolRepository + Send + Sync>,
+    coin_repo: Arc<dyn CoinRepository + Send + Sync>,
+    pool_service: Arc<PoolService>,
+    dex_service: Arc<dyn DEXService + Send + Sync>,
+}
+
+impl Kriya {
+    pub fn new(
+        rpc_pool: Arc<RpcPool>,
+        pool_repo: Arc<dyn PoolRepository + Send + Sync>,
+        coin_repo: Arc<dyn CoinRepository + Send + Sync>,
+        pool_service: Arc<PoolService>,
+        dex_service: Arc<dyn DEXService + Send + Sync>,
+    ) -> Self {
+        Kriya {
+            exchange: constant::KRIYA_EXCHANGE.to_string(),
+            rpc_pool,
+            pool_repo,
+            coin_repo,
+            pool_service,
+            dex_service,
+        }
+    }
+}
+
+impl Display for Kriya {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "KriyaEventProcessor")
+    }
+}
+
+#[async_trait]
+impl EventProcessor for Kriya {
+    async fn process_tx_event(
+        &self,
+        event_type: &str,
+        sender: &str,
+        data: Value,
+        tx_digest: &str,
+    ) -> Result<()> {
+        match event_type {
+            constant::KRIYA_SWAP_EVENT
+            | constant::KRIYA_ADD_LIQUIDITY_EVENT
+            | constant::KRIYA_REMOVE_LIQUIDITY_EVENT => {
+                let pool_id = data
+                    .get("pool_id")
+                    .ok_or(anyhow!("Missing pool field in event data"))?
+                    .as_str()
+                    .ok_or(anyhow!("Pool field is not a string in event data"))?;
+
+                self.process_pool(pool_id).await?;
+                Ok(())
+            }
+            _ => Err(anyhow!("Unknown event type: {}", event_type)),
+        }
+    }
+
+    async fn process_raw_event(
+        &self,
+        event_type: &str,
+        sender: &str,
+        event: Event,
+        tx_digest: &str,
+    ) -> Result<OnchainEvent> {
+        match event_type {
+            constant::KRIYA_SWAP_EVENT => {
+                let pool_id = Self::extract_pool_id_from_event(event_type, &event)?;
+                self.process_pool(&pool_id).await?;
+
+                Ok(OnchainEvent::DEXSwap(indexer::DEXSwapEvent {
+                    exchange: self.exchange.clone(),
+                    pool_id,
+                }))
+            }
+            constant::KRIYA_ADD_LIQUIDITY_EVENT | constant::KRIYA_REMOVE_LIQUIDITY_EVENT => {
+                let pool_id = Self::extract_pool_id_from_event(event_type, &event)?;
+                self.process_pool(&pool_id).await?;
+
+                Ok(OnchainEvent::DEXLiquidity(indexer::DEXLiquidityEvent {
+                    exchange: self.exchange.clone(),
+                    pool_id,
+                }))
+            }
+            _ => Err(anyhow!("Unknown event type: {}", event_type)),
+        }
+    }
+
+    fn get_event_id(&self, event_type: &str, event: &Event) -> Result<String> {
+        let pool_id = Self::extract_pool_id_from_event(event_type, event)?;
+        let event_type = event.type_.to_string();
+
+        Ok(format!("{}_{}_{}", &self.exchange, &event_type, &pool_id))
+    }
+
+    fn is_dedupable(&self, event_type: &str) -> bool {
+        event_type == constant::KRIYA_SWAP_EVENT
+    }
+}
+
+impl Kriya {
+    async fn process_pool(&self, pool_id: &str) -> Result<crate::types::Pool> {
+        let pool = self.dex_service.get_pool_data(pool_id).await?;
+
+        self.pool_service.save_pool_to_db(pool.clone()).await?;
+
+        Ok(pool)
+    }
+
+    fn extract_pool_id_from_event(event_type: &str, event: &Event) -> Result<String> {
+        let pool_id = match event_type {
+            constant::KRIYA_SWAP_EVENT => {
+                let data = bcs::from_bytes::<SwapEvent>(&event.contents)?;
+                info!("Swap event data: {:?}", data);
+                data.pool_id.to_string()
+            }
+            constant::KRIYA_ADD_LIQUIDITY_EVENT => {
+                let data = bcs::from_bytes::<LiquidityAddedEvent>(&event.contents)?;
+                info!("Liquidity added event data: {:?}", data);
+                data.pool_id.to_string()
+            }
+            constant::KRIYA_REMOVE_LIQUIDITY_EVENT => {
+                let data = bcs::from_bytes::<LiquidityRemovedEvent>(&event.contents)?;
+                info!("Liquidity removed event data: {:?}", data);
+                data.pool_id.to_string()
+            }
+            _ => {
+                return Err(anyhow!("Unknown event type: {}", event_type));
+            }
+        };
+
+        Ok(pool_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POOL_ID: &str = "0x5af4976b871fa1813362f352fa4cada3883a96191bb7212db1bd5d13685ae305";
+
+    fn swap_event_bytes() -> Vec<u8> {
+        let mut bytes = ObjectID::from_hex_literal(POOL_ID).unwrap().to_vec();
+        bytes.extend_from_slice(&[1u8; 32]);
+        for value in [
+            1_000_000_000_000u64,
+            2_000_000_000,
+            1_000_000_000,
+            1_994_000,
+        ] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+
+        bytes
+    }
+
+    #[test]
+    fn swap_event_decodes_from_bcs() {
+        let data = bcs::from_bytes::<SwapEvent>(&swap_event_bytes()).unwrap();
+
+        assert_eq!(data.pool_id, ObjectID::from_hex_literal(POOL_ID).unwrap());
+        assert_eq!(data.user, SuiAddress::from_bytes([1u8; 32]).unwrap());
+        assert_eq!(data.reserve_x, 1_000_000_000_000);
+        assert_eq!(data.reserve_y, 2_000_000_000);
+        assert_eq!(data.amount_in, 1_000_000_000);
+        assert_eq!(data.amount_out, 1_994_000);
+    }
+
+    #[test]
+    fn pool_id_is_extracted_from_swap_event() {
+        let event = indexer::test_event(constant::KRIYA_SWAP_EVENT, swap_event_bytes());
+
+        let pool_id =
+            Kriya::extract_pool_id_from_event(constant::KRIYA_SWAP_EVENT, &event).unwrap();
+
+        assert_eq!(pool_id, POOL_ID);
+    }
+
+    #[test]
+    fn truncated_swap_event_is_an_error() {
+        let mut bytes = swap_event_bytes();
+        bytes.truncate(bytes.len() - 1);
+        let event = indexer::test_event(constant::KRIYA_SWAP_EVENT, bytes);
+
+        assert!(Kriya::extract_pool_id_from_event(constant::KRIYA_SWAP_EVENT, &event).is_err());
+    }
+}
@@ -3,7 +3,7 @@ use crate::{
     indexer::{self, EventProcessor, OnchainEvent},
     service::{db_service::pool::PoolService, dex::DEXService},
     types::I32,
-    utils::tick_math,
+    utils::{self, rpc_pool::RpcPool, tick_math},
 };
 use db::models::{
     coin::{Coin, NewCoin, UpdateCoin},
@@ -20,7 +20,6 @@ use serde_json::Value;
 use serde_with::{serde_as, DisplayFromStr};
 use std::fmt::{Debug, Display};
 use std::sync::Arc;
-use sui_sdk::SuiClient;
 use sui_types::{
     base_types::{ObjectID, SuiAddress},
     event::Event,
@@ -115,7 +114,7 @@ struct RemoveLiquidityEvent {
 
 pub struct Momentum {
     exchange: String,
-    sui_client: Arc<SuiClient>,
+    rpc_pool: Arc<RpcPool>,
     pool_repo: Arc<dyn PoolRepository + Send + Sync>,
     coin_repo: Arc<dyn CoinRepository + Send + Sync>,
     pool_service: Arc<PoolService>,
@@ -124,7 +123,7 @@ pub struct Momentum {
 
 impl Momentum {
     pub fn new(
-        sui_client: Arc<SuiClient>,
+        rpc_pool: Arc<RpcPool>,
         pool_repo: Arc<dyn PoolRepository + Send + Sync>,
         coin_repo: Arc<dyn CoinRepository + Send + Sync>,
         pool_service: Arc<PoolService>,
@@ -132,7 +131,7 @@ impl Momentum {
     ) -> Self {
         Momentum {
             exchange: constant::MOMENTUM_EXCHANGE.to_string(),
-            sui_client,
+            rpc_pool,
             pool_repo,
             coin_repo,
             pool_service,
@@ -216,7 +215,22 @@ impl EventProcessor for Momentum {
     ) -> Result<OnchainEvent> {
         match event_type {
             constant::MOMENTUM_SWAP_EVENT => {
-                let pool_id = self.extract_pool_id_from_event(&event)?;
+                let pool_id = match self.extract_pool_id_from_event(&event) {
+                    Ok(pool_id) => pool_id,
+                    Err(e) => {
+                        warn!(
+                            "Failed to decode swap event of tx {}, falling back to RPC: {}",
+                            tx_digest, e
+                        );
+                        utils::extract_pool_id_fallback(
+                            &self.rpc_pool,
+                            tx_digest,
+                            &event,
+                            "pool_id",
+                        )
+                        .await?
+                    }
+                };
                 let pool = self.process_pool(&pool_id).await?;
 
                 Ok(OnchainEvent::DEXSwap(indexer::DEXSwapEvent {
@@ -251,7 +265,10 @@ impl EventProcessor for Momentum {
     fn get_event_id(&self, event_type: &str, event: &Event) -> Result<String> {
         match event_type {
             constant::MOMENTUM_SWAP_EVENT => {
-                let pool_id = self.extract_pool_id_from_event(event)?;
+                let pool_id = self.extract_pool_id_from_event(event).unwrap_or_else(|e| {
+                    warn!("Failed to decode swap event, keying it by contents: {}", e);
+                    utils::undecoded_event_key(event)
+                });
 
                 Ok(format!("{}_{}_{}", &self.exchange, event_type, &pool_id))
             }
@@ -278,6 +295,10 @@ impl EventProcessor for Momentum {
             _ => Err(anyhow!("Unknown event type: {}", event_type)),
         }
     }
+
+    fn is_dedupable(&self, event_type: &str) -> bool {
+        event_type == constant::MOMENTUM_SWAP_EVENT
+    }
 }
 
 impl Momentum {
@@ -292,19 +313,22 @@ impl Momentum {
     async fn process_add_liquidity_event(&self, event: &AddLiquidityEvent) -> Result<()> {
         let ticks = vec![event.lower_tick_index.bits, event.upper_tick_index.bits];
 
-        for tick in ticks {
-            let pool_tick = PoolTick {
-                id: 0, // ID will be auto-generated by the database
-                address: event.pool_id.to_string(),
-                tick_index: tick_math::i32_from_u32(tick)?,
-                liquidity_gross: None,
-                liquidity_net: None,
-                created_at: None,
-                updated_at: None,
-            };
-
-            self.pool_service.save_pool_tick_to_db(&pool_tick).await?;
-        }
+        let pool_ticks = ticks
+            .into_iter()
+            .map(|tick| {
+                Ok(PoolTick {
+                    id: 0, // ID will be auto-generated by the database
+                    address: event.pool_id.to_string(),
+                    tick_index: tick_math::i32_from_u32(tick)?,
+                    liquidity_gross: None,
+                    liquidity_net: None,
+                    created_at: None,
+                    updated_at: None,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        self.pool_service.save_pool_ticks_to_db(&pool_ticks).await?;
 
         Ok(())
     }
@@ -312,19 +336,22 @@ impl Momentum {
     async fn process_remove_liquidity_event(&self, event: &RemoveLiquidityEvent) -> Result<()> {
         let ticks = vec![event.lower_tick_index.bits, event.upper_tick_index.bits];
 
-        for tick in ticks {
-            let pool_tick = PoolTick {
-                id: 0, // ID will be auto-generated by the database
-                address: event.pool_id.to_string(),
-                tick_index: tick_math::i32_from_u32(tick)?,
-                liquidity_gross: None,
-                liquidity_net: None,
-                created_at: None, // Created at will be set by the database
-                updated_at: None, // Updated at will be set by the database
-            };
-
-            self.pool_service.save_pool_tick_to_db(&pool_tick).await?;
-        }
+        let pool_ticks = ticks
+            .into_iter()
+            .map(|tick| {
+                Ok(PoolTick {
+                    id: 0, // ID will be auto-generated by the database
+                    address: event.pool_id.to_string(),
+                    tick_index: tick_math::i32_from_u32(tick)?,
+                    liquidity_gross: None,
+                    liquidity_net: None,
+                    created_at: None, // Created at will be set by the database
+                    updated_at: None, // Updated at will be set by the database
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        self.pool_service.save_pool_ticks_to_db(&pool_ticks).await?;
 
         Ok(())
     }
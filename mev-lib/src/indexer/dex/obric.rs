@@ -2,7 +2,7 @@ use crate::{
     constant,
     indexer::{self, EventProcessor, OnchainEvent},
     service::{db_service::pool::PoolService, dex::DEXService},
-    utils,
+    utils::{self, rpc_pool::RpcPool},
 };
 use db::models::{
     coin::{Coin, NewCoin, UpdateCoin},
@@ -18,7 +18,6 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::fmt::{Debug, Display};
 use std::sync::Arc;
-use sui_sdk::SuiClient;
 use sui_types::{base_types::ObjectID, event::Event};
 use tokio::time::{Duration, Instant};
 use tracing::{debug, error, info, instrument, trace, warn};
@@ -41,7 +40,7 @@ struct ObricSwapEvent {
 
 pub struct Obric {
     exchange: String,
-    sui_client: Arc<SuiClient>,
+    rpc_pool: Arc<RpcPool>,
     pool_repo: Arc<dyn PoolRepository + Send + Sync>,
     coin_repo: Arc<dyn CoinRepository + Send + Sync>,
     pool_service: Arc<PoolService>,
@@ -50,7 +49,7 @@ pub struct Obric {
 
 impl Obric {
     pub fn new(
-        sui_client: Arc<SuiClient>,
+        rpc_pool: Arc<RpcPool>,
         pool_repo: Arc<dyn PoolRepository + Send + Sync>,
         coin_repo: Arc<dyn CoinRepository + Send + Sync>,
         pool_service: Arc<PoolService>,
@@ -58,7 +57,7 @@ impl Obric {
     ) -> Self {
         Obric {
             exchange: constant::OBRIC_EXCHANGE.to_string(),
-            sui_client,
+            rpc_pool,
             pool_repo,
             coin_repo,
             pool_service,
@@ -125,6 +124,10 @@ impl EventProcessor for Obric {
 
         Ok(format!("{}_{}_{}", &self.exchange, &event_type, &pool_id))
     }
+
+    fn is_dedupable(&self, event_type: &str) -> bool {
+        event_type == constant::OBRIC_SWAP_EVENT
+    }
 }
 
 impl Obric {
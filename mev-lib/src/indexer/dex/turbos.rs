@@ -3,12 +3,13 @@ use crate::{
     indexer::{self, EventProcessor, OnchainEvent},
     service::{db_service::pool::PoolService, dex::DEXService},
     types::{I128Json, I128, I32},
-    utils::tick_math,
+    utils::{rpc_pool::RpcPool, tick_math},
 };
 use db::models::{
     coin::{Coin, NewCoin, UpdateCoin},
     pool::{NewPool, Pool, UpdatePool},
     pool_tick::{NewPoolTick, PoolTick, UpdatePoolTick},
+    swap_event::NewSwapEvent,
 };
 use db::repositories::{CoinRepository, PoolRepository};
 
@@ -20,7 +21,6 @@ use serde_json::Value;
 use serde_with::{serde_as, DisplayFromStr};
 use std::fmt::{Debug, Display};
 use std::sync::Arc;
-use sui_sdk::SuiClient;
 use sui_types::{
     base_types::{ObjectID, SuiAddress},
     event::Event,
@@ -68,6 +68,19 @@ struct BurnEvent {
     liquidity_delta: u128,
 }
 
+#[serde_as]
+#[derive(Debug, Deserialize, Serialize)]
+struct SwapEventJson {
+    pool: ObjectID,
+    #[serde_as(as = "DisplayFromStr")]
+    amount_a: u64,
+    #[serde_as(as = "DisplayFromStr")]
+    amount_b: u64,
+    #[serde_as(as = "DisplayFromStr")]
+    fee_amount: u64,
+    a_to_b: bool,
+}
+
 #[serde_as]
 #[derive(Debug, Deserialize, Serialize)]
 struct MintEventJson {
@@ -100,7 +113,7 @@ struct BurnEventJson {
 
 pub struct Turbos {
     exchange: String,
-    sui_client: Arc<SuiClient>,
+    rpc_pool: Arc<RpcPool>,
     pool_repo: Arc<dyn PoolRepository + Send + Sync>,
     coin_repo: Arc<dyn CoinRepository + Send + Sync>,
     pool_service: Arc<PoolService>,
@@ -109,7 +122,7 @@ pub struct Turbos {
 
 impl Turbos {
     pub fn new(
-        sui_client: Arc<SuiClient>,
+        rpc_pool: Arc<RpcPool>,
         pool_repo: Arc<dyn PoolRepository + Send + Sync>,
         coin_repo: Arc<dyn CoinRepository + Send + Sync>,
         pool_service: Arc<PoolService>,
@@ -117,7 +130,7 @@ impl Turbos {
     ) -> Self {
         Turbos {
             exchange: constant::TURBOS_EXCHANGE.to_string(),
-            sui_client,
+            rpc_pool,
             pool_repo,
             coin_repo,
             pool_service,
@@ -143,13 +156,9 @@ impl EventProcessor for Turbos {
     ) -> Result<()> {
         match event_type {
             constant::TURBOS_SWAP_EVENT => {
-                let pool_id = data
-                    .get("pool")
-                    .ok_or(anyhow!("Missing pool field in event data"))?
-                    .as_str()
-                    .ok_or(anyhow!("Pool field is not a string in event data"))?;
+                let event: SwapEventJson = serde_json::from_value(data.clone())?;
 
-                self.process_pool(pool_id).await?;
+                self.process_pool(&event.pool.to_string()).await?;
                 Ok(())
             }
             constant::TURBOS_ADD_LIQUIDITY_EVENT => {
@@ -254,6 +263,56 @@ impl EventProcessor for Turbos {
             _ => Err(anyhow!("Unknown event type: {}", event_type)),
         }
     }
+
+    fn is_dedupable(&self, event_type: &str) -> bool {
+        event_type == constant::TURBOS_SWAP_EVENT
+    }
+
+    fn decode_swap_event(
+        &self,
+        event_type: &str,
+        event: &Event,
+        tx_digest: &str,
+        event_seq: u64,
+    ) -> Result<Option<NewSwapEvent>> {
+        if event_type != constant::TURBOS_SWAP_EVENT {
+            return Ok(None);
+        }
+
+        let data = bcs::from_bytes::<SwapEvent>(&event.contents)?;
+        Ok(Some(Self::new_swap_event(
+            data.pool,
+            data.a_to_b,
+            data.amount_a,
+            data.amount_b,
+            data.fee_amount,
+            tx_digest,
+            event_seq,
+        )))
+    }
+
+    fn decode_tx_swap_event(
+        &self,
+        event_type: &str,
+        data: &Value,
+        tx_digest: &str,
+        event_seq: u64,
+    ) -> Result<Option<NewSwapEvent>> {
+        if event_type != constant::TURBOS_SWAP_EVENT {
+            return Ok(None);
+        }
+
+        let event: SwapEventJson = serde_json::from_value(data.clone())?;
+        Ok(Some(Self::new_swap_event(
+            event.pool,
+            event.a_to_b,
+            event.amount_a,
+            event.amount_b,
+            event.fee_amount,
+            tx_digest,
+            event_seq,
+        )))
+    }
 }
 
 impl Turbos {
@@ -266,22 +325,56 @@ impl Turbos {
         Ok(pool)
     }
 
+    /// Normalizes a swap for volume analytics.
+    /// Turbos reports the amounts per coin of the pool,
+    /// the direction of the swap tells which one is the input.
+    ///
+    fn new_swap_event(
+        pool_id: ObjectID,
+        a_to_b: bool,
+        amount_a: u64,
+        amount_b: u64,
+        fee_amount: u64,
+        tx_digest: &str,
+        event_seq: u64,
+    ) -> NewSwapEvent {
+        let (amount_in, amount_out) = if a_to_b {
+            (amount_a, amount_b)
+        } else {
+            (amount_b, amount_a)
+        };
+
+        NewSwapEvent {
+            exchange: constant::TURBOS_EXCHANGE.to_string(),
+            pool_id: pool_id.to_string(),
+            a_to_b,
+            amount_in: amount_in.to_string(),
+            amount_out: amount_out.to_string(),
+            fee_amount: Some(fee_amount.to_string()),
+            tx_digest: tx_digest.to_string(),
+            event_seq: event_seq as i64,
+        }
+    }
+
     async fn process_add_liquidity_event(&self, event: &MintEvent) -> Result<()> {
         let ticks = vec![event.tick_lower_index.bits, event.tick_upper_index.bits];
 
-        for tick in ticks {
-            let pool_tick = PoolTick {
-                id: 0, // ID will be auto-generated by the database
-                address: event.pool.to_string(),
-                tick_index: tick_math::i32_from_u32(tick)?,
-                liquidity_gross: None,
-                liquidity_net: None,
-                created_at: None,
-                updated_at: None,
-            };
-
-            self.pool_service.save_pool_tick_to_db(&pool_tick).await?;
-        }
+        let pool_ticks = ticks
+            .into_iter()
+            .map(|tick| {
+                Ok(PoolTick {
+                    id: 0, // ID will be auto-generated by the database
+                    address: event.pool.to_string(),
+                    tick_index: tick_math::i32_from_u32(tick)?,
+                    liquidity_gross: None,
+                    liquidity_net: None,
+                    created_at: None,
+                    updated_at: None,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        self.pool_service.save_pool_ticks_to_db(&pool_ticks).await?;
 
         Ok(())
     }
@@ -289,19 +382,22 @@ impl Turbos {
     async fn process_remove_liquidity_event(&self, event: &BurnEvent) -> Result<()> {
         let ticks = vec![event.tick_lower_index.bits, event.tick_upper_index.bits];
 
-        for tick in ticks {
-            let pool_tick = PoolTick {
-                id: 0, // ID will be auto-generated by the database
-                address: event.pool.to_string(),
-                tick_index: tick_math::i32_from_u32(tick)?,
-                liquidity_gross: None,
-                liquidity_net: None,
-                created_at: None, // Created at will be set by the database
-                updated_at: None, // Updated at will be set by the database
-            };
-
-            self.pool_service.save_pool_tick_to_db(&pool_tick).await?;
-        }
+        let pool_ticks = ticks
+            .into_iter()
+            .map(|tick| {
+                Ok(PoolTick {
+                    id: 0, // ID will be auto-generated by the database
+                    address: event.pool.to_string(),
+                    tick_index: tick_math::i32_from_u32(tick)?,
+                    liquidity_gross: None,
+                    liquidity_net: None,
+                    created_at: None, // Created at will be set by the database
+                    updated_at: None, // Updated at will be set by the database
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        self.pool_service.save_pool_ticks_to_db(&pool_ticks).await?;
 
         Ok(())
     }
@@ -322,3 +418,27 @@ impl Turbos {
         Ok(pool_id)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_swap_event_orders_amounts_by_direction() {
+        let pool_id = ObjectID::random();
+
+        let a_to_b = Turbos::new_swap_event(pool_id, true, 100, 90, 1, "tx", 2);
+        assert_eq!(
+            (a_to_b.amount_in.as_str(), a_to_b.amount_out.as_str()),
+            ("100", "90")
+        );
+        assert_eq!(a_to_b.event_seq, 2);
+
+        let b_to_a = Turbos::new_swap_event(pool_id, false, 100, 90, 1, "tx", 3);
+        assert_eq!(
+            (b_to_a.amount_in.as_str(), b_to_a.amount_out.as_str()),
+            ("90", "100")
+        );
+        assert_eq!(b_to_a.pool_id, pool_id.to_string());
+    }
+}
@@ -5,6 +5,7 @@ use crate::{
     service::{db_service, lending},
     types::Borrower,
     types::U256,
+    utils::rpc_pool::RpcPool,
 };
 use db::models::{
     coin::{Coin, NewCoin, UpdateCoin},
@@ -31,7 +32,6 @@ use std::{
     sync::Arc,
 };
 use sui_json_rpc_types::{SuiObjectDataOptions, SuiParsedData};
-use sui_sdk::SuiClient;
 use sui_types::{
     base_types::{ObjectID, SequenceNumber, SuiAddress},
     event::Event,
@@ -111,6 +111,38 @@ struct RepayEventJson {
     pub amount: u64,
 }
 
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct LiquidationEvent {
+    pub sender: SuiAddress,
+    pub user: SuiAddress,
+    pub collateral_asset: u8,
+    pub collateral_price: U256,
+    pub collateral_amount: u64,
+    pub treasury: u64,
+    pub debt_asset: u8,
+    pub debt_price: U256,
+    pub debt_amount: u64,
+}
+
+#[serde_as]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct LiquidationEventJson {
+    pub sender: SuiAddress,
+    pub user: SuiAddress,
+    pub collateral_asset: u8,
+    #[serde_as(as = "DisplayFromStr")]
+    pub collateral_price: U256,
+    #[serde_as(as = "DisplayFromStr")]
+    pub collateral_amount: u64,
+    #[serde_as(as = "DisplayFromStr")]
+    pub treasury: u64,
+    pub debt_asset: u8,
+    #[serde_as(as = "DisplayFromStr")]
+    pub debt_price: U256,
+    #[serde_as(as = "DisplayFromStr")]
+    pub debt_amount: u64,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 struct StateUpdatedEvent {
     pub user: SuiAddress,
@@ -138,7 +170,7 @@ struct StateUpdatedEventJson {
 
 pub struct Navi {
     platform: String,
-    client: Arc<SuiClient>,
+    rpc_pool: Arc<RpcPool>,
     config: Arc<NaviConfig>,
     service: Arc<dyn lending::LendingService + Send + Sync>,
     db_service: Arc<db_service::lending::LendingService>,
@@ -146,14 +178,14 @@ pub struct Navi {
 
 impl Navi {
     pub fn new(
-        client: Arc<SuiClient>,
+        rpc_pool: Arc<RpcPool>,
         config: Arc<NaviConfig>,
         service: Arc<dyn lending::LendingService + Send + Sync>,
         db_service: Arc<db_service::lending::LendingService>,
     ) -> Self {
         Navi {
             platform: constant::NAVI_LENDING.to_string(),
-            client,
+            rpc_pool,
             config,
             service,
             db_service,
@@ -226,6 +258,39 @@ impl EventProcessor for Navi {
 
                 self.process_repay(&event).await?;
             }
+            constant::NAVI_LIQUIDATE_EVENT => {
+                let event: LiquidationEventJson = serde_json::from_value(data)
+                    .map_err(|e| anyhow!("Failed to deserialize liquidation event: {}", e))?;
+
+                let event = LiquidationEvent {
+                    sender: event.sender,
+                    user: event.user,
+                    collateral_asset: event.collateral_asset,
+                    collateral_price: event.collateral_price,
+                    collateral_amount: event.collateral_amount,
+                    treasury: event.treasury,
+                    debt_asset: event.debt_asset,
+                    debt_price: event.debt_price,
+                    debt_amount: event.debt_amount,
+                };
+
+                self.process_liquidate(&event).await?;
+            }
+            constant::NAVI_STATE_UPDATED_EVENT => {
+                let event: StateUpdatedEventJson = serde_json::from_value(data)
+                    .map_err(|e| anyhow!("Failed to deserialize state updated event: {}", e))?;
+
+                let event = StateUpdatedEvent {
+                    user: event.user,
+                    asset: event.asset,
+                    user_supply_balance: event.user_supply_balance,
+                    user_borrow_balance: event.user_borrow_balance,
+                    new_supply_index: event.new_supply_index,
+                    new_borrow_index: event.new_borrow_index,
+                };
+
+                self.process_state_updated(&event).await?;
+            }
             _ => return Err(anyhow!("Unsupported event type: {}", event_type)),
         }
 
@@ -264,6 +329,18 @@ impl EventProcessor for Navi {
 
                 self.process_repay(&event).await
             }
+            constant::NAVI_LIQUIDATE_EVENT => {
+                let event: LiquidationEvent = bcs::from_bytes(&event.contents)
+                    .map_err(|e| anyhow!("Failed to deserialize liquidation event: {}", e))?;
+
+                self.process_liquidate(&event).await
+            }
+            constant::NAVI_STATE_UPDATED_EVENT => {
+                let event: StateUpdatedEvent = bcs::from_bytes(&event.contents)
+                    .map_err(|e| anyhow!("Failed to deserialize state updated event: {}", e))?;
+
+                self.process_state_updated(&event).await
+            }
 
             _ => return Err(anyhow!("Unsupported event type: {}", event_type)),
         }
@@ -280,6 +357,32 @@ impl EventProcessor for Navi {
                 &event.sender.to_string(),
                 event_type
             )),
+            constant::NAVI_LIQUIDATE_EVENT => {
+                let event_data: LiquidationEvent = bcs::from_bytes(&event.contents)
+                    .map_err(|e| anyhow!("Failed to deserialize liquidation event: {}", e))?;
+
+                // The transaction sender is the liquidator, key by the liquidated user
+                // and the asset pair so distinct liquidations are not merged.
+                Ok(format!(
+                    "{}_{}_{}_{}_{}",
+                    &self.platform,
+                    event_data.user,
+                    event_type,
+                    event_data.debt_asset,
+                    event_data.collateral_asset
+                ))
+            }
+            constant::NAVI_STATE_UPDATED_EVENT => {
+                let event_data: StateUpdatedEvent = bcs::from_bytes(&event.contents)
+                    .map_err(|e| anyhow!("Failed to deserialize state updated event: {}", e))?;
+
+                // Indexes are market-wide, only the latest update of each asset
+                // in a checkpoint matters.
+                Ok(format!(
+                    "{}_{}_{}",
+                    &self.platform, event_type, event_data.asset
+                ))
+            }
 
             _ => Err(anyhow!("Unsupported event type: {}", event_type)),
         }
@@ -431,6 +534,75 @@ impl Navi {
         }))
     }
 
+    /// Processes the Navi liquidation event.
+    /// Refreshes the liquidated user's debt and collateral positions,
+    /// both of which are reduced by the liquidation.
+    ///
+    async fn process_liquidate(&self, event: &LiquidationEvent) -> Result<OnchainEvent> {
+        info!("Processing Navi liquidation event: {:?}", event);
+
+        let user = event.user.to_string();
+
+        if self
+            .db_service
+            .find_existing_borrower(&self.platform, &user)?
+            .is_none()
+        {
+            self.create_new_borrower(&user).await?;
+        }
+
+        let user_borrow = self
+            .service
+            .fetch_user_borrow(user.clone(), None, None, Some(event.debt_asset))
+            .await?;
+
+        self.db_service
+            .save_user_borrow_to_db(user_borrow.clone())
+            .await?;
+
+        let user_deposit = self
+            .service
+            .fetch_user_deposit(user.clone(), None, None, Some(event.collateral_asset))
+            .await?;
+
+        self.db_service
+            .save_user_deposit_to_db(user_deposit.clone())
+            .await?;
+
+        Ok(OnchainEvent::LendingLiquidate(
+            indexer::lending::LiquidateEvent {
+                platform: self.platform.clone(),
+                borrower: user,
+                liquidator: event.sender.to_string(),
+                debt_coin: user_borrow.coin_type,
+                debt_asset_id: Some(event.debt_asset),
+                debt_amount: event.debt_amount.to_string(),
+                collateral_coin: user_deposit.coin_type,
+                collateral_asset_id: Some(event.collateral_asset),
+                collateral_amount: event.collateral_amount.to_string(),
+            },
+        ))
+    }
+
+    /// Processes the Navi state updated event,
+    /// which carries the latest supply and borrow indexes of the asset.
+    ///
+    async fn process_state_updated(&self, event: &StateUpdatedEvent) -> Result<OnchainEvent> {
+        info!("Processing Navi state updated event: {:?}", event);
+
+        let coin = self.db_service.find_coin_by_navi_asset_id(event.asset)?;
+
+        Ok(OnchainEvent::LendingIndexUpdated(
+            indexer::lending::IndexUpdatedEvent {
+                platform: self.platform.clone(),
+                coin_type: coin.coin_type,
+                asset_id: Some(event.asset),
+                borrow_index: Some(event.new_borrow_index.to_string()),
+                supply_index: Some(event.new_supply_index.to_string()),
+            },
+        ))
+    }
+
     async fn create_new_borrower(&self, address: &str) -> Result<crate::types::Borrower> {
         let borrower = crate::types::Borrower {
             platform: self.platform.clone(),
@@ -443,3 +615,250 @@ impl Navi {
         Ok(borrower)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::db_service::mock::{MockDb, MockLendingService};
+    use std::sync::atomic::Ordering;
+
+    fn address(byte: u8) -> SuiAddress {
+        SuiAddress::from_bytes([byte; 32]).unwrap()
+    }
+
+    /// Move u256, 32 bytes little endian.
+    fn u256_bytes(value: u128) -> Vec<u8> {
+        let mut bytes = value.to_le_bytes().to_vec();
+        bytes.extend([0u8; 16]);
+        bytes
+    }
+
+    /// `lending::LiquidationEvent` of 2 USDC repaid by `address(1)` for 1 SUI of `address(2)`.
+    fn liquidation_event_bytes() -> Vec<u8> {
+        let mut bytes = vec![];
+        bytes.extend(address(1).to_vec());
+        bytes.extend(address(2).to_vec());
+        bytes.push(0);
+        bytes.extend(u256_bytes(3_500_000_000));
+        bytes.extend(1_000_000_000u64.to_le_bytes());
+        bytes.extend(10_000_000u64.to_le_bytes());
+        bytes.push(10);
+        bytes.extend(u256_bytes(1_000_000_000));
+        bytes.extend(2_000_000u64.to_le_bytes());
+        bytes
+    }
+
+    fn navi(db: &Arc<MockDb>) -> Navi {
+        let user = address(2).to_string();
+        let lending_service = MockLendingService {
+            user_deposits: vec![crate::types::UserDeposit {
+                platform: constant::NAVI_LENDING.to_string(),
+                borrower: user.clone(),
+                obligation_id: None,
+                coin_type: "0x2::sui::SUI".to_string(),
+                amount: "4000000000".to_string(),
+            }],
+            user_borrows: vec![crate::types::UserBorrow {
+                platform: constant::NAVI_LENDING.to_string(),
+                borrower: user,
+                obligation_id: None,
+                coin_type: "0xusdc".to_string(),
+                amount: "3000000".to_string(),
+                debt_borrow_index: None,
+            }],
+            ..Default::default()
+        };
+        let config = crate::config::test_config();
+
+        Navi::new(
+            Arc::new(RpcPool::without_clients()),
+            Arc::new(config.navi.clone()),
+            Arc::new(lending_service),
+            Arc::new(db.lending_service(config)),
+        )
+    }
+
+    #[tokio::test]
+    async fn liquidation_event_decodes_from_bcs() {
+        let db = Arc::new(MockDb::default());
+        let navi = navi(&db);
+        let event = indexer::test_event(constant::NAVI_LIQUIDATE_EVENT, liquidation_event_bytes());
+
+        let event_id = navi
+            .get_event_id(constant::NAVI_LIQUIDATE_EVENT, &event)
+            .unwrap();
+        let onchain_event = navi
+            .process_raw_event(constant::NAVI_LIQUIDATE_EVENT, "", event, "tx")
+            .await
+            .unwrap();
+
+        assert!(event_id.ends_with(&format!("{}_10_0", constant::NAVI_LIQUIDATE_EVENT)));
+        assert!(event_id.contains(&address(2).to_string()));
+        let OnchainEvent::LendingLiquidate(liquidation) = onchain_event else {
+            panic!("Not a liquidation event");
+        };
+        assert_eq!(liquidation.borrower, address(2).to_string());
+        assert_eq!(liquidation.liquidator, address(1).to_string());
+        assert_eq!(liquidation.debt_coin, "0xusdc");
+        assert_eq!(liquidation.debt_asset_id, Some(10));
+        assert_eq!(liquidation.debt_amount, "2000000");
+        assert_eq!(liquidation.collateral_coin, "0x2::sui::SUI");
+        assert_eq!(liquidation.collateral_asset_id, Some(0));
+        assert_eq!(liquidation.collateral_amount, "1000000000");
+    }
+
+    #[tokio::test]
+    async fn liquidation_event_decodes_from_json() {
+        let db = Arc::new(MockDb::default());
+        let navi = navi(&db);
+        let data = serde_json::json!({
+            "sender": address(1).to_string(),
+            "user": address(2).to_string(),
+            "collateral_asset": 0,
+            "collateral_price": "3500000000",
+            "collateral_amount": "1000000000",
+            "treasury": "10000000",
+            "debt_asset": 10,
+            "debt_price": "1000000000",
+            "debt_amount": "2000000",
+        });
+
+        navi.process_tx_event(constant::NAVI_LIQUIDATE_EVENT, "", data, "tx")
+            .await
+            .unwrap();
+
+        assert_eq!(db.user_borrows.lock().unwrap().len(), 1);
+        assert_eq!(db.user_deposits.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn liquidation_saves_the_new_borrower_and_its_positions() {
+        let db = Arc::new(MockDb::default());
+        let navi = navi(&db);
+        let event = indexer::test_event(constant::NAVI_LIQUIDATE_EVENT, liquidation_event_bytes());
+
+        navi.process_raw_event(constant::NAVI_LIQUIDATE_EVENT, "", event, "tx")
+            .await
+            .unwrap();
+
+        let borrowers = db.borrowers.lock().unwrap();
+        assert_eq!(borrowers.len(), 1);
+        assert_eq!(borrowers[0].borrower, address(2).to_string());
+        assert_eq!(borrowers[0].status, constant::PENDING_STATUS);
+        assert_eq!(db.user_borrows.lock().unwrap()[0].amount, "3000000");
+        assert_eq!(db.user_deposits.lock().unwrap()[0].amount, "4000000000");
+    }
+
+    #[tokio::test]
+    async fn liquidation_keeps_the_stored_borrower() {
+        let db = Arc::new(MockDb::default());
+        db.insert_borrower(&db::models::borrower::NewBorrower {
+            platform: constant::NAVI_LENDING.to_string(),
+            borrower: address(2).to_string(),
+            obligation_id: None,
+            status: constant::READY_STATUS,
+        });
+        let navi = navi(&db);
+        let event = indexer::test_event(constant::NAVI_LIQUIDATE_EVENT, liquidation_event_bytes());
+
+        navi.process_raw_event(constant::NAVI_LIQUIDATE_EVENT, "", event, "tx")
+            .await
+            .unwrap();
+
+        let borrowers = db.borrowers.lock().unwrap();
+        assert_eq!(borrowers.len(), 1);
+        assert_eq!(borrowers[0].status, constant::READY_STATUS);
+    }
+
+    #[tokio::test]
+    async fn liquidation_does_not_create_the_borrower_on_database_errors() {
+        let db = Arc::new(MockDb::default());
+        db.unavailable.store(true, Ordering::SeqCst);
+        let navi = navi(&db);
+        let event = indexer::test_event(constant::NAVI_LIQUIDATE_EVENT, liquidation_event_bytes());
+
+        let result = navi
+            .process_raw_event(constant::NAVI_LIQUIDATE_EVENT, "", event, "tx")
+            .await;
+
+        assert!(result.is_err());
+        assert!(db.borrowers.lock().unwrap().is_empty());
+        assert!(db.user_borrows.lock().unwrap().is_empty());
+    }
+
+    /// `logic::StateUpdated` of the SUI market, asset 0, updated by `address(2)`.
+    fn state_updated_event_bytes() -> Vec<u8> {
+        let mut bytes = vec![];
+        bytes.extend(address(2).to_vec());
+        bytes.push(0);
+        bytes.extend(u256_bytes(4_000_000_000));
+        bytes.extend(u256_bytes(0));
+        bytes.extend(u256_bytes(1_030_000_000_000_000_000_000_000_000));
+        bytes.extend(u256_bytes(1_080_000_000_000_000_000_000_000_000));
+        bytes
+    }
+
+    fn sui_coin() -> db::models::coin::Coin {
+        let mut coin = MockDb::coin(1, "0x2::sui::SUI");
+        coin.navi_asset_id = Some(0);
+        coin
+    }
+
+    #[tokio::test]
+    async fn state_updated_event_decodes_from_bcs() {
+        let db = Arc::new(MockDb::default());
+        db.coins.lock().unwrap().push(sui_coin());
+        let navi = navi(&db);
+        let event = indexer::test_event(
+            constant::NAVI_STATE_UPDATED_EVENT,
+            state_updated_event_bytes(),
+        );
+
+        let event_id = navi
+            .get_event_id(constant::NAVI_STATE_UPDATED_EVENT, &event)
+            .unwrap();
+        let onchain_event = navi
+            .process_raw_event(constant::NAVI_STATE_UPDATED_EVENT, "", event, "tx")
+            .await
+            .unwrap();
+
+        assert_eq!(
+            event_id,
+            format!(
+                "{}_{}_0",
+                constant::NAVI_LENDING,
+                constant::NAVI_STATE_UPDATED_EVENT
+            )
+        );
+        let OnchainEvent::LendingIndexUpdated(index_updated) = onchain_event else {
+            panic!("Not an index updated event");
+        };
+        assert_eq!(index_updated.platform, constant::NAVI_LENDING);
+        assert_eq!(index_updated.coin_type, "0x2::sui::SUI");
+        assert_eq!(index_updated.asset_id, Some(0));
+        assert_eq!(
+            index_updated.supply_index.as_deref(),
+            Some("1030000000000000000000000000")
+        );
+        assert_eq!(
+            index_updated.borrow_index.as_deref(),
+            Some("1080000000000000000000000000")
+        );
+    }
+
+    #[tokio::test]
+    async fn state_updated_event_of_an_unknown_asset_is_an_error() {
+        let db = Arc::new(MockDb::default());
+        let navi = navi(&db);
+        let event = indexer::test_event(
+            constant::NAVI_STATE_UPDATED_EVENT,
+            state_updated_event_bytes(),
+        );
+
+        let result = navi
+            .process_raw_event(constant::NAVI_STATE_UPDATED_EVENT, "", event, "tx")
+            .await;
+
+        assert!(result.is_err());
+    }
+}
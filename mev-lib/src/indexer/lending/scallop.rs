@@ -4,7 +4,7 @@ use crate::{
     indexer::{self, EventProcessor, OnchainEvent},
     service::{db_service, lending},
     types::{Borrower, FixedPoint32, FixedPoint32Json, TypeName},
-    utils,
+    utils::{self, rpc_pool::RpcPool, ttl_cache::TtlCache},
 };
 use db::models::{
     user_borrow::{NewUserBorrow, UpdateUserBorrow, UserBorrow},
@@ -23,12 +23,9 @@ use serde_json::Value;
 use serde_with::{serde_as, DisplayFromStr};
 use std::fmt::{Debug, Display};
 use std::sync::Arc;
-use sui_sdk::{
-    rpc_types::{
-        SuiData, SuiMoveValue, SuiObjectData, SuiObjectDataFilter, SuiObjectDataOptions,
-        SuiObjectResponseQuery,
-    },
-    SuiClient,
+use sui_sdk::rpc_types::{
+    SuiData, SuiMoveValue, SuiObjectData, SuiObjectDataFilter, SuiObjectDataOptions,
+    SuiObjectResponseQuery,
 };
 use sui_types::{
     base_types::{ObjectID, ObjectType, SequenceNumber, SuiAddress},
@@ -79,6 +76,51 @@ struct WithdrawEventJson {
     pub withdraw_amount: u64,
 }
 
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct BorrowEvent {
+    pub borrower: SuiAddress,
+    pub obligation: ObjectID,
+    pub asset: TypeName,
+    pub amount: u64,
+    pub time: u64,
+}
+
+#[serde_as]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct BorrowEventJson {
+    pub borrower: SuiAddress,
+    pub obligation: ObjectID,
+    pub asset: TypeName,
+    #[serde_as(as = "DisplayFromStr")]
+    pub amount: u64,
+    #[serde_as(as = "DisplayFromStr")]
+    pub time: u64,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct BorrowEventV2 {
+    pub borrower: SuiAddress,
+    pub obligation: ObjectID,
+    pub asset: TypeName,
+    pub amount: u64,
+    pub borrow_fee: u64,
+    pub time: u64,
+}
+
+#[serde_as]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct BorrowEventV2Json {
+    pub borrower: SuiAddress,
+    pub obligation: ObjectID,
+    pub asset: TypeName,
+    #[serde_as(as = "DisplayFromStr")]
+    pub amount: u64,
+    #[serde_as(as = "DisplayFromStr")]
+    pub borrow_fee: u64,
+    #[serde_as(as = "DisplayFromStr")]
+    pub time: u64,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 struct BorrowEventV3 {
     pub borrower: SuiAddress,
@@ -109,6 +151,38 @@ struct BorrowEventV3Json {
     pub time: u64,
 }
 
+// Older borrow events are normalized into the latest version,
+// the fees they do not carry are zero.
+impl From<BorrowEvent> for BorrowEventV3 {
+    fn from(event: BorrowEvent) -> Self {
+        BorrowEventV3 {
+            borrower: event.borrower,
+            obligation: event.obligation,
+            asset: event.asset,
+            amount: event.amount,
+            borrow_fee: 0,
+            borrow_fee_discount: 0,
+            borrow_referral_fee: 0,
+            time: event.time,
+        }
+    }
+}
+
+impl From<BorrowEventV2> for BorrowEventV3 {
+    fn from(event: BorrowEventV2) -> Self {
+        BorrowEventV3 {
+            borrower: event.borrower,
+            obligation: event.obligation,
+            asset: event.asset,
+            amount: event.amount,
+            borrow_fee: event.borrow_fee,
+            borrow_fee_discount: 0,
+            borrow_referral_fee: 0,
+            time: event.time,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 struct RepayEvent {
     pub repayer: SuiAddress,
@@ -132,25 +206,30 @@ struct RepayEventJson {
 
 pub struct Scallop {
     platform: String,
-    client: Arc<SuiClient>,
+    rpc_pool: Arc<RpcPool>,
     config: Arc<ScallopConfig>,
     service: Arc<dyn lending::LendingService + Send + Sync>,
     db_service: Arc<db_service::lending::LendingService>,
+    // sender -> obligation ID, short-lived so a new obligation is picked up by the next checkpoints
+    obligation_id_cache: TtlCache<String>,
 }
 
 impl Scallop {
     pub fn new(
-        client: Arc<SuiClient>,
+        rpc_pool: Arc<RpcPool>,
         config: Arc<ScallopConfig>,
         service: Arc<dyn lending::LendingService + Send + Sync>,
         db_service: Arc<db_service::lending::LendingService>,
     ) -> Self {
         Self {
             platform: constant::SCALLOP_LENDING.to_string(),
-            client,
+            rpc_pool,
             config,
             service,
             db_service,
+            obligation_id_cache: TtlCache::new(Duration::from_millis(
+                constant::OBLIGATION_ID_CACHE_TTL_MS,
+            )),
         }
     }
 }
@@ -197,6 +276,35 @@ impl EventProcessor for Scallop {
 
                 self.process_withdraw(&event, sender).await?;
             }
+            constant::SCALLOP_BORROW_EVENT => {
+                let event: BorrowEventJson = serde_json::from_value(data)
+                    .map_err(|e| anyhow!("Failed to deserialize borrow event: {}", e))?;
+
+                let event = BorrowEvent {
+                    borrower: event.borrower,
+                    obligation: event.obligation,
+                    asset: event.asset,
+                    amount: event.amount,
+                    time: event.time,
+                };
+
+                self.process_borrow(&event.into(), sender).await?;
+            }
+            constant::SCALLOP_BORROW_EVENT_V2 => {
+                let event: BorrowEventV2Json = serde_json::from_value(data)
+                    .map_err(|e| anyhow!("Failed to deserialize borrow event: {}", e))?;
+
+                let event = BorrowEventV2 {
+                    borrower: event.borrower,
+                    obligation: event.obligation,
+                    asset: event.asset,
+                    amount: event.amount,
+                    borrow_fee: event.borrow_fee,
+                    time: event.time,
+                };
+
+                self.process_borrow(&event.into(), sender).await?;
+            }
             constant::SCALLOP_BORROW_EVENT_V3 => {
                 let event: BorrowEventV3Json = serde_json::from_value(data)
                     .map_err(|e| anyhow!("Failed to deserialize borrow event: {}", e))?;
@@ -258,9 +366,10 @@ impl EventProcessor for Scallop {
 
                 self.process_withdraw(&event, sender).await
             }
-            constant::SCALLOP_BORROW_EVENT_V3 => {
-                let event: BorrowEventV3 = bcs::from_bytes(&event.contents)
-                    .map_err(|e| anyhow!("Failed to decode borrow event: {}", e))?;
+            constant::SCALLOP_BORROW_EVENT
+            | constant::SCALLOP_BORROW_EVENT_V2
+            | constant::SCALLOP_BORROW_EVENT_V3 => {
+                let event = Self::decode_borrow_event(event_type, &event.contents)?;
 
                 self.process_borrow(&event, sender).await
             }
@@ -302,6 +411,23 @@ impl EventProcessor for Scallop {
 }
 
 impl Scallop {
+    /// Decodes any version of the borrow event into the latest one.
+    ///
+    fn decode_borrow_event(event_type: &str, contents: &[u8]) -> Result<BorrowEventV3> {
+        let event = match event_type {
+            constant::SCALLOP_BORROW_EVENT => {
+                bcs::from_bytes::<BorrowEvent>(contents).map(BorrowEventV3::from)
+            }
+            constant::SCALLOP_BORROW_EVENT_V2 => {
+                bcs::from_bytes::<BorrowEventV2>(contents).map(BorrowEventV3::from)
+            }
+            constant::SCALLOP_BORROW_EVENT_V3 => bcs::from_bytes::<BorrowEventV3>(contents),
+            _ => return Err(anyhow!("Unsupported borrow event type: {}", event_type)),
+        };
+
+        event.map_err(|e| anyhow!("Failed to decode borrow event: {}", e))
+    }
+
     async fn process_deposit(&self, event: &DepositEvent, sender: &str) -> Result<OnchainEvent> {
         self.is_owner_obligation_id(sender, event.obligation.to_string().as_str())
             .await?;
@@ -472,7 +598,16 @@ impl Scallop {
 
     // helper functions
     async fn is_owner_obligation_id(&self, sender: &str, obligation_id: &str) -> Result<()> {
-        let owner_obligation_id = self.service.find_obligation_id_from_address(sender).await?;
+        let owner_obligation_id = match self.obligation_id_cache.get(sender) {
+            Some(owner_obligation_id) => owner_obligation_id,
+            None => {
+                let owner_obligation_id =
+                    self.service.find_obligation_id_from_address(sender).await?;
+                self.obligation_id_cache
+                    .insert(sender, owner_obligation_id.clone());
+                owner_obligation_id
+            }
+        };
         info!(
             "Owner obligation ID for sender {}: {}",
             sender, owner_obligation_id
@@ -506,3 +641,167 @@ impl Scallop {
         Ok(borrower)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::db_service::mock::{MockDb, MockLendingService};
+    use std::sync::atomic::Ordering;
+
+    const OBLIGATION_ID: &str =
+        "0x1d0bdc4c4c5a6e8b2e35fa7b8a5d0b1a8c6e1f0a9d3b4c5e6f708192a3b4c5d6";
+    const ASSET: &str =
+        "dba34672e30cb065b1f93e3ab55318768fd6fef66c15942c9f7cb846e2f900e7::usdc::USDC";
+
+    /// BCS of the fields all the borrow event versions start with.
+    ///
+    fn borrow_event_head() -> Vec<u8> {
+        let mut bytes = SuiAddress::from_bytes([1u8; 32]).unwrap().to_vec();
+        bytes.extend(ObjectID::from_hex_literal(OBLIGATION_ID).unwrap().to_vec());
+        bytes.extend(bcs::to_bytes(ASSET).unwrap());
+        bytes.extend(5_000_000u64.to_le_bytes());
+
+        bytes
+    }
+
+    fn assert_borrow(event: &BorrowEventV3) {
+        assert_eq!(event.borrower, SuiAddress::from_bytes([1u8; 32]).unwrap());
+        assert_eq!(
+            event.obligation,
+            ObjectID::from_hex_literal(OBLIGATION_ID).unwrap()
+        );
+        assert_eq!(event.asset.name, ASSET);
+        assert_eq!(event.amount, 5_000_000);
+        assert_eq!(event.time, 1_735_689_600);
+    }
+
+    #[test]
+    fn borrow_event_v1_decodes_without_fees() {
+        let mut bytes = borrow_event_head();
+        bytes.extend(1_735_689_600u64.to_le_bytes());
+
+        let event = Scallop::decode_borrow_event(constant::SCALLOP_BORROW_EVENT, &bytes).unwrap();
+
+        assert_borrow(&event);
+        assert_eq!(event.borrow_fee, 0);
+        assert_eq!(event.borrow_fee_discount, 0);
+        assert_eq!(event.borrow_referral_fee, 0);
+    }
+
+    #[test]
+    fn borrow_event_v2_decodes_with_its_fee() {
+        let mut bytes = borrow_event_head();
+        bytes.extend(2_500u64.to_le_bytes());
+        bytes.extend(1_735_689_600u64.to_le_bytes());
+
+        let event =
+            Scallop::decode_borrow_event(constant::SCALLOP_BORROW_EVENT_V2, &bytes).unwrap();
+
+        assert_borrow(&event);
+        assert_eq!(event.borrow_fee, 2_500);
+        assert_eq!(event.borrow_fee_discount, 0);
+        assert_eq!(event.borrow_referral_fee, 0);
+    }
+
+    #[test]
+    fn borrow_event_v3_decodes_with_all_fees() {
+        let mut bytes = borrow_event_head();
+        for value in [2_500u64, 500, 250, 1_735_689_600] {
+            bytes.extend(value.to_le_bytes());
+        }
+
+        let event =
+            Scallop::decode_borrow_event(constant::SCALLOP_BORROW_EVENT_V3, &bytes).unwrap();
+
+        assert_borrow(&event);
+        assert_eq!(event.borrow_fee, 2_500);
+        assert_eq!(event.borrow_fee_discount, 500);
+        assert_eq!(event.borrow_referral_fee, 250);
+    }
+
+    #[test]
+    fn borrow_event_of_another_version_is_an_error() {
+        // a V1 event does not carry the fees of a V3 one
+        let mut bytes = borrow_event_head();
+        bytes.extend(1_735_689_600u64.to_le_bytes());
+
+        assert!(Scallop::decode_borrow_event(constant::SCALLOP_BORROW_EVENT_V3, &bytes).is_err());
+    }
+
+    fn scallop(lending_service: &Arc<MockLendingService>) -> Scallop {
+        let db = Arc::new(MockDb::default());
+        let config = crate::config::test_config();
+
+        Scallop::new(
+            Arc::new(RpcPool::without_clients()),
+            Arc::new(config.scallop.clone()),
+            Arc::clone(lending_service) as Arc<dyn lending::LendingService + Send + Sync>,
+            Arc::new(db.lending_service(config)),
+        )
+    }
+
+    #[tokio::test]
+    async fn obligation_id_is_looked_up_once_per_sender() {
+        let lending_service = Arc::new(MockLendingService {
+            obligation_id: Some("0xobligation".to_string()),
+            ..Default::default()
+        });
+        let processor = scallop(&lending_service);
+
+        for sender in ["0x1", "0x1", "0x2", "0x1"] {
+            processor
+                .is_owner_obligation_id(sender, "0xobligation")
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(
+            lending_service.obligation_id_lookups.load(Ordering::SeqCst),
+            2
+        );
+    }
+
+    #[tokio::test]
+    async fn cached_obligation_id_of_another_owner_is_rejected() {
+        let lending_service = Arc::new(MockLendingService {
+            obligation_id: Some("0xobligation".to_string()),
+            ..Default::default()
+        });
+        let processor = scallop(&lending_service);
+
+        processor
+            .is_owner_obligation_id("0x1", "0xobligation")
+            .await
+            .unwrap();
+        let result = processor.is_owner_obligation_id("0x1", "0xother").await;
+
+        assert!(result.is_err());
+        assert_eq!(
+            lending_service.obligation_id_lookups.load(Ordering::SeqCst),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn failed_lookup_is_not_cached() {
+        let lending_service = Arc::new(MockLendingService {
+            unavailable: true,
+            ..Default::default()
+        });
+        let processor = scallop(&lending_service);
+
+        assert!(processor
+            .is_owner_obligation_id("0x1", "0xobligation")
+            .await
+            .is_err());
+        assert!(processor
+            .is_owner_obligation_id("0x1", "0xobligation")
+            .await
+            .is_err());
+
+        assert_eq!(
+            lending_service.obligation_id_lookups.load(Ordering::SeqCst),
+            2
+        );
+    }
+}
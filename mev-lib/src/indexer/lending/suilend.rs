@@ -4,7 +4,7 @@ use crate::{
     indexer::{self, EventProcessor, OnchainEvent},
     service::{self, db_service, lending},
     types::{Borrower, FixedPoint32, TypeName},
-    utils,
+    utils::{self, rpc_pool::RpcPool, ttl_cache::TtlCache},
 };
 use db::repositories::{
     CoinRepository, PoolRepository, UserBorrowRepository, UserDepositRepository,
@@ -32,12 +32,14 @@ use std::{
     vec,
 };
 use sui_sdk::rpc_types::{SuiData, SuiMoveValue, SuiObjectDataOptions};
-use sui_sdk::SuiClient;
 use sui_types::{
     base_types::{ObjectID, SuiAddress},
     event::Event,
 };
-use tokio::{sync::mpsc, time::Instant};
+use tokio::{
+    sync::mpsc,
+    time::{Duration, Instant},
+};
 use tracing::{debug, error, info, instrument, trace, warn};
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -125,25 +127,30 @@ struct RepayEventJson {
 
 pub struct SuiLend {
     platform: String,
-    client: Arc<SuiClient>,
+    rpc_pool: Arc<RpcPool>,
     config: Arc<SuilendConfig>,
     service: Arc<dyn lending::LendingService + Send + Sync>,
     db_service: Arc<db_service::lending::LendingService>,
+    // sender -> obligation ID, short-lived so a new obligation is picked up by the next checkpoints
+    obligation_id_cache: TtlCache<String>,
 }
 
 impl SuiLend {
     pub fn new(
-        client: Arc<SuiClient>,
+        rpc_pool: Arc<RpcPool>,
         config: Arc<SuilendConfig>,
         service: Arc<dyn lending::LendingService + Send + Sync>,
         db_service: Arc<db_service::lending::LendingService>,
     ) -> Self {
         SuiLend {
             platform: constant::SUILEND_LENDING.to_string(),
-            client,
+            rpc_pool,
             config,
             service,
             db_service,
+            obligation_id_cache: TtlCache::new(Duration::from_millis(
+                constant::OBLIGATION_ID_CACHE_TTL_MS,
+            )),
         }
     }
 }
@@ -476,7 +483,16 @@ impl SuiLend {
     }
 
     async fn is_owner_obligation_id(&self, sender: &str, obligation_id: &str) -> Result<()> {
-        let owner_obligation_id = self.service.find_obligation_id_from_address(sender).await?;
+        let owner_obligation_id = match self.obligation_id_cache.get(sender) {
+            Some(owner_obligation_id) => owner_obligation_id,
+            None => {
+                let owner_obligation_id =
+                    self.service.find_obligation_id_from_address(sender).await?;
+                self.obligation_id_cache
+                    .insert(sender, owner_obligation_id.clone());
+                owner_obligation_id
+            }
+        };
         info!(
             "Owner obligation ID for sender {}: {}",
             sender, owner_obligation_id
@@ -494,3 +510,87 @@ impl SuiLend {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::db_service::mock::{MockDb, MockLendingService};
+    use std::sync::atomic::Ordering;
+
+    fn suilend(lending_service: &Arc<MockLendingService>) -> SuiLend {
+        let db = Arc::new(MockDb::default());
+        let config = crate::config::test_config();
+
+        SuiLend::new(
+            Arc::new(RpcPool::without_clients()),
+            Arc::new(config.suilend.clone()),
+            Arc::clone(lending_service) as Arc<dyn lending::LendingService + Send + Sync>,
+            Arc::new(db.lending_service(config)),
+        )
+    }
+
+    #[tokio::test]
+    async fn obligation_id_is_looked_up_once_per_sender() {
+        let lending_service = Arc::new(MockLendingService {
+            obligation_id: Some("0xobligation".to_string()),
+            ..Default::default()
+        });
+        let processor = suilend(&lending_service);
+
+        for sender in ["0x1", "0x1", "0x2", "0x1"] {
+            processor
+                .is_owner_obligation_id(sender, "0xobligation")
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(
+            lending_service.obligation_id_lookups.load(Ordering::SeqCst),
+            2
+        );
+    }
+
+    #[tokio::test]
+    async fn cached_obligation_id_of_another_owner_is_rejected() {
+        let lending_service = Arc::new(MockLendingService {
+            obligation_id: Some("0xobligation".to_string()),
+            ..Default::default()
+        });
+        let processor = suilend(&lending_service);
+
+        processor
+            .is_owner_obligation_id("0x1", "0xobligation")
+            .await
+            .unwrap();
+        let result = processor.is_owner_obligation_id("0x1", "0xother").await;
+
+        assert!(result.is_err());
+        assert_eq!(
+            lending_service.obligation_id_lookups.load(Ordering::SeqCst),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn failed_lookup_is_not_cached() {
+        let lending_service = Arc::new(MockLendingService {
+            unavailable: true,
+            ..Default::default()
+        });
+        let processor = suilend(&lending_service);
+
+        assert!(processor
+            .is_owner_obligation_id("0x1", "0xobligation")
+            .await
+            .is_err());
+        assert!(processor
+            .is_owner_obligation_id("0x1", "0xobligation")
+            .await
+            .is_err());
+
+        assert_eq!(
+            lending_service.obligation_id_lookups.load(Ordering::SeqCst),
+            2
+        );
+    }
+}
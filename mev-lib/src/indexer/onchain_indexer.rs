@@ -1,12 +1,13 @@
 use crate::{
-    config::Config,
+    config::{Config, IndexerConfig},
     constant,
     indexer::{self, registry::EventProcessorRegistry},
     service::{
+        alert::{AlertSink, LaggingAlert, WebhookAlertSink},
         db_service::{lending, pool},
         registry::ServiceRegistry,
     },
-    utils,
+    utils::{self, rpc_pool::RpcPool, sui_client::ResilientSuiClient},
 };
 use db::{
     models::metric::{Metric, NewMetric, UpdateMetric},
@@ -14,25 +15,30 @@ use db::{
         CoinRepository, MetricRepository, PoolRepository, UserBorrowRepository,
         UserDepositRepository,
     },
+    DbPool,
 };
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use futures::{
     stream::{self, StreamExt},
     Future,
 };
 use prometheus::{core::Atomic, Registry};
+use serde::{Deserialize, Serialize};
 use std::{
     path::PathBuf,
     str::FromStr,
     sync::atomic::{AtomicBool, AtomicU64, Ordering},
     time::{SystemTime, UNIX_EPOCH},
-    {collections::HashMap, sync::Arc},
+    {
+        collections::{BTreeSet, HashMap, HashSet},
+        sync::Arc,
+    },
 };
 use sui_data_ingestion_core::{
-    DataIngestionMetrics, ExecutorProgress, FileProgressStore, IndexerExecutor, ReaderOptions,
-    ShimProgressStore, Worker, WorkerPool,
+    DataIngestionMetrics, ExecutorProgress, FileProgressStore, IndexerExecutor, ProgressStore,
+    ReaderOptions, ShimProgressStore, Worker, WorkerPool,
 };
 use sui_sdk::{
     rpc_types::{CheckpointId, EventFilter, SuiTransactionBlockResponseOptions},
@@ -40,10 +46,10 @@ use sui_sdk::{
         digests::{Digest, TransactionDigest},
         messages_checkpoint::CheckpointSequenceNumber,
     },
-    SuiClient,
 };
 use sui_types::{
-    effects::TransactionEffectsAPI, event::Event, full_checkpoint_content::CheckpointData,
+    base_types::ObjectID, effects::TransactionEffectsAPI, event::Event,
+    full_checkpoint_content::CheckpointData, Identifier,
 };
 use tokio::{
     sync::{mpsc, oneshot, RwLock},
@@ -52,11 +58,92 @@ use tokio::{
 use tokio_tungstenite::tungstenite::client;
 use tracing::{debug, error, info, instrument, trace, warn};
 
+/// Progress store used by the local checkpoint reader.
+/// - Shim: always starts from the given checkpoint, progress is not persisted.
+/// - File: progress is persisted to the given file and resumed on restart.
+///
+#[derive(Debug, Clone)]
+pub enum ProgressStoreKind {
+    Shim(CheckpointSequenceNumber),
+    File(PathBuf),
+}
+
+impl ProgressStoreKind {
+    /// Uses a file store when `indexer.indexer_progress_filepath` is configured,
+    /// otherwise a shim store starting at `initial_checkpoint_number`.
+    ///
+    pub fn from_config(
+        config: &IndexerConfig,
+        initial_checkpoint_number: CheckpointSequenceNumber,
+    ) -> Self {
+        match &config.indexer_progress_filepath {
+            Some(path) => ProgressStoreKind::File(PathBuf::from(path)),
+            None => ProgressStoreKind::Shim(initial_checkpoint_number),
+        }
+    }
+
+    /// Opens the store of the progress of `task_name`.
+    /// A missing progress file is initialized at `initial_checkpoint_number`,
+    /// an existing one is resumed from its watermark.
+    ///
+    fn into_store(
+        self,
+        task_name: &str,
+        initial_checkpoint_number: CheckpointSequenceNumber,
+    ) -> Result<LocalProgressStore> {
+        match self {
+            ProgressStoreKind::Shim(checkpoint_number) => Ok(LocalProgressStore::Shim(
+                ShimProgressStore(checkpoint_number),
+            )),
+            ProgressStoreKind::File(path) => {
+                if !path.exists() {
+                    let mut content = serde_json::Map::new();
+                    content.insert(task_name.to_string(), initial_checkpoint_number.into());
+                    std::fs::write(&path, serde_json::to_vec(&content)?)?;
+                    warn!(
+                        "Initialized progress file {:?} at checkpoint #{}",
+                        path, initial_checkpoint_number
+                    );
+                }
+
+                Ok(LocalProgressStore::File(FileProgressStore::new(path)))
+            }
+        }
+    }
+}
+
+enum LocalProgressStore {
+    Shim(ShimProgressStore),
+    File(FileProgressStore),
+}
+
+#[async_trait]
+impl ProgressStore for LocalProgressStore {
+    async fn load(&mut self, task_name: String) -> Result<CheckpointSequenceNumber> {
+        match self {
+            LocalProgressStore::Shim(store) => store.load(task_name).await,
+            LocalProgressStore::File(store) => store.load(task_name).await,
+        }
+    }
+
+    async fn save(
+        &mut self,
+        task_name: String,
+        checkpoint_number: CheckpointSequenceNumber,
+    ) -> Result<()> {
+        match self {
+            LocalProgressStore::Shim(store) => store.save(task_name, checkpoint_number).await,
+            LocalProgressStore::File(store) => store.save(task_name, checkpoint_number).await,
+        }
+    }
+}
+
 pub async fn setup_local_reader<W: Worker + 'static>(
     worker: W,
     local_chk_path: String, // path to local directory with checkpoints
-    indexer_progress_filepath: String, // path to file with indexer progress
+    progress_store_kind: ProgressStoreKind, // where the indexer progress is kept
     remote_store_url: Option<String>, // for fallback
+    remote_store_options: Vec<(String, String)>, // e.g. credentials and region of an S3/GCS store
     initial_checkpoint_number: CheckpointSequenceNumber,
     concurrency: usize,
 ) -> Result<(
@@ -65,23 +152,23 @@ pub async fn setup_local_reader<W: Worker + 'static>(
 )> {
     let (exit_sender, exit_receiver) = oneshot::channel();
     let metrics = DataIngestionMetrics::new(&Registry::new());
+    let task_name = "local_reader".to_string();
 
-    //let progress_store = FileProgressStore::new(PathBuf::from(indexer_progress_filepath));
-    let progress_store = ShimProgressStore(initial_checkpoint_number);
+    let progress_store = progress_store_kind.into_store(&task_name, initial_checkpoint_number)?;
 
     let mut executor = IndexerExecutor::new(
         progress_store,
         1, /* number of workflow types */
         metrics,
     );
-    let worker_pool = WorkerPool::new(worker, "local_reader".to_string(), concurrency);
+    let worker_pool = WorkerPool::new(worker, task_name, concurrency);
     executor.register(worker_pool).await?;
 
     Ok((
         executor.run(
             PathBuf::from(local_chk_path), // path to a local directory
             remote_store_url,              // optional remote store URL
-            vec![],                        // optional remote store access options
+            remote_store_options,          // optional remote store access options
             ReaderOptions::default(),      /* remote_read_batch_size */
             exit_receiver,
         ),
@@ -100,104 +187,137 @@ impl Worker for OnchainIndexer {
         let chk_timestamp = checkpoint.checkpoint_summary.timestamp_ms;
         let lagging_timestamp_ms = utils::lagging_timestamp_ms(chk_timestamp);
 
-        // for development purposes, scan only 1 checkpoint
+        // for development purposes, scan only a few checkpoints
         if self.config.indexer.dev_mode
-            && seq_number > self.config.indexer.start_checkpoint_number + 1
+            && utils::is_beyond_dev_mode_range(
+                seq_number,
+                self.config.indexer.start_checkpoint_number,
+                self.config.indexer.dev_mode_checkpoint_count,
+            )
         {
             return Ok(());
         }
 
+        // a single worker must never go backward, the lending state would be replayed out of order
+        if self.config.indexer.strict_ordering {
+            let last_seq_number = self
+                .last_started_seq_number
+                .fetch_max(seq_number, Ordering::SeqCst);
+            if seq_number < last_seq_number {
+                return Err(anyhow!(
+                    "Checkpoint #{} out of order with strict ordering, last started #{}",
+                    seq_number,
+                    last_seq_number
+                ));
+            }
+        }
+
         warn!(
             "Start processing chk #{} with timestamp {}, lagging {}ms",
             seq_number, chk_timestamp, lagging_timestamp_ms,
         );
 
         let event_map = self.collect_unique_events(checkpoint);
-        let unique_events: Vec<_> = event_map.into_values().collect();
+        let event_map = self.skip_recently_processed_events(event_map, seq_number);
+        let unique_events: Vec<_> = event_map.into_iter().collect();
 
         info!(
             "Checkpoint #{}: collected {} unique events from transactions",
             seq_number,
             unique_events.len()
         );
+        let has_events = !unique_events.is_empty();
+
+        // the writes of the checkpoint are committed before the executor advances the progress,
+        // a failed transaction fails the checkpoint, which the executor processes again
+        let writes = self.process_checkpoint_events(checkpoint, unique_events, seq_number);
+        let (events, processed_event_ids) = match &self.checkpoint_db_pool {
+            Some(db_pool) => db::in_checkpoint_transaction(db_pool, writes).await?,
+            None => writes.await?,
+        };
+        self.record_processed_events(processed_event_ids, seq_number);
+
+        // a reprocessed checkpoint still has its events processed, but must not skew the metrics
+        let count_metrics = self
+            .counted_checkpoints
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(seq_number);
+        if !count_metrics {
+            warn!(
+                "Chk #{} was already counted, reprocessed it without updating the metrics",
+                seq_number
+            );
+        }
 
-        let events = if unique_events.is_empty() {
-            let elapsed_time = start_time.elapsed();
+        let elapsed_time = start_time.elapsed();
+        if !has_events {
             warn!(
                 "Found no events in checkpoint #{} in {:?}ms",
                 seq_number,
                 elapsed_time.as_millis()
             );
-
-            vec![]
         } else {
-            let results = stream::iter(unique_events)
-                .map(|(event, tx_digest)| async move { self.process_event(event, tx_digest).await })
-                .buffer_unordered(10)
-                .collect::<Vec<_>>()
-                .await
-                .into_iter()
-                .filter_map(|result| result.ok())
-                .collect::<Vec<_>>();
-
-            let elapsed_time = start_time.elapsed();
             warn!(
                 "Processed chk #{} with {} events in {:?}ms.",
                 seq_number,
-                results.len(),
+                events.len(),
                 elapsed_time.as_millis(),
             );
 
-            // processing time metrics
-            let processing_time = elapsed_time.as_millis() as u64;
-
-            if processing_time > self.max_processing_time.load(Ordering::SeqCst) {
-                self.max_processing_time
-                    .store(processing_time, Ordering::SeqCst);
-            };
+            // processing time metrics, a reprocessed checkpoint is already counted
+            if count_metrics {
+                let processing_time = elapsed_time.as_millis() as u64;
 
-            if processing_time < self.min_processing_time.load(Ordering::SeqCst) {
-                self.min_processing_time
-                    .store(processing_time, Ordering::SeqCst);
-            };
+                if processing_time > self.max_processing_time.load(Ordering::SeqCst) {
+                    self.max_processing_time
+                        .store(processing_time, Ordering::SeqCst);
+                };
 
-            self.total_processing_time
-                .fetch_add(processing_time, Ordering::SeqCst);
+                if processing_time < self.min_processing_time.load(Ordering::SeqCst) {
+                    self.min_processing_time
+                        .store(processing_time, Ordering::SeqCst);
+                };
 
-            self.total_processed_checkpoints
-                .fetch_add(1, Ordering::SeqCst);
+                self.total_processing_time
+                    .fetch_add(processing_time, Ordering::SeqCst);
 
-            results
-        };
+                self.total_processed_checkpoints
+                    .fetch_add(1, Ordering::SeqCst);
+            }
+        }
 
         // lagging timestamp metrics
         let lagging_timestamp_ms = utils::lagging_timestamp_ms(chk_timestamp);
 
-        if lagging_timestamp_ms > self.max_lagging.load(Ordering::SeqCst) {
-            self.max_lagging
-                .store(lagging_timestamp_ms, Ordering::SeqCst);
-        };
+        if count_metrics {
+            if lagging_timestamp_ms > self.max_lagging.load(Ordering::SeqCst) {
+                self.max_lagging
+                    .store(lagging_timestamp_ms, Ordering::SeqCst);
+            };
 
-        if lagging_timestamp_ms < self.min_lagging.load(Ordering::SeqCst) {
-            self.min_lagging
-                .store(lagging_timestamp_ms, Ordering::SeqCst);
-        };
+            if lagging_timestamp_ms < self.min_lagging.load(Ordering::SeqCst) {
+                self.min_lagging
+                    .store(lagging_timestamp_ms, Ordering::SeqCst);
+            };
 
-        self.total_lagging
-            .fetch_add(lagging_timestamp_ms, Ordering::SeqCst);
+            self.total_lagging
+                .fetch_add(lagging_timestamp_ms, Ordering::SeqCst);
 
-        self.total_checkpoints.fetch_add(1, Ordering::SeqCst);
+            self.total_checkpoints.fetch_add(1, Ordering::SeqCst);
 
-        // update the latest seq number and timestamp
-        if seq_number > self.latest_seq_number.load(Ordering::SeqCst) {
-            self.latest_seq_number.store(seq_number, Ordering::SeqCst);
+            // an old checkpoint lags by design, it must not throttle nor alert
+            self.tune_event_concurrency(lagging_timestamp_ms);
         }
 
-        if chk_timestamp > self.latest_timestamp_ms.load(Ordering::SeqCst) {
-            self.latest_timestamp_ms
-                .store(chk_timestamp, Ordering::SeqCst);
+        self.advance_latest_checkpoint(seq_number, chk_timestamp);
+
+        if count_metrics {
+            self.alert_lagging(lagging_timestamp_ms);
         }
 
+        self.notify_changed_pools(seq_number, &events);
+
         warn!(
             "Latest chk #{} with timestamp {}, lagging {}ms",
             self.latest_seq_number.load(Ordering::SeqCst),
@@ -206,51 +326,288 @@ impl Worker for OnchainIndexer {
         );
 
         // save the metrics to the database for each 1K checkpoints
-        if seq_number % 1_000 == 0 {
-            let avg_processing_time = if self.total_processed_checkpoints.load(Ordering::SeqCst) > 0
-            {
-                self.total_processing_time.load(Ordering::SeqCst) as f32
-                    / self.total_processed_checkpoints.load(Ordering::SeqCst) as f32
-            } else {
-                0.0
-            };
+        // with the latest seq number, so a backfill never moves the persisted one backward
+        if count_metrics && seq_number % 1_000 == 0 {
+            let total_checkpoints = self.total_checkpoints.load(Ordering::SeqCst);
+            let new_metric = self.build_metric(self.latest_seq_number.load(Ordering::SeqCst));
+            self.db_lending_service.save_metric_to_db(new_metric)?;
+            self.flushed_total_checkpoints
+                .store(total_checkpoints, Ordering::SeqCst);
+        }
 
-            let avg_lagging = if self.total_checkpoints.load(Ordering::SeqCst) > 0 {
-                self.total_lagging.load(Ordering::SeqCst) as f32
-                    / self.total_checkpoints.load(Ordering::SeqCst) as f32
-            } else {
-                0.0
-            };
+        Ok(())
+    }
+}
 
-            let new_metric = crate::types::Metric {
-                latest_seq_number: seq_number as i32,
-                total_checkpoints: self.total_checkpoints.load(Ordering::SeqCst) as i32,
-                total_processed_checkpoints: self.total_processed_checkpoints.load(Ordering::SeqCst)
-                    as i32,
-                max_processing_time: self.max_processing_time.load(Ordering::SeqCst) as f32,
-                min_processing_time: self.min_processing_time.load(Ordering::SeqCst) as f32,
-                avg_processing_time,
-                max_lagging: self.max_lagging.load(Ordering::SeqCst) as f32,
-                min_lagging: self.min_lagging.load(Ordering::SeqCst) as f32,
-                avg_lagging,
-            };
+/// Delay before resubscribing to the events after `attempt` failed subscriptions in a row,
+/// doubling from `EVENT_SUBSCRIPTION_BASE_DELAY_MS` up to `EVENT_SUBSCRIPTION_MAX_DELAY_MS`.
+///
+fn event_subscription_delay(attempt: u32) -> Duration {
+    utils::backoff_delay(
+        Duration::from_millis(constant::EVENT_SUBSCRIPTION_BASE_DELAY_MS),
+        Duration::from_millis(constant::EVENT_SUBSCRIPTION_MAX_DELAY_MS),
+        attempt,
+    )
+}
 
-            self.db_lending_service.save_metric_to_db(new_metric)?;
+/// Event concurrency for the next window, from the average lagging of the last one:
+/// raised above the threshold, lowered below half of it, within `[min, max]`.
+///
+fn next_event_concurrency(
+    current: u64,
+    avg_lagging: u64,
+    threshold: u64,
+    min: u64,
+    max: u64,
+) -> u64 {
+    if avg_lagging > threshold {
+        (current + constant::EVENT_CONCURRENCY_STEP).min(max)
+    } else if avg_lagging < threshold / 2 {
+        current
+            .saturating_sub(constant::EVENT_CONCURRENCY_STEP)
+            .max(min)
+    } else {
+        current
+    }
+}
+
+/// Checkpoints already counted in the metrics:
+/// all of them up to `watermark`, plus the ones above it finished out of order by the workers.
+/// The watermark is seeded from the first checkpoint inserted,
+/// so processing starting past it does not leave a gap which is never filled.
+///
+struct CountedCheckpoints {
+    watermark: Option<u64>,
+    above_watermark: BTreeSet<u64>,
+    seeded: bool,
+}
+
+impl CountedCheckpoints {
+    fn new(watermark: Option<u64>) -> Self {
+        CountedCheckpoints {
+            watermark,
+            above_watermark: BTreeSet::new(),
+            seeded: false,
         }
+    }
 
-        Ok(())
+    /// Marks the checkpoint as counted, returns false if it already was.
+    ///
+    fn insert(&mut self, seq_number: u64) -> bool {
+        if !self.seeded {
+            self.seeded = true;
+            // the checkpoints skipped before the first one are never processed by this run
+            self.watermark = self.watermark.max(seq_number.checked_sub(1));
+        }
+
+        if self
+            .watermark
+            .is_some_and(|watermark| seq_number <= watermark)
+            || !self.above_watermark.insert(seq_number)
+        {
+            return false;
+        }
+
+        // move the watermark over the contiguous counted checkpoints
+        while let Some(&first) = self.above_watermark.first() {
+            if first != self.watermark.map_or(0, |watermark| watermark + 1) {
+                break;
+            }
+            self.watermark = Some(first);
+            self.above_watermark.pop_first();
+        }
+
+        true
+    }
+}
+
+/// Checkpoint each dedupable event ID was last processed in,
+/// for the dedup across the last `window` checkpoints.
+///
+struct ProcessedEvents {
+    window: u64,
+    last_processed: HashMap<String, u64>,
+}
+
+impl ProcessedEvents {
+    fn new(window: u64) -> Self {
+        ProcessedEvents {
+            window,
+            last_processed: HashMap::new(),
+        }
+    }
+
+    /// Evicts the events processed before the window of the checkpoint,
+    /// to keep the map bounded.
+    ///
+    fn evict(&mut self, seq_number: u64) {
+        let window = self.window;
+        self.last_processed.retain(|_, last_seq_number| {
+            utils::is_within_dedup_window(seq_number, *last_seq_number, window)
+        });
+    }
+
+    /// Whether the event was processed within the window of the checkpoint.
+    ///
+    fn is_recent(&self, event_id: &str, seq_number: u64) -> bool {
+        self.last_processed
+            .get(event_id)
+            .is_some_and(|last_seq_number| {
+                utils::is_within_dedup_window(seq_number, *last_seq_number, self.window)
+            })
+    }
+
+    /// Records the event as processed in the checkpoint,
+    /// keeping the latest checkpoint as checkpoints finish out of order.
+    ///
+    fn record(&mut self, event_id: String, seq_number: u64) {
+        let last_seq_number = self.last_processed.entry(event_id).or_insert(seq_number);
+        *last_seq_number = (*last_seq_number).max(seq_number);
+    }
+}
+
+/// Values of the in-memory counters of the indexer,
+/// to hand them off to another instance, e.g. through a file on shutdown,
+/// without waiting for the metrics persisted each 1K checkpoints.
+///
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MetricSnapshot {
+    pub latest_seq_number: u64,
+    pub last_started_seq_number: u64,
+    pub latest_timestamp_ms: u64,
+    pub total_checkpoints: u64,
+    pub total_processed_checkpoints: u64,
+    pub max_processing_time: u64,
+    pub min_processing_time: u64,
+    pub total_processing_time: u64,
+    pub max_lagging: u64,
+    pub min_lagging: u64,
+    pub total_lagging: u64,
+    pub next_alert_timestamp: u64,
+    pub alert_backoff_factor: u64,
+    pub current_event_concurrency: u64,
+    pub window_lagging: u64,
+    pub window_checkpoints: u64,
+    pub event_counts: HashMap<String, u64>,
+}
+
+/// Borrowed in-memory counters of the indexer, read and written by a `MetricSnapshot`.
+///
+struct Counters<'a> {
+    latest_seq_number: &'a AtomicU64,
+    last_started_seq_number: &'a AtomicU64,
+    latest_timestamp_ms: &'a AtomicU64,
+    total_checkpoints: &'a AtomicU64,
+    total_processed_checkpoints: &'a AtomicU64,
+    max_processing_time: &'a AtomicU64,
+    min_processing_time: &'a AtomicU64,
+    total_processing_time: &'a AtomicU64,
+    max_lagging: &'a AtomicU64,
+    min_lagging: &'a AtomicU64,
+    total_lagging: &'a AtomicU64,
+    next_alert_timestamp: &'a AtomicU64,
+    alert_backoff_factor: &'a AtomicU64,
+    current_event_concurrency: &'a AtomicU64,
+    window_lagging: &'a AtomicU64,
+    window_checkpoints: &'a AtomicU64,
+    event_counts: &'a std::sync::RwLock<HashMap<String, AtomicU64>>,
+}
+
+impl Counters<'_> {
+    fn snapshot(&self) -> MetricSnapshot {
+        MetricSnapshot {
+            latest_seq_number: self.latest_seq_number.load(Ordering::SeqCst),
+            last_started_seq_number: self.last_started_seq_number.load(Ordering::SeqCst),
+            latest_timestamp_ms: self.latest_timestamp_ms.load(Ordering::SeqCst),
+            total_checkpoints: self.total_checkpoints.load(Ordering::SeqCst),
+            total_processed_checkpoints: self.total_processed_checkpoints.load(Ordering::SeqCst),
+            max_processing_time: self.max_processing_time.load(Ordering::SeqCst),
+            min_processing_time: self.min_processing_time.load(Ordering::SeqCst),
+            total_processing_time: self.total_processing_time.load(Ordering::SeqCst),
+            max_lagging: self.max_lagging.load(Ordering::SeqCst),
+            min_lagging: self.min_lagging.load(Ordering::SeqCst),
+            total_lagging: self.total_lagging.load(Ordering::SeqCst),
+            next_alert_timestamp: self.next_alert_timestamp.load(Ordering::SeqCst),
+            alert_backoff_factor: self.alert_backoff_factor.load(Ordering::SeqCst),
+            current_event_concurrency: self.current_event_concurrency.load(Ordering::SeqCst),
+            window_lagging: self.window_lagging.load(Ordering::SeqCst),
+            window_checkpoints: self.window_checkpoints.load(Ordering::SeqCst),
+            event_counts: self.event_counts(),
+        }
+    }
+
+    fn restore(&self, snapshot: MetricSnapshot) {
+        self.latest_seq_number
+            .store(snapshot.latest_seq_number, Ordering::SeqCst);
+        self.last_started_seq_number
+            .store(snapshot.last_started_seq_number, Ordering::SeqCst);
+        self.latest_timestamp_ms
+            .store(snapshot.latest_timestamp_ms, Ordering::SeqCst);
+        self.total_checkpoints
+            .store(snapshot.total_checkpoints, Ordering::SeqCst);
+        self.total_processed_checkpoints
+            .store(snapshot.total_processed_checkpoints, Ordering::SeqCst);
+        self.max_processing_time
+            .store(snapshot.max_processing_time, Ordering::SeqCst);
+        self.min_processing_time
+            .store(snapshot.min_processing_time, Ordering::SeqCst);
+        self.total_processing_time
+            .store(snapshot.total_processing_time, Ordering::SeqCst);
+        self.max_lagging
+            .store(snapshot.max_lagging, Ordering::SeqCst);
+        self.min_lagging
+            .store(snapshot.min_lagging, Ordering::SeqCst);
+        self.total_lagging
+            .store(snapshot.total_lagging, Ordering::SeqCst);
+        self.next_alert_timestamp
+            .store(snapshot.next_alert_timestamp, Ordering::SeqCst);
+        self.alert_backoff_factor
+            .store(snapshot.alert_backoff_factor, Ordering::SeqCst);
+        self.current_event_concurrency
+            .store(snapshot.current_event_concurrency, Ordering::SeqCst);
+        self.window_lagging
+            .store(snapshot.window_lagging, Ordering::SeqCst);
+        self.window_checkpoints
+            .store(snapshot.window_checkpoints, Ordering::SeqCst);
+
+        match self.event_counts.write() {
+            Ok(mut event_counts) => {
+                *event_counts = snapshot
+                    .event_counts
+                    .into_iter()
+                    .map(|(source, count)| (source, AtomicU64::new(count)))
+                    .collect();
+            }
+            Err(e) => error!("Failed to write event counts: {}", e),
+        }
+    }
+
+    fn event_counts(&self) -> HashMap<String, u64> {
+        match self.event_counts.read() {
+            Ok(event_counts) => event_counts
+                .iter()
+                .map(|(source, count)| (source.clone(), count.load(Ordering::SeqCst)))
+                .collect(),
+            Err(e) => {
+                error!("Failed to read event counts: {}", e);
+                HashMap::new()
+            }
+        }
     }
 }
 
+#[derive(Clone)]
 pub struct OnchainIndexer {
     config: Arc<Config>,
-    client: Arc<SuiClient>,
+    rpc_pool: Arc<RpcPool>,
     db_pool_service: Arc<pool::PoolService>,
     db_lending_service: Arc<lending::LendingService>,
     service_registry: Arc<ServiceRegistry>,
     event_processor_registry: Arc<EventProcessorRegistry>,
 
     latest_seq_number: Arc<AtomicU64>,
+    // highest checkpoint started in this run, to check the order with strict ordering
+    last_started_seq_number: Arc<AtomicU64>,
     pub latest_timestamp_ms: Arc<AtomicU64>,
     pub start_seq_number: u64,
 
@@ -262,22 +619,42 @@ pub struct OnchainIndexer {
     max_lagging: Arc<AtomicU64>,
     min_lagging: Arc<AtomicU64>,
     total_lagging: Arc<AtomicU64>,
+    // total checkpoints when the metrics were last persisted, to skip unchanged flushes
+    flushed_total_checkpoints: Arc<AtomicU64>,
 
     next_alert_timestamp: Arc<AtomicU64>,
     alert_backoff_factor: Arc<AtomicU64>,
+    alert_sink: Option<Arc<dyn AlertSink>>,
+
+    current_event_concurrency: Arc<AtomicU64>,
+    window_lagging: Arc<AtomicU64>,
+    window_checkpoints: Arc<AtomicU64>,
+
+    event_counts: Arc<std::sync::RwLock<HashMap<String, AtomicU64>>>,
+    // receives the pools changed by each checkpoint, e.g. to trigger arbitrage
+    changed_pools_sender: Option<mpsc::Sender<HashSet<String>>>,
+    // checkpoint each event ID was last processed in, for the cross-checkpoint dedup
+    processed_events: Arc<std::sync::Mutex<ProcessedEvents>>,
+    // checkpoints counted in the metrics, so a reprocessed checkpoint is not counted twice
+    counted_checkpoints: Arc<std::sync::Mutex<CountedCheckpoints>>,
+    // pool of the checkpoint transactions, the writes are not transactional when not set
+    checkpoint_db_pool: Option<DbPool>,
 }
 
 impl OnchainIndexer {
     pub fn new(
         config: Arc<Config>,
-        client: Arc<SuiClient>,
+        rpc_pool: Arc<RpcPool>,
         db_pool_service: Arc<pool::PoolService>,
         db_lending_service: Arc<lending::LendingService>,
         service_registry: Arc<ServiceRegistry>,
         event_processor_registry: Arc<EventProcessorRegistry>,
         latest_timestamp_ms: Arc<AtomicU64>,
+        changed_pools_sender: Option<mpsc::Sender<HashSet<String>>>,
     ) -> Self {
         let mut start_seq_number = config.indexer.start_checkpoint_number;
+        // nothing before the start checkpoint is counted yet
+        let mut counted_watermark = start_seq_number.checked_sub(1);
         let total_checkpoints = Arc::new(AtomicU64::new(0));
         let total_processed_checkpoints = Arc::new(AtomicU64::new(0));
         let max_processing_time = Arc::new(AtomicU64::new(0));
@@ -298,6 +675,8 @@ impl OnchainIndexer {
                 );
 
                 start_seq_number = latest_checkpoint.latest_seq_number as u64;
+                // the restored totals already count the persisted checkpoint
+                counted_watermark = Some(start_seq_number);
 
                 total_checkpoints
                     .store(latest_checkpoint.total_checkpoints as u64, Ordering::SeqCst);
@@ -340,15 +719,33 @@ impl OnchainIndexer {
         }
 
         let latest_seq_number = Arc::new(AtomicU64::new(start_seq_number));
+        // the restored metrics are already persisted
+        let initial_total_checkpoints = total_checkpoints.load(Ordering::SeqCst);
+
+        let alert_sink = config
+            .indexer
+            .alert_webhook_url
+            .as_deref()
+            .map(|url| Arc::new(WebhookAlertSink::new(url)) as Arc<dyn AlertSink>);
+
+        // not clamp, which panics on a misconfigured range
+        let (min_concurrency, max_concurrency) = config.indexer.event_concurrency_range();
+        let event_concurrency = config
+            .indexer
+            .event_concurrency
+            .max(min_concurrency)
+            .min(max_concurrency) as u64;
+        let processed_events = ProcessedEvents::new(config.indexer.dedup_window_checkpoints);
 
         OnchainIndexer {
             config,
-            client,
+            rpc_pool,
             db_pool_service,
             db_lending_service,
             service_registry,
             event_processor_registry,
             latest_seq_number,
+            last_started_seq_number: Arc::new(AtomicU64::new(0)),
             latest_timestamp_ms,
             start_seq_number,
             total_checkpoints,
@@ -359,17 +756,456 @@ impl OnchainIndexer {
             max_lagging,
             min_lagging,
             total_lagging,
+            flushed_total_checkpoints: Arc::new(AtomicU64::new(initial_total_checkpoints)),
             next_alert_timestamp: Arc::new(AtomicU64::new(0)),
             alert_backoff_factor: Arc::new(AtomicU64::new(0)),
+            alert_sink,
+            current_event_concurrency: Arc::new(AtomicU64::new(event_concurrency)),
+            window_lagging: Arc::new(AtomicU64::new(0)),
+            window_checkpoints: Arc::new(AtomicU64::new(0)),
+            event_counts: Arc::new(std::sync::RwLock::new(HashMap::new())),
+            changed_pools_sender,
+            processed_events: Arc::new(std::sync::Mutex::new(processed_events)),
+            counted_checkpoints: Arc::new(std::sync::Mutex::new(CountedCheckpoints::new(
+                counted_watermark,
+            ))),
+            checkpoint_db_pool: None,
+        }
+    }
+
+    /// Commits the writes of each checkpoint in a single transaction on `db_pool`,
+    /// so a checkpoint failing or crashing in the middle persists nothing.
+    /// Each checkpoint worker holds a connection of the pool while processing.
+    ///
+    pub fn with_checkpoint_transactions(mut self, db_pool: DbPool) -> Self {
+        self.checkpoint_db_pool = Some(db_pool);
+        self
+    }
+
+    /// Sends the lagging alerts to `alert_sink` instead of the configured webhook, if any.
+    ///
+    pub fn with_alert_sink(mut self, alert_sink: Arc<dyn AlertSink>) -> Self {
+        self.alert_sink = Some(alert_sink);
+        self
+    }
+
+    /// Builds a metric snapshot from the in-memory counters.
+    /// Averages fall back to 0 when no checkpoint has been counted yet.
+    ///
+    fn build_metric(&self, seq_number: u64) -> crate::types::Metric {
+        let total_checkpoints = self.total_checkpoints.load(Ordering::SeqCst);
+        let total_processed_checkpoints = self.total_processed_checkpoints.load(Ordering::SeqCst);
+
+        let avg_processing_time = if total_processed_checkpoints > 0 {
+            self.total_processing_time.load(Ordering::SeqCst) as f32
+                / total_processed_checkpoints as f32
+        } else {
+            0.0
+        };
+
+        let avg_lagging = if total_checkpoints > 0 {
+            self.total_lagging.load(Ordering::SeqCst) as f32 / total_checkpoints as f32
+        } else {
+            0.0
+        };
+
+        // min values are initialized to u64::MAX until the first sample
+        let min_processing_time = match self.min_processing_time.load(Ordering::SeqCst) {
+            u64::MAX => 0.0,
+            v => v as f32,
+        };
+
+        let min_lagging = match self.min_lagging.load(Ordering::SeqCst) {
+            u64::MAX => 0.0,
+            v => v as f32,
+        };
+
+        crate::types::Metric {
+            latest_seq_number: seq_number as i32,
+            total_checkpoints: total_checkpoints as i32,
+            total_processed_checkpoints: total_processed_checkpoints as i32,
+            max_processing_time: self.max_processing_time.load(Ordering::SeqCst) as f32,
+            min_processing_time,
+            avg_processing_time,
+            max_lagging: self.max_lagging.load(Ordering::SeqCst) as f32,
+            min_lagging,
+            avg_lagging,
+        }
+    }
+
+    /// Persists the current metrics, e.g. before shutting down or periodically,
+    /// so the counters accumulated since the last 1K checkpoint boundary are not lost.
+    /// Skipped when no checkpoint was counted since the last time the metrics were persisted.
+    ///
+    pub fn flush_metrics(&self) -> Result<()> {
+        let total_checkpoints = self.total_checkpoints.load(Ordering::SeqCst);
+        if total_checkpoints == self.flushed_total_checkpoints.load(Ordering::SeqCst) {
+            info!("No checkpoint processed since the last flush, skip flushing metrics");
+            return Ok(());
+        }
+
+        let metric = self.build_metric(self.latest_seq_number.load(Ordering::SeqCst));
+        self.db_lending_service.save_metric_to_db(metric)?;
+        self.flushed_total_checkpoints
+            .store(total_checkpoints, Ordering::SeqCst);
+
+        warn!(
+            "Flushed metrics at chk #{}",
+            self.latest_seq_number.load(Ordering::SeqCst)
+        );
+
+        Ok(())
+    }
+
+    /// Flushes the metrics every `period` until `shutdown` is sent or dropped.
+    /// The final flush on shutdown is left to the caller, once this returned.
+    ///
+    pub async fn run_metrics_flush(&self, period: Duration, mut shutdown: oneshot::Receiver<()>) {
+        let mut interval = tokio::time::interval(period);
+
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {
+                    if let Err(e) = self.flush_metrics() {
+                        error!("Failed to flush metrics: {}", e);
+                    }
+                }
+                _ = &mut shutdown => {
+                    info!("Stopped flushing metrics");
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Reads all the in-memory counters.
+    ///
+    pub fn snapshot_counters(&self) -> MetricSnapshot {
+        self.counters().snapshot()
+    }
+
+    /// Overwrites all the in-memory counters with the snapshot,
+    /// e.g. one taken by the previous instance, instead of the values restored from the database.
+    /// Call it before processing checkpoints.
+    ///
+    pub fn restore_counters(&self, snapshot: MetricSnapshot) {
+        let latest_seq_number = snapshot.latest_seq_number;
+        self.counters().restore(snapshot);
+
+        *self
+            .counted_checkpoints
+            .lock()
+            .unwrap_or_else(|e| e.into_inner()) = CountedCheckpoints::new(Some(latest_seq_number));
+
+        info!("Restored counters at chk #{}", latest_seq_number);
+    }
+
+    fn counters(&self) -> Counters<'_> {
+        Counters {
+            latest_seq_number: &self.latest_seq_number,
+            last_started_seq_number: &self.last_started_seq_number,
+            latest_timestamp_ms: &self.latest_timestamp_ms,
+            total_checkpoints: &self.total_checkpoints,
+            total_processed_checkpoints: &self.total_processed_checkpoints,
+            max_processing_time: &self.max_processing_time,
+            min_processing_time: &self.min_processing_time,
+            total_processing_time: &self.total_processing_time,
+            max_lagging: &self.max_lagging,
+            min_lagging: &self.min_lagging,
+            total_lagging: &self.total_lagging,
+            next_alert_timestamp: &self.next_alert_timestamp,
+            alert_backoff_factor: &self.alert_backoff_factor,
+            current_event_concurrency: &self.current_event_concurrency,
+            window_lagging: &self.window_lagging,
+            window_checkpoints: &self.window_checkpoints,
+            event_counts: &self.event_counts,
+        }
+    }
+
+    /// Lag of the latest processed checkpoint behind the current time,
+    /// 0 until a checkpoint is processed.
+    ///
+    pub fn latest_lagging_ms(&self) -> u64 {
+        utils::lagging_timestamp_ms(self.latest_timestamp_ms.load(Ordering::SeqCst))
+    }
+
+    /// Moves the latest seq number and timestamp forward to the processed checkpoint,
+    /// a checkpoint finished out of order by a worker never moves them backward.
+    ///
+    fn advance_latest_checkpoint(&self, seq_number: u64, chk_timestamp: u64) {
+        self.latest_seq_number.fetch_max(seq_number, Ordering::SeqCst);
+        self.latest_timestamp_ms.fetch_max(chk_timestamp, Ordering::SeqCst);
+    }
+
+    /// Returns the concurrency currently used to process events of a checkpoint.
+    ///
+    pub fn event_concurrency(&self) -> u64 {
+        self.current_event_concurrency.load(Ordering::SeqCst)
+    }
+
+    /// Returns the number of processed events by exchange, lending platform or oracle.
+    ///
+    pub fn event_counts_snapshot(&self) -> HashMap<String, u64> {
+        self.counters().event_counts()
+    }
+
+    /// Renders the event counters and the checkpoint metrics in Prometheus text format.
+    ///
+    pub fn render_prometheus_metrics(&self) -> String {
+        let metric = self.build_metric(self.latest_seq_number.load(Ordering::SeqCst));
+        let mut output = String::new();
+
+        output.push_str("# TYPE indexer_events_total counter\n");
+        let mut event_counts = self.event_counts_snapshot().into_iter().collect::<Vec<_>>();
+        event_counts.sort();
+        for (source, count) in event_counts {
+            output.push_str(&format!(
+                "indexer_events_total{{source=\"{}\"}} {}\n",
+                source, count
+            ));
+        }
+
+        let gauges = [
+            ("indexer_latest_seq_number", metric.latest_seq_number as f32),
+            ("indexer_total_checkpoints", metric.total_checkpoints as f32),
+            (
+                "indexer_total_processed_checkpoints",
+                metric.total_processed_checkpoints as f32,
+            ),
+            ("indexer_max_processing_time_ms", metric.max_processing_time),
+            ("indexer_min_processing_time_ms", metric.min_processing_time),
+            ("indexer_avg_processing_time_ms", metric.avg_processing_time),
+            ("indexer_max_lagging_ms", metric.max_lagging),
+            ("indexer_min_lagging_ms", metric.min_lagging),
+            ("indexer_avg_lagging_ms", metric.avg_lagging),
+            ("indexer_event_concurrency", self.event_concurrency() as f32),
+        ];
+
+        for (name, value) in gauges {
+            output.push_str(&format!("# TYPE {} gauge\n{} {}\n", name, name, value));
+        }
+
+        output
+    }
+
+    /// Increments the event counter of the event source.
+    ///
+    fn count_event(&self, event: &indexer::OnchainEvent) {
+        let Some(source) = event.source() else {
+            return;
+        };
+
+        if let Ok(event_counts) = self.event_counts.read() {
+            if let Some(count) = event_counts.get(source) {
+                count.fetch_add(1, Ordering::SeqCst);
+                return;
+            }
+        }
+
+        match self.event_counts.write() {
+            Ok(mut event_counts) => {
+                event_counts
+                    .entry(source.to_string())
+                    .or_insert_with(|| AtomicU64::new(0))
+                    .fetch_add(1, Ordering::SeqCst);
+            }
+            Err(e) => error!("Failed to update event counts: {}", e),
+        }
+    }
+
+    /// Fires a lagging alert when the lagging exceeds the threshold.
+    /// Alerts are repeated with an exponential backoff while the lagging stays above it,
+    /// the backoff is reset once the lagging is back under the threshold.
+    ///
+    fn alert_lagging(&self, lagging_timestamp_ms: u64) {
+        let threshold = self.config.indexer.lagging_ms_threshold;
+
+        // back under the threshold, the next lagging is alerted right away
+        if lagging_timestamp_ms <= threshold {
+            if self.alert_backoff_factor.swap(0, Ordering::SeqCst) > 0 {
+                self.next_alert_timestamp.store(0, Ordering::SeqCst);
+                info!(
+                    "Lagging {}ms back under the threshold {}ms",
+                    lagging_timestamp_ms, threshold
+                );
+            }
+            return;
+        }
+
+        let now = utils::get_current_timestamp_ms();
+        let next_alert_timestamp = self.next_alert_timestamp.load(Ordering::SeqCst);
+        if now < next_alert_timestamp {
+            return;
+        }
+
+        let backoff_factor = self
+            .alert_backoff_factor
+            .load(Ordering::SeqCst)
+            .min(constant::LAGGING_ALERT_MAX_BACKOFF_FACTOR);
+        let interval = constant::LAGGING_ALERT_BASE_INTERVAL_MS * 2u64.pow(backoff_factor as u32);
+
+        // another checkpoint already fired this alert
+        if self
+            .next_alert_timestamp
+            .compare_exchange(
+                next_alert_timestamp,
+                now + interval,
+                Ordering::SeqCst,
+                Ordering::SeqCst,
+            )
+            .is_err()
+        {
+            return;
+        }
+        self.alert_backoff_factor
+            .store(backoff_factor + 1, Ordering::SeqCst);
+
+        let alert = LaggingAlert {
+            lagging_ms: lagging_timestamp_ms,
+            lagging_ms_threshold: threshold,
+            latest_seq_number: self.latest_seq_number.load(Ordering::SeqCst),
+            latest_timestamp_ms: self.latest_timestamp_ms.load(Ordering::SeqCst),
+        };
+
+        error!(
+            "Lagging {}ms exceeds the threshold {}ms at chk #{}, next alert in {}ms",
+            alert.lagging_ms, threshold, alert.latest_seq_number, interval
+        );
+
+        // do not hold the checkpoint processing on the webhook
+        if let Some(alert_sink) = &self.alert_sink {
+            let alert_sink = Arc::clone(alert_sink);
+            tokio::spawn(async move {
+                if let Err(e) = alert_sink.send(&alert).await {
+                    error!("Failed to send lagging alert: {}", e);
+                }
+            });
+        }
+    }
+
+    /// Sends the pools changed by the checkpoint events to the subscriber, if any.
+    /// Never blocks the indexing, the set is dropped if the channel is full.
+    ///
+    fn notify_changed_pools(&self, seq_number: u64, events: &[indexer::OnchainEvent]) {
+        let Some(sender) = &self.changed_pools_sender else {
+            return;
+        };
+
+        let changed_pools = events
+            .iter()
+            .filter_map(|event| event.pool_id())
+            .map(|pool_id| pool_id.to_string())
+            .collect::<HashSet<_>>();
+
+        if changed_pools.is_empty() {
+            return;
+        }
+
+        debug!(
+            "Checkpoint #{} changed {} pools",
+            seq_number,
+            changed_pools.len()
+        );
+
+        if let Err(e) = sender.try_send(changed_pools) {
+            warn!(
+                "Failed to send changed pools of checkpoint #{}: {}",
+                seq_number, e
+            );
+        }
+    }
+
+    /// Adjusts the event processing concurrency every N checkpoints.
+    /// Raises it when the average lagging of the window is above the threshold,
+    /// lowers it when the average lagging is below half of the threshold.
+    ///
+    fn tune_event_concurrency(&self, lagging_timestamp_ms: u64) {
+        self.window_lagging
+            .fetch_add(lagging_timestamp_ms, Ordering::SeqCst);
+        let window_checkpoints = self.window_checkpoints.fetch_add(1, Ordering::SeqCst) + 1;
+
+        if window_checkpoints < constant::EVENT_CONCURRENCY_TUNING_INTERVAL {
+            return;
+        }
+
+        // another checkpoint already closed this window
+        if self
+            .window_checkpoints
+            .compare_exchange(window_checkpoints, 0, Ordering::SeqCst, Ordering::SeqCst)
+            .is_err()
+        {
+            return;
+        }
+
+        let avg_lagging = self.window_lagging.swap(0, Ordering::SeqCst) / window_checkpoints;
+        let threshold = self.config.indexer.lagging_ms_threshold;
+        let (min_concurrency, max_concurrency) = self.config.indexer.event_concurrency_range();
+        let (min_concurrency, max_concurrency) = (min_concurrency as u64, max_concurrency as u64);
+        let current = self.current_event_concurrency.load(Ordering::SeqCst);
+        let next = next_event_concurrency(
+            current,
+            avg_lagging,
+            threshold,
+            min_concurrency,
+            max_concurrency,
+        );
+
+        if next != current {
+            self.current_event_concurrency.store(next, Ordering::SeqCst);
+            warn!(
+                "Average lagging {}ms over {} checkpoints, event concurrency {} -> {}",
+                avg_lagging, window_checkpoints, current, next
+            );
         }
     }
 
+    /// Records the swaps and processes the unique events of the checkpoint,
+    /// returns the events processed and the IDs of the ones to dedup in the next checkpoints.
+    /// A failed event is skipped, it does not fail the checkpoint.
+    ///
+    async fn process_checkpoint_events(
+        &self,
+        checkpoint: &CheckpointData,
+        unique_events: Vec<(String, (Event, String))>,
+        seq_number: u64,
+    ) -> Result<(Vec<indexer::OnchainEvent>, Vec<String>)> {
+        // every swap is recorded, the dedup keeps only the last one of each pool
+        self.record_swap_events(checkpoint).await;
+
+        let event_concurrency = self.current_event_concurrency.load(Ordering::SeqCst) as usize;
+
+        let mut processed_event_ids = vec![];
+        let events = stream::iter(unique_events)
+            .map(|(event_id, (event, tx_digest))| async move {
+                let dedupable = self.is_window_dedupable(&event);
+                let result = self.process_event(event, tx_digest, seq_number).await;
+                (event_id, dedupable, result)
+            })
+            .buffer_unordered(event_concurrency.max(1))
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .filter_map(|(event_id, dedupable, result)| {
+                let event = result.ok()?;
+                if dedupable {
+                    processed_event_ids.push(event_id);
+                }
+                Some(event)
+            })
+            .collect::<Vec<_>>();
+
+        Ok((events, processed_event_ids))
+    }
+
     /// Process a single event in checkpoint data.
     ///
+    /// Records the event to the failed events table if `indexer.record_failed_events` is set.
+    ///
     async fn process_event(
         &self,
         event: Event,
         tx_digest: String,
+        seq_number: u64,
     ) -> Result<indexer::OnchainEvent> {
         let start = Instant::now();
 
@@ -381,16 +1217,103 @@ impl OnchainIndexer {
             .await
             .map_err(|e| {
                 error!("failed to process event: {}: {}", event_type, e);
+
+                if self.config.indexer.record_failed_events {
+                    if let Err(db_err) = self.db_lending_service.save_failed_event_to_db(
+                        &event_type,
+                        &tx_digest,
+                        seq_number,
+                        &e.to_string(),
+                    ) {
+                        error!("failed to record failed event {}: {}", event_type, db_err);
+                    }
+                }
+
                 e
             })?;
 
+        self.count_event(&processed_event);
+
         let elapsed = start.elapsed();
         info!("Processed event {:?} in {:?}", event_type, elapsed);
 
         Ok(processed_event)
     }
 
-    /// Process transaction events by tx_digest.
+    /// Reprocesses checkpoints in the range [start, end] sequentially.
+    /// Checkpoints up to the latest processed one are skipped unless `force` is true.
+    ///
+    pub async fn reprocess_checkpoint_range(
+        &self,
+        start: u64,
+        end: u64,
+        force: bool,
+    ) -> Result<()> {
+        if start > end {
+            return Err(anyhow!("Invalid checkpoint range: {} > {}", start, end));
+        }
+
+        let network_config = self
+            .config
+            .networks
+            .get(&self.config.run_mode)
+            .ok_or_else(|| anyhow!("Network config not found for {}", self.config.run_mode))?;
+
+        let latest_seq_number = self.latest_seq_number.load(Ordering::SeqCst);
+        let total = end - start + 1;
+
+        for (i, seq_number) in (start..=end).enumerate() {
+            if !force && seq_number <= latest_seq_number {
+                info!(
+                    "Skip chk #{}, already processed up to #{}",
+                    seq_number, latest_seq_number
+                );
+                continue;
+            }
+
+            let checkpoint =
+                Self::fetch_checkpoint(&network_config.remote_store_url, seq_number).await?;
+
+            self.process_checkpoint(&checkpoint).await?;
+
+            warn!("Backfilled chk #{} ({}/{})", seq_number, i + 1, total);
+        }
+
+        Ok(())
+    }
+
+    /// Downloads a checkpoint from the remote store.
+    /// The blob is a 1-byte encoding flag followed by the BCS encoded checkpoint data.
+    ///
+    pub async fn fetch_checkpoint(
+        remote_store_url: &str,
+        seq_number: u64,
+    ) -> Result<CheckpointData> {
+        let url = format!(
+            "{}/{}.chk",
+            remote_store_url.trim_end_matches('/'),
+            seq_number
+        );
+
+        let bytes = reqwest::get(&url)
+            .await
+            .map_err(|e| anyhow!("Failed to fetch checkpoint {}: {}", url, e))?
+            .error_for_status()
+            .map_err(|e| anyhow!("Failed to fetch checkpoint {}: {}", url, e))?
+            .bytes()
+            .await?;
+
+        match bytes.first() {
+            Some(1) => bcs::from_bytes::<CheckpointData>(&bytes[1..])
+                .map_err(|e| anyhow!("Failed to decode checkpoint #{}: {}", seq_number, e)),
+            _ => Err(anyhow!(
+                "Unsupported blob encoding for checkpoint #{}",
+                seq_number
+            )),
+        }
+    }
+
+    /// Process transaction events by tx_digest.
     ///
     pub async fn process_tx_events(&self, tx_digest: &str) -> Result<()> {
         let tx_digest = TransactionDigest::from_str(tx_digest)
@@ -406,9 +1329,16 @@ impl OnchainIndexer {
             show_balance_changes: true,
         };
         let tx = self
-            .client
-            .read_api()
-            .get_transaction_with_options(tx_digest, options)
+            .rpc_pool
+            .call(|client| {
+                let options = options.clone();
+                async move {
+                    client
+                        .read_api()
+                        .get_transaction_with_options(tx_digest, options)
+                        .await
+                }
+            })
             .await?;
 
         if let Some(events) = tx.events {
@@ -442,23 +1372,268 @@ impl OnchainIndexer {
         Ok(())
     }
 
+    /// Event filters matching the modules of all registered event types,
+    /// generic events are matched regardless of their type parameters.
+    ///
+    pub fn event_subscription_filters(&self) -> Result<Vec<EventFilter>> {
+        let modules = self
+            .event_processor_registry
+            .registered_event_types()
+            .iter()
+            .map(|event_type| {
+                let parts = event_type.split("::").collect::<Vec<_>>();
+                if parts.len() != 3 {
+                    return Err(anyhow!("Invalid event type: {}", event_type));
+                }
+                Ok((parts[0].to_string(), parts[1].to_string()))
+            })
+            .collect::<Result<BTreeSet<_>>>()?;
+
+        modules
+            .into_iter()
+            .map(|(package, module)| {
+                Ok(EventFilter::MoveEventModule {
+                    package: ObjectID::from_hex_literal(&package)
+                        .map_err(|e| anyhow!("Invalid package ID {}: {}", package, e))?,
+                    module: Identifier::new(module.as_str())
+                        .map_err(|e| anyhow!("Invalid module {}: {}", module, e))?,
+                })
+            })
+            .collect()
+    }
+
+    /// Subscribes to the events matching any of the given filters over websocket,
+    /// as a low-latency alternative to reading full checkpoints.
+    /// Each event is routed through the processor registry.
+    /// Reconnects with an exponential backoff whenever the subscription drops,
+    /// the backoff is reset once a subscription is established.
+    /// The websocket client is rebuilt before resubscribing,
+    /// as a client whose connection dropped cannot subscribe anymore.
+    ///
+    pub async fn run_event_subscription(
+        &self,
+        sui_client: Arc<ResilientSuiClient>,
+        event_filters: Vec<EventFilter>,
+    ) -> Result<()> {
+        let filter = EventFilter::Any(event_filters);
+        let mut attempt = 0;
+
+        loop {
+            let client = sui_client.client().await;
+
+            match client.event_api().subscribe_event(filter.clone()).await {
+                Ok(mut subscription) => {
+                    warn!("Event subscription established");
+                    attempt = 0;
+
+                    while let Some(event) = subscription.next().await {
+                        let event = match event {
+                            Ok(event) => event,
+                            Err(e) => {
+                                error!("Event subscription failed: {}", e);
+                                break;
+                            }
+                        };
+
+                        let start = Instant::now();
+                        let event_type = event.type_.to_string();
+                        let tx_digest = event.id.tx_digest.to_string();
+
+                        match self
+                            .event_processor_registry
+                            .process_tx_event(event, &tx_digest)
+                            .await
+                        {
+                            Ok(_) => {
+                                info!(
+                                    "Processed event {} in {:?}ms",
+                                    event_type,
+                                    start.elapsed().as_millis()
+                                );
+                            }
+                            // module filters also match events without a processor
+                            Err(e) => {
+                                debug!("Failed to process event: {}: {}", event_type, e);
+                            }
+                        }
+                    }
+
+                    warn!("Event subscription closed");
+                }
+                Err(e) => {
+                    error!("Failed to subscribe to events: {}", e);
+                }
+            }
+
+            let delay = event_subscription_delay(attempt);
+            attempt = attempt.saturating_add(1);
+            warn!(
+                "Reconnecting event subscription in {:?}, attempt {}",
+                delay, attempt
+            );
+            sleep(delay).await;
+
+            if let Err(e) = sui_client.reconnect(&client).await {
+                error!("Failed to reconnect Sui client: {}", e);
+            }
+        }
+    }
+
+    /// Drops the dedupable events whose ID was processed
+    /// within the last `indexer.dedup_window_checkpoints` checkpoints.
+    /// Entries older than the window are evicted to keep the map bounded.
+    fn skip_recently_processed_events(
+        &self,
+        event_map: HashMap<String, (Event, String)>,
+        seq_number: u64,
+    ) -> HashMap<String, (Event, String)> {
+        if self.config.indexer.dedup_window_checkpoints == 0 {
+            return event_map;
+        }
+
+        let mut processed_events = self
+            .processed_events
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        processed_events.evict(seq_number);
+
+        event_map
+            .into_iter()
+            .filter(|(event_id, (event, _))| {
+                if !self.is_window_dedupable(event)
+                    || !processed_events.is_recent(event_id, seq_number)
+                {
+                    return true;
+                }
+
+                debug!(
+                    "Skip event {} already processed within the window of chk #{}",
+                    event_id, seq_number
+                );
+                false
+            })
+            .collect()
+    }
+
+    /// Records the events processed successfully in the checkpoint,
+    /// a failed event is processed again in the next checkpoints.
+    fn record_processed_events(&self, event_ids: Vec<String>, seq_number: u64) {
+        if self.config.indexer.dedup_window_checkpoints == 0 {
+            return;
+        }
+
+        let mut processed_events = self
+            .processed_events
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        for event_id in event_ids {
+            processed_events.record(event_id, seq_number);
+        }
+    }
+
+    /// Whether the event may be skipped when processed within the dedup window.
+    fn is_window_dedupable(&self, event: &Event) -> bool {
+        if !self.event_processor_registry.is_dedupable_event(event) {
+            return false;
+        }
+
+        let type_str = event.type_.to_string();
+        !self.is_no_dedup_event_type(type_str.split('<').next().unwrap_or(&type_str))
+    }
+
+    /// Whether every occurrence of the event type must be processed,
+    /// as set by `indexer.no_dedup_event_types`.
+    ///
+    fn is_no_dedup_event_type(&self, event_type: &str) -> bool {
+        self.config
+            .indexer
+            .no_dedup_event_types
+            .iter()
+            .any(|t| t == event_type)
+    }
+
+    /// Records every swap of the checkpoint for volume analytics,
+    /// before the swaps of a pool are deduplicated down to the last one.
+    /// The event seq is the index of the event in its tx.
+    async fn record_swap_events(&self, checkpoint: &CheckpointData) {
+        let mut swap_events = vec![];
+
+        for tx in &checkpoint.transactions {
+            let Some(tx_events) = &tx.events else {
+                continue;
+            };
+            let tx_digest = tx.effects.transaction_digest().to_string();
+
+            for (event_seq, event) in tx_events.data.iter().enumerate() {
+                let type_str = event.type_.to_string();
+                let event_type = type_str.split('<').next().unwrap_or(&type_str);
+                if !self
+                    .event_processor_registry
+                    .is_known_event_type(event_type)
+                {
+                    continue;
+                }
+
+                if let Some(swap_event) = self.event_processor_registry.decode_swap_event(
+                    event,
+                    &tx_digest,
+                    event_seq as u64,
+                ) {
+                    swap_events.push(swap_event);
+                }
+            }
+        }
+
+        if !swap_events.is_empty() {
+            self.event_processor_registry
+                .record_swap_events(&swap_events)
+                .await;
+        }
+    }
+
     /// helper method to extract unique events
     /// from checkpoint transactions and return a map of event type to a tuple of (event, transaction_digest)
     fn collect_unique_events(
         &self,
         checkpoint: &CheckpointData,
+    ) -> HashMap<String, (Event, String)> {
+        self.collect_unique_tx_events(checkpoint.transactions.iter().filter_map(|tx| {
+            let tx_events = tx.events.as_ref()?;
+            Some((tx.effects.transaction_digest(), tx_events.data.as_slice()))
+        }))
+    }
+
+    /// Same as `collect_unique_events` for the events of each tx, in checkpoint order.
+    ///
+    fn collect_unique_tx_events<'a>(
+        &self,
+        txs: impl IntoIterator<Item = (&'a TransactionDigest, &'a [Event])>,
     ) -> HashMap<String, (Event, String)> {
         let mut event_map = HashMap::new();
 
-        for tx in &checkpoint.transactions {
-            let Some(tx_events) = &tx.events else {
-                continue;
-            };
+        for (tx_digest, tx_events) in txs {
+            for (event_index, event) in tx_events.iter().enumerate() {
+                // skip unrelated events before the costlier event ID extraction
+                let type_str = event.type_.to_string();
+                let event_type = type_str.split('<').next().unwrap_or(&type_str);
+                if !self
+                    .event_processor_registry
+                    .is_known_event_type(event_type)
+                {
+                    continue;
+                }
 
-            for event in &tx_events.data {
-                if let Ok(event_type) = self.event_processor_registry.get_event_id(event) {
+                let no_dedup = self.is_no_dedup_event_type(event_type);
+
+                if let Ok(event_id) = self.event_processor_registry.get_event_id(event) {
                     // Only clone when inserting - replaces older events of same type with newer ones
-                    let tx_digest = tx.effects.transaction_digest().to_string();
+                    let tx_digest = tx_digest.to_string();
+                    // keep every occurrence of the no-dedup event types
+                    let event_type = if no_dedup {
+                        format!("{}_{}_{}", event_id, tx_digest, event_index)
+                    } else {
+                        event_id
+                    };
                     info!(
                         "insert event with type {} from tx {} to the checkpoint map",
                         event_type, tx_digest
@@ -471,3 +1646,743 @@ impl OnchainIndexer {
         event_map
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::db_service::mock::MockDb;
+
+    #[test]
+    fn event_concurrency_rises_with_the_lagging() {
+        let mut concurrency = 10;
+        for avg_lagging in [1_500, 2_000, 3_000, 4_000] {
+            concurrency = next_event_concurrency(concurrency, avg_lagging, 1_000, 4, 14);
+        }
+
+        // raised by a step per window, up to the max
+        assert_eq!(concurrency, 14);
+    }
+
+    #[test]
+    fn event_concurrency_falls_below_half_the_threshold() {
+        assert_eq!(next_event_concurrency(10, 400, 1_000, 4, 14), 8);
+        assert_eq!(next_event_concurrency(5, 400, 1_000, 4, 14), 4);
+        // between half the threshold and the threshold it is kept
+        assert_eq!(next_event_concurrency(10, 700, 1_000, 4, 14), 10);
+    }
+
+    /// Counters of an indexer, all set to `value`.
+    struct TestCounters {
+        values: Vec<AtomicU64>,
+        event_counts: std::sync::RwLock<HashMap<String, AtomicU64>>,
+    }
+
+    impl TestCounters {
+        fn new(value: u64) -> Self {
+            TestCounters {
+                values: (0..16).map(|_| AtomicU64::new(value)).collect(),
+                event_counts: std::sync::RwLock::new(HashMap::new()),
+            }
+        }
+
+        fn counters(&self) -> Counters<'_> {
+            let v = &self.values;
+
+            Counters {
+                latest_seq_number: &v[0],
+                last_started_seq_number: &v[1],
+                latest_timestamp_ms: &v[2],
+                total_checkpoints: &v[3],
+                total_processed_checkpoints: &v[4],
+                max_processing_time: &v[5],
+                min_processing_time: &v[6],
+                total_processing_time: &v[7],
+                max_lagging: &v[8],
+                min_lagging: &v[9],
+                total_lagging: &v[10],
+                next_alert_timestamp: &v[11],
+                alert_backoff_factor: &v[12],
+                current_event_concurrency: &v[13],
+                window_lagging: &v[14],
+                window_checkpoints: &v[15],
+                event_counts: &self.event_counts,
+            }
+        }
+    }
+
+    fn snapshot() -> MetricSnapshot {
+        MetricSnapshot {
+            latest_seq_number: 1000,
+            last_started_seq_number: 1001,
+            latest_timestamp_ms: 1002,
+            total_checkpoints: 1003,
+            total_processed_checkpoints: 1004,
+            max_processing_time: 1005,
+            min_processing_time: 1006,
+            total_processing_time: 1007,
+            max_lagging: 1008,
+            min_lagging: 1009,
+            total_lagging: 1010,
+            next_alert_timestamp: 1011,
+            alert_backoff_factor: 1012,
+            current_event_concurrency: 1013,
+            window_lagging: 1014,
+            window_checkpoints: 1015,
+            event_counts: HashMap::from([("cetus".to_string(), 42), ("navi".to_string(), 7)]),
+        }
+    }
+
+    #[test]
+    fn counters_round_trip_through_a_snapshot() {
+        let counters = TestCounters::new(0);
+        counters.counters().restore(snapshot());
+
+        assert_eq!(counters.counters().snapshot(), snapshot());
+    }
+
+    #[test]
+    fn counters_round_trip_through_a_json_file() {
+        let counters = TestCounters::new(0);
+        counters.counters().restore(snapshot());
+
+        let json = serde_json::to_string(&counters.counters().snapshot()).unwrap();
+        let restored = TestCounters::new(u64::MAX);
+        restored
+            .counters()
+            .restore(serde_json::from_str(&json).unwrap());
+
+        assert_eq!(restored.counters().snapshot(), snapshot());
+    }
+
+    #[test]
+    fn restore_replaces_the_event_counts() {
+        let counters = TestCounters::new(0);
+        counters
+            .event_counts
+            .write()
+            .unwrap()
+            .insert("scallop".to_string(), AtomicU64::new(3));
+
+        counters.counters().restore(snapshot());
+
+        let event_counts = counters.counters().event_counts();
+        assert_eq!(event_counts.len(), 2);
+        assert_eq!(event_counts.get("cetus"), Some(&42));
+        assert_eq!(event_counts.get("scallop"), None);
+    }
+
+    #[test]
+    fn reprocessed_checkpoint_is_counted_once() {
+        let mut counted = CountedCheckpoints::new(Some(99));
+
+        assert!(counted.insert(100));
+        assert!(!counted.insert(100));
+        assert!(!counted.insert(99));
+        assert!(counted.insert(101));
+        assert!(!counted.insert(101));
+    }
+
+    #[test]
+    fn checkpoints_finished_out_of_order_move_the_watermark() {
+        let mut counted = CountedCheckpoints::new(Some(99));
+
+        assert!(counted.insert(100));
+        assert!(counted.insert(102));
+        assert!(counted.insert(103));
+        assert_eq!(counted.watermark, Some(100));
+        assert!(!counted.insert(102));
+
+        assert!(counted.insert(101));
+        assert_eq!(counted.watermark, Some(103));
+        assert!(counted.above_watermark.is_empty());
+    }
+
+    #[test]
+    fn watermark_is_seeded_from_the_first_checkpoint() {
+        // e.g. restored from the metrics at #99, then started from a later checkpoint
+        let mut counted = CountedCheckpoints::new(Some(99));
+
+        for seq_number in 1_000..1_100 {
+            assert!(counted.insert(seq_number));
+        }
+
+        assert_eq!(counted.watermark, Some(1_099));
+        assert!(counted.above_watermark.is_empty());
+        assert!(!counted.insert(1_000));
+    }
+
+    #[test]
+    fn watermark_is_seeded_without_counted_checkpoints() {
+        let mut counted = CountedCheckpoints::new(None);
+
+        assert!(counted.insert(1_000));
+        assert!(counted.insert(1_001));
+
+        assert_eq!(counted.watermark, Some(1_001));
+        assert!(counted.above_watermark.is_empty());
+        assert!(!counted.insert(1_000));
+    }
+
+    #[test]
+    fn first_checkpoint_below_the_watermark_is_not_counted() {
+        let mut counted = CountedCheckpoints::new(Some(99));
+
+        assert!(!counted.insert(50));
+        assert_eq!(counted.watermark, Some(99));
+        assert!(counted.insert(100));
+    }
+
+    #[test]
+    fn processed_events_within_the_window_are_recent() {
+        let mut processed_events = ProcessedEvents::new(3);
+        processed_events.record("swap_pool_a".to_string(), 100);
+
+        assert!(processed_events.is_recent("swap_pool_a", 100));
+        assert!(processed_events.is_recent("swap_pool_a", 103));
+        // checkpoints are processed concurrently, an older one is within the window too
+        assert!(processed_events.is_recent("swap_pool_a", 98));
+        assert!(!processed_events.is_recent("swap_pool_b", 101));
+    }
+
+    #[test]
+    fn processed_events_past_the_window_are_not_recent() {
+        let mut processed_events = ProcessedEvents::new(3);
+        processed_events.record("swap_pool_a".to_string(), 100);
+
+        assert!(!processed_events.is_recent("swap_pool_a", 104));
+
+        processed_events.evict(104);
+        assert!(processed_events.last_processed.is_empty());
+    }
+
+    #[test]
+    fn processed_events_keep_the_latest_checkpoint() {
+        let mut processed_events = ProcessedEvents::new(3);
+        processed_events.record("swap_pool_a".to_string(), 105);
+        // an older checkpoint finished later
+        processed_events.record("swap_pool_a".to_string(), 100);
+
+        assert!(processed_events.is_recent("swap_pool_a", 108));
+        processed_events.evict(108);
+        assert_eq!(
+            processed_events.last_processed.get("swap_pool_a"),
+            Some(&105)
+        );
+    }
+
+    /// Indexer on an in-memory database and without any RPC client,
+    /// in dev mode so nothing is restored from the database.
+    fn test_indexer(changed_pools_sender: Option<mpsc::Sender<HashSet<String>>>) -> OnchainIndexer {
+        test_indexer_on(Arc::new(MockDb::default()), changed_pools_sender)
+    }
+
+    /// Same as `test_indexer`, reading and writing `db`.
+    fn test_indexer_on(
+        db: Arc<MockDb>,
+        changed_pools_sender: Option<mpsc::Sender<HashSet<String>>>,
+    ) -> OnchainIndexer {
+        let mut config = crate::config::test_config();
+        config.indexer.dev_mode = true;
+        let config = Arc::new(config);
+
+        let service_registry = Arc::new(db.service_registry(Arc::clone(&config)));
+        let event_processor_registry = Arc::new(db.event_processor_registry(&service_registry));
+
+        OnchainIndexer::new(
+            config,
+            Arc::new(RpcPool::without_clients()),
+            Arc::clone(&service_registry.db_pool_service),
+            Arc::clone(&service_registry.db_lending_service),
+            service_registry,
+            event_processor_registry,
+            Arc::new(AtomicU64::new(0)),
+            changed_pools_sender,
+        )
+    }
+
+    fn swap(exchange: &str, pool_id: &str) -> indexer::OnchainEvent {
+        indexer::OnchainEvent::DEXSwap(indexer::DEXSwapEvent {
+            exchange: exchange.to_string(),
+            pool_id: pool_id.to_string(),
+        })
+    }
+
+    #[test]
+    fn events_are_counted_by_exchange() {
+        let indexer = test_indexer(None);
+
+        indexer.count_event(&swap(constant::CETUS_EXCHANGE, "0xpool_a"));
+        indexer.count_event(&swap(constant::BLUEFIN_EXCHANGE, "0xpool_b"));
+        indexer.count_event(&swap(constant::CETUS_EXCHANGE, "0xpool_c"));
+        // a void event has no source to count
+        indexer.count_event(&indexer::OnchainEvent::VoidEvent);
+
+        assert_eq!(
+            indexer.event_counts_snapshot(),
+            HashMap::from([
+                (constant::CETUS_EXCHANGE.to_string(), 2),
+                (constant::BLUEFIN_EXCHANGE.to_string(), 1),
+            ])
+        );
+    }
+
+    #[test]
+    fn prometheus_metrics_render_the_event_counts_by_exchange() {
+        let indexer = test_indexer(None);
+        indexer.count_event(&swap(constant::CETUS_EXCHANGE, "0xpool_a"));
+        indexer.count_event(&swap(constant::BLUEFIN_EXCHANGE, "0xpool_b"));
+        indexer.count_event(&swap(constant::CETUS_EXCHANGE, "0xpool_c"));
+
+        let output = indexer.render_prometheus_metrics();
+
+        // sorted by source
+        assert!(output.starts_with(&format!(
+            "# TYPE indexer_events_total counter\n\
+             indexer_events_total{{source=\"{}\"}} 1\n\
+             indexer_events_total{{source=\"{}\"}} 2\n",
+            constant::BLUEFIN_EXCHANGE,
+            constant::CETUS_EXCHANGE
+        )));
+        assert!(output.contains(&format!(
+            "# TYPE indexer_event_concurrency gauge\nindexer_event_concurrency {}\n",
+            indexer.event_concurrency()
+        )));
+    }
+
+    #[test]
+    fn changed_pools_are_sent_once_per_pool() {
+        let (sender, mut receiver) = mpsc::channel(1);
+        let indexer = test_indexer(Some(sender));
+
+        indexer.notify_changed_pools(
+            100,
+            &[
+                swap(constant::CETUS_EXCHANGE, "0xpool_a"),
+                swap(constant::BLUEFIN_EXCHANGE, "0xpool_b"),
+                // the same pool changed twice in the checkpoint
+                swap(constant::CETUS_EXCHANGE, "0xpool_a"),
+                indexer::OnchainEvent::VoidEvent,
+            ],
+        );
+
+        assert_eq!(
+            receiver.try_recv().unwrap(),
+            HashSet::from(["0xpool_a".to_string(), "0xpool_b".to_string()])
+        );
+    }
+
+    #[test]
+    fn checkpoint_without_changed_pools_sends_nothing() {
+        let (sender, mut receiver) = mpsc::channel(1);
+        let indexer = test_indexer(Some(sender));
+
+        indexer.notify_changed_pools(100, &[indexer::OnchainEvent::VoidEvent]);
+
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[test]
+    fn event_subscription_delay_doubles_on_each_attempt() {
+        let delays = (0..4).map(event_subscription_delay).collect::<Vec<_>>();
+
+        assert_eq!(
+            delays,
+            [500, 1_000, 2_000, 4_000].map(Duration::from_millis)
+        );
+    }
+
+    #[test]
+    fn event_subscription_delay_is_capped() {
+        let max_delay = Duration::from_millis(constant::EVENT_SUBSCRIPTION_MAX_DELAY_MS);
+
+        // 500ms * 2^6 is past the cap
+        assert_eq!(event_subscription_delay(5), Duration::from_millis(16_000));
+        assert_eq!(event_subscription_delay(6), max_delay);
+        // the doubling saturates instead of overflowing
+        assert_eq!(event_subscription_delay(64), max_delay);
+        assert_eq!(event_subscription_delay(u32::MAX), max_delay);
+    }
+
+    /// Alert sink forwarding the alerts to a channel.
+    struct ChannelAlertSink(mpsc::UnboundedSender<LaggingAlert>);
+
+    #[async_trait]
+    impl AlertSink for ChannelAlertSink {
+        async fn send(&self, alert: &LaggingAlert) -> Result<()> {
+            self.0
+                .send(alert.clone())
+                .map_err(|e| anyhow!("Failed to forward the alert: {}", e))
+        }
+    }
+
+    /// Indexer alerting above a lagging of 1s, with the alerts it sends.
+    fn alerting_indexer() -> (OnchainIndexer, mpsc::UnboundedReceiver<LaggingAlert>) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let mut indexer = test_indexer(None).with_alert_sink(Arc::new(ChannelAlertSink(sender)));
+        let mut config = (*indexer.config).clone();
+        config.indexer.lagging_ms_threshold = 1_000;
+        indexer.config = Arc::new(config);
+
+        (indexer, receiver)
+    }
+
+    /// Alerts sent by the spawned tasks so far.
+    async fn sent_alerts(receiver: &mut mpsc::UnboundedReceiver<LaggingAlert>) -> Vec<u64> {
+        // let the spawned sends run
+        for _ in 0..10 {
+            tokio::task::yield_now().await;
+        }
+
+        let mut alerts = vec![];
+        while let Ok(alert) = receiver.try_recv() {
+            alerts.push(alert.lagging_ms);
+        }
+        alerts
+    }
+
+    #[tokio::test]
+    async fn lagging_under_the_threshold_is_not_alerted() {
+        let (indexer, mut receiver) = alerting_indexer();
+
+        indexer.alert_lagging(500);
+        indexer.alert_lagging(1_000);
+
+        assert!(sent_alerts(&mut receiver).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn lagging_above_the_threshold_is_alerted_once_per_crossing() {
+        let (indexer, mut receiver) = alerting_indexer();
+
+        indexer.alert_lagging(2_000);
+        // still above the threshold, within the backoff
+        indexer.alert_lagging(3_000);
+        assert_eq!(sent_alerts(&mut receiver).await, vec![2_000]);
+
+        // back under the threshold, then crossing it again
+        indexer.alert_lagging(500);
+        indexer.alert_lagging(4_000);
+        indexer.alert_lagging(5_000);
+        assert_eq!(sent_alerts(&mut receiver).await, vec![4_000]);
+    }
+
+    /// Indexer tuning its event concurrency from 10 between 4 and 14,
+    /// with a lagging threshold of 1s.
+    fn tuning_indexer() -> OnchainIndexer {
+        let mut indexer = test_indexer(None);
+        let mut config = (*indexer.config).clone();
+        config.indexer.lagging_ms_threshold = 1_000;
+        config.indexer.min_event_concurrency = Some(4);
+        config.indexer.max_event_concurrency = Some(14);
+        indexer.config = Arc::new(config);
+        indexer
+            .current_event_concurrency
+            .store(10, Ordering::SeqCst);
+
+        indexer
+    }
+
+    /// Tunes the concurrency over a full window of checkpoints lagging `lagging_ms`.
+    fn tune_window(indexer: &OnchainIndexer, lagging_ms: u64) {
+        for _ in 0..constant::EVENT_CONCURRENCY_TUNING_INTERVAL {
+            indexer.tune_event_concurrency(lagging_ms);
+        }
+    }
+
+    #[test]
+    fn event_concurrency_is_kept_within_a_window() {
+        let indexer = tuning_indexer();
+
+        for _ in 1..constant::EVENT_CONCURRENCY_TUNING_INTERVAL {
+            indexer.tune_event_concurrency(5_000);
+        }
+
+        assert_eq!(indexer.event_concurrency(), 10);
+    }
+
+    #[test]
+    fn event_concurrency_increases_while_lagging() {
+        let indexer = tuning_indexer();
+
+        tune_window(&indexer, 5_000);
+        assert_eq!(indexer.event_concurrency(), 12);
+
+        tune_window(&indexer, 5_000);
+        tune_window(&indexer, 5_000);
+        assert_eq!(indexer.event_concurrency(), 14);
+    }
+
+    #[test]
+    fn event_concurrency_decreases_once_caught_up() {
+        let indexer = tuning_indexer();
+
+        tune_window(&indexer, 100);
+        assert_eq!(indexer.event_concurrency(), 8);
+
+        for _ in 0..3 {
+            tune_window(&indexer, 100);
+        }
+        assert_eq!(indexer.event_concurrency(), 4);
+
+        // a lagging between half the threshold and the threshold keeps it
+        tune_window(&indexer, 700);
+        assert_eq!(indexer.event_concurrency(), 4);
+    }
+
+    fn indexer_config(indexer_progress_filepath: Option<String>) -> IndexerConfig {
+        let mut config = crate::config::test_config().indexer;
+        config.indexer_progress_filepath = indexer_progress_filepath;
+
+        config
+    }
+
+    #[test]
+    fn progress_store_kind_follows_the_progress_filepath() {
+        assert!(matches!(
+            ProgressStoreKind::from_config(&indexer_config(None), 42),
+            ProgressStoreKind::Shim(42)
+        ));
+        assert!(matches!(
+            ProgressStoreKind::from_config(&indexer_config(Some("progress.json".to_string())), 42),
+            ProgressStoreKind::File(path) if path == PathBuf::from("progress.json")
+        ));
+    }
+
+    #[tokio::test]
+    async fn shim_progress_store_starts_at_the_checkpoint() {
+        let mut store = ProgressStoreKind::Shim(42)
+            .into_store("local_reader", 1)
+            .unwrap();
+
+        assert_eq!(store.load("local_reader".to_string()).await.unwrap(), 42);
+    }
+
+    #[tokio::test]
+    async fn missing_progress_file_starts_at_the_initial_checkpoint() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("progress.json");
+
+        let mut store = ProgressStoreKind::File(path.clone())
+            .into_store("local_reader", 42)
+            .unwrap();
+
+        assert!(path.exists());
+        assert_eq!(store.load("local_reader".to_string()).await.unwrap(), 42);
+    }
+
+    #[tokio::test]
+    async fn progress_file_resumes_from_the_saved_watermark() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("progress.json");
+
+        let mut store = ProgressStoreKind::File(path.clone())
+            .into_store("local_reader", 42)
+            .unwrap();
+        store.save("local_reader".to_string(), 100).await.unwrap();
+
+        // a restart resumes from the file, not from the initial checkpoint
+        let mut store = ProgressStoreKind::File(path)
+            .into_store("local_reader", 42)
+            .unwrap();
+        assert_eq!(store.load("local_reader".to_string()).await.unwrap(), 100);
+    }
+
+    /// Indexer recording its failed events to `db` when `record_failed_events`.
+    fn recording_indexer(db: Arc<MockDb>, record_failed_events: bool) -> OnchainIndexer {
+        let mut indexer = test_indexer_on(db, None);
+        let mut config = (*indexer.config).clone();
+        config.indexer.record_failed_events = record_failed_events;
+        indexer.config = Arc::new(config);
+
+        indexer
+    }
+
+    #[tokio::test]
+    async fn failed_event_is_recorded_with_its_error() {
+        let db = Arc::new(MockDb::default());
+        let indexer = recording_indexer(Arc::clone(&db), true);
+        let event = indexer::test_event("0x1::unknown::Event", vec![]);
+        let event_type = event.type_.to_string();
+
+        let result = indexer.process_event(event, "0xtx".to_string(), 42).await;
+
+        assert!(result.is_err());
+        let failed_events = db.failed_events.lock().unwrap();
+        assert_eq!(failed_events.len(), 1);
+        assert_eq!(failed_events[0].event_type, event_type);
+        assert_eq!(failed_events[0].tx_digest, "0xtx");
+        assert_eq!(failed_events[0].checkpoint_seq_number, 42);
+        assert_eq!(failed_events[0].error, result.unwrap_err().to_string());
+        assert!(failed_events[0].error.contains("No processor found"));
+    }
+
+    #[tokio::test]
+    async fn failed_event_is_not_recorded_by_default() {
+        let db = Arc::new(MockDb::default());
+        let indexer = recording_indexer(Arc::clone(&db), false);
+        let event = indexer::test_event("0x1::unknown::Event", vec![]);
+
+        let result = indexer.process_event(event, "0xtx".to_string(), 42).await;
+
+        assert!(result.is_err());
+        assert!(db.failed_events.lock().unwrap().is_empty());
+    }
+
+    /// Processor giving the same event ID to all the events of a type.
+    struct EventTypeIdProcessor;
+
+    impl std::fmt::Display for EventTypeIdProcessor {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "EventTypeIdProcessor")
+        }
+    }
+
+    #[async_trait]
+    impl indexer::EventProcessor for EventTypeIdProcessor {
+        async fn process_tx_event(
+            &self,
+            _event_type: &str,
+            _sender: &str,
+            _data: serde_json::Value,
+            _tx_digest: &str,
+        ) -> Result<()> {
+            Ok(())
+        }
+
+        async fn process_raw_event(
+            &self,
+            _event_type: &str,
+            _sender: &str,
+            _event: Event,
+            _tx_digest: &str,
+        ) -> Result<indexer::OnchainEvent> {
+            Ok(indexer::OnchainEvent::VoidEvent)
+        }
+
+        fn get_event_id(&self, event_type: &str, _event: &Event) -> Result<String> {
+            Ok(event_type.to_string())
+        }
+    }
+
+    /// Indexer processing the types of `events`, never deduplicating the type of `no_dedup_event`.
+    fn dedup_indexer(events: &[&Event], no_dedup_event: &Event) -> OnchainIndexer {
+        let db = Arc::new(MockDb::default());
+        let mut indexer = test_indexer_on(Arc::clone(&db), None);
+        let mut config = (*indexer.config).clone();
+        config.indexer.no_dedup_event_types = vec![no_dedup_event.type_.to_string()];
+        indexer.config = Arc::new(config);
+
+        let service_registry = Arc::new(db.service_registry(Arc::clone(&indexer.config)));
+        let mut registry = db.event_processor_registry(&service_registry);
+        registry.register_oracle_processor(
+            events.iter().map(|e| e.type_.to_string()).collect(),
+            Arc::new(EventTypeIdProcessor),
+        );
+        indexer.event_processor_registry = Arc::new(registry);
+
+        indexer
+    }
+
+    #[test]
+    fn no_dedup_event_types_keep_every_occurrence() {
+        let price = indexer::test_event("0x1::oracle::PriceUpdated", vec![]);
+        let deposit = indexer::test_event("0x1::lending::Deposit", vec![]);
+        let indexer = dedup_indexer(&[&price, &deposit], &deposit);
+        let (tx_a, tx_b) = (
+            TransactionDigest::new([1; 32]),
+            TransactionDigest::new([2; 32]),
+        );
+        let events = [price.clone(), deposit.clone()];
+
+        let unique_events = indexer
+            .collect_unique_tx_events([(&tx_a, events.as_slice()), (&tx_b, events.as_slice())]);
+
+        // the price events share an ID, only the last one is kept
+        let price_type = price.type_.to_string();
+        assert_eq!(unique_events[&price_type].1, tx_b.to_string());
+        // both deposits are kept, each under its own key
+        let deposit_txs = unique_events
+            .iter()
+            .filter(|(key, _)| **key != price_type)
+            .map(|(_, (_, tx_digest))| tx_digest.clone())
+            .collect::<HashSet<_>>();
+        assert_eq!(
+            deposit_txs,
+            HashSet::from([tx_a.to_string(), tx_b.to_string()])
+        );
+        assert_eq!(unique_events.len(), 3);
+    }
+
+    #[test]
+    fn unknown_event_types_are_not_collected() {
+        let price = indexer::test_event("0x1::oracle::PriceUpdated", vec![]);
+        let unknown = indexer::test_event("0x1::unknown::Event", vec![]);
+        let indexer = dedup_indexer(&[&price], &price);
+        let tx = TransactionDigest::new([1; 32]);
+
+        let unique_events = indexer.collect_unique_tx_events([(&tx, [unknown].as_slice())]);
+
+        assert!(unique_events.is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn metrics_are_flushed_every_period_until_shutdown() {
+        let db = Arc::new(MockDb::default());
+        let indexer = test_indexer_on(Arc::clone(&db), None);
+        indexer.total_checkpoints.store(1, Ordering::SeqCst);
+        let (shutdown_sender, shutdown_receiver) = oneshot::channel();
+        let flushing = indexer.clone();
+        let task = tokio::spawn(async move {
+            flushing
+                .run_metrics_flush(Duration::from_secs(60), shutdown_receiver)
+                .await
+        });
+        let flushed = || db.metrics.lock().unwrap().len();
+
+        // the first tick is immediate
+        sleep(Duration::from_secs(1)).await;
+        assert_eq!(flushed(), 1);
+
+        // nothing counted over the next period, nothing written
+        sleep(Duration::from_secs(60)).await;
+        assert_eq!(flushed(), 1);
+
+        indexer.total_checkpoints.store(2, Ordering::SeqCst);
+        sleep(Duration::from_secs(30)).await;
+        assert_eq!(flushed(), 1);
+        sleep(Duration::from_secs(30)).await;
+        assert_eq!(flushed(), 2);
+
+        shutdown_sender.send(()).unwrap();
+        task.await.unwrap();
+        indexer.total_checkpoints.store(3, Ordering::SeqCst);
+        sleep(Duration::from_secs(120)).await;
+        assert_eq!(flushed(), 2);
+    }
+
+    #[test]
+    fn latest_lagging_is_zero_before_the_first_checkpoint() {
+        let indexer = test_indexer(None);
+
+        assert_eq!(indexer.latest_lagging_ms(), 0);
+    }
+
+    #[test]
+    fn latest_lagging_follows_the_latest_processed_checkpoint() {
+        let indexer = test_indexer(None);
+        let now = utils::get_current_timestamp_ms();
+
+        indexer.advance_latest_checkpoint(100, now - 5_000);
+        let lagging = indexer.latest_lagging_ms();
+        assert!((5_000..6_000).contains(&lagging), "{}", lagging);
+
+        // an older checkpoint finished after it doesn't move it backward
+        indexer.advance_latest_checkpoint(99, now - 60_000);
+        let lagging = indexer.latest_lagging_ms();
+        assert!((5_000..6_000).contains(&lagging), "{}", lagging);
+        assert_eq!(indexer.latest_seq_number.load(Ordering::SeqCst), 100);
+
+        indexer.advance_latest_checkpoint(101, now);
+        assert!(indexer.latest_lagging_ms() < 1_000);
+    }
+}
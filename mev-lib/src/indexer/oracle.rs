@@ -1 +1,3 @@
 pub mod pyth;
+pub mod supra;
+pub mod switchboard;
@@ -2,7 +2,7 @@ use crate::{
     constant,
     indexer::{self, EventProcessor, OnchainEvent},
     service::db_service::{lending::LendingService, pool::PoolService},
-    utils,
+    utils::{self, rpc_pool::RpcPool},
 };
 use db::models::{
     coin::{Coin, NewCoin, UpdateCoin},
@@ -17,7 +17,6 @@ use serde_json::Value;
 use serde_with::{serde_as, DisplayFromStr};
 use std::fmt::{Debug, Display};
 use std::sync::Arc;
-use sui_sdk::SuiClient;
 use sui_types::event::Event;
 use tokio::time::{Duration, Instant};
 use tracing::{debug, error, event, info, instrument, trace, warn};
@@ -110,7 +109,7 @@ pub struct Price {
 
 pub struct Pyth {
     oracle_name: String,
-    sui_client: Arc<SuiClient>,
+    rpc_pool: Arc<RpcPool>,
     coin_repo: Arc<dyn CoinRepository + Send + Sync>,
     db_pool_service: Arc<PoolService>,
     db_lending_service: Arc<LendingService>,
@@ -118,14 +117,14 @@ pub struct Pyth {
 
 impl Pyth {
     pub fn new(
-        sui_client: Arc<SuiClient>,
+        rpc_pool: Arc<RpcPool>,
         coin_repo: Arc<dyn CoinRepository + Send + Sync>,
         db_pool_service: Arc<PoolService>,
         db_lending_service: Arc<LendingService>,
     ) -> Self {
         Pyth {
             oracle_name: "pyth".to_string(),
-            sui_client,
+            rpc_pool,
             coin_repo,
             db_pool_service,
             db_lending_service,
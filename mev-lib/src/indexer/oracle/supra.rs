@@ -0,0 +1,169 @@
+use crate::{
+    config::SupraConfig,
+    constant,
+    indexer::{self, EventProcessor, OnchainEvent},
+    service::db_service::lending::LendingService,
+};
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use serde_with::{serde_as, DisplayFromStr};
+use std::fmt::{Debug, Display};
+use std::sync::Arc;
+use sui_types::event::Event;
+use tracing::{debug, error, info, instrument, trace, warn};
+
+/// Price update of a single trading pair emitted by the Supra SValueFeed.
+///
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PriceUpdateEvent {
+    pub pair: u32,
+    pub value: u128,
+    pub decimal: u16,
+    pub timestamp: u64,
+    pub round: u64,
+}
+
+#[serde_as]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PriceUpdateEventJson {
+    pub pair: u32,
+    #[serde_as(as = "DisplayFromStr")]
+    pub value: u128,
+    pub decimal: u16,
+    #[serde_as(as = "DisplayFromStr")]
+    pub timestamp: u64,
+    #[serde_as(as = "DisplayFromStr")]
+    pub round: u64,
+}
+
+pub struct Supra {
+    oracle_name: String,
+    event_type: String,
+    db_lending_service: Arc<LendingService>,
+}
+
+impl Supra {
+    pub fn new(config: Arc<SupraConfig>, db_lending_service: Arc<LendingService>) -> Self {
+        Supra {
+            oracle_name: constant::SUPRA_ORACLE.to_string(),
+            event_type: Self::price_update_event_type(&config),
+            db_lending_service,
+        }
+    }
+
+    /// Full type of the Supra price update event,
+    /// the package ID depends on the deployment.
+    ///
+    pub fn price_update_event_type(config: &SupraConfig) -> String {
+        format!(
+            "{}::{}",
+            config.package_id,
+            constant::SUPRA_UPDATE_PRICE_EVENT
+        )
+    }
+}
+
+impl Display for Supra {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "SupraEventProcessor")
+    }
+}
+
+#[async_trait]
+impl EventProcessor for Supra {
+    async fn process_tx_event(
+        &self,
+        event_type: &str,
+        sender: &str,
+        data: Value,
+        tx_digest: &str,
+    ) -> Result<()> {
+        if event_type != self.event_type {
+            return Err(anyhow!("Unknown event type: {}", event_type));
+        }
+
+        let event: PriceUpdateEventJson = serde_json::from_value(data)
+            .map_err(|e| anyhow!("Failed to deserialize event data: {:?}", e))?;
+
+        info!("Supra price update event: {:?}", event);
+
+        let raw_event = PriceUpdateEvent {
+            pair: event.pair,
+            value: event.value,
+            decimal: event.decimal,
+            timestamp: event.timestamp,
+            round: event.round,
+        };
+
+        self.process_update_price(raw_event).await?;
+
+        Ok(())
+    }
+
+    async fn process_raw_event(
+        &self,
+        event_type: &str,
+        sender: &str,
+        event: Event,
+        tx_digest: &str,
+    ) -> Result<OnchainEvent> {
+        if event_type != self.event_type {
+            return Err(anyhow!("Unknown event type: {}", event_type));
+        }
+
+        let event: PriceUpdateEvent = bcs::from_bytes(&event.contents)
+            .map_err(|e| anyhow!("Failed to decode event: {:?}", e))?;
+
+        info!("Supra price update event: {:?}", event);
+
+        self.process_update_price(event).await
+    }
+
+    fn get_event_id(&self, event_type: &str, event: &Event) -> Result<String> {
+        if event_type != self.event_type {
+            return Err(anyhow!("Unknown Supra event type: {}", event_type));
+        }
+
+        let event_data: PriceUpdateEvent = bcs::from_bytes(&event.contents)
+            .map_err(|e| anyhow!("Failed to decode event: {:?}", e))?;
+
+        // The pair ID is used as the event ID
+        // In a checkpoint processing scenario, we will select the latest price update event
+        // for each pair, ignoring all the previous events.
+        Ok(format!(
+            "{}_{}_{}",
+            &self.oracle_name, event_type, event_data.pair
+        ))
+    }
+}
+
+impl Supra {
+    /// Processes the Supra price update event.
+    /// Updates the Supra price of the coins associated with the pair ID.
+    ///
+    async fn process_update_price(&self, event_data: PriceUpdateEvent) -> Result<OnchainEvent> {
+        let supra_price = crate::types::SupraPrice {
+            pair_id: event_data.pair,
+            price: event_data.value.to_string(),
+            decimals: event_data.decimal,
+            latest_updated_timestamp_ms: event_data.timestamp,
+        };
+
+        // save to db
+        self.db_lending_service
+            .save_supra_price(supra_price)
+            .await?;
+
+        Ok(OnchainEvent::OraclePrice(indexer::OraclePriceEvent {
+            oracle: self.oracle_name.clone(),
+            feed_id: event_data.pair.to_string(),
+            spot_price: event_data.value.to_string(),
+            ema_price: event_data.value.to_string(),
+            publish_time: event_data.timestamp / 1_000,
+            vaa: None,
+        }))
+    }
+}
@@ -0,0 +1,195 @@
+use crate::{
+    config::SwitchboardConfig,
+    constant,
+    indexer::{self, EventProcessor, OnchainEvent},
+    service::db_service::lending::LendingService,
+};
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use serde_with::{serde_as, DisplayFromStr};
+use std::fmt::{Debug, Display};
+use std::sync::Arc;
+use sui_types::base_types::ObjectID;
+use sui_types::event::Event;
+use tracing::{debug, error, info, instrument, trace, warn};
+
+/// Signed 18-decimal value used by Switchboard On-Demand.
+///
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SwitchboardDecimal {
+    pub value: u128,
+    pub neg: bool,
+}
+
+#[serde_as]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SwitchboardDecimalJson {
+    #[serde_as(as = "DisplayFromStr")]
+    pub value: u128,
+    pub neg: bool,
+}
+
+/// Result update of a single aggregator emitted by Switchboard On-Demand.
+///
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct AggregatorUpdatedEvent {
+    pub aggregator_id: ObjectID,
+    pub feed_hash: Vec<u8>,
+    pub result: SwitchboardDecimal,
+    pub timestamp_ms: u64,
+}
+
+#[serde_as]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct AggregatorUpdatedEventJson {
+    pub aggregator_id: String,
+    pub feed_hash: Vec<u8>,
+    pub result: SwitchboardDecimalJson,
+    #[serde_as(as = "DisplayFromStr")]
+    pub timestamp_ms: u64,
+}
+
+pub struct Switchboard {
+    oracle_name: String,
+    event_type: String,
+    db_lending_service: Arc<LendingService>,
+}
+
+impl Switchboard {
+    pub fn new(config: Arc<SwitchboardConfig>, db_lending_service: Arc<LendingService>) -> Self {
+        Switchboard {
+            oracle_name: constant::SWITCHBOARD_ORACLE.to_string(),
+            event_type: Self::price_update_event_type(&config),
+            db_lending_service,
+        }
+    }
+
+    /// Full type of the Switchboard aggregator update event,
+    /// the package ID depends on the deployment.
+    ///
+    pub fn price_update_event_type(config: &SwitchboardConfig) -> String {
+        format!(
+            "{}::{}",
+            config.package_id,
+            constant::SWITCHBOARD_UPDATE_PRICE_EVENT
+        )
+    }
+}
+
+impl Display for Switchboard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "SwitchboardEventProcessor")
+    }
+}
+
+#[async_trait]
+impl EventProcessor for Switchboard {
+    async fn process_tx_event(
+        &self,
+        event_type: &str,
+        sender: &str,
+        data: Value,
+        tx_digest: &str,
+    ) -> Result<()> {
+        if event_type != self.event_type {
+            return Err(anyhow!("Unknown event type: {}", event_type));
+        }
+
+        let event: AggregatorUpdatedEventJson = serde_json::from_value(data)
+            .map_err(|e| anyhow!("Failed to deserialize event data: {:?}", e))?;
+
+        info!("Switchboard aggregator update event: {:?}", event);
+
+        let raw_event = AggregatorUpdatedEvent {
+            aggregator_id: event
+                .aggregator_id
+                .parse::<ObjectID>()
+                .map_err(|e| anyhow!("Invalid aggregator ID: {}", e))?,
+            feed_hash: event.feed_hash,
+            result: SwitchboardDecimal {
+                value: event.result.value,
+                neg: event.result.neg,
+            },
+            timestamp_ms: event.timestamp_ms,
+        };
+
+        self.process_update_price(raw_event).await?;
+
+        Ok(())
+    }
+
+    async fn process_raw_event(
+        &self,
+        event_type: &str,
+        sender: &str,
+        event: Event,
+        tx_digest: &str,
+    ) -> Result<OnchainEvent> {
+        if event_type != self.event_type {
+            return Err(anyhow!("Unknown event type: {}", event_type));
+        }
+
+        let event: AggregatorUpdatedEvent = bcs::from_bytes(&event.contents)
+            .map_err(|e| anyhow!("Failed to decode event: {:?}", e))?;
+
+        info!("Switchboard aggregator update event: {:?}", event);
+
+        self.process_update_price(event).await
+    }
+
+    fn get_event_id(&self, event_type: &str, event: &Event) -> Result<String> {
+        if event_type != self.event_type {
+            return Err(anyhow!("Unknown Switchboard event type: {}", event_type));
+        }
+
+        let event_data: AggregatorUpdatedEvent = bcs::from_bytes(&event.contents)
+            .map_err(|e| anyhow!("Failed to decode event: {:?}", e))?;
+
+        // The aggregator ID is used as the event ID
+        // so only the latest update of each aggregator in a checkpoint is processed.
+        Ok(format!(
+            "{}_{}_{}",
+            &self.oracle_name, event_type, event_data.aggregator_id
+        ))
+    }
+}
+
+impl Switchboard {
+    /// Processes the Switchboard aggregator update event.
+    /// Updates the Switchboard price of the coins associated with the aggregator ID.
+    ///
+    async fn process_update_price(
+        &self,
+        event_data: AggregatorUpdatedEvent,
+    ) -> Result<OnchainEvent> {
+        if event_data.result.neg {
+            return Err(anyhow!(
+                "Negative Switchboard result for aggregator {}",
+                event_data.aggregator_id
+            ));
+        }
+
+        let switchboard_price = crate::types::SwitchboardPrice {
+            aggregator_id: event_data.aggregator_id.to_string(),
+            price: event_data.result.value.to_string(),
+            latest_updated_timestamp_ms: event_data.timestamp_ms,
+        };
+
+        // save to db
+        self.db_lending_service
+            .save_switchboard_price(switchboard_price)
+            .await?;
+
+        Ok(OnchainEvent::OraclePrice(indexer::OraclePriceEvent {
+            oracle: self.oracle_name.clone(),
+            feed_id: event_data.aggregator_id.to_string(),
+            spot_price: event_data.result.value.to_string(),
+            ema_price: event_data.result.value.to_string(),
+            publish_time: event_data.timestamp_ms / 1_000,
+            vaa: None,
+        }))
+    }
+}
@@ -7,7 +7,7 @@ use crate::{
         registry::ServiceRegistry,
     },
     types::Borrower,
-    utils,
+    utils::{self, rpc_pool::RpcPool},
 };
 use db::{
     models,
@@ -15,10 +15,9 @@ use db::{
 };
 
 use anyhow::{anyhow, Result};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::{path::Path, str::FromStr, sync::Arc};
 use sui_json_rpc_types::SuiEvent;
-use sui_sdk::SuiClient;
 use sui_types::event::{self, Event};
 use tokio::{
     sync::mpsc,
@@ -34,25 +33,19 @@ pub struct EventProcessorRegistry {
     dex_processors: HashMap<String, Arc<dyn EventProcessor + Send + Sync>>,
     lending_processors: HashMap<String, Arc<dyn EventProcessor + Send + Sync>>,
     oracle_processors: HashMap<String, Arc<dyn EventProcessor + Send + Sync>>,
+    known_event_types: HashSet<String>,
 }
 
 impl EventProcessorRegistry {
     pub fn new(
         config: Arc<Config>,
-        client: Arc<SuiClient>,
+        rpc_pool: Arc<RpcPool>,
         pool_repo: Arc<dyn PoolRepository + Send + Sync>,
         coin_repo: Arc<dyn CoinRepository + Send + Sync>,
         db_pool_service: Arc<PoolService>,
         db_lending_service: Arc<LendingService>,
         service_registry: Arc<ServiceRegistry>,
     ) -> Self {
-        let mut dex_processors: HashMap<String, Arc<dyn EventProcessor + Send + Sync>> =
-            HashMap::new();
-        let mut lending_processors: HashMap<String, Arc<dyn EventProcessor + Send + Sync>> =
-            HashMap::new();
-        let mut oracle_processors: HashMap<String, Arc<dyn EventProcessor + Send + Sync>> =
-            HashMap::new();
-
         let navi_config = Arc::new(config.navi.clone());
         let scallop_config = Arc::new(config.scallop.clone());
         let suilend_config = Arc::new(config.suilend.clone());
@@ -90,6 +83,10 @@ impl EventProcessorRegistry {
             .get_dex_service(constant::OBRIC_EXCHANGE)
             .unwrap();
 
+        let kriya_service = service_registry
+            .get_dex_service(constant::KRIYA_EXCHANGE)
+            .unwrap();
+
         let navi_service = service_registry
             .get_lending_service(constant::NAVI_LENDING)
             .unwrap();
@@ -104,7 +101,7 @@ impl EventProcessorRegistry {
 
         // dex processors
         let cetus_processor = Arc::new(dex::cetus::Cetus::new(
-            Arc::clone(&client),
+            Arc::clone(&rpc_pool),
             Arc::clone(&pool_repo),
             Arc::clone(&coin_repo),
             Arc::clone(&db_pool_service),
@@ -112,7 +109,7 @@ impl EventProcessorRegistry {
         ));
 
         let bluefin_processor = Arc::new(dex::bluefin::Bluefin::new(
-            Arc::clone(&client),
+            Arc::clone(&rpc_pool),
             Arc::clone(&pool_repo),
             Arc::clone(&coin_repo),
             Arc::clone(&db_pool_service),
@@ -120,7 +117,7 @@ impl EventProcessorRegistry {
         ));
 
         let turbos_processor = Arc::new(dex::turbos::Turbos::new(
-            Arc::clone(&client),
+            Arc::clone(&rpc_pool),
             Arc::clone(&pool_repo),
             Arc::clone(&coin_repo),
             Arc::clone(&db_pool_service),
@@ -128,7 +125,7 @@ impl EventProcessorRegistry {
         ));
 
         let momentum_processor = Arc::new(dex::momentum::Momentum::new(
-            Arc::clone(&client),
+            Arc::clone(&rpc_pool),
             Arc::clone(&pool_repo),
             Arc::clone(&coin_repo),
             Arc::clone(&db_pool_service),
@@ -136,7 +133,7 @@ impl EventProcessorRegistry {
         ));
 
         let aftermath_processor = Arc::new(dex::aftermath::Aftermath::new(
-            Arc::clone(&client),
+            Arc::clone(&rpc_pool),
             Arc::clone(&pool_repo),
             Arc::clone(&coin_repo),
             Arc::clone(&db_pool_service),
@@ -144,7 +141,7 @@ impl EventProcessorRegistry {
         ));
 
         let flowx_processor = Arc::new(dex::flowx::FlowX::new(
-            Arc::clone(&client),
+            Arc::clone(&rpc_pool),
             Arc::clone(&pool_repo),
             Arc::clone(&coin_repo),
             Arc::clone(&db_pool_service),
@@ -152,7 +149,7 @@ impl EventProcessorRegistry {
         ));
 
         let bluemove_processor = Arc::new(dex::bluemove::Bluemove::new(
-            Arc::clone(&client),
+            Arc::clone(&rpc_pool),
             Arc::clone(&pool_repo),
             Arc::clone(&coin_repo),
             Arc::clone(&db_pool_service),
@@ -160,30 +157,38 @@ impl EventProcessorRegistry {
         ));
 
         let obric_processor = Arc::new(dex::obric::Obric::new(
-            Arc::clone(&client),
+            Arc::clone(&rpc_pool),
             Arc::clone(&pool_repo),
             Arc::clone(&coin_repo),
             Arc::clone(&db_pool_service),
             Arc::clone(&obric_service),
         ));
 
+        let kriya_processor = Arc::new(dex::kriya::Kriya::new(
+            Arc::clone(&rpc_pool),
+            Arc::clone(&pool_repo),
+            Arc::clone(&coin_repo),
+            Arc::clone(&db_pool_service),
+            Arc::clone(&kriya_service),
+        ));
+
         // lending processors
         let navi_processor = Arc::new(lending::navi::Navi::new(
-            Arc::clone(&client),
+            Arc::clone(&rpc_pool),
             Arc::clone(&navi_config),
             Arc::clone(&navi_service),
             Arc::clone(&db_lending_service),
         ));
 
         let suilend_processor = Arc::new(lending::suilend::SuiLend::new(
-            Arc::clone(&client),
+            Arc::clone(&rpc_pool),
             Arc::clone(&suilend_config),
             Arc::clone(&suilend_service),
             Arc::clone(&db_lending_service),
         ));
 
         let scallop_processor = Arc::new(lending::scallop::Scallop::new(
-            Arc::clone(&client),
+            Arc::clone(&rpc_pool),
             Arc::clone(&scallop_config),
             Arc::clone(&scallop_service),
             Arc::clone(&db_lending_service),
@@ -191,206 +196,251 @@ impl EventProcessorRegistry {
 
         // oracle processors
         let pyth_processor = Arc::new(oracle::pyth::Pyth::new(
-            Arc::clone(&client),
+            Arc::clone(&rpc_pool),
             Arc::clone(&coin_repo),
             Arc::clone(&db_pool_service),
             Arc::clone(&db_lending_service),
         ));
 
+        let mut registry = Self {
+            config: Arc::clone(&config),
+            db_pool_service,
+            db_lending_service,
+            service_registry,
+            dex_processors: HashMap::new(),
+            lending_processors: HashMap::new(),
+            oracle_processors: HashMap::new(),
+            known_event_types: HashSet::new(),
+        };
+
         // dexs
         if config.arbitrage_enabled {
-            dex_processors.insert(
-                constant::CETUS_SWAP_EVENT.to_string(),
-                Arc::clone(&cetus_processor) as Arc<dyn EventProcessor + Send + Sync>,
-            );
-
-            dex_processors.insert(
-                constant::CETUS_ADD_LIQUIDITY_EVENT.to_string(),
-                Arc::clone(&cetus_processor) as Arc<dyn EventProcessor + Send + Sync>,
-            );
-
-            dex_processors.insert(
-                constant::CETUS_REMOVE_LIQUIDITY_EVENT.to_string(),
-                Arc::clone(&cetus_processor) as Arc<dyn EventProcessor + Send + Sync>,
-            );
-
-            dex_processors.insert(
-                constant::BLUEFIN_SWAP_EVENT.to_string(),
-                Arc::clone(&bluefin_processor) as Arc<dyn EventProcessor + Send + Sync>,
-            );
-
-            dex_processors.insert(
-                constant::BLUEFIN_TICK_UPDATED_EVENT.to_string(),
-                Arc::clone(&bluefin_processor) as Arc<dyn EventProcessor + Send + Sync>,
-            );
-
-            dex_processors.insert(
-                constant::TURBOS_SWAP_EVENT.to_string(),
-                Arc::clone(&turbos_processor) as Arc<dyn EventProcessor + Send + Sync>,
-            );
-
-            dex_processors.insert(
-                constant::TURBOS_ADD_LIQUIDITY_EVENT.to_string(),
-                Arc::clone(&turbos_processor) as Arc<dyn EventProcessor + Send + Sync>,
-            );
-
-            dex_processors.insert(
-                constant::TURBOS_REMOVE_LIQUIDITY_EVENT.to_string(),
-                Arc::clone(&turbos_processor) as Arc<dyn EventProcessor + Send + Sync>,
-            );
-
-            dex_processors.insert(
-                constant::MOMENTUM_SWAP_EVENT.to_string(),
-                Arc::clone(&momentum_processor) as Arc<dyn EventProcessor + Send + Sync>,
-            );
-
-            dex_processors.insert(
-                constant::MOMENTUM_ADD_LIQUIDITY_EVENT.to_string(),
-                Arc::clone(&momentum_processor) as Arc<dyn EventProcessor + Send + Sync>,
-            );
-
-            dex_processors.insert(
-                constant::MOMENTUM_REMOVE_LIQUIDITY_EVENT.to_string(),
-                Arc::clone(&momentum_processor) as Arc<dyn EventProcessor + Send + Sync>,
-            );
-
-            dex_processors.insert(
-                constant::FLOWX_SWAP_EVENT.to_string(),
-                Arc::clone(&flowx_processor) as Arc<dyn EventProcessor + Send + Sync>,
-            );
-
-            dex_processors.insert(
-                constant::FLOWX_MODIFY_LIQUIDITY_EVENT.to_string(),
-                Arc::clone(&flowx_processor) as Arc<dyn EventProcessor + Send + Sync>,
-            );
-
-            dex_processors.insert(
-                constant::BLUEMOVE_SWAP_EVENT.to_string(),
-                Arc::clone(&bluemove_processor) as Arc<dyn EventProcessor + Send + Sync>,
-            );
-
-            dex_processors.insert(
-                constant::AFTERMATH_SWAP_EVENT.to_string(),
-                Arc::clone(&aftermath_processor) as Arc<dyn EventProcessor + Send + Sync>,
-            );
-
-            dex_processors.insert(
-                constant::OBRIC_SWAP_EVENT.to_string(),
-                Arc::clone(&obric_processor) as Arc<dyn EventProcessor + Send + Sync>,
+            registry.register_dex_processor(
+                vec![
+                    constant::CETUS_SWAP_EVENT.to_string(),
+                    constant::CETUS_ADD_LIQUIDITY_EVENT.to_string(),
+                    constant::CETUS_REMOVE_LIQUIDITY_EVENT.to_string(),
+                ],
+                cetus_processor,
+            );
+
+            registry.register_dex_processor(
+                vec![
+                    constant::BLUEFIN_SWAP_EVENT.to_string(),
+                    constant::BLUEFIN_TICK_UPDATED_EVENT.to_string(),
+                ],
+                bluefin_processor,
+            );
+
+            registry.register_dex_processor(
+                vec![
+                    constant::TURBOS_SWAP_EVENT.to_string(),
+                    constant::TURBOS_ADD_LIQUIDITY_EVENT.to_string(),
+                    constant::TURBOS_REMOVE_LIQUIDITY_EVENT.to_string(),
+                ],
+                turbos_processor,
+            );
+
+            registry.register_dex_processor(
+                vec![
+                    constant::MOMENTUM_SWAP_EVENT.to_string(),
+                    constant::MOMENTUM_ADD_LIQUIDITY_EVENT.to_string(),
+                    constant::MOMENTUM_REMOVE_LIQUIDITY_EVENT.to_string(),
+                ],
+                momentum_processor,
+            );
+
+            registry.register_dex_processor(
+                vec![
+                    constant::FLOWX_SWAP_EVENT.to_string(),
+                    constant::FLOWX_MODIFY_LIQUIDITY_EVENT.to_string(),
+                ],
+                flowx_processor,
+            );
+
+            registry.register_dex_processor(
+                vec![
+                    constant::BLUEMOVE_SWAP_EVENT.to_string(),
+                    constant::BLUEMOVE_ADD_LIQUIDITY_EVENT.to_string(),
+                    constant::BLUEMOVE_REMOVE_LIQUIDITY_EVENT.to_string(),
+                ],
+                bluemove_processor,
+            );
+
+            registry.register_dex_processor(
+                vec![
+                    constant::AFTERMATH_SWAP_EVENT.to_string(),
+                    constant::AFTERMATH_ADD_LIQUIDITY_EVENT.to_string(),
+                    constant::AFTERMATH_REMOVE_LIQUIDITY_EVENT.to_string(),
+                ],
+                aftermath_processor,
+            );
+
+            registry.register_dex_processor(
+                vec![constant::OBRIC_SWAP_EVENT.to_string()],
+                obric_processor,
+            );
+
+            registry.register_dex_processor(
+                vec![
+                    constant::KRIYA_SWAP_EVENT.to_string(),
+                    constant::KRIYA_ADD_LIQUIDITY_EVENT.to_string(),
+                    constant::KRIYA_REMOVE_LIQUIDITY_EVENT.to_string(),
+                ],
+                kriya_processor,
             );
         }
 
-        // lendings
-        if config.liquidation_enabled {
-            // navi
-            lending_processors.insert(
-                constant::NAVI_BORROW_EVENT.to_string(),
-                Arc::clone(&navi_processor) as Arc<dyn EventProcessor + Send + Sync>,
-            );
-
-            lending_processors.insert(
-                constant::NAVI_DEPOSIT_EVENT.to_string(),
-                Arc::clone(&navi_processor) as Arc<dyn EventProcessor + Send + Sync>,
-            );
-
-            lending_processors.insert(
-                constant::NAVI_REPAY_EVENT.to_string(),
-                Arc::clone(&navi_processor) as Arc<dyn EventProcessor + Send + Sync>,
-            );
-
-            lending_processors.insert(
-                constant::NAVI_WITHDRAW_EVENT.to_string(),
-                Arc::clone(&navi_processor) as Arc<dyn EventProcessor + Send + Sync>,
-            );
-
-            lending_processors.insert(
-                constant::NAVI_LIQUIDATE_EVENT.to_string(),
-                Arc::clone(&navi_processor) as Arc<dyn EventProcessor + Send + Sync>,
-            );
-
-            lending_processors.insert(
-                constant::NAVI_STATE_UPDATED_EVENT.to_string(),
-                Arc::clone(&navi_processor) as Arc<dyn EventProcessor + Send + Sync>,
-            );
-
-            // suilend
-            lending_processors.insert(
-                constant::SUILEND_BORROW_EVENT.to_string(),
-                Arc::clone(&suilend_processor) as Arc<dyn EventProcessor + Send + Sync>,
-            );
-
-            lending_processors.insert(
-                constant::SUILEND_DEPOSIT_EVENT.to_string(),
-                Arc::clone(&suilend_processor) as Arc<dyn EventProcessor + Send + Sync>,
+        // lendings, `liquidation_enabled` is the master switch for the per-platform toggles
+        if config.liquidation_enabled && config.navi.enabled {
+            registry.register_lending_processor(
+                vec![
+                    constant::NAVI_BORROW_EVENT.to_string(),
+                    constant::NAVI_DEPOSIT_EVENT.to_string(),
+                    constant::NAVI_REPAY_EVENT.to_string(),
+                    constant::NAVI_WITHDRAW_EVENT.to_string(),
+                    constant::NAVI_LIQUIDATE_EVENT.to_string(),
+                    constant::NAVI_STATE_UPDATED_EVENT.to_string(),
+                ],
+                navi_processor,
             );
+        }
 
-            lending_processors.insert(
-                constant::SUILEND_REPAY_EVENT.to_string(),
-                Arc::clone(&suilend_processor) as Arc<dyn EventProcessor + Send + Sync>,
+        if config.liquidation_enabled && config.suilend.enabled {
+            registry.register_lending_processor(
+                vec![
+                    constant::SUILEND_BORROW_EVENT.to_string(),
+                    constant::SUILEND_DEPOSIT_EVENT.to_string(),
+                    constant::SUILEND_REPAY_EVENT.to_string(),
+                    constant::SUILEND_WITHDRAW_EVENT.to_string(),
+                    constant::SUILEND_LIQUIDATE_EVENT.to_string(),
+                ],
+                suilend_processor,
             );
+        }
 
-            lending_processors.insert(
-                constant::SUILEND_WITHDRAW_EVENT.to_string(),
-                Arc::clone(&suilend_processor) as Arc<dyn EventProcessor + Send + Sync>,
+        if config.liquidation_enabled && config.scallop.enabled {
+            registry.register_lending_processor(
+                vec![
+                    constant::SCALLOP_BORROW_EVENT.to_string(),
+                    constant::SCALLOP_BORROW_EVENT_V2.to_string(),
+                    constant::SCALLOP_BORROW_EVENT_V3.to_string(),
+                    constant::SCALLOP_DEPOSIT_EVENT.to_string(),
+                    constant::SCALLOP_REPAY_EVENT.to_string(),
+                    constant::SCALLOP_WITHDRAW_EVENT.to_string(),
+                    constant::SCALLOP_LIQUIDATE_EVENT_V2.to_string(),
+                ],
+                scallop_processor,
             );
+        }
 
-            lending_processors.insert(
-                constant::SUILEND_LIQUIDATE_EVENT.to_string(),
-                Arc::clone(&suilend_processor) as Arc<dyn EventProcessor + Send + Sync>,
-            );
+        // oracles
+        registry.register_oracle_processor(
+            vec![constant::PYTH_UPDATE_PRICE_EVENT.to_string()],
+            pyth_processor,
+        );
 
-            // scallop
-            lending_processors.insert(
-                constant::SCALLOP_BORROW_EVENT.to_string(),
-                Arc::clone(&scallop_processor) as Arc<dyn EventProcessor + Send + Sync>,
-            );
+        if let Some(supra_config) = &config.supra {
+            let supra_processor = Arc::new(oracle::supra::Supra::new(
+                Arc::new(supra_config.clone()),
+                Arc::clone(&registry.db_lending_service),
+            ));
 
-            lending_processors.insert(
-                constant::SCALLOP_BORROW_EVENT_V2.to_string(),
-                Arc::clone(&scallop_processor) as Arc<dyn EventProcessor + Send + Sync>,
+            registry.register_oracle_processor(
+                vec![oracle::supra::Supra::price_update_event_type(supra_config)],
+                supra_processor,
             );
+        }
 
-            lending_processors.insert(
-                constant::SCALLOP_BORROW_EVENT_V3.to_string(),
-                Arc::clone(&scallop_processor) as Arc<dyn EventProcessor + Send + Sync>,
-            );
+        if let Some(switchboard_config) = &config.switchboard {
+            let switchboard_processor = Arc::new(oracle::switchboard::Switchboard::new(
+                Arc::new(switchboard_config.clone()),
+                Arc::clone(&registry.db_lending_service),
+            ));
 
-            lending_processors.insert(
-                constant::SCALLOP_DEPOSIT_EVENT.to_string(),
-                Arc::clone(&scallop_processor) as Arc<dyn EventProcessor + Send + Sync>,
+            registry.register_oracle_processor(
+                vec![oracle::switchboard::Switchboard::price_update_event_type(
+                    switchboard_config,
+                )],
+                switchboard_processor,
             );
+        }
 
-            lending_processors.insert(
-                constant::SCALLOP_REPAY_EVENT.to_string(),
-                Arc::clone(&scallop_processor) as Arc<dyn EventProcessor + Send + Sync>,
-            );
+        registry
+    }
 
-            lending_processors.insert(
-                constant::SCALLOP_WITHDRAW_EVENT.to_string(),
-                Arc::clone(&scallop_processor) as Arc<dyn EventProcessor + Send + Sync>,
-            );
+    /// Registers a DEX event processor for the given event types.
+    /// DEX processors are only dispatched to when arbitrage is enabled.
+    /// An event type registered twice is dispatched to the last processor.
+    ///
+    pub fn register_dex_processor(
+        &mut self,
+        event_types: Vec<String>,
+        processor: Arc<dyn EventProcessor + Send + Sync>,
+    ) {
+        register_processor(
+            &mut self.dex_processors,
+            &mut self.known_event_types,
+            event_types,
+            processor,
+        );
+    }
 
-            lending_processors.insert(
-                constant::SCALLOP_LIQUIDATE_EVENT_V2.to_string(),
-                Arc::clone(&scallop_processor) as Arc<dyn EventProcessor + Send + Sync>,
-            );
-        }
+    /// Registers a lending event processor for the given event types.
+    /// Lending processors are only dispatched to when liquidation is enabled.
+    /// An event type registered twice is dispatched to the last processor.
+    ///
+    pub fn register_lending_processor(
+        &mut self,
+        event_types: Vec<String>,
+        processor: Arc<dyn EventProcessor + Send + Sync>,
+    ) {
+        register_processor(
+            &mut self.lending_processors,
+            &mut self.known_event_types,
+            event_types,
+            processor,
+        );
+    }
 
-        // oracles
-        oracle_processors.insert(
-            constant::PYTH_UPDATE_PRICE_EVENT.to_string(),
-            Arc::clone(&pyth_processor) as Arc<dyn EventProcessor + Send + Sync>,
+    /// Registers an oracle event processor for the given event types.
+    /// Oracle processors are always dispatched to.
+    /// An event type registered twice is dispatched to the last processor.
+    ///
+    pub fn register_oracle_processor(
+        &mut self,
+        event_types: Vec<String>,
+        processor: Arc<dyn EventProcessor + Send + Sync>,
+    ) {
+        register_processor(
+            &mut self.oracle_processors,
+            &mut self.known_event_types,
+            event_types,
+            processor,
         );
+    }
 
-        Self {
-            config,
-            db_pool_service,
-            db_lending_service,
-            service_registry,
-            dex_processors,
-            lending_processors,
-            oracle_processors,
-        }
+    /// Whether a processor is registered for the event type, without generics.
+    /// Cheap pre-filter for the events of a checkpoint.
+    ///
+    pub fn is_known_event_type(&self, event_type: &str) -> bool {
+        self.known_event_types.contains(event_type)
+    }
+
+    /// Returns all registered event types, sorted.
+    /// Mostly for diagnostics.
+    ///
+    pub fn registered_event_types(&self) -> Vec<String> {
+        let mut event_types = self
+            .dex_processors
+            .keys()
+            .chain(self.lending_processors.keys())
+            .chain(self.oracle_processors.keys())
+            .cloned()
+            .collect::<Vec<_>>();
+        event_types.sort();
+
+        event_types
     }
 
     /// Processes tx events.
@@ -399,9 +449,19 @@ impl EventProcessorRegistry {
     pub async fn process_tx_event(&self, event: SuiEvent, tx_digest: &str) -> Result<()> {
         let event_type = utils::extract_event_type(&event.type_.to_string())?;
         let sender = event.sender.to_string();
+        let event_seq = event.id.event_seq;
         let data = event.parsed_json;
 
         if let Some(processor) = self.find_processor_for_event_type(&event_type) {
+            match processor.decode_tx_swap_event(&event_type, &data, tx_digest, event_seq) {
+                Ok(Some(swap_event)) => self.record_swap_events(&[swap_event]).await,
+                Ok(None) => {}
+                Err(e) => error!(
+                    "{} failed to decode swap event {} of tx {}: {}",
+                    processor, event_type, tx_digest, e
+                ),
+            }
+
             processor
                 .process_tx_event(&event_type, &sender, data, tx_digest)
                 .await
@@ -462,6 +522,54 @@ impl EventProcessorRegistry {
         }
     }
 
+    /// Decodes the swap of a DEX swap event from checkpoint data,
+    /// `None` for the other events or when it can't be decoded.
+    ///
+    pub fn decode_swap_event(
+        &self,
+        event: &Event,
+        tx_digest: &str,
+        event_seq: u64,
+    ) -> Option<models::swap_event::NewSwapEvent> {
+        let event_type = utils::extract_event_type(&event.type_.to_string()).ok()?;
+        let processor = self.find_processor_for_event_type(&event_type)?;
+
+        processor
+            .decode_swap_event(&event_type, event, tx_digest, event_seq)
+            .unwrap_or_else(|e| {
+                error!(
+                    "{} failed to decode swap event {} of tx {}: {}",
+                    processor, event_type, tx_digest, e
+                );
+                None
+            })
+    }
+
+    /// Records swaps for volume analytics.
+    /// A failure is only logged, the events are processed regardless.
+    ///
+    pub async fn record_swap_events(&self, swap_events: &[models::swap_event::NewSwapEvent]) {
+        if let Err(e) = self
+            .db_pool_service
+            .save_swap_events_to_db(swap_events)
+            .await
+        {
+            error!("{}", e);
+        }
+    }
+
+    /// Whether the event can be skipped when recently processed,
+    /// as decided by its processor.
+    ///
+    pub fn is_dedupable_event(&self, event: &Event) -> bool {
+        let Ok(event_type) = utils::extract_event_type(&event.type_.to_string()) else {
+            return false;
+        };
+
+        self.find_processor_for_event_type(&event_type)
+            .is_some_and(|processor| processor.is_dedupable(&event_type))
+    }
+
     /// Finds the appropriate processor for the given event type.
     ///
     fn find_processor_for_event_type(
@@ -490,3 +598,154 @@ impl EventProcessorRegistry {
         None
     }
 }
+
+/// Maps each event type to the processor, replacing the processor registered before, if any.
+fn register_processor(
+    processors: &mut HashMap<String, Arc<dyn EventProcessor + Send + Sync>>,
+    known_event_types: &mut HashSet<String>,
+    event_types: Vec<String>,
+    processor: Arc<dyn EventProcessor + Send + Sync>,
+) {
+    for event_type in event_types {
+        known_event_types.insert(event_type.clone());
+        if let Some(previous) = processors.insert(event_type.clone(), Arc::clone(&processor)) {
+            warn!(
+                "{} replaces {} as the processor of {}",
+                processor, previous, event_type
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::indexer::test_event;
+    use crate::service::db_service::mock::MockDb;
+    use async_trait::async_trait;
+    use serde_json::Value;
+    use std::fmt;
+
+    /// Processor identified by its name in the event IDs it returns.
+    struct NamedProcessor(&'static str);
+
+    impl fmt::Display for NamedProcessor {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{}", self.0)
+        }
+    }
+
+    #[async_trait]
+    impl EventProcessor for NamedProcessor {
+        async fn process_tx_event(
+            &self,
+            _event_type: &str,
+            _sender: &str,
+            _data: Value,
+            _tx_digest: &str,
+        ) -> Result<()> {
+            Ok(())
+        }
+
+        async fn process_raw_event(
+            &self,
+            _event_type: &str,
+            _sender: &str,
+            _event: Event,
+            _tx_digest: &str,
+        ) -> Result<OnchainEvent> {
+            Ok(OnchainEvent::VoidEvent)
+        }
+
+        fn get_event_id(&self, event_type: &str, _event: &Event) -> Result<String> {
+            Ok(format!("{}:{}", self.0, event_type))
+        }
+    }
+
+    fn test_registry(arbitrage_enabled: bool) -> EventProcessorRegistry {
+        let mut config = crate::config::test_config();
+        config.arbitrage_enabled = arbitrage_enabled;
+
+        let db = Arc::new(MockDb::default());
+        let service_registry = Arc::new(db.service_registry(Arc::new(config)));
+        db.event_processor_registry(&service_registry)
+    }
+
+    /// Event type of `event` as the registry looks it up.
+    fn event_type(event: &Event) -> String {
+        utils::extract_event_type(&event.type_.to_string()).unwrap()
+    }
+
+    #[test]
+    fn processors_are_resolved_by_event_type() {
+        let mut registry = test_registry(true);
+        let swap = test_event("0x1::stub::Swap", vec![]);
+        let price = test_event("0x2::stub::Price", vec![]);
+        registry.register_dex_processor(vec![event_type(&swap)], Arc::new(NamedProcessor("dex")));
+        registry.register_oracle_processor(
+            vec![event_type(&price)],
+            Arc::new(NamedProcessor("oracle")),
+        );
+
+        assert_eq!(
+            registry.get_event_id(&swap).unwrap(),
+            format!("dex:{}", event_type(&swap))
+        );
+        assert_eq!(
+            registry.get_event_id(&price).unwrap(),
+            format!("oracle:{}", event_type(&price))
+        );
+        assert!(registry.is_known_event_type(&event_type(&swap)));
+        assert!(registry.is_known_event_type(&event_type(&price)));
+
+        let registered = registry.registered_event_types();
+        assert!(registered.contains(&event_type(&swap)));
+        assert!(registered.contains(&event_type(&price)));
+        assert!(registered.is_sorted());
+    }
+
+    #[test]
+    fn unknown_event_type_has_no_processor() {
+        let registry = test_registry(true);
+        let unknown = test_event("0x3::stub::Unknown", vec![]);
+
+        assert!(!registry.is_known_event_type(&event_type(&unknown)));
+        assert!(registry.get_event_id(&unknown).is_err());
+        assert!(!registry.is_dedupable_event(&unknown));
+    }
+
+    #[test]
+    fn duplicate_registration_dispatches_to_the_last_processor() {
+        let mut registry = test_registry(true);
+        let price = test_event("0x2::stub::Price", vec![]);
+        registry
+            .register_oracle_processor(vec![event_type(&price)], Arc::new(NamedProcessor("first")));
+        registry.register_oracle_processor(
+            vec![event_type(&price)],
+            Arc::new(NamedProcessor("second")),
+        );
+
+        assert_eq!(
+            registry.get_event_id(&price).unwrap(),
+            format!("second:{}", event_type(&price))
+        );
+        let registered = registry.registered_event_types();
+        assert_eq!(
+            registered
+                .iter()
+                .filter(|registered| **registered == event_type(&price))
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn dex_processors_are_skipped_when_arbitrage_is_disabled() {
+        let mut registry = test_registry(false);
+        let swap = test_event("0x1::stub::Swap", vec![]);
+        registry.register_dex_processor(vec![event_type(&swap)], Arc::new(NamedProcessor("dex")));
+
+        assert!(registry.is_known_event_type(&event_type(&swap)));
+        assert!(registry.get_event_id(&swap).is_err());
+    }
+}
@@ -1,3 +1,4 @@
+pub mod alert;
 pub mod db_service;
 pub mod dex;
 pub mod lending;
@@ -0,0 +1,55 @@
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, error, info, instrument, trace, warn};
+
+/// Alert fired when the indexer lags behind the chain more than the configured threshold.
+///
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LaggingAlert {
+    pub lagging_ms: u64,
+    pub lagging_ms_threshold: u64,
+    pub latest_seq_number: u64,
+    pub latest_timestamp_ms: u64,
+}
+
+/// Destination of the indexer alerts.
+///
+#[async_trait]
+pub trait AlertSink: Send + Sync {
+    async fn send(&self, alert: &LaggingAlert) -> Result<()>;
+}
+
+/// Posts the alerts as JSON to a webhook URL.
+///
+pub struct WebhookAlertSink {
+    url: String,
+    client: reqwest::Client,
+}
+
+impl WebhookAlertSink {
+    pub fn new(url: &str) -> Self {
+        WebhookAlertSink {
+            url: url.to_string(),
+            client: reqwest::Client::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl AlertSink for WebhookAlertSink {
+    async fn send(&self, alert: &LaggingAlert) -> Result<()> {
+        self.client
+            .post(&self.url)
+            .json(alert)
+            .send()
+            .await
+            .map_err(|e| anyhow!("Failed to post alert to {}: {}", self.url, e))?
+            .error_for_status()
+            .map_err(|e| anyhow!("Alert webhook {} rejected the alert: {}", self.url, e))?;
+
+        debug!("Posted alert to {}: {:?}", self.url, alert);
+
+        Ok(())
+    }
+}
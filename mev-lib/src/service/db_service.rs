@@ -1,2 +1,5 @@
 pub mod lending;
 pub mod pool;
+
+#[cfg(test)]
+pub(crate) mod mock;
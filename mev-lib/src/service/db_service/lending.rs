@@ -1,7 +1,7 @@
 use crate::{
     config::Config,
     constant,
-    types::{BorrowerAsset, PythPrice},
+    types::{BorrowerAsset, BorrowerStatus, PythPrice},
     utils,
 };
 use db::models::{
@@ -11,9 +11,10 @@ use db::models::{
     user_borrow, user_deposit,
 };
 use db::repositories::{
-    BorrowerRepository, CoinRepository, MetricRepository, SharedObjectRepository,
-    UserBorrowRepository, UserDepositRepository,
+    BorrowerRepository, CoinRepository, FailedEventRepository, MetricRepository,
+    SharedObjectRepository, UserBorrowRepository, UserDepositRepository,
 };
+use db::RepoError;
 
 use anyhow::{anyhow, Result};
 use rayon::prelude::*;
@@ -33,6 +34,7 @@ pub struct LendingService {
     borrower_repo: Arc<dyn BorrowerRepository + Send + Sync>,
     metric_repo: Arc<dyn MetricRepository + Send + Sync>,
     shared_object_repo: Arc<dyn SharedObjectRepository + Send + Sync>,
+    failed_event_repo: Arc<dyn FailedEventRepository + Send + Sync>,
 }
 
 impl LendingService {
@@ -44,6 +46,7 @@ impl LendingService {
         borrower_repo: Arc<dyn BorrowerRepository + Send + Sync>,
         metric_repo: Arc<dyn MetricRepository + Send + Sync>,
         shared_object_repo: Arc<dyn SharedObjectRepository + Send + Sync>,
+        failed_event_repo: Arc<dyn FailedEventRepository + Send + Sync>,
     ) -> Self {
         LendingService {
             config,
@@ -53,6 +56,7 @@ impl LendingService {
             borrower_repo,
             metric_repo,
             shared_object_repo,
+            failed_event_repo,
         }
     }
 
@@ -74,7 +78,7 @@ impl LendingService {
                 self.borrower_repo
                     .update(existing_borrower.id, &update_borrower)?
             }
-            Err(_) => {
+            Err(RepoError::NotFound) => {
                 let new_borrower = db::models::borrower::NewBorrower {
                     platform: borrower.platform.clone(),
                     borrower: borrower.borrower.clone(),
@@ -83,27 +87,225 @@ impl LendingService {
                 };
                 self.borrower_repo.create(&new_borrower)?
             }
+            Err(e) => {
+                return Err(anyhow!(
+                    "Failed to find borrower {} on platform {}: {}",
+                    borrower.borrower,
+                    borrower.platform,
+                    e
+                ))
+            }
         };
 
         Ok(borrower)
     }
 
+    /// Saves the borrower with its borrows and deposits atomically,
+    /// either all of them are saved or none is.
+    ///
+    pub fn save_borrower_with_positions(
+        &self,
+        borrower: crate::types::Borrower,
+        user_borrows: Vec<crate::types::UserBorrow>,
+        user_deposits: Vec<crate::types::UserDeposit>,
+    ) -> Result<models::borrower::Borrower> {
+        let (new_borrower, new_borrows, new_deposits) =
+            Self::new_portfolio(&borrower, user_borrows, user_deposits)?;
+
+        self.borrower_repo
+            .save_with_positions(&new_borrower, &new_borrows, &new_deposits)
+            .map_err(|e| {
+                anyhow!(
+                    "Failed to save borrower {} on platform {} with its positions: {}",
+                    borrower.borrower,
+                    borrower.platform,
+                    e
+                )
+            })
+    }
+
+    /// Replaces the stored borrows and deposits of the borrower with the given ones,
+    /// the deletion and the save happen in one transaction.
+    ///
+    pub fn replace_borrower_with_positions(
+        &self,
+        borrower: crate::types::Borrower,
+        user_borrows: Vec<crate::types::UserBorrow>,
+        user_deposits: Vec<crate::types::UserDeposit>,
+    ) -> Result<models::borrower::Borrower> {
+        let (new_borrower, new_borrows, new_deposits) =
+            Self::new_portfolio(&borrower, user_borrows, user_deposits)?;
+
+        self.borrower_repo
+            .replace_with_positions(&new_borrower, &new_borrows, &new_deposits)
+            .map_err(|e| {
+                anyhow!(
+                    "Failed to replace the positions of borrower {} on platform {}: {}",
+                    borrower.borrower,
+                    borrower.platform,
+                    e
+                )
+            })
+    }
+
+    fn new_portfolio(
+        borrower: &crate::types::Borrower,
+        user_borrows: Vec<crate::types::UserBorrow>,
+        user_deposits: Vec<crate::types::UserDeposit>,
+    ) -> Result<(
+        NewBorrower,
+        Vec<user_borrow::NewUserBorrow>,
+        Vec<user_deposit::NewUserDeposit>,
+    )> {
+        // reject the whole portfolio before the transaction if any amount is malformed
+        for amount in user_borrows
+            .iter()
+            .map(|b| &b.amount)
+            .chain(user_deposits.iter().map(|d| &d.amount))
+        {
+            utils::validate_amount_str(amount).map_err(|e| {
+                anyhow!(
+                    "Invalid position of {} on {}: {}",
+                    borrower.borrower,
+                    borrower.platform,
+                    e
+                )
+            })?;
+        }
+
+        let new_borrower = NewBorrower {
+            platform: borrower.platform.clone(),
+            borrower: borrower.borrower.clone(),
+            obligation_id: borrower.obligation_id.clone(),
+            status: borrower.status,
+        };
+
+        let new_borrows = user_borrows
+            .into_iter()
+            .map(|user_borrow| user_borrow::NewUserBorrow {
+                platform: user_borrow.platform,
+                borrower: user_borrow.borrower,
+                coin_type: user_borrow.coin_type,
+                amount: user_borrow.amount,
+                obligation_id: user_borrow.obligation_id,
+                debt_borrow_index: user_borrow.debt_borrow_index,
+            })
+            .collect::<Vec<_>>();
+
+        let new_deposits = user_deposits
+            .into_iter()
+            .map(|user_deposit| user_deposit::NewUserDeposit {
+                platform: user_deposit.platform,
+                borrower: user_deposit.borrower,
+                coin_type: user_deposit.coin_type,
+                amount: user_deposit.amount,
+                obligation_id: user_deposit.obligation_id,
+            })
+            .collect::<Vec<_>>();
+
+        Ok((new_borrower, new_borrows, new_deposits))
+    }
+
+    /// Rebuilds the borrower portfolio from on-chain data,
+    /// for positions which drifted after missed checkpoints.
+    /// The stored deposits and borrows are replaced with the fetched ones,
+    /// and the borrower is saved with its obligation ID as ready.
+    ///
+    pub async fn resync_borrower(
+        &self,
+        lending_service: &(dyn crate::service::lending::LendingService + Send + Sync),
+        platform: &str,
+        borrower: &str,
+    ) -> Result<models::borrower::Borrower> {
+        let existing_borrower = match self
+            .borrower_repo
+            .find_by_platform_and_address(platform, borrower)
+        {
+            Ok(existing_borrower) => Some(existing_borrower),
+            Err(RepoError::NotFound) => None,
+            Err(e) => {
+                return Err(anyhow!(
+                    "Failed to find borrower {} on platform {}: {}",
+                    borrower,
+                    platform,
+                    e
+                ))
+            }
+        };
+
+        if let Some(existing_borrower) = &existing_borrower {
+            let current_status = BorrowerStatus::try_from(existing_borrower.status)?;
+            if !current_status.can_transition_to(BorrowerStatus::Ready) {
+                return Err(anyhow!(
+                    "Borrower {} on platform {} can't go from {} to {}",
+                    borrower,
+                    platform,
+                    current_status,
+                    BorrowerStatus::Ready
+                ));
+            }
+        }
+
+        let obligation_id = match existing_borrower.and_then(|b| b.obligation_id) {
+            Some(obligation_id) => Some(obligation_id),
+            // platforms without obligations don't support the lookup
+            None => lending_service
+                .find_obligation_id_from_address(borrower)
+                .await
+                .ok(),
+        };
+
+        let (user_deposits, user_borrows) = lending_service
+            .fetch_borrower_portfolio(borrower.to_string(), obligation_id.clone())
+            .await?;
+
+        let borrower_m = self.replace_borrower_with_positions(
+            crate::types::Borrower {
+                platform: platform.to_string(),
+                borrower: borrower.to_string(),
+                obligation_id,
+                status: BorrowerStatus::Ready.into(),
+            },
+            user_borrows,
+            user_deposits,
+        )?;
+
+        info!("Resynced borrower {} on platform {}", borrower, platform);
+
+        Ok(borrower_m)
+    }
+
+    /// Updates the status of the borrower.
+    /// Fails if the borrower can't move from its current status to `status`,
+    /// e.g. from liquidated back to pending.
+    ///
     pub fn update_borrower_status_to_db(
         &self,
         platform: &str,
         borrower: &str,
-        status: i32,
+        status: BorrowerStatus,
     ) -> Result<models::borrower::Borrower> {
         match self
             .borrower_repo
             .find_by_platform_and_address(platform, borrower)
         {
             Ok(existing_borrower) => {
+                let current_status = BorrowerStatus::try_from(existing_borrower.status)?;
+                if !current_status.can_transition_to(status) {
+                    return Err(anyhow!(
+                        "Borrower {} on platform {} can't go from {} to {}",
+                        borrower,
+                        platform,
+                        current_status,
+                        status
+                    ));
+                }
+
                 let update_borrower = UpdateBorrower {
                     platform: None,
                     borrower: None,
                     obligation_id: None,
-                    status: Some(status),
+                    status: Some(status.into()),
                 };
                 let borrower_m = self
                     .borrower_repo
@@ -115,7 +317,7 @@ impl LendingService {
                 );
                 Ok(borrower_m)
             }
-            Err(_) => {
+            Err(RepoError::NotFound) => {
                 error!(
                     "Borrower {} on platform {} not found for status update",
                     borrower, platform
@@ -126,9 +328,35 @@ impl LendingService {
                     platform
                 ))
             }
+            Err(e) => Err(anyhow!(
+                "Failed to find borrower {} on platform {}: {}",
+                borrower,
+                platform,
+                e
+            )),
         }
     }
 
+    /// Marks the borrower ready for liquidation checks.
+    ///
+    pub fn mark_borrower_ready(
+        &self,
+        platform: &str,
+        borrower: &str,
+    ) -> Result<models::borrower::Borrower> {
+        self.update_borrower_status_to_db(platform, borrower, BorrowerStatus::Ready)
+    }
+
+    /// Marks the borrower liquidated, only a ready borrower can be liquidated.
+    ///
+    pub fn mark_borrower_liquidated(
+        &self,
+        platform: &str,
+        borrower: &str,
+    ) -> Result<models::borrower::Borrower> {
+        self.update_borrower_status_to_db(platform, borrower, BorrowerStatus::Liquidated)
+    }
+
     pub async fn delete_borrower_portfolio_from_db(
         &self,
         platform: &str,
@@ -142,10 +370,62 @@ impl LendingService {
         Ok(())
     }
 
+    /// Finds the deposits and borrows of a borrower stored in the database.
+    ///
+    pub fn find_borrower_portfolio_from_db(
+        &self,
+        platform: &str,
+        borrower: &str,
+    ) -> Result<(
+        Vec<crate::types::UserDeposit>,
+        Vec<crate::types::UserBorrow>,
+    )> {
+        let user_deposits = self
+            .user_deposit_repo
+            .find_by_platform_and_address(platform, borrower)
+            .map_err(|e| anyhow!("Error finding user deposits of {}: {}", borrower, e))?
+            .into_iter()
+            .map(|d| crate::types::UserDeposit {
+                platform: d.platform,
+                borrower: d.borrower,
+                obligation_id: d.obligation_id,
+                coin_type: d.coin_type,
+                amount: d.amount,
+            })
+            .collect();
+
+        let user_borrows = self
+            .user_borrow_repo
+            .find_by_platform_and_address(platform, borrower)
+            .map_err(|e| anyhow!("Error finding user borrows of {}: {}", borrower, e))?
+            .into_iter()
+            .map(|b| crate::types::UserBorrow {
+                platform: b.platform,
+                borrower: b.borrower,
+                obligation_id: b.obligation_id,
+                coin_type: b.coin_type,
+                amount: b.amount,
+                debt_borrow_index: b.debt_borrow_index,
+            })
+            .collect();
+
+        Ok((user_deposits, user_borrows))
+    }
+
     pub async fn save_user_borrow_to_db(
         &self,
         user_borrow: crate::types::UserBorrow,
     ) -> Result<()> {
+        utils::validate_amount_str(&user_borrow.amount).map_err(|e| {
+            anyhow!(
+                "Invalid borrow of {} on {} for {}: {}",
+                user_borrow.borrower,
+                user_borrow.platform,
+                user_borrow.coin_type,
+                e
+            )
+        })?;
+
         let user_borrow = match self
             .user_borrow_repo
             .find_by_platform_and_address_and_coin_type(
@@ -166,7 +446,7 @@ impl LendingService {
                 self.user_borrow_repo
                     .update(existing_borrow.id, &update_borrow)?
             }
-            Err(_) => {
+            Err(RepoError::NotFound) => {
                 let new_borrow = user_borrow::NewUserBorrow {
                     platform: user_borrow.platform.clone(),
                     borrower: user_borrow.borrower.clone(),
@@ -178,6 +458,14 @@ impl LendingService {
 
                 self.user_borrow_repo.create(&new_borrow)?
             }
+            Err(e) => {
+                return Err(anyhow!(
+                    "Failed to find user borrow of {} on platform {}: {}",
+                    user_borrow.borrower,
+                    user_borrow.platform,
+                    e
+                ))
+            }
         };
 
         Ok(())
@@ -187,6 +475,16 @@ impl LendingService {
         &self,
         user_deposit: crate::types::UserDeposit,
     ) -> Result<()> {
+        utils::validate_amount_str(&user_deposit.amount).map_err(|e| {
+            anyhow!(
+                "Invalid deposit of {} on {} for {}: {}",
+                user_deposit.borrower,
+                user_deposit.platform,
+                user_deposit.coin_type,
+                e
+            )
+        })?;
+
         let user_deposit = match self
             .user_deposit_repo
             .find_by_platform_and_address_and_coin_type(
@@ -205,7 +503,7 @@ impl LendingService {
                 self.user_deposit_repo
                     .update(existing_deposit.id, &update_deposit)?
             }
-            Err(_) => {
+            Err(RepoError::NotFound) => {
                 let new_deposit = user_deposit::NewUserDeposit {
                     platform: user_deposit.platform.clone(),
                     borrower: user_deposit.borrower.clone(),
@@ -215,6 +513,14 @@ impl LendingService {
                 };
                 self.user_deposit_repo.create(&new_deposit)?
             }
+            Err(e) => {
+                return Err(anyhow!(
+                    "Failed to find user deposit of {} on platform {}: {}",
+                    user_deposit.borrower,
+                    user_deposit.platform,
+                    e
+                ))
+            }
         };
 
         Ok(())
@@ -223,31 +529,41 @@ impl LendingService {
     /// Saves the Pyth price to the database.
     /// This function will:
     /// 1. Find the coins associated with the Pyth feed ID.
-    /// 2. Update the price of each coin in parallel.
-    /// 3. Save the updated coins to the MEV database if `use_mev_db` is true.
-    /// 4. Return the updated coins as a vector of `models::coin::Coin`.
+    /// 2. Update the price of all the coins in a single transaction.
+    /// 3. Return the number of updated coins.
+    ///
+    /// A feed ID not mapped to any coin yet is a no-op returning 0,
+    /// only database errors are returned as errors.
     ///
     pub async fn save_pyth_price(
         &self,
         pyth_price: crate::types::PythPrice,
         use_hermes: bool,
-    ) -> Result<Vec<models::coin::Coin>> {
+    ) -> Result<usize> {
         let coin_models = self
             .coin_repo
             .find_by_pyth_feed_id(&pyth_price.feed_id)
             .map_err(|e| {
-                error!("Error finding coin by Pyth feed ID: {:?}", e);
-                anyhow!("Error finding coin by Pyth feed ID")
+                anyhow!(
+                    "Error finding coin by Pyth feed ID {}: {}",
+                    pyth_price.feed_id,
+                    e
+                )
             })?;
 
+        // feeds of assets not mapped to a coin yet are streamed too, nothing to update
+        if coin_models.is_empty() {
+            debug!("No coin mapped to Pyth feed ID: {}", pyth_price.feed_id);
+            return Ok(0);
+        }
+
         info!(
             "Found {} coins for Pyth feed ID: {}",
             coin_models.len(),
             pyth_price.feed_id
         );
 
-        // update coin price in parallel
-        let updated_coins = if use_hermes {
+        let updates = if use_hermes {
             // if price hermes, update the price directly to the model
             coin_models
                 .iter()
@@ -273,13 +589,17 @@ impl LendingService {
                             pyth_price.latest_updated_timestamp,
                         )),
                         vaa: pyth_price.vaa.clone(),
+                        supra_pair_id: None,
+                        supra_latest_updated_at: None,
+                        switchboard_aggregator_id: None,
+                        switchboard_latest_updated_at: None,
                     };
-                    self.coin_repo.update(coin_model.id, &update_coin)
+                    (coin_model.id, update_coin)
                 })
-                .collect::<Result<Vec<_>, _>>()?
+                .collect::<Vec<_>>()
         } else {
             coin_models
-                .par_iter()
+                .iter()
                 .map(|coin_model| {
                     let update_coin = UpdateCoin {
                         coin_type: None,
@@ -302,13 +622,203 @@ impl LendingService {
                         hermes_price: None,
                         hermes_latest_updated_at: None,
                         vaa: None,
+                        supra_pair_id: None,
+                        supra_latest_updated_at: None,
+                        switchboard_aggregator_id: None,
+                        switchboard_latest_updated_at: None,
                     };
 
-                    self.coin_repo.update(coin_model.id, &update_coin)
+                    (coin_model.id, update_coin)
                 })
-                .collect::<Result<Vec<_>, _>>()?
+                .collect::<Vec<_>>()
         };
 
+        let updated_coins = self.coin_repo.update_prices_batch(&updates).map_err(|e| {
+            anyhow!(
+                "Failed to update prices for Pyth feed ID {}: {}",
+                pyth_price.feed_id,
+                e
+            )
+        })?;
+
+        if updated_coins != coin_models.len() {
+            return Err(anyhow!(
+                "Updated {} coins for Pyth feed ID {}, expected {}",
+                updated_coins,
+                pyth_price.feed_id,
+                coin_models.len()
+            ));
+        }
+
+        Ok(updated_coins)
+    }
+
+    /// Saves the Supra price to the database.
+    /// Updates the Supra price and its timestamp of all coins associated with the pair ID
+    /// in a single transaction, and returns the number of updated coins.
+    ///
+    pub async fn save_supra_price(&self, supra_price: crate::types::SupraPrice) -> Result<usize> {
+        let pair_id = i32::try_from(supra_price.pair_id)
+            .map_err(|_| anyhow!("Supra pair ID {} out of range", supra_price.pair_id))?;
+
+        let coin_models = self.coin_repo.find_by_supra_pair_id(pair_id).map_err(|e| {
+            anyhow!(
+                "Error finding coin by Supra pair ID {}: {}",
+                supra_price.pair_id,
+                e
+            )
+        })?;
+
+        if coin_models.is_empty() {
+            debug!("No coin mapped to Supra pair ID: {}", supra_price.pair_id);
+            return Ok(0);
+        }
+
+        info!(
+            "Found {} coins for Supra pair ID: {}",
+            coin_models.len(),
+            supra_price.pair_id
+        );
+
+        let updates = coin_models
+            .iter()
+            .map(|coin_model| {
+                let update_coin = UpdateCoin {
+                    coin_type: None,
+                    decimals: None,
+                    name: None,
+                    symbol: None,
+                    price_pyth: None,
+                    price_supra: Some(supra_price.price.clone()),
+                    price_switchboard: None,
+                    pyth_feed_id: None,
+                    pyth_info_object_id: None,
+                    pyth_latest_updated_at: None,
+                    pyth_ema_price: None,
+                    pyth_decimals: None,
+                    navi_asset_id: None,
+                    navi_oracle_id: None,
+                    navi_feed_id: None,
+                    hermes_price: None,
+                    hermes_latest_updated_at: None,
+                    vaa: None,
+                    supra_pair_id: None,
+                    supra_latest_updated_at: Some(utils::timestamp_ms_to_naive_datetime(
+                        supra_price.latest_updated_timestamp_ms,
+                    )),
+                    switchboard_aggregator_id: None,
+                    switchboard_latest_updated_at: None,
+                };
+
+                (coin_model.id, update_coin)
+            })
+            .collect::<Vec<_>>();
+
+        let updated_coins = self.coin_repo.update_prices_batch(&updates).map_err(|e| {
+            anyhow!(
+                "Failed to update prices for Supra pair ID {}: {}",
+                supra_price.pair_id,
+                e
+            )
+        })?;
+
+        if updated_coins != coin_models.len() {
+            return Err(anyhow!(
+                "Updated {} coins for Supra pair ID {}, expected {}",
+                updated_coins,
+                supra_price.pair_id,
+                coin_models.len()
+            ));
+        }
+
+        Ok(updated_coins)
+    }
+
+    /// Saves the Switchboard price to the database.
+    /// Updates the Switchboard price and its timestamp of all coins associated
+    /// with the aggregator ID in a single transaction,
+    /// and returns the number of updated coins.
+    ///
+    pub async fn save_switchboard_price(
+        &self,
+        switchboard_price: crate::types::SwitchboardPrice,
+    ) -> Result<usize> {
+        let coin_models = self
+            .coin_repo
+            .find_by_switchboard_aggregator_id(&switchboard_price.aggregator_id)
+            .map_err(|e| {
+                anyhow!(
+                    "Error finding coin by Switchboard aggregator ID {}: {}",
+                    switchboard_price.aggregator_id,
+                    e
+                )
+            })?;
+
+        if coin_models.is_empty() {
+            debug!(
+                "No coin mapped to Switchboard aggregator ID: {}",
+                switchboard_price.aggregator_id
+            );
+            return Ok(0);
+        }
+
+        info!(
+            "Found {} coins for Switchboard aggregator ID: {}",
+            coin_models.len(),
+            switchboard_price.aggregator_id
+        );
+
+        let updates = coin_models
+            .iter()
+            .map(|coin_model| {
+                let update_coin = UpdateCoin {
+                    coin_type: None,
+                    decimals: None,
+                    name: None,
+                    symbol: None,
+                    price_pyth: None,
+                    price_supra: None,
+                    price_switchboard: Some(switchboard_price.price.clone()),
+                    pyth_feed_id: None,
+                    pyth_info_object_id: None,
+                    pyth_latest_updated_at: None,
+                    pyth_ema_price: None,
+                    pyth_decimals: None,
+                    navi_asset_id: None,
+                    navi_oracle_id: None,
+                    navi_feed_id: None,
+                    hermes_price: None,
+                    hermes_latest_updated_at: None,
+                    vaa: None,
+                    supra_pair_id: None,
+                    supra_latest_updated_at: None,
+                    switchboard_aggregator_id: None,
+                    switchboard_latest_updated_at: Some(utils::timestamp_ms_to_naive_datetime(
+                        switchboard_price.latest_updated_timestamp_ms,
+                    )),
+                };
+
+                (coin_model.id, update_coin)
+            })
+            .collect::<Vec<_>>();
+
+        let updated_coins = self.coin_repo.update_prices_batch(&updates).map_err(|e| {
+            anyhow!(
+                "Failed to update prices for Switchboard aggregator ID {}: {}",
+                switchboard_price.aggregator_id,
+                e
+            )
+        })?;
+
+        if updated_coins != coin_models.len() {
+            return Err(anyhow!(
+                "Updated {} coins for Switchboard aggregator ID {}, expected {}",
+                updated_coins,
+                switchboard_price.aggregator_id,
+                coin_models.len()
+            ));
+        }
+
         Ok(updated_coins)
     }
 
@@ -335,6 +845,27 @@ impl LendingService {
         Ok(())
     }
 
+    /// Records an event which failed processing, for later debugging.
+    ///
+    pub fn save_failed_event_to_db(
+        &self,
+        event_type: &str,
+        tx_digest: &str,
+        seq_number: u64,
+        error: &str,
+    ) -> Result<models::failed_event::FailedEvent> {
+        let new_failed_event = models::failed_event::NewFailedEvent {
+            event_type: event_type.to_string(),
+            tx_digest: tx_digest.to_string(),
+            checkpoint_seq_number: seq_number as i64,
+            error: error.to_string(),
+        };
+
+        self.failed_event_repo
+            .create(&new_failed_event)
+            .map_err(|e| anyhow!("Failed to save failed event {}: {}", event_type, e))
+    }
+
     pub fn save_shared_object_to_db(
         &self,
         object_id: &str,
@@ -345,17 +876,20 @@ impl LendingService {
                 let update_object = db::models::shared_object::UpdateSharedObject {
                     object_id: None,
                     initial_shared_version: Some(initial_shared_version as i64),
+                    cached_at: Some(chrono::Utc::now().naive_utc()),
                 };
                 self.shared_object_repo
                     .update(existing_object.id, &update_object)?
             }
-            Err(_) => {
+            Err(RepoError::NotFound) => {
                 let new_object = db::models::shared_object::NewSharedObject {
                     object_id: object_id.to_string(),
                     initial_shared_version: initial_shared_version as i64,
+                    cached_at: Some(chrono::Utc::now().naive_utc()),
                 };
                 self.shared_object_repo.create(&new_object)?
             }
+            Err(e) => return Err(anyhow!("Failed to find shared object {}: {}", object_id, e)),
         };
 
         Ok(shared_object)
@@ -508,22 +1042,241 @@ impl LendingService {
     }
 
     pub fn find_coin_by_type(&self, coin_type: &str) -> Result<Coin> {
-        self.coin_repo.find_by_coin_type(coin_type).map_err(|e| {
+        let coin_type = utils::canonicalize_coin_type(coin_type);
+
+        self.coin_repo.find_by_coin_type(&coin_type).map_err(|e| {
             error!("Failed to find coin by type {}: {}", coin_type, e);
             anyhow!("Error finding coin by type: {}", e)
         })
     }
 
+    pub fn find_coin_by_navi_asset_id(&self, asset_id: u8) -> Result<Coin> {
+        self.coin_repo
+            .find_by_navi_asset_id(asset_id as i32)
+            .map_err(|e| {
+                error!("Failed to find coin by Navi asset ID {}: {}", asset_id, e);
+                anyhow!("Error finding coin by Navi asset ID: {}", e)
+            })
+    }
+
+    /// Whether the latest Pyth price of a coin, from Hermes or on-chain,
+    /// was published at most `max_age_secs` ago.
+    /// Returns false if the coin has never been priced.
+    ///
+    pub fn is_price_fresh(&self, coin_type: &str, max_age_secs: u64) -> Result<bool> {
+        let coin = self.find_coin_by_type(coin_type)?;
+
+        let latest_updated_at = [coin.hermes_latest_updated_at, coin.pyth_latest_updated_at]
+            .into_iter()
+            .flatten()
+            .max();
+
+        Ok(match latest_updated_at {
+            Some(updated_at) => crate::types::PythPrice::is_timestamp_fresh(
+                updated_at.and_utc().timestamp().max(0) as u64,
+                max_age_secs,
+                utils::get_current_timestamp_secs(),
+            ),
+            None => false,
+        })
+    }
+
+    /// Finds the coins without any price, from any oracle.
+    ///
+    pub fn find_coins_without_price(&self) -> Result<Vec<models::coin::Coin>> {
+        self.coin_repo
+            .find_coins_without_price()
+            .map_err(|e| anyhow!("Error finding coins without price: {}", e))
+    }
+
+    /// Finds the priced coins whose prices were all updated more than `max_age_secs` ago.
+    ///
+    pub fn find_coins_with_stale_price(
+        &self,
+        max_age_secs: u64,
+    ) -> Result<Vec<models::coin::Coin>> {
+        let older_than =
+            chrono::Utc::now().naive_utc() - chrono::Duration::seconds(max_age_secs as i64);
+
+        self.coin_repo
+            .find_coins_with_stale_price(older_than)
+            .map_err(|e| anyhow!("Error finding coins with stale price: {}", e))
+    }
+
+    /// Finds the borrower assets like `find_borrower_coins`,
+    /// failing if the price of any of them is older than `max_age_secs`
+    /// since a stale VAA is rejected by the on-chain Pyth update.
+    ///
+    pub fn find_borrower_coins_with_fresh_prices(
+        &self,
+        platform: &str,
+        borrower: &str,
+        max_age_secs: u64,
+    ) -> Result<HashSet<BorrowerAsset>> {
+        let assets = self.find_borrower_coins(platform, borrower)?;
+
+        for asset in assets.iter() {
+            if !self.is_price_fresh(&asset.coin_type, max_age_secs)? {
+                return Err(anyhow!(
+                    "Price of {} is older than {}s, refresh it before building the PTB",
+                    asset.coin_type,
+                    max_age_secs
+                ));
+            }
+        }
+
+        Ok(assets)
+    }
+
+    /// Finds the latest Pyth price in USD of a coin, with the coin decimals.
+    /// Returns an error if the coin has no price data.
+    ///
+    pub fn find_coin_usd_price(&self, coin_type: &str) -> Result<(Decimal, u32)> {
+        let coin = self.find_coin_by_type(coin_type)?;
+
+        let (price, pyth_decimals) = match (coin.price_pyth.as_deref(), coin.pyth_decimals) {
+            (Some(price), Some(pyth_decimals)) => (price, pyth_decimals),
+            _ => return Err(anyhow!("Missing price data for coin {}", coin_type)),
+        };
+
+        let price = Decimal::from_str(price)
+            .map_err(|e| anyhow!("Failed to parse price of coin {}: {}", coin_type, e))?;
+
+        Ok((
+            utils::sui_from_mist(price, pyth_decimals as usize),
+            coin.decimals as u32,
+        ))
+    }
+
+    /// Computes the health factor of a borrower given the lending markets of the platform.
+    /// Collateral value is weighted by the market liquidation threshold,
+    /// the health factor is the weighted collateral value over the debt value.
+    /// Returns `Decimal::MAX` as the health factor if the borrower has no debt.
+    ///
+    pub fn calc_health_factor(
+        &self,
+        markets: &[crate::types::LendingMarket],
+        deposits: &[crate::types::UserDeposit],
+        borrows: &[crate::types::UserBorrow],
+    ) -> Result<crate::types::CalcHFResult> {
+        let mut total_collateral_usd = Decimal::ZERO;
+        let mut weighted_collateral_usd = Decimal::ZERO;
+        let mut total_debt_usd = Decimal::ZERO;
+
+        for deposit in deposits {
+            let market = markets
+                .iter()
+                .find(|m| m.coin_type == deposit.coin_type)
+                .ok_or_else(|| anyhow!("Lending market not found for {}", deposit.coin_type))?;
+
+            let value = self.coin_usd_value(&deposit.coin_type, &deposit.amount)?;
+            total_collateral_usd += value;
+            weighted_collateral_usd += value * market.liquidation_threshold;
+        }
+
+        for borrow in borrows {
+            total_debt_usd += self.coin_usd_value(&borrow.coin_type, &borrow.amount)?;
+        }
+
+        let health_factor = if total_debt_usd.is_zero() {
+            Decimal::MAX
+        } else {
+            weighted_collateral_usd / total_debt_usd
+        };
+
+        Ok(crate::types::CalcHFResult {
+            health_factor,
+            total_collateral_usd,
+            total_debt_usd,
+        })
+    }
+
+    /// Exports the borrower with its deposits and borrows, their coin info and USD totals,
+    /// as JSON.
+    ///
+    pub async fn export_borrower_portfolio(
+        &self,
+        platform: &str,
+        borrower: &str,
+    ) -> Result<serde_json::Value> {
+        let borrower_m = self.find_borrower_by_platform_and_address(platform, borrower)?;
+
+        let deposits = self
+            .find_user_deposits_with_coin_info(platform, borrower, false)
+            .await?
+            .into_iter()
+            .map(|d| crate::types::PortfolioPosition {
+                usd_value: self.coin_usd_value(&d.coin_type, &d.amount).ok(),
+                coin_type: d.coin_type,
+                amount: d.amount,
+                decimals: d.decimals,
+                price_pyth: d.price_pyth,
+                pyth_decimals: d.pyth_decimals,
+            })
+            .collect::<Vec<_>>();
+
+        let borrows = self
+            .find_user_borrows_with_coin_info(platform, borrower, false)
+            .await?
+            .into_iter()
+            .map(|b| crate::types::PortfolioPosition {
+                usd_value: self.coin_usd_value(&b.coin_type, &b.amount).ok(),
+                coin_type: b.coin_type,
+                amount: b.amount,
+                decimals: b.decimals,
+                price_pyth: b.price_pyth,
+                pyth_decimals: b.pyth_decimals,
+            })
+            .collect::<Vec<_>>();
+
+        let portfolio = crate::types::BorrowerPortfolio {
+            platform: borrower_m.platform,
+            borrower: borrower_m.borrower,
+            obligation_id: borrower_m.obligation_id,
+            status: borrower_m.status,
+            total_collateral_usd: deposits.iter().filter_map(|d| d.usd_value).sum(),
+            total_debt_usd: borrows.iter().filter_map(|b| b.usd_value).sum(),
+            deposits,
+            borrows,
+        };
+
+        serde_json::to_value(&portfolio)
+            .map_err(|e| anyhow!("Failed to serialize portfolio of {}: {}", borrower, e))
+    }
+
+    fn coin_usd_value(&self, coin_type: &str, amount: &str) -> Result<Decimal> {
+        let (price, decimals) = self.find_coin_usd_price(coin_type)?;
+        let amount = Decimal::from_str(amount)
+            .map_err(|e| anyhow!("Failed to parse amount of coin {}: {}", coin_type, e))?;
+
+        Ok(utils::sui_from_mist(amount, decimals as usize) * price)
+    }
+
     pub fn find_borrower_given_obligation_id(
         &self,
         platform: &str,
         obligation_id: &str,
     ) -> Result<String> {
-        let user_borrow = self
-            .user_borrow_repo
-            .find_by_platform_and_obligation_id(platform, obligation_id)?;
+        // borrowers saved before their obligation ID was known only have it on their borrows
+        match self
+            .borrower_repo
+            .find_by_platform_and_obligation_id(platform, obligation_id)
+        {
+            Ok(borrower) => Ok(borrower.borrower),
+            Err(RepoError::NotFound) => {
+                let user_borrow = self
+                    .user_borrow_repo
+                    .find_by_platform_and_obligation_id(platform, obligation_id)?;
 
-        Ok(user_borrow.borrower)
+                Ok(user_borrow.borrower)
+            }
+            Err(e) => Err(anyhow!(
+                "Failed to find borrower of obligation {} on platform {}: {}",
+                obligation_id,
+                platform,
+                e
+            )),
+        }
     }
 
     /// Finds all borrowers with a specific status.
@@ -541,6 +1294,31 @@ impl LendingService {
             .map_err(|e| anyhow!("Error finding latest seq number: {}", e))
     }
 
+    /// Retrieves the metrics persisted for the checkpoints between both seq numbers, inclusive,
+    /// in ascending order.
+    ///
+    pub fn find_metrics_between(
+        &self,
+        start_seq: u64,
+        end_seq: u64,
+    ) -> Result<Vec<db::models::metric::Metric>> {
+        let start = i32::try_from(start_seq)
+            .map_err(|e| anyhow!("Invalid start seq number {}: {}", start_seq, e))?;
+        // the column is an i32, clamp an open-ended range
+        let end = i32::try_from(end_seq).unwrap_or(i32::MAX);
+
+        self.metric_repo
+            .find_metrics_between(start, end)
+            .map_err(|e| {
+                anyhow!(
+                    "Error finding metrics between #{} and #{}: {}",
+                    start,
+                    end,
+                    e
+                )
+            })
+    }
+
     pub fn find_all_pyth_feed_ids(&self) -> Result<Vec<String>> {
         self.coin_repo
             .find_all_pyth_feed_ids()
@@ -564,6 +1342,29 @@ impl LendingService {
             })
     }
 
+    /// Finds the borrower, `None` if it is not stored yet.
+    /// Other database errors are returned, so callers don't create an existing borrower.
+    ///
+    pub fn find_existing_borrower(
+        &self,
+        platform: &str,
+        address: &str,
+    ) -> Result<Option<Borrower>> {
+        match self
+            .borrower_repo
+            .find_by_platform_and_address(platform, address)
+        {
+            Ok(borrower) => Ok(Some(borrower)),
+            Err(RepoError::NotFound) => Ok(None),
+            Err(e) => Err(anyhow!(
+                "Error finding borrower by platform {} and address {}: {}",
+                platform,
+                address,
+                e
+            )),
+        }
+    }
+
     pub async fn find_all_borrowers_by_status(
         &self,
         status: i32,
@@ -581,6 +1382,18 @@ impl LendingService {
             .map_err(|e| anyhow!("Error finding distinct user borrows: {}", e))
     }
 
+    /// Distinct user borrows loaded in batches of `batch_size`,
+    /// to go through a large table without loading it at once.
+    ///
+    pub fn iter_distinct_user_borrows(
+        &self,
+        batch_size: i64,
+    ) -> impl Iterator<Item = Result<Vec<models::user_borrow::UserBorrowDistinct>>> + '_ {
+        self.user_borrow_repo
+            .iter_distinct_platform_and_address(batch_size)
+            .map(|batch| batch.map_err(|e| anyhow!("Error finding distinct user borrows: {}", e)))
+    }
+
     pub fn find_distinct_user_deposits(
         &self,
     ) -> Result<Vec<models::user_deposit::UserDepositDistinct>> {
@@ -597,4 +1410,514 @@ impl LendingService {
             .find_by_object_id(object_id)
             .map_err(|e| anyhow!("Error finding shared object by ID {}: {}", object_id, e))
     }
+
+    pub fn find_all_shared_objects(&self) -> Result<Vec<models::shared_object::SharedObject>> {
+        self.shared_object_repo
+            .find_all()
+            .map_err(|e| anyhow!("Error finding shared objects: {}", e))
+    }
+
+    /// Drops the cached shared object, e.g. when its initial shared version went stale,
+    /// it is fetched from chain again on next use.
+    /// Returns the number of deleted entries.
+    ///
+    pub fn invalidate_shared_object(&self, object_id: &str) -> Result<usize> {
+        self.shared_object_repo
+            .delete_by_object_id(object_id)
+            .map_err(|e| anyhow!("Error deleting shared object {}: {}", object_id, e))
+    }
+
+    /// Returns true if the cached shared object is older than `shared_object_cache_ttl_secs`.
+    /// A TTL of 0 means the cache never expires.
+    ///
+    pub fn is_shared_object_cache_expired(
+        &self,
+        shared_object: &models::shared_object::SharedObject,
+    ) -> bool {
+        let ttl_secs = self.config.shared_object_cache_ttl_secs;
+        if ttl_secs == 0 {
+            return false;
+        }
+
+        // entries cached before the TTL was introduced have no timestamp
+        match shared_object.cached_at.or(shared_object.created_at) {
+            Some(cached_at) => {
+                let age = chrono::Utc::now().naive_utc() - cached_at;
+                age.num_seconds() >= ttl_secs as i64
+            }
+            None => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::db_service::mock::{MockDb, MockLendingService};
+
+    fn user_deposit(coin_type: &str, amount: &str) -> crate::types::UserDeposit {
+        crate::types::UserDeposit {
+            platform: "scallop".to_string(),
+            borrower: "0xborrower".to_string(),
+            obligation_id: Some("0xobligation".to_string()),
+            coin_type: coin_type.to_string(),
+            amount: amount.to_string(),
+        }
+    }
+
+    fn user_borrow(coin_type: &str, amount: &str) -> crate::types::UserBorrow {
+        crate::types::UserBorrow {
+            platform: "scallop".to_string(),
+            borrower: "0xborrower".to_string(),
+            obligation_id: Some("0xobligation".to_string()),
+            coin_type: coin_type.to_string(),
+            amount: amount.to_string(),
+            debt_borrow_index: None,
+        }
+    }
+
+    /// Database holding a liquidated borrower with a deposit and two borrows.
+    fn db_with_borrower() -> (Arc<MockDb>, LendingService) {
+        let db = Arc::new(MockDb::default());
+        let service = db.lending_service(crate::config::test_config());
+
+        service
+            .save_borrower_with_positions(
+                crate::types::Borrower {
+                    platform: "scallop".to_string(),
+                    borrower: "0xborrower".to_string(),
+                    obligation_id: Some("0xobligation".to_string()),
+                    status: constant::SUCCEED_STATUS,
+                },
+                vec![user_borrow("0xusdc", "20"), user_borrow("0xusdt", "5")],
+                vec![user_deposit("0x2::sui::SUI", "100")],
+            )
+            .unwrap();
+
+        (db, service)
+    }
+
+    fn positions(db: &MockDb) -> (Vec<(String, String)>, Vec<(String, String)>) {
+        let deposits = db
+            .user_deposits
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|d| (d.coin_type.clone(), d.amount.clone()))
+            .collect();
+        let borrows = db
+            .user_borrows
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|b| (b.coin_type.clone(), b.amount.clone()))
+            .collect();
+
+        (deposits, borrows)
+    }
+
+    #[tokio::test]
+    async fn resync_borrower_replaces_the_stored_portfolio() {
+        let (db, service) = db_with_borrower();
+        let lending_service = MockLendingService {
+            user_deposits: vec![user_deposit("0x2::sui::SUI", "80")],
+            user_borrows: vec![user_borrow("0xusdc", "25")],
+            ..Default::default()
+        };
+
+        let borrower = service
+            .resync_borrower(&lending_service, "scallop", "0xborrower")
+            .await
+            .unwrap();
+
+        // the liquidated borrower is ready again, with the stored obligation
+        assert_eq!(borrower.status, constant::READY_STATUS);
+        assert_eq!(borrower.obligation_id.as_deref(), Some("0xobligation"));
+        assert_eq!(
+            *lending_service.fetched_obligation_id.lock().unwrap(),
+            Some(Some("0xobligation".to_string()))
+        );
+
+        // the borrow repaid on-chain is dropped
+        let (deposits, borrows) = positions(&db);
+        assert_eq!(
+            deposits,
+            vec![("0x2::sui::SUI".to_string(), "80".to_string())]
+        );
+        assert_eq!(borrows, vec![("0xusdc".to_string(), "25".to_string())]);
+    }
+
+    #[tokio::test]
+    async fn resync_borrower_looks_up_the_obligation_of_a_new_borrower() {
+        let db = Arc::new(MockDb::default());
+        let service = db.lending_service(crate::config::test_config());
+        let lending_service = MockLendingService {
+            user_deposits: vec![user_deposit("0x2::sui::SUI", "80")],
+            obligation_id: Some("0xfound".to_string()),
+            ..Default::default()
+        };
+
+        let borrower = service
+            .resync_borrower(&lending_service, "scallop", "0xborrower")
+            .await
+            .unwrap();
+
+        assert_eq!(borrower.status, constant::READY_STATUS);
+        assert_eq!(borrower.obligation_id.as_deref(), Some("0xfound"));
+        assert_eq!(db.borrowers.lock().unwrap().len(), 1);
+        assert_eq!(positions(&db).0.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn resync_borrower_keeps_the_portfolio_when_the_fetch_fails() {
+        let (db, service) = db_with_borrower();
+        let lending_service = MockLendingService {
+            unavailable: true,
+            ..Default::default()
+        };
+
+        let result = service
+            .resync_borrower(&lending_service, "scallop", "0xborrower")
+            .await;
+
+        assert!(result.is_err());
+        let (deposits, borrows) = positions(&db);
+        assert_eq!(deposits.len(), 1);
+        assert_eq!(borrows.len(), 2);
+        assert_eq!(
+            db.borrowers.lock().unwrap()[0].status,
+            constant::SUCCEED_STATUS
+        );
+    }
+
+    #[test]
+    fn liquidated_borrower_can_not_go_back_to_pending() {
+        let (db, service) = db_with_borrower();
+
+        let result =
+            service.update_borrower_status_to_db("scallop", "0xborrower", BorrowerStatus::Pending);
+
+        assert!(result.is_err());
+        assert_eq!(
+            db.borrowers.lock().unwrap()[0].status,
+            constant::SUCCEED_STATUS
+        );
+    }
+
+    #[test]
+    fn borrower_is_not_created_when_the_pool_is_exhausted() {
+        let db = Arc::new(MockDb::default());
+        db.unavailable
+            .store(true, std::sync::atomic::Ordering::SeqCst);
+        let service = db.lending_service(crate::config::test_config());
+
+        let result = service.save_borrower_to_db(crate::types::Borrower {
+            platform: "scallop".to_string(),
+            borrower: "0xborrower".to_string(),
+            obligation_id: None,
+            status: constant::PENDING_STATUS,
+        });
+
+        assert!(result.is_err());
+        assert!(db.borrowers.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn borrower_is_marked_ready_then_liquidated() {
+        let db = Arc::new(MockDb::default());
+        db.insert_borrower(&NewBorrower {
+            platform: "scallop".to_string(),
+            borrower: "0xborrower".to_string(),
+            obligation_id: None,
+            status: constant::PENDING_STATUS,
+        });
+        let service = db.lending_service(crate::config::test_config());
+
+        // a pending borrower must be ready before it is liquidated
+        assert!(service
+            .mark_borrower_liquidated("scallop", "0xborrower")
+            .is_err());
+
+        let borrower = service
+            .mark_borrower_ready("scallop", "0xborrower")
+            .unwrap();
+        assert_eq!(borrower.status, constant::READY_STATUS);
+
+        let borrower = service
+            .mark_borrower_liquidated("scallop", "0xborrower")
+            .unwrap();
+        assert_eq!(borrower.status, constant::SUCCEED_STATUS);
+    }
+
+    #[test]
+    fn status_of_an_unknown_borrower_is_not_updated() {
+        let db = Arc::new(MockDb::default());
+        let service = db.lending_service(crate::config::test_config());
+
+        assert!(service
+            .mark_borrower_ready("scallop", "0xborrower")
+            .is_err());
+        assert!(db.borrowers.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn malformed_amounts_are_not_saved() {
+        let db = Arc::new(MockDb::default());
+        let service = db.lending_service(crate::config::test_config());
+
+        assert!(service
+            .save_user_borrow_to_db(user_borrow("0xusdc", "-20"))
+            .await
+            .is_err());
+        assert!(service
+            .save_user_deposit_to_db(user_deposit("0x2::sui::SUI", "1e9"))
+            .await
+            .is_err());
+
+        assert!(db.user_borrows.lock().unwrap().is_empty());
+        assert!(db.user_deposits.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn malformed_amount_keeps_the_stored_position() {
+        let (db, service) = db_with_borrower();
+
+        let result = service
+            .save_user_borrow_to_db(user_borrow("0xusdc", "twenty"))
+            .await;
+
+        assert!(result.is_err());
+        let (_, borrows) = positions(&db);
+        assert!(borrows.contains(&("0xusdc".to_string(), "20".to_string())));
+    }
+
+    #[tokio::test]
+    async fn valid_amount_updates_the_stored_position() {
+        let (db, service) = db_with_borrower();
+
+        service
+            .save_user_borrow_to_db(user_borrow("0xusdc", "22.5"))
+            .await
+            .unwrap();
+
+        let (_, borrows) = positions(&db);
+        assert!(borrows.contains(&("0xusdc".to_string(), "22.5".to_string())));
+    }
+
+    #[test]
+    fn borrower_is_found_by_its_obligation_id() {
+        let (_, service) = db_with_borrower();
+
+        assert_eq!(
+            service
+                .find_borrower_given_obligation_id("scallop", "0xobligation")
+                .unwrap(),
+            "0xborrower"
+        );
+        assert!(service
+            .find_borrower_given_obligation_id("suilend", "0xobligation")
+            .is_err());
+    }
+
+    #[test]
+    fn borrower_without_obligation_id_is_found_by_its_borrows() {
+        let db = Arc::new(MockDb::default());
+        db.insert_borrower(&NewBorrower {
+            platform: "scallop".to_string(),
+            borrower: "0xborrower".to_string(),
+            obligation_id: None,
+            status: constant::PENDING_STATUS,
+        });
+        let service = db.lending_service(crate::config::test_config());
+        UserBorrowRepository::create(
+            &*db,
+            &user_borrow::NewUserBorrow {
+                platform: "scallop".to_string(),
+                borrower: "0xborrower".to_string(),
+                coin_type: "0xusdc".to_string(),
+                amount: "20".to_string(),
+                obligation_id: Some("0xobligation".to_string()),
+                debt_borrow_index: None,
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            service
+                .find_borrower_given_obligation_id("scallop", "0xobligation")
+                .unwrap(),
+            "0xborrower"
+        );
+        assert!(service
+            .find_borrower_given_obligation_id("scallop", "0xother")
+            .is_err());
+    }
+
+    fn supra_price(pair_id: u32) -> crate::types::SupraPrice {
+        crate::types::SupraPrice {
+            pair_id,
+            price: "1234500000".to_string(),
+            decimals: 9,
+            latest_updated_timestamp_ms: 1_700_000_000_000,
+        }
+    }
+
+    #[tokio::test]
+    async fn save_supra_price_updates_the_coins_of_the_pair_in_one_batch() {
+        let db = Arc::new(MockDb::default());
+        for (id, coin_type, pair_id) in [
+            (1, "0x2::sui::SUI", 90),
+            (2, "0xvsui", 90),
+            (3, "0xusdc", 47),
+        ] {
+            let mut coin = MockDb::coin(id, coin_type);
+            coin.supra_pair_id = Some(pair_id);
+            db.coins.lock().unwrap().push(coin);
+        }
+        let service = db.lending_service(crate::config::test_config());
+
+        let updated = service.save_supra_price(supra_price(90)).await.unwrap();
+
+        assert_eq!(updated, 2);
+        assert_eq!(*db.price_batches.lock().unwrap(), vec![2]);
+        let coins = db.coins.lock().unwrap();
+        assert_eq!(coins[0].price_supra.as_deref(), Some("1234500000"));
+        assert_eq!(coins[1].price_supra.as_deref(), Some("1234500000"));
+        assert!(coins[1].supra_latest_updated_at.is_some());
+        assert_eq!(coins[2].price_supra, None);
+    }
+
+    #[tokio::test]
+    async fn save_supra_price_without_coins_of_the_pair() {
+        let db = Arc::new(MockDb::default());
+        let service = db.lending_service(crate::config::test_config());
+
+        let updated = service.save_supra_price(supra_price(90)).await.unwrap();
+
+        assert_eq!(updated, 0);
+        assert!(db.price_batches.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn save_supra_price_rejects_out_of_range_pair_ids() {
+        let db = Arc::new(MockDb::default());
+        let service = db.lending_service(crate::config::test_config());
+
+        let result = service.save_supra_price(supra_price(u32::MAX)).await;
+
+        assert!(result.is_err_and(|e| e.to_string().contains("out of range")));
+    }
+
+    #[tokio::test]
+    async fn save_switchboard_price_updates_the_coins_of_the_aggregator_in_one_batch() {
+        let db = Arc::new(MockDb::default());
+        for (id, coin_type, aggregator_id) in [
+            (1, "0x2::sui::SUI", "0xaggregator_sui"),
+            (2, "0xvsui", "0xaggregator_sui"),
+            (3, "0xusdc", "0xaggregator_usdc"),
+        ] {
+            let mut coin = MockDb::coin(id, coin_type);
+            coin.switchboard_aggregator_id = Some(aggregator_id.to_string());
+            db.coins.lock().unwrap().push(coin);
+        }
+        let service = db.lending_service(crate::config::test_config());
+
+        let updated = service
+            .save_switchboard_price(crate::types::SwitchboardPrice {
+                aggregator_id: "0xaggregator_sui".to_string(),
+                price: "3210000000000000000".to_string(),
+                latest_updated_timestamp_ms: 1_700_000_000_000,
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(updated, 2);
+        assert_eq!(*db.price_batches.lock().unwrap(), vec![2]);
+        let coins = db.coins.lock().unwrap();
+        assert_eq!(
+            coins[0].price_switchboard.as_deref(),
+            Some("3210000000000000000")
+        );
+        assert!(coins[1].switchboard_latest_updated_at.is_some());
+        assert_eq!(coins[2].price_switchboard, None);
+    }
+
+    /// Lending service whose shared objects expire after a minute.
+    fn shared_object_service(db: &Arc<MockDb>) -> LendingService {
+        let mut config = crate::config::test_config();
+        config.shared_object_cache_ttl_secs = 60;
+
+        db.lending_service(config)
+    }
+
+    fn cached_shared_object(db: &MockDb, age_secs: i64) -> models::shared_object::SharedObject {
+        let cached_at = chrono::Utc::now().naive_utc() - chrono::Duration::seconds(age_secs);
+        let shared_object = models::shared_object::SharedObject {
+            id: 1,
+            object_id: "0xshared".to_string(),
+            initial_shared_version: 42,
+            created_at: Some(cached_at),
+            updated_at: Some(cached_at),
+            cached_at: Some(cached_at),
+        };
+        db.shared_objects
+            .lock()
+            .unwrap()
+            .push(shared_object.clone());
+
+        shared_object
+    }
+
+    #[test]
+    fn shared_object_within_the_ttl_is_not_expired() {
+        let db = Arc::new(MockDb::default());
+        let service = shared_object_service(&db);
+        cached_shared_object(&db, 30);
+
+        let shared_object = service.find_shared_object_by_id("0xshared").unwrap();
+
+        assert!(!service.is_shared_object_cache_expired(&shared_object));
+    }
+
+    #[test]
+    fn shared_object_past_the_ttl_is_expired() {
+        let db = Arc::new(MockDb::default());
+        let service = shared_object_service(&db);
+        cached_shared_object(&db, 60);
+
+        let shared_object = service.find_shared_object_by_id("0xshared").unwrap();
+
+        assert!(service.is_shared_object_cache_expired(&shared_object));
+    }
+
+    #[test]
+    fn shared_object_never_expires_without_ttl() {
+        let db = Arc::new(MockDb::default());
+        let service = db.lending_service(crate::config::test_config());
+        let mut shared_object = cached_shared_object(&db, 3_600);
+
+        let mut config = crate::config::test_config();
+        config.shared_object_cache_ttl_secs = 0;
+        let service_without_ttl = db.lending_service(config);
+
+        assert!(service.is_shared_object_cache_expired(&shared_object));
+        assert!(!service_without_ttl.is_shared_object_cache_expired(&shared_object));
+
+        // entries cached before the TTL fall back to their creation time
+        shared_object.cached_at = None;
+        assert!(service.is_shared_object_cache_expired(&shared_object));
+    }
+
+    #[test]
+    fn saving_an_expired_shared_object_refreshes_it() {
+        let db = Arc::new(MockDb::default());
+        let service = shared_object_service(&db);
+        cached_shared_object(&db, 3_600);
+
+        let shared_object = service.save_shared_object_to_db("0xshared", 43).unwrap();
+
+        assert_eq!(shared_object.initial_shared_version, 43);
+        assert!(!service.is_shared_object_cache_expired(&shared_object));
+        assert_eq!(db.shared_objects.lock().unwrap().len(), 1);
+    }
 }
@@ -0,0 +1,1244 @@
+//! In-memory repositories for the tests of the database services.
+//! Only the queries used by the tested services are implemented.
+
+use crate::config::Config;
+use crate::indexer::registry::EventProcessorRegistry;
+use crate::service::{
+    db_service::{lending::LendingService, pool::PoolService},
+    dex, lending,
+    registry::ServiceRegistry,
+};
+use crate::utils::{ptb::PTBHelper, rpc_pool::RpcPool};
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use db::models::{
+    borrower::{Borrower, NewBorrower, UpdateBorrower},
+    coin::{Coin, NewCoin, UpdateCoin},
+    failed_event::{FailedEvent, NewFailedEvent},
+    metric::{Metric, NewMetric, UpdateMetric},
+    pool::{NewPool, Pool, UpdatePool},
+    pool_tick::{NewPoolTick, PoolTick, UpdatePoolTick},
+    shared_object::{NewSharedObject, SharedObject, UpdateSharedObject},
+    swap_event::{NewSwapEvent, SwapEvent},
+    user_borrow::{
+        NewUserBorrow, UpdateUserBorrow, UserBorrow, UserBorrowCoin, UserBorrowDistinct,
+        UserBorrowWithCoinInfo,
+    },
+    user_deposit::{
+        NewUserDeposit, UpdateUserDeposit, UserDeposit, UserDepositDistinct,
+        UserDepositWithCoinInfo,
+    },
+};
+use db::repositories::{
+    BorrowerRepository, CoinRepository, FailedEventRepository, MetricRepository, PoolRepository,
+    PoolTickRepository, SharedObjectRepository, SwapEventRepository, UserBorrowRepository,
+    UserDepositRepository,
+};
+use db::{RepoError, RepoResult};
+use std::sync::{
+    atomic::{AtomicBool, AtomicUsize, Ordering},
+    Arc, Mutex,
+};
+
+#[derive(Default)]
+pub(crate) struct MockDb {
+    pub borrowers: Mutex<Vec<Borrower>>,
+    pub user_borrows: Mutex<Vec<UserBorrow>>,
+    pub user_deposits: Mutex<Vec<UserDeposit>>,
+    pub coins: Mutex<Vec<Coin>>,
+    pub pools: Mutex<Vec<Pool>>,
+    pub pool_ticks: Mutex<Vec<PoolTick>>,
+    pub shared_objects: Mutex<Vec<SharedObject>>,
+    pub failed_events: Mutex<Vec<FailedEvent>>,
+    pub metrics: Mutex<Vec<Metric>>,
+    /// Size of every batch of price updates, one per transaction.
+    pub price_batches: Mutex<Vec<usize>>,
+    /// When set, the borrower lookups fail as if the connection pool was exhausted.
+    pub unavailable: AtomicBool,
+}
+
+impl MockDb {
+    /// Lending service whose repositories all read and write this database.
+    ///
+    pub fn lending_service(self: &Arc<Self>, config: Config) -> LendingService {
+        LendingService::new(
+            Arc::new(config),
+            Arc::clone(self),
+            Arc::clone(self),
+            Arc::clone(self),
+            Arc::clone(self),
+            Arc::clone(self),
+            Arc::clone(self),
+            Arc::clone(self),
+        )
+    }
+
+    /// Pool service whose repositories all read and write this database,
+    /// without any RPC client.
+    ///
+    pub fn pool_service(self: &Arc<Self>, config: Config) -> PoolService {
+        PoolService::new(
+            Arc::new(config),
+            Arc::new(RpcPool::without_clients()),
+            Arc::clone(self),
+            Arc::clone(self),
+            Arc::clone(self),
+            Arc::clone(self),
+        )
+    }
+
+    /// Service registry whose services all read and write this database,
+    /// without any RPC client.
+    ///
+    pub fn service_registry(self: &Arc<Self>, config: Arc<Config>) -> ServiceRegistry {
+        let rpc_pool = Arc::new(RpcPool::without_clients());
+        let db_pool_service = Arc::new(self.pool_service((*config).clone()));
+        let db_lending_service = Arc::new(self.lending_service((*config).clone()));
+        let ptb_helper = Arc::new(PTBHelper::new(
+            Arc::clone(&config),
+            Arc::clone(&rpc_pool),
+            Arc::clone(&db_pool_service),
+            Arc::clone(&db_lending_service),
+        ));
+
+        ServiceRegistry::new(
+            config,
+            rpc_pool,
+            Arc::clone(self) as Arc<dyn CoinRepository + Send + Sync>,
+            Arc::clone(self) as Arc<dyn PoolRepository + Send + Sync>,
+            db_pool_service,
+            db_lending_service,
+            ptb_helper,
+        )
+    }
+
+    /// Event processor registry built on the services of `service_registry`,
+    /// without any RPC client.
+    ///
+    pub fn event_processor_registry(
+        self: &Arc<Self>,
+        service_registry: &Arc<ServiceRegistry>,
+    ) -> EventProcessorRegistry {
+        EventProcessorRegistry::new(
+            Arc::clone(&service_registry.config),
+            Arc::new(RpcPool::without_clients()),
+            Arc::clone(self) as Arc<dyn PoolRepository + Send + Sync>,
+            Arc::clone(self) as Arc<dyn CoinRepository + Send + Sync>,
+            Arc::clone(&service_registry.db_pool_service),
+            Arc::clone(&service_registry.db_lending_service),
+            Arc::clone(service_registry),
+        )
+    }
+
+    /// Coin without metadata nor prices.
+    ///
+    pub fn coin(id: i32, coin_type: &str) -> Coin {
+        Coin {
+            id,
+            coin_type: coin_type.to_string(),
+            decimals: 9,
+            name: None,
+            symbol: None,
+            price_pyth: None,
+            price_supra: None,
+            price_switchboard: None,
+            created_at: None,
+            updated_at: None,
+            pyth_feed_id: None,
+            pyth_info_object_id: None,
+            pyth_latest_updated_at: None,
+            pyth_ema_price: None,
+            pyth_decimals: None,
+            navi_asset_id: None,
+            navi_oracle_id: None,
+            navi_feed_id: None,
+            hermes_price: None,
+            hermes_latest_updated_at: None,
+            vaa: None,
+            supra_pair_id: None,
+            supra_latest_updated_at: None,
+            switchboard_aggregator_id: None,
+            switchboard_latest_updated_at: None,
+        }
+    }
+
+    /// Pool of the exchange with the comma-separated `coins`, without any state.
+    ///
+    pub fn pool(id: i32, exchange: &str, address: &str, coins: &str) -> Pool {
+        Pool {
+            id,
+            exchange: exchange.to_string(),
+            address: address.to_string(),
+            liquidity: None,
+            current_sqrt_price: None,
+            tick_spacing: None,
+            fee_rate: None,
+            is_pause: None,
+            created_at: None,
+            updated_at: None,
+            coins: coins.to_string(),
+            coin_amounts: None,
+            weights: None,
+            fees_swap_in: None,
+            fees_swap_out: None,
+            current_tick_index: None,
+            pool_type: None,
+        }
+    }
+
+    pub fn insert_borrower(&self, new_borrower: &NewBorrower) -> Borrower {
+        let mut borrowers = self.borrowers.lock().unwrap();
+        let borrower = Borrower {
+            id: borrowers.len() as i32 + 1,
+            platform: new_borrower.platform.clone(),
+            borrower: new_borrower.borrower.clone(),
+            obligation_id: new_borrower.obligation_id.clone(),
+            status: new_borrower.status,
+            created_at: None,
+            updated_at: None,
+        };
+        borrowers.push(borrower.clone());
+
+        borrower
+    }
+
+    fn upsert_with_positions(
+        &self,
+        new_borrower: &NewBorrower,
+        new_borrows: &[NewUserBorrow],
+        new_deposits: &[NewUserDeposit],
+    ) -> Borrower {
+        let existing = BorrowerRepository::find_by_platform_and_address(
+            self,
+            &new_borrower.platform,
+            &new_borrower.borrower,
+        )
+        .ok();
+        let borrower = match existing {
+            Some(existing) => {
+                let mut borrowers = self.borrowers.lock().unwrap();
+                let borrower = borrowers.iter_mut().find(|b| b.id == existing.id).unwrap();
+                borrower.obligation_id = new_borrower.obligation_id.clone();
+                borrower.status = new_borrower.status;
+                borrower.clone()
+            }
+            None => self.insert_borrower(new_borrower),
+        };
+
+        for new_borrow in new_borrows {
+            self.user_borrows.lock().unwrap().retain(|b| {
+                (&b.platform, &b.borrower, &b.coin_type)
+                    != (
+                        &new_borrow.platform,
+                        &new_borrow.borrower,
+                        &new_borrow.coin_type,
+                    )
+            });
+            UserBorrowRepository::create(self, new_borrow).unwrap();
+        }
+
+        for new_deposit in new_deposits {
+            self.user_deposits.lock().unwrap().retain(|d| {
+                (&d.platform, &d.borrower, &d.coin_type)
+                    != (
+                        &new_deposit.platform,
+                        &new_deposit.borrower,
+                        &new_deposit.coin_type,
+                    )
+            });
+            UserDepositRepository::create(self, new_deposit).unwrap();
+        }
+
+        borrower
+    }
+}
+
+impl BorrowerRepository for MockDb {
+    fn create(&self, new_borrower: &NewBorrower) -> RepoResult<Borrower> {
+        Ok(self.insert_borrower(new_borrower))
+    }
+
+    fn update(&self, id: i32, update_borrower: &UpdateBorrower) -> RepoResult<Borrower> {
+        let mut borrowers = self.borrowers.lock().unwrap();
+        let borrower = borrowers
+            .iter_mut()
+            .find(|b| b.id == id)
+            .ok_or(RepoError::NotFound)?;
+        if let Some(obligation_id) = &update_borrower.obligation_id {
+            borrower.obligation_id = Some(obligation_id.clone());
+        }
+        if let Some(status) = update_borrower.status {
+            borrower.status = status;
+        }
+
+        Ok(borrower.clone())
+    }
+
+    fn delete(&self, _id: i32) -> RepoResult<bool> {
+        unimplemented!()
+    }
+
+    fn find_by_id(&self, _id: i32) -> RepoResult<Borrower> {
+        unimplemented!()
+    }
+
+    fn find_all(&self) -> RepoResult<Vec<Borrower>> {
+        Ok(self.borrowers.lock().unwrap().clone())
+    }
+
+    fn find_by_platform_and_address(&self, platform: &str, address: &str) -> RepoResult<Borrower> {
+        if self.unavailable.load(Ordering::SeqCst) {
+            return Err(RepoError::Pool(
+                "timed out waiting for connection".to_string(),
+            ));
+        }
+
+        self.borrowers
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|b| b.platform == platform && b.borrower == address)
+            .cloned()
+            .ok_or(RepoError::NotFound)
+    }
+
+    fn find_by_platform_and_obligation_id(
+        &self,
+        platform: &str,
+        obligation_id: &str,
+    ) -> RepoResult<Borrower> {
+        self.borrowers
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|b| b.platform == platform && b.obligation_id.as_deref() == Some(obligation_id))
+            .cloned()
+            .ok_or(RepoError::NotFound)
+    }
+
+    fn find_all_by_status(&self, _status: i32) -> RepoResult<Vec<Borrower>> {
+        unimplemented!()
+    }
+
+    fn find_stale(&self, _platform: &str, _older_than: NaiveDateTime) -> RepoResult<Vec<Borrower>> {
+        unimplemented!()
+    }
+
+    fn save_with_positions(
+        &self,
+        new_borrower: &NewBorrower,
+        new_borrows: &[NewUserBorrow],
+        new_deposits: &[NewUserDeposit],
+    ) -> RepoResult<Borrower> {
+        Ok(self.upsert_with_positions(new_borrower, new_borrows, new_deposits))
+    }
+
+    fn replace_with_positions(
+        &self,
+        new_borrower: &NewBorrower,
+        new_borrows: &[NewUserBorrow],
+        new_deposits: &[NewUserDeposit],
+    ) -> RepoResult<Borrower> {
+        UserBorrowRepository::delete_by_platform_and_address(
+            self,
+            &new_borrower.platform,
+            &new_borrower.borrower,
+        )?;
+        UserDepositRepository::delete_by_platform_and_address(
+            self,
+            &new_borrower.platform,
+            &new_borrower.borrower,
+        )?;
+
+        Ok(self.upsert_with_positions(new_borrower, new_borrows, new_deposits))
+    }
+}
+
+impl UserBorrowRepository for MockDb {
+    fn create(&self, new_borrow: &NewUserBorrow) -> RepoResult<UserBorrow> {
+        let mut user_borrows = self.user_borrows.lock().unwrap();
+        let user_borrow = UserBorrow {
+            id: user_borrows.len() as i32 + 1,
+            platform: new_borrow.platform.clone(),
+            borrower: new_borrow.borrower.clone(),
+            coin_type: new_borrow.coin_type.clone(),
+            amount: new_borrow.amount.clone(),
+            created_at: None,
+            updated_at: None,
+            obligation_id: new_borrow.obligation_id.clone(),
+            debt_borrow_index: new_borrow.debt_borrow_index.clone(),
+        };
+        user_borrows.push(user_borrow.clone());
+
+        Ok(user_borrow)
+    }
+
+    fn update(&self, id: i32, update_borrow: &UpdateUserBorrow) -> RepoResult<UserBorrow> {
+        let mut user_borrows = self.user_borrows.lock().unwrap();
+        let user_borrow = user_borrows
+            .iter_mut()
+            .find(|b| b.id == id)
+            .ok_or(RepoError::NotFound)?;
+        if let Some(amount) = &update_borrow.amount {
+            user_borrow.amount = amount.clone();
+        }
+
+        Ok(user_borrow.clone())
+    }
+
+    fn delete(&self, _id: i32) -> RepoResult<bool> {
+        unimplemented!()
+    }
+
+    fn find_by_id(&self, _id: i32) -> RepoResult<UserBorrow> {
+        unimplemented!()
+    }
+
+    fn find_all(&self) -> RepoResult<Vec<UserBorrow>> {
+        Ok(self.user_borrows.lock().unwrap().clone())
+    }
+
+    fn delete_by_platform_and_address(&self, platform: &str, address: &str) -> RepoResult<bool> {
+        let mut user_borrows = self.user_borrows.lock().unwrap();
+        let count = user_borrows.len();
+        user_borrows.retain(|b| b.platform != platform || b.borrower != address);
+
+        Ok(user_borrows.len() < count)
+    }
+
+    fn find_by_platform_and_address(
+        &self,
+        platform: &str,
+        address: &str,
+    ) -> RepoResult<Vec<UserBorrow>> {
+        Ok(self
+            .user_borrows
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|b| b.platform == platform && b.borrower == address)
+            .cloned()
+            .collect())
+    }
+
+    fn find_by_platform_and_address_with_coin_info(
+        &self,
+        _platform: &str,
+        _address: &str,
+    ) -> RepoResult<Vec<UserBorrowWithCoinInfo>> {
+        unimplemented!()
+    }
+
+    fn find_distinct_platform_and_address(&self) -> RepoResult<Vec<UserBorrowDistinct>> {
+        unimplemented!()
+    }
+
+    fn iter_distinct_platform_and_address(
+        &self,
+        _batch_size: i64,
+    ) -> Box<dyn Iterator<Item = RepoResult<Vec<UserBorrowDistinct>>> + Send + '_> {
+        unimplemented!()
+    }
+
+    fn find_coins_by_platform_and_address(
+        &self,
+        _platform: &str,
+        _address: &str,
+    ) -> RepoResult<Vec<UserBorrowCoin>> {
+        unimplemented!()
+    }
+
+    fn delete_by_platform_and_address_and_obligation_id(
+        &self,
+        _platform: &str,
+        _address: &str,
+        _obligation_id: &str,
+    ) -> RepoResult<bool> {
+        unimplemented!()
+    }
+
+    fn find_by_platform_and_address_and_coin_type(
+        &self,
+        platform: &str,
+        address: &str,
+        coin_type: &str,
+    ) -> RepoResult<UserBorrow> {
+        self.user_borrows
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|b| b.platform == platform && b.borrower == address && b.coin_type == coin_type)
+            .cloned()
+            .ok_or(RepoError::NotFound)
+    }
+
+    fn find_by_platform_and_obligation_id(
+        &self,
+        platform: &str,
+        obligation_id: &str,
+    ) -> RepoResult<UserBorrow> {
+        self.user_borrows
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|b| b.platform == platform && b.obligation_id.as_deref() == Some(obligation_id))
+            .cloned()
+            .ok_or(RepoError::NotFound)
+    }
+}
+
+impl UserDepositRepository for MockDb {
+    fn create(&self, new_deposit: &NewUserDeposit) -> RepoResult<UserDeposit> {
+        let mut user_deposits = self.user_deposits.lock().unwrap();
+        let user_deposit = UserDeposit {
+            id: user_deposits.len() as i32 + 1,
+            platform: new_deposit.platform.clone(),
+            borrower: new_deposit.borrower.clone(),
+            coin_type: new_deposit.coin_type.clone(),
+            amount: new_deposit.amount.clone(),
+            created_at: None,
+            updated_at: None,
+            obligation_id: new_deposit.obligation_id.clone(),
+        };
+        user_deposits.push(user_deposit.clone());
+
+        Ok(user_deposit)
+    }
+
+    fn update(&self, id: i32, update_deposit: &UpdateUserDeposit) -> RepoResult<UserDeposit> {
+        let mut user_deposits = self.user_deposits.lock().unwrap();
+        let user_deposit = user_deposits
+            .iter_mut()
+            .find(|d| d.id == id)
+            .ok_or(RepoError::NotFound)?;
+        if let Some(amount) = &update_deposit.amount {
+            user_deposit.amount = amount.clone();
+        }
+
+        Ok(user_deposit.clone())
+    }
+
+    fn delete(&self, _id: i32) -> RepoResult<bool> {
+        unimplemented!()
+    }
+
+    fn find_by_id(&self, _id: i32) -> RepoResult<UserDeposit> {
+        unimplemented!()
+    }
+
+    fn find_all(&self) -> RepoResult<Vec<UserDeposit>> {
+        Ok(self.user_deposits.lock().unwrap().clone())
+    }
+
+    fn delete_by_platform_and_address(&self, platform: &str, address: &str) -> RepoResult<bool> {
+        let mut user_deposits = self.user_deposits.lock().unwrap();
+        let count = user_deposits.len();
+        user_deposits.retain(|d| d.platform != platform || d.borrower != address);
+
+        Ok(user_deposits.len() < count)
+    }
+
+    fn find_by_platform_and_address(
+        &self,
+        platform: &str,
+        address: &str,
+    ) -> RepoResult<Vec<UserDeposit>> {
+        Ok(self
+            .user_deposits
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|d| d.platform == platform && d.borrower == address)
+            .cloned()
+            .collect())
+    }
+
+    fn find_by_platform_and_address_and_coin_type(
+        &self,
+        platform: &str,
+        address: &str,
+        coin_type: &str,
+    ) -> RepoResult<UserDeposit> {
+        self.user_deposits
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|d| d.platform == platform && d.borrower == address && d.coin_type == coin_type)
+            .cloned()
+            .ok_or(RepoError::NotFound)
+    }
+
+    fn find_by_platform_and_address_with_coin_info(
+        &self,
+        _platform: &str,
+        _address: &str,
+    ) -> RepoResult<Vec<UserDepositWithCoinInfo>> {
+        unimplemented!()
+    }
+
+    fn delete_by_platform_and_address_and_obligation_id(
+        &self,
+        _platform: &str,
+        _address: &str,
+        _obligation_id: &str,
+    ) -> RepoResult<bool> {
+        unimplemented!()
+    }
+
+    fn find_distinct_platform_and_address(&self) -> RepoResult<Vec<UserDepositDistinct>> {
+        unimplemented!()
+    }
+}
+
+impl CoinRepository for MockDb {
+    fn create(&self, _coin: &NewCoin) -> RepoResult<Coin> {
+        unimplemented!()
+    }
+
+    fn update(&self, _id: i32, _coin: &UpdateCoin) -> RepoResult<Coin> {
+        unimplemented!()
+    }
+
+    fn force_update_decimals(&self, _id: i32, _decimals: i32) -> RepoResult<Coin> {
+        unimplemented!()
+    }
+
+    fn upsert(&self, _new_coin: &NewCoin, _update_coin: &UpdateCoin) -> RepoResult<Coin> {
+        unimplemented!()
+    }
+
+    /// Updates the oracle prices only.
+    ///
+    fn update_prices_batch(&self, updates: &[(i32, UpdateCoin)]) -> RepoResult<usize> {
+        let mut coins = self.coins.lock().unwrap();
+        let mut updated = 0;
+        for (id, update_coin) in updates {
+            let Some(coin) = coins.iter_mut().find(|c| c.id == *id) else {
+                continue;
+            };
+            if let Some(price) = &update_coin.price_supra {
+                coin.price_supra = Some(price.clone());
+                coin.supra_latest_updated_at = update_coin.supra_latest_updated_at;
+            }
+            if let Some(price) = &update_coin.price_switchboard {
+                coin.price_switchboard = Some(price.clone());
+                coin.switchboard_latest_updated_at = update_coin.switchboard_latest_updated_at;
+            }
+            updated += 1;
+        }
+        self.price_batches.lock().unwrap().push(updates.len());
+
+        Ok(updated)
+    }
+
+    fn delete(&self, _id: i32) -> RepoResult<bool> {
+        unimplemented!()
+    }
+
+    fn find_by_id(&self, _id: i32) -> RepoResult<Coin> {
+        unimplemented!()
+    }
+
+    fn find_all(&self) -> RepoResult<Vec<Coin>> {
+        unimplemented!()
+    }
+
+    fn find_by_coin_type(&self, _coin_type: &str) -> RepoResult<Coin> {
+        unimplemented!()
+    }
+
+    /// Coins in insertion order, as the database doesn't sort them either.
+    ///
+    fn find_by_coin_types(&self, coin_types: &[String]) -> RepoResult<Vec<Coin>> {
+        Ok(self
+            .coins
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|c| coin_types.contains(&c.coin_type))
+            .cloned()
+            .collect())
+    }
+
+    fn find_by_pyth_feed_id(&self, _pyth_feed_id: &str) -> RepoResult<Vec<Coin>> {
+        unimplemented!()
+    }
+
+    fn find_by_supra_pair_id(&self, supra_pair_id: i32) -> RepoResult<Vec<Coin>> {
+        Ok(self
+            .coins
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|c| c.supra_pair_id == Some(supra_pair_id))
+            .cloned()
+            .collect())
+    }
+
+    fn find_by_switchboard_aggregator_id(&self, aggregator_id: &str) -> RepoResult<Vec<Coin>> {
+        Ok(self
+            .coins
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|c| c.switchboard_aggregator_id.as_deref() == Some(aggregator_id))
+            .cloned()
+            .collect())
+    }
+
+    fn find_by_navi_asset_id(&self, asset_id: i32) -> RepoResult<Coin> {
+        self.coins
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|c| c.navi_asset_id == Some(asset_id))
+            .cloned()
+            .ok_or(RepoError::NotFound)
+    }
+
+    fn find_all_pyth_feed_ids(&self) -> RepoResult<Vec<String>> {
+        unimplemented!()
+    }
+
+    fn find_coins_without_price(&self) -> RepoResult<Vec<Coin>> {
+        unimplemented!()
+    }
+
+    fn find_coins_with_stale_price(&self, _older_than: NaiveDateTime) -> RepoResult<Vec<Coin>> {
+        unimplemented!()
+    }
+}
+
+impl PoolRepository for MockDb {
+    fn create(&self, _pool: &NewPool) -> RepoResult<Pool> {
+        unimplemented!()
+    }
+
+    fn update(&self, _id: i32, _pool: &UpdatePool) -> RepoResult<Pool> {
+        unimplemented!()
+    }
+
+    fn delete(&self, _id: i32) -> RepoResult<bool> {
+        unimplemented!()
+    }
+
+    fn find_by_id(&self, _id: i32) -> RepoResult<Pool> {
+        unimplemented!()
+    }
+
+    fn find_by_address(&self, address: &str) -> RepoResult<Pool> {
+        self.pools
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|p| p.address == address)
+            .cloned()
+            .ok_or(RepoError::NotFound)
+    }
+
+    fn find_by_addresses(&self, _addresses: &[&str]) -> RepoResult<Vec<Pool>> {
+        unimplemented!()
+    }
+
+    fn find_by_coins_containing(&self, _coin_a: &str, _coin_b: &str) -> RepoResult<Vec<Pool>> {
+        unimplemented!()
+    }
+
+    fn upsert(&self, _new_pool: &NewPool, _update_pool: &UpdatePool) -> RepoResult<Pool> {
+        unimplemented!()
+    }
+
+    fn find_all(&self) -> RepoResult<Vec<Pool>> {
+        unimplemented!()
+    }
+
+    fn find_by_exchange_paged(
+        &self,
+        _exchange: &str,
+        _offset: i64,
+        _limit: i64,
+    ) -> RepoResult<Vec<Pool>> {
+        unimplemented!()
+    }
+
+    fn count_by_exchange(&self, _exchange: &str) -> RepoResult<i64> {
+        unimplemented!()
+    }
+}
+
+impl PoolTickRepository for MockDb {
+    fn create(&self, new_pool_tick: &NewPoolTick) -> RepoResult<PoolTick> {
+        let mut pool_ticks = self.pool_ticks.lock().unwrap();
+        let pool_tick = PoolTick {
+            id: pool_ticks.len() as i32 + 1,
+            address: new_pool_tick.address.clone(),
+            tick_index: new_pool_tick.tick_index,
+            liquidity_net: new_pool_tick.liquidity_net.clone(),
+            liquidity_gross: new_pool_tick.liquidity_gross.clone(),
+            created_at: None,
+            updated_at: None,
+        };
+        pool_ticks.push(pool_tick.clone());
+
+        Ok(pool_tick)
+    }
+
+    fn update(&self, id: i32, update_pool_tick: &UpdatePoolTick) -> RepoResult<PoolTick> {
+        let mut pool_ticks = self.pool_ticks.lock().unwrap();
+        let pool_tick = pool_ticks
+            .iter_mut()
+            .find(|t| t.id == id)
+            .ok_or(RepoError::NotFound)?;
+        if let Some(liquidity_net) = &update_pool_tick.liquidity_net {
+            pool_tick.liquidity_net = Some(liquidity_net.clone());
+        }
+        if let Some(liquidity_gross) = &update_pool_tick.liquidity_gross {
+            pool_tick.liquidity_gross = Some(liquidity_gross.clone());
+        }
+
+        Ok(pool_tick.clone())
+    }
+
+    fn delete(&self, _id: i32) -> RepoResult<bool> {
+        unimplemented!()
+    }
+
+    fn find_by_id(&self, _id: i32) -> RepoResult<PoolTick> {
+        unimplemented!()
+    }
+
+    fn find_all(&self) -> RepoResult<Vec<PoolTick>> {
+        unimplemented!()
+    }
+
+    fn find_by_address_and_tick_index(
+        &self,
+        address: &str,
+        tick_index: i32,
+    ) -> RepoResult<PoolTick> {
+        self.pool_ticks
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|t| t.address == address && t.tick_index == tick_index)
+            .cloned()
+            .ok_or(RepoError::NotFound)
+    }
+
+    fn find_by_address(&self, _address: &str) -> RepoResult<Vec<PoolTick>> {
+        unimplemented!()
+    }
+
+    fn find_lower_tick_for_address(
+        &self,
+        _address: &str,
+        _tick_index: i32,
+    ) -> RepoResult<Option<PoolTick>> {
+        unimplemented!()
+    }
+
+    fn find_higher_tick_for_address(
+        &self,
+        _address: &str,
+        _tick_index: i32,
+    ) -> RepoResult<Option<PoolTick>> {
+        unimplemented!()
+    }
+
+    /// Ticks strictly below `tick_index`, the closest first.
+    ///
+    fn find_lower_ticks_for_address(
+        &self,
+        address: &str,
+        tick_index: i32,
+        limit: i64,
+    ) -> RepoResult<Vec<PoolTick>> {
+        let mut ticks = self
+            .pool_ticks
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|t| t.address == address && t.tick_index < tick_index)
+            .cloned()
+            .collect::<Vec<_>>();
+        ticks.sort_by_key(|t| std::cmp::Reverse(t.tick_index));
+        ticks.truncate(limit as usize);
+
+        Ok(ticks)
+    }
+
+    /// Ticks strictly above `tick_index`, the closest first.
+    ///
+    fn find_higher_ticks_for_address(
+        &self,
+        address: &str,
+        tick_index: i32,
+        limit: i64,
+    ) -> RepoResult<Vec<PoolTick>> {
+        let mut ticks = self
+            .pool_ticks
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|t| t.address == address && t.tick_index > tick_index)
+            .cloned()
+            .collect::<Vec<_>>();
+        ticks.sort_by_key(|t| t.tick_index);
+        ticks.truncate(limit as usize);
+
+        Ok(ticks)
+    }
+
+    fn upsert_batch(&self, _pool_ticks: &[NewPoolTick]) -> RepoResult<Vec<PoolTick>> {
+        unimplemented!()
+    }
+
+    /// Same arithmetic as the database: the net liquidity bits wrap around,
+    /// the gross liquidity never goes below zero.
+    ///
+    fn add_liquidity_deltas(&self, new_pool_ticks: &[NewPoolTick]) -> RepoResult<Vec<PoolTick>> {
+        let mut pool_ticks = self.pool_ticks.lock().unwrap();
+        let mut saved_pool_ticks = vec![];
+
+        for new_pool_tick in new_pool_ticks {
+            let position = pool_ticks.iter().position(|t| {
+                t.address == new_pool_tick.address && t.tick_index == new_pool_tick.tick_index
+            });
+            let pool_tick = match position {
+                Some(position) => &mut pool_ticks[position],
+                None => {
+                    let id = pool_ticks.len() as i32 + 1;
+                    pool_ticks.push(PoolTick {
+                        id,
+                        address: new_pool_tick.address.clone(),
+                        tick_index: new_pool_tick.tick_index,
+                        liquidity_net: None,
+                        liquidity_gross: None,
+                        created_at: None,
+                        updated_at: None,
+                    });
+                    pool_ticks.last_mut().unwrap()
+                }
+            };
+
+            let net = |value: &Option<String>| -> u128 {
+                value.as_deref().unwrap_or("0").parse().unwrap()
+            };
+            let gross = |value: &Option<String>| -> i128 {
+                value.as_deref().unwrap_or("0").parse().unwrap()
+            };
+            pool_tick.liquidity_net = Some(
+                net(&pool_tick.liquidity_net)
+                    .wrapping_add(net(&new_pool_tick.liquidity_net))
+                    .to_string(),
+            );
+            pool_tick.liquidity_gross = Some(
+                (gross(&pool_tick.liquidity_gross) + gross(&new_pool_tick.liquidity_gross))
+                    .max(0)
+                    .to_string(),
+            );
+            saved_pool_ticks.push(pool_tick.clone());
+        }
+
+        Ok(saved_pool_ticks)
+    }
+
+    fn delete_ticks_outside_range(
+        &self,
+        address: &str,
+        lower_tick_index: i32,
+        upper_tick_index: i32,
+    ) -> RepoResult<usize> {
+        let mut pool_ticks = self.pool_ticks.lock().unwrap();
+        let count = pool_ticks.len();
+        pool_ticks.retain(|t| {
+            t.address != address || (lower_tick_index..=upper_tick_index).contains(&t.tick_index)
+        });
+
+        Ok(count - pool_ticks.len())
+    }
+}
+
+impl SwapEventRepository for MockDb {
+    fn create(&self, _swap_event: &NewSwapEvent) -> RepoResult<SwapEvent> {
+        unimplemented!()
+    }
+
+    fn create_batch(&self, _swap_events: &[NewSwapEvent]) -> RepoResult<usize> {
+        unimplemented!()
+    }
+
+    fn delete(&self, _id: i32) -> RepoResult<bool> {
+        unimplemented!()
+    }
+
+    fn find_by_id(&self, _id: i32) -> RepoResult<SwapEvent> {
+        unimplemented!()
+    }
+
+    fn find_by_pool_id(&self, _pool_id: &str) -> RepoResult<Vec<SwapEvent>> {
+        unimplemented!()
+    }
+
+    fn find_by_tx_digest(&self, _tx_digest: &str) -> RepoResult<Vec<SwapEvent>> {
+        unimplemented!()
+    }
+}
+
+impl MetricRepository for MockDb {
+    fn create(&self, metric: &NewMetric) -> RepoResult<Metric> {
+        let mut metrics = self.metrics.lock().unwrap();
+        let metric = Metric {
+            id: metrics.len() as i32 + 1,
+            latest_seq_number: metric.latest_seq_number,
+            total_checkpoints: metric.total_checkpoints,
+            total_processed_checkpoints: metric.total_processed_checkpoints,
+            max_processing_time: metric.max_processing_time,
+            min_processing_time: metric.min_processing_time,
+            avg_processing_time: metric.avg_processing_time,
+            max_lagging: metric.max_lagging,
+            min_lagging: metric.min_lagging,
+            avg_lagging: metric.avg_lagging,
+            created_at: None,
+            updated_at: None,
+        };
+        metrics.push(metric.clone());
+
+        Ok(metric)
+    }
+
+    fn update(&self, _id: i32, _metric: &UpdateMetric) -> RepoResult<Metric> {
+        unimplemented!()
+    }
+
+    fn delete(&self, _id: i32) -> RepoResult<bool> {
+        unimplemented!()
+    }
+
+    fn find_by_id(&self, _id: i32) -> RepoResult<Metric> {
+        unimplemented!()
+    }
+
+    fn find_latest_seq_number(&self) -> RepoResult<Option<Metric>> {
+        unimplemented!()
+    }
+
+    fn find_metrics_between(&self, _start_seq: i32, _end_seq: i32) -> RepoResult<Vec<Metric>> {
+        unimplemented!()
+    }
+
+    fn avg_lagging_between(&self, _start_seq: i32, _end_seq: i32) -> RepoResult<Option<f32>> {
+        unimplemented!()
+    }
+}
+
+impl SharedObjectRepository for MockDb {
+    fn create(&self, shared_object: &NewSharedObject) -> RepoResult<SharedObject> {
+        let mut shared_objects = self.shared_objects.lock().unwrap();
+        let now = chrono::Utc::now().naive_utc();
+        let created = SharedObject {
+            id: shared_objects.len() as i32 + 1,
+            object_id: shared_object.object_id.clone(),
+            initial_shared_version: shared_object.initial_shared_version,
+            created_at: Some(now),
+            updated_at: Some(now),
+            cached_at: shared_object.cached_at,
+        };
+        shared_objects.push(created.clone());
+
+        Ok(created)
+    }
+
+    fn update(&self, id: i32, shared_object: &UpdateSharedObject) -> RepoResult<SharedObject> {
+        let mut shared_objects = self.shared_objects.lock().unwrap();
+        let existing = shared_objects
+            .iter_mut()
+            .find(|o| o.id == id)
+            .ok_or(RepoError::NotFound)?;
+        if let Some(object_id) = &shared_object.object_id {
+            existing.object_id = object_id.clone();
+        }
+        if let Some(initial_shared_version) = shared_object.initial_shared_version {
+            existing.initial_shared_version = initial_shared_version;
+        }
+        if let Some(cached_at) = shared_object.cached_at {
+            existing.cached_at = Some(cached_at);
+        }
+        existing.updated_at = Some(chrono::Utc::now().naive_utc());
+
+        Ok(existing.clone())
+    }
+
+    fn delete(&self, _id: i32) -> RepoResult<bool> {
+        unimplemented!()
+    }
+
+    fn find_by_id(&self, _id: i32) -> RepoResult<SharedObject> {
+        unimplemented!()
+    }
+
+    fn find_by_object_id(&self, object_id: &str) -> RepoResult<SharedObject> {
+        self.shared_objects
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|o| o.object_id == object_id)
+            .cloned()
+            .ok_or(RepoError::NotFound)
+    }
+
+    fn find_all(&self) -> RepoResult<Vec<SharedObject>> {
+        unimplemented!()
+    }
+
+    fn delete_by_object_id(&self, object_id: &str) -> RepoResult<usize> {
+        let mut shared_objects = self.shared_objects.lock().unwrap();
+        let count = shared_objects.len();
+        shared_objects.retain(|o| o.object_id != object_id);
+
+        Ok(count - shared_objects.len())
+    }
+}
+
+impl FailedEventRepository for MockDb {
+    fn create(&self, failed_event: &NewFailedEvent) -> RepoResult<FailedEvent> {
+        let mut failed_events = self.failed_events.lock().unwrap();
+        let failed_event = FailedEvent {
+            id: failed_events.len() as i32 + 1,
+            event_type: failed_event.event_type.clone(),
+            tx_digest: failed_event.tx_digest.clone(),
+            checkpoint_seq_number: failed_event.checkpoint_seq_number,
+            error: failed_event.error.clone(),
+            created_at: None,
+        };
+        failed_events.push(failed_event.clone());
+
+        Ok(failed_event)
+    }
+
+    fn delete(&self, _id: i32) -> RepoResult<bool> {
+        unimplemented!()
+    }
+
+    fn find_by_id(&self, _id: i32) -> RepoResult<FailedEvent> {
+        unimplemented!()
+    }
+
+    fn find_by_checkpoint_seq_number(&self, seq_number: i64) -> RepoResult<Vec<FailedEvent>> {
+        Ok(self
+            .failed_events
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|e| e.checkpoint_seq_number == seq_number)
+            .cloned()
+            .collect())
+    }
+}
+
+/// DEX service serving the given pools, fetching any other pool fails.
+///
+#[derive(Default)]
+pub(crate) struct MockDEXService {
+    pub pools: Vec<crate::types::Pool>,
+}
+
+#[async_trait]
+impl dex::DEXService for MockDEXService {
+    async fn get_pool_data(&self, pool_id: &str) -> Result<crate::types::Pool> {
+        self.pools
+            .iter()
+            .find(|pool| pool.pool_id == pool_id)
+            .cloned()
+            .ok_or_else(|| anyhow!("Pool {} not found", pool_id))
+    }
+}
+
+/// On-chain lending service returning fixed positions,
+/// recording the obligation ID the portfolio is fetched with.
+///
+#[derive(Default)]
+pub(crate) struct MockLendingService {
+    pub user_deposits: Vec<crate::types::UserDeposit>,
+    pub user_borrows: Vec<crate::types::UserBorrow>,
+    pub obligation_id: Option<String>,
+    /// When set, every fetch fails as if the RPC was down.
+    pub unavailable: bool,
+    pub fetched_obligation_id: Mutex<Option<Option<String>>>,
+    /// Number of obligation ID lookups, each one an RPC call on chain.
+    pub obligation_id_lookups: AtomicUsize,
+}
+
+impl MockLendingService {
+    fn check_available(&self) -> Result<()> {
+        if self.unavailable {
+            return Err(anyhow!("RPC unavailable"));
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl lending::LendingService for MockLendingService {
+    async fn fetch_borrower_portfolio(
+        &self,
+        _borrower: String,
+        obligation_id: Option<String>,
+    ) -> Result<(
+        Vec<crate::types::UserDeposit>,
+        Vec<crate::types::UserBorrow>,
+    )> {
+        *self.fetched_obligation_id.lock().unwrap() = Some(obligation_id);
+        self.check_available()?;
+
+        Ok((self.user_deposits.clone(), self.user_borrows.clone()))
+    }
+
+    /// Returns the first deposit, of the coin type if given.
+    ///
+    async fn fetch_user_deposit(
+        &self,
+        _borrower: String,
+        _obligation_id: Option<String>,
+        coin_type: Option<String>,
+        _asset_id: Option<u8>,
+    ) -> Result<crate::types::UserDeposit> {
+        self.check_available()?;
+
+        self.user_deposits
+            .iter()
+            .find(|d| coin_type.is_none() || coin_type.as_ref() == Some(&d.coin_type))
+            .cloned()
+            .ok_or_else(|| anyhow!("No deposit"))
+    }
+
+    /// Returns the first borrow, of the coin type if given.
+    ///
+    async fn fetch_user_borrow(
+        &self,
+        _borrower: String,
+        _obligation_id: Option<String>,
+        coin_type: Option<String>,
+        _asset_id: Option<u8>,
+    ) -> Result<crate::types::UserBorrow> {
+        self.check_available()?;
+
+        self.user_borrows
+            .iter()
+            .find(|b| coin_type.is_none() || coin_type.as_ref() == Some(&b.coin_type))
+            .cloned()
+            .ok_or_else(|| anyhow!("No borrow"))
+    }
+
+    async fn find_obligation_id_from_address(&self, _borrower: &str) -> Result<String> {
+        self.obligation_id_lookups.fetch_add(1, Ordering::SeqCst);
+        self.check_available()?;
+
+        self.obligation_id
+            .clone()
+            .ok_or_else(|| anyhow!("No obligation"))
+    }
+}
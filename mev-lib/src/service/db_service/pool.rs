@@ -1,21 +1,23 @@
 use crate::{
     config::{self, Config},
     constant, indexer,
-    service::registry::ServiceRegistry,
-    utils,
+    service::{dex, registry::ServiceRegistry},
+    utils::{self, rpc_pool::RpcPool},
 };
 use db::models::{
     self,
     coin::{Coin, NewCoin, UpdateCoin},
     pool::{NewPool, Pool, UpdatePool},
     pool_tick::{NewPoolTick, PoolTick, UpdatePoolTick},
+    swap_event::NewSwapEvent,
 };
-use db::repositories::{CoinRepository, PoolRepository, PoolTickRepository};
+use db::repositories::{CoinRepository, PoolRepository, PoolTickRepository, SwapEventRepository};
+use db::RepoError;
 
 use anyhow::{anyhow, Result};
 use rayon::prelude::*;
 use rust_decimal::{prelude::*, Decimal};
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 use tokio::{
     sync::RwLock,
     time::{Duration, Instant},
@@ -24,23 +26,29 @@ use tracing::{debug, error, info, instrument, trace, warn};
 
 pub struct PoolService {
     config: Arc<Config>,
+    rpc_pool: Arc<RpcPool>,
     pool_repo: Arc<dyn PoolRepository + Send + Sync>,
     coin_repo: Arc<dyn CoinRepository + Send + Sync>,
     pool_tick_repo: Arc<dyn PoolTickRepository + Send + Sync>,
+    swap_event_repo: Arc<dyn SwapEventRepository + Send + Sync>,
 }
 
 impl PoolService {
     pub fn new(
         config: Arc<Config>,
+        rpc_pool: Arc<RpcPool>,
         pool_repo: Arc<dyn PoolRepository + Send + Sync>,
         coin_repo: Arc<dyn CoinRepository + Send + Sync>,
         pool_tick_repo: Arc<dyn PoolTickRepository + Send + Sync>,
+        swap_event_repo: Arc<dyn SwapEventRepository + Send + Sync>,
     ) -> Self {
         PoolService {
             config,
+            rpc_pool,
             pool_repo,
             coin_repo,
             pool_tick_repo,
+            swap_event_repo,
         }
     }
 
@@ -58,7 +66,7 @@ impl PoolService {
         let coins = pool
             .coins
             .iter()
-            .map(|c| c.coin_type.clone())
+            .map(|c| utils::canonicalize_coin_type(&c.coin_type))
             .collect::<Vec<String>>()
             .join(",");
 
@@ -92,50 +100,45 @@ impl PoolService {
                 .join(",")
         });
 
-        match self.pool_repo.find_by_address(&pool_id) {
-            Ok(pool_model) => {
-                let update_pool = UpdatePool {
-                    exchange: Some(pool.exchange.clone()),
-                    address: Some(pool.pool_id.clone()),
-                    coins: Some(coins),
-                    coin_amounts,
-                    weights,
-                    liquidity: pool.liquidity.clone(),
-                    current_sqrt_price: pool.current_sqrt_price.clone(),
-                    current_tick_index: pool.current_tick_index,
-                    tick_spacing: pool.tick_spacing,
-                    fee_rate: pool.fee_rate,
-                    is_pause: pool.is_pause,
-                    fees_swap_in,
-                    fees_swap_out,
-                    pool_type: pool.pool_type.clone(),
-                };
+        let new_pool = NewPool {
+            exchange: pool.exchange.clone(),
+            address: pool.pool_id.clone(),
+            coins: coins.clone(),
+            coin_amounts: coin_amounts.clone(),
+            weights: weights.clone(),
+            liquidity: pool.liquidity.clone(),
+            current_sqrt_price: pool.current_sqrt_price.clone(),
+            current_tick_index: pool.current_tick_index,
+            tick_spacing: pool.tick_spacing,
+            fee_rate: pool.fee_rate,
+            is_pause: pool.is_pause,
+            fees_swap_in: fees_swap_in.clone(),
+            fees_swap_out: fees_swap_out.clone(),
+            pool_type: pool.pool_type.clone(),
+        };
 
-                let _ = self.pool_repo.update(pool_model.id, &update_pool)?;
-                info!("Updated pool {} in DB", pool_id);
-            }
-            Err(e) => {
-                let new_pool = NewPool {
-                    exchange: pool.exchange.clone(),
-                    address: pool.pool_id.clone(),
-                    coins,
-                    coin_amounts,
-                    weights,
-                    liquidity: pool.liquidity.clone(),
-                    current_sqrt_price: pool.current_sqrt_price.clone(),
-                    current_tick_index: pool.current_tick_index,
-                    tick_spacing: pool.tick_spacing,
-                    fee_rate: pool.fee_rate,
-                    is_pause: pool.is_pause,
-                    fees_swap_in,
-                    fees_swap_out,
-                    pool_type: pool.pool_type.clone(),
-                };
+        let update_pool = UpdatePool {
+            exchange: Some(pool.exchange.clone()),
+            address: Some(pool.pool_id.clone()),
+            coins: Some(coins),
+            coin_amounts,
+            weights,
+            liquidity: pool.liquidity.clone(),
+            current_sqrt_price: pool.current_sqrt_price.clone(),
+            current_tick_index: pool.current_tick_index,
+            tick_spacing: pool.tick_spacing,
+            fee_rate: pool.fee_rate,
+            is_pause: pool.is_pause,
+            fees_swap_in,
+            fees_swap_out,
+            pool_type: pool.pool_type.clone(),
+        };
 
-                let _ = self.pool_repo.create(&new_pool)?;
-                info!("Created new pool {} in DB", pool_id);
-            }
-        }
+        // upsert, as concurrent checkpoint workers may save the same pool
+        self.pool_repo
+            .upsert(&new_pool, &update_pool)
+            .map_err(|e| anyhow!("Failed to save pool {}: {}", pool_id, e))?;
+        info!("Saved pool {} in DB", pool_id);
 
         for coin in pool_coins.iter() {
             if let Err(e) = self.save_coin_to_db(coin.clone()).await {
@@ -178,8 +181,8 @@ impl PoolService {
                     .update(pool_tick_model.id, &update_pool_tick)?;
                 info!("Updated PoolTick: {:?}", updated_pool_tick);
             }
-            Err(e) => {
-                info!("PoolTick not found in DB {:?}, create new one", e);
+            Err(RepoError::NotFound) => {
+                info!("PoolTick not found in DB, create new one");
                 let new_pool_tick = NewPoolTick {
                     address: pool_tick.address.clone(),
                     tick_index: pool_tick.tick_index,
@@ -190,68 +193,225 @@ impl PoolService {
                 let created_pool_tick = self.pool_tick_repo.create(&new_pool_tick)?;
                 info!("Created new PoolTick: {:?}", created_pool_tick);
             }
+            Err(e) => {
+                return Err(anyhow!(
+                    "Failed to find PoolTick {} at tick {}: {}",
+                    pool_tick.address,
+                    pool_tick.tick_index,
+                    e
+                ))
+            }
         }
 
         Ok(())
     }
 
-    pub async fn save_coin_to_db(&self, coin: crate::types::Coin) -> Result<models::coin::Coin> {
-        let coin_model = self.coin_repo.find_by_coin_type(&coin.coin_type);
-
-        match coin_model {
-            Ok(coin_model) => {
-                let update_coin = UpdateCoin {
-                    coin_type: Some(coin.coin_type.clone()),
-                    decimals: None, // TODO: do not update decimals, they should not change
-                    name: coin.name.clone(),
-                    symbol: coin.symbol.clone(),
-                    price_pyth: None,
-                    price_supra: None,
-                    price_switchboard: None,
-                    pyth_feed_id: coin.pyth_feed_id.clone(),
-                    pyth_info_object_id: coin.pyth_info_object_id.clone(),
-                    pyth_latest_updated_at: None,
-                    pyth_ema_price: None,
-                    pyth_decimals: None,
-                    navi_asset_id: None,
-                    navi_oracle_id: None,
-                    navi_feed_id: None,
-                    hermes_price: None,
-                    hermes_latest_updated_at: None,
-                    vaa: None,
-                };
+    /// Saves multiple pool ticks to the database in a single upsert.
+    /// Ticks sharing the same (address, tick_index) are merged first,
+    /// later values win, and `None` liquidity values keep what is already stored.
+    ///
+    pub async fn save_pool_ticks_to_db(&self, pool_ticks: &[PoolTick]) -> Result<()> {
+        let mut new_pool_ticks: Vec<NewPoolTick> = Vec::with_capacity(pool_ticks.len());
 
-                self.coin_repo
-                    .update(coin_model.id, &update_coin)
-                    .map_err(|e| anyhow!("Failed to update coin {}: {}", coin.coin_type, e))
+        for pool_tick in pool_ticks {
+            match new_pool_ticks
+                .iter_mut()
+                .find(|t| t.address == pool_tick.address && t.tick_index == pool_tick.tick_index)
+            {
+                Some(existing) => {
+                    if pool_tick.liquidity_net.is_some() {
+                        existing.liquidity_net = pool_tick.liquidity_net.clone();
+                    }
+                    if pool_tick.liquidity_gross.is_some() {
+                        existing.liquidity_gross = pool_tick.liquidity_gross.clone();
+                    }
+                }
+                None => new_pool_ticks.push(NewPoolTick {
+                    address: pool_tick.address.clone(),
+                    tick_index: pool_tick.tick_index,
+                    liquidity_net: pool_tick.liquidity_net.clone(),
+                    liquidity_gross: pool_tick.liquidity_gross.clone(),
+                }),
             }
-            Err(e) => {
-                let new_coin = NewCoin {
-                    coin_type: coin.coin_type.clone(),
-                    decimals: coin.decimals as i32,
-                    name: coin.name.clone(),
-                    symbol: coin.symbol.clone(),
-                    price_pyth: None,
-                    price_supra: None,
-                    price_switchboard: None,
-                    pyth_feed_id: coin.pyth_feed_id.clone(),
-                    pyth_info_object_id: coin.pyth_info_object_id.clone(),
-                    pyth_latest_updated_at: None,
-                    pyth_ema_price: None,
-                    pyth_decimals: None,
-                    navi_asset_id: None,
-                    navi_oracle_id: None,
-                    navi_feed_id: None,
-                    hermes_price: None,
-                    hermes_latest_updated_at: None,
-                    vaa: None,
-                };
-                let created_coin = self.coin_repo.create(&new_coin)?;
-                info!("Created new coin {} in DB", created_coin.coin_type);
+        }
 
-                Ok(created_coin)
-            }
+        let saved_pool_ticks = self
+            .pool_tick_repo
+            .upsert_batch(&new_pool_ticks)
+            .map_err(|e| anyhow!("Failed to upsert pool ticks: {}", e))?;
+        info!("Upserted {} PoolTicks", saved_pool_ticks.len());
+
+        Ok(())
+    }
+
+    /// Applies a liquidity change of a position to its boundary ticks,
+    /// as in Uniswap v3: the gross liquidity of both ticks changes by `liquidity_delta`,
+    /// the net liquidity by `liquidity_delta` at the lower tick and by its opposite at the upper tick.
+    /// `liquidity_delta` is positive when adding liquidity and negative when removing it.
+    ///
+    pub async fn apply_liquidity_delta_to_ticks(
+        &self,
+        pool_id: &str,
+        tick_lower: i32,
+        tick_upper: i32,
+        liquidity_delta: i128,
+    ) -> Result<()> {
+        if tick_lower >= tick_upper {
+            return Err(anyhow!(
+                "Invalid tick range [{}, {}] for pool {}",
+                tick_lower,
+                tick_upper,
+                pool_id
+            ));
+        }
+
+        let net_delta_upper = liquidity_delta
+            .checked_neg()
+            .ok_or_else(|| anyhow!("Liquidity delta {} overflows", liquidity_delta))?;
+
+        let new_pool_ticks = vec![
+            NewPoolTick {
+                address: pool_id.to_string(),
+                tick_index: tick_lower,
+                liquidity_net: Some(utils::tick_math::liquidity_net_to_bits(liquidity_delta)),
+                liquidity_gross: Some(liquidity_delta.to_string()),
+            },
+            NewPoolTick {
+                address: pool_id.to_string(),
+                tick_index: tick_upper,
+                liquidity_net: Some(utils::tick_math::liquidity_net_to_bits(net_delta_upper)),
+                liquidity_gross: Some(liquidity_delta.to_string()),
+            },
+        ];
+
+        let saved_pool_ticks = self
+            .pool_tick_repo
+            .add_liquidity_deltas(&new_pool_ticks)
+            .map_err(|e| {
+                anyhow!(
+                    "Failed to apply liquidity delta {} to ticks of pool {}: {}",
+                    liquidity_delta,
+                    pool_id,
+                    e
+                )
+            })?;
+        info!(
+            "Applied liquidity delta to {} PoolTicks",
+            saved_pool_ticks.len()
+        );
+
+        Ok(())
+    }
+
+    /// Saves swaps for volume analytics, skipping the ones already recorded.
+    /// The swaps are normalized by the DEX processors, amounts are in raw units of the coins.
+    ///
+    pub async fn save_swap_events_to_db(&self, swap_events: &[NewSwapEvent]) -> Result<usize> {
+        self.swap_event_repo
+            .create_batch(swap_events)
+            .map_err(|e| anyhow!("Failed to save {} swap events: {}", swap_events.len(), e))
+    }
+
+    pub async fn save_coin_to_db(
+        &self,
+        mut coin: crate::types::Coin,
+    ) -> Result<models::coin::Coin> {
+        coin.coin_type = utils::canonicalize_coin_type(&coin.coin_type);
+
+        let new_coin = NewCoin {
+            coin_type: coin.coin_type.clone(),
+            decimals: coin.decimals as i32,
+            name: coin.name.clone(),
+            symbol: coin.symbol.clone(),
+            price_pyth: None,
+            price_supra: None,
+            price_switchboard: None,
+            pyth_feed_id: coin.pyth_feed_id.clone(),
+            pyth_info_object_id: coin.pyth_info_object_id.clone(),
+            pyth_latest_updated_at: None,
+            pyth_ema_price: None,
+            pyth_decimals: None,
+            navi_asset_id: None,
+            navi_oracle_id: None,
+            navi_feed_id: None,
+            hermes_price: None,
+            hermes_latest_updated_at: None,
+            vaa: None,
+            supra_pair_id: None,
+            supra_latest_updated_at: None,
+            switchboard_aggregator_id: None,
+            switchboard_latest_updated_at: None,
+        };
+
+        let update_coin = UpdateCoin {
+            coin_type: Some(coin.coin_type.clone()),
+            decimals: None, // do not update decimals, see reconcile_coin_decimals
+            name: coin.name.clone(),
+            symbol: coin.symbol.clone(),
+            price_pyth: None,
+            price_supra: None,
+            price_switchboard: None,
+            pyth_feed_id: coin.pyth_feed_id.clone(),
+            pyth_info_object_id: coin.pyth_info_object_id.clone(),
+            pyth_latest_updated_at: None,
+            pyth_ema_price: None,
+            pyth_decimals: None,
+            navi_asset_id: None,
+            navi_oracle_id: None,
+            navi_feed_id: None,
+            hermes_price: None,
+            hermes_latest_updated_at: None,
+            vaa: None,
+            supra_pair_id: None,
+            supra_latest_updated_at: None,
+            switchboard_aggregator_id: None,
+            switchboard_latest_updated_at: None,
+        };
+
+        // upsert, as concurrent checkpoint workers may save the same coin
+        self.coin_repo
+            .upsert(&new_coin, &update_coin)
+            .map_err(|e| anyhow!("Failed to save coin {}: {}", coin.coin_type, e))
+    }
+
+    /// Re-fetches the on-chain metadata of a coin and corrects its decimals
+    /// if they differ from the stored ones, e.g. after a bad metadata response.
+    /// Returns whether the decimals were updated.
+    ///
+    pub async fn reconcile_coin_decimals(&self, coin_type: &str) -> Result<bool> {
+        let coin_type = &utils::canonicalize_coin_type(coin_type);
+
+        let coin_model = self
+            .coin_repo
+            .find_by_coin_type(coin_type)
+            .map_err(|e| anyhow!("Failed to find coin {}: {}", coin_type, e))?;
+
+        let metadata = self
+            .rpc_pool
+            .call(|client| async move {
+                client
+                    .coin_read_api()
+                    .get_coin_metadata(coin_type.to_string())
+                    .await
+            })
+            .await?
+            .ok_or_else(|| anyhow!("Failed to get coin metadata for type: {}", coin_type))?;
+
+        let decimals = metadata.decimals as i32;
+        if coin_model.decimals == decimals {
+            return Ok(false);
         }
+
+        warn!(
+            "Coin {} decimals mismatch: stored {}, on-chain {}",
+            coin_type, coin_model.decimals, decimals
+        );
+
+        self.coin_repo
+            .force_update_decimals(coin_model.id, decimals)
+            .map_err(|e| anyhow!("Failed to update decimals of coin {}: {}", coin_type, e))?;
+
+        Ok(true)
     }
 
     /// Retrieves pool and its coins from the database.
@@ -273,17 +433,28 @@ impl PoolService {
             .find_by_address(pool_id)
             .map_err(|e| anyhow!("Failed to find pool: {}", e))?;
 
-        let coins = pool.coins.split(',').collect::<Vec<_>>();
+        let coins = pool
+            .coins
+            .split(',')
+            .map(utils::canonicalize_coin_type)
+            .collect::<Vec<_>>();
         let coins_len = coins.len();
 
+        let found_coins = self
+            .coin_repo
+            .find_by_coin_types(&coins)
+            .map_err(|e| anyhow!("Failed to find coins {:?}: {}", coins, e))?;
+
+        // keep the pool coin order, callers zip coins with weights and amounts
         let coin_models = coins
-            .into_iter()
-            .map(|coin_type| {
-                self.coin_repo
-                    .find_by_coin_type(coin_type)
-                    .map_err(|e| anyhow!("Failed to find coin {}: {}", coin_type, e))
+            .iter()
+            .filter_map(|coin_type| {
+                found_coins
+                    .iter()
+                    .find(|c| &c.coin_type == coin_type)
+                    .cloned()
             })
-            .collect::<Result<Vec<_>>>()?;
+            .collect::<Vec<_>>();
 
         if coin_models.len() != coins_len {
             return Err(anyhow!(
@@ -314,12 +485,13 @@ impl PoolService {
     )> {
         let (pool, coin_models) = self.find_pool_from_db(pool_id, shio_auction_digest).await?;
 
+        let coin_type_out = &utils::canonicalize_coin_type(coin_type_out);
+        let coin_type_in = &utils::canonicalize_coin_type(coin_type_in);
+
         let coins = pool
             .coins
             .split(',')
-            .collect::<Vec<_>>()
-            .into_iter()
-            .map(|c| c.to_string())
+            .map(utils::canonicalize_coin_type)
             .collect::<Vec<_>>();
 
         if coins.len() < 2 {
@@ -433,6 +605,177 @@ impl PoolService {
         Ok((pool, coin_in.clone(), coin_out.clone()))
     }
 
+    /// Quotes a swap of `amount_in` on a weighted pool with the Balancer-style formula,
+    /// after deducting the `fees_swap_in` fee of the coin in.
+    /// Amounts are in the smallest unit of their coin.
+    /// Weights and fees are stored scaled by the decimals of their coin,
+    /// they are normalized back before the computation.
+    ///
+    pub async fn quote_weighted_swap(
+        &self,
+        pool_id: &str,
+        coin_in: &str,
+        coin_out: &str,
+        amount_in: Decimal,
+    ) -> Result<Decimal> {
+        let (_, coin_in, coin_out) = self
+            .find_weighted_pool_from_db(pool_id, coin_out, coin_in, None)
+            .await?;
+
+        let (_, weight_in, balance_in, decimals_in, fee_swap_in) = coin_in;
+        let (_, weight_out, balance_out, decimals_out, _) = coin_out;
+
+        let scale_in = Decimal::from(10).powu(decimals_in as u64);
+        let scale_out = Decimal::from(10).powu(decimals_out as u64);
+
+        let amount_in_after_fee =
+            utils::net_value_given_fee_rate(amount_in, fee_swap_in / scale_in)?;
+
+        utils::weighted_amount_out(
+            balance_in,
+            weight_in / scale_in,
+            balance_out,
+            weight_out / scale_out,
+            amount_in_after_fee,
+        )
+    }
+
+    /// Returns the reserves of the pool as (coin_type, amount) tuples, in the pool coin order.
+    /// Amounts are in the smallest unit of their coin.
+    ///
+    pub async fn get_reserves(&self, pool_id: &str) -> Result<Vec<(String, Decimal)>> {
+        let pool = self
+            .pool_repo
+            .find_by_address(pool_id)
+            .map_err(|e| anyhow!("Failed to find pool: {}", e))?;
+
+        let coin_amounts = pool
+            .coin_amounts
+            .as_deref()
+            .ok_or_else(|| anyhow!("Pool {} does not have coin amounts", pool.id))?;
+
+        utils::parse_pool_reserves(&pool.coins, coin_amounts)
+            .map_err(|e| anyhow!("Invalid reserves of pool {}: {}", pool_id, e))
+    }
+
+    /// Quotes a swap of `amount_in` on a constant-product (x * y = k) pool,
+    /// after deducting the pool `fee_rate`.
+    /// Amounts are in the smallest unit of their coin.
+    ///
+    pub async fn quote_constant_product_swap(
+        &self,
+        pool_id: &str,
+        coin_in: &str,
+        coin_out: &str,
+        amount_in: Decimal,
+    ) -> Result<Decimal> {
+        let coin_in = &utils::canonicalize_coin_type(coin_in);
+        let coin_out = &utils::canonicalize_coin_type(coin_out);
+
+        let pool = self
+            .pool_repo
+            .find_by_address(pool_id)
+            .map_err(|e| anyhow!("Failed to find pool: {}", e))?;
+
+        let reserves = self.get_reserves(pool_id).await?;
+
+        let (_, reserve_in) = reserves
+            .iter()
+            .find(|(c, _)| c == coin_in)
+            .ok_or_else(|| anyhow!("Coin type {} not found in pool {}", coin_in, pool_id))?;
+        let (_, reserve_out) = reserves
+            .iter()
+            .find(|(c, _)| c == coin_out)
+            .ok_or_else(|| anyhow!("Coin type {} not found in pool {}", coin_out, pool_id))?;
+
+        let fee_rate = Decimal::from(pool.fee_rate.unwrap_or(0))
+            / Decimal::from(constant::FEE_RATE_DENOMINATOR);
+        let amount_in_after_fee = utils::net_value_given_fee_rate(amount_in, fee_rate)?;
+
+        utils::constant_product_amount_out(*reserve_in, *reserve_out, amount_in_after_fee)
+    }
+
+    /// Quotes a swap of `amount_in` on an Obric pool, priced by the oracle:
+    /// the input net of the pool `fee_rate` is converted at the Pyth prices of both coins,
+    /// then adjusted for the coin decimals.
+    /// Fails if the pool can't cover the amount out with its reserve.
+    /// Amounts are in the smallest unit of their coin.
+    ///
+    pub async fn quote_obric_swap(
+        &self,
+        pool_id: &str,
+        coin_in: &str,
+        coin_out: &str,
+        amount_in: Decimal,
+    ) -> Result<Decimal> {
+        let coin_in = &utils::canonicalize_coin_type(coin_in);
+        let coin_out = &utils::canonicalize_coin_type(coin_out);
+
+        let pool = self
+            .pool_repo
+            .find_by_address(pool_id)
+            .map_err(|e| anyhow!("Failed to find pool: {}", e))?;
+
+        if pool.exchange != constant::OBRIC_EXCHANGE {
+            return Err(anyhow!("Pool {} is not an Obric pool", pool_id));
+        }
+
+        let reserves = self.get_reserves(pool_id).await?;
+
+        if !reserves.iter().any(|(c, _)| c == coin_in) {
+            return Err(anyhow!(
+                "Coin type {} not found in pool {}",
+                coin_in,
+                pool_id
+            ));
+        }
+        let (_, reserve_out) = reserves
+            .iter()
+            .find(|(c, _)| c == coin_out)
+            .ok_or_else(|| anyhow!("Coin type {} not found in pool {}", coin_out, pool_id))?;
+
+        let (price_in, decimals_in) = self.find_coin_oracle_price(coin_in)?;
+        let (price_out, decimals_out) = self.find_coin_oracle_price(coin_out)?;
+
+        let fee_rate = Decimal::from(pool.fee_rate.unwrap_or(0))
+            / Decimal::from(constant::FEE_RATE_DENOMINATOR);
+        let amount_in_after_fee = utils::net_value_given_fee_rate(amount_in, fee_rate)?;
+
+        utils::oracle_priced_amount_out(
+            amount_in_after_fee,
+            price_in,
+            decimals_in,
+            price_out,
+            decimals_out,
+            *reserve_out,
+        )
+        .map_err(|e| anyhow!("Failed to quote Obric pool {}: {}", pool_id, e))
+    }
+
+    /// Latest Pyth price in USD of a coin, with the coin decimals.
+    ///
+    fn find_coin_oracle_price(&self, coin_type: &str) -> Result<(Decimal, u32)> {
+        let coin_type = &utils::canonicalize_coin_type(coin_type);
+
+        let coin = self
+            .coin_repo
+            .find_by_coin_type(coin_type)
+            .map_err(|e| anyhow!("Failed to find coin {}: {}", coin_type, e))?;
+
+        let (price, pyth_decimals) = match (coin.price_pyth.as_deref(), coin.pyth_decimals) {
+            (Some(price), Some(pyth_decimals)) => (price, pyth_decimals),
+            _ => return Err(anyhow!("Missing price data for coin {}", coin_type)),
+        };
+
+        let price = Decimal::from_str(price)
+            .map_err(|e| anyhow!("Failed to parse price of coin {}: {}", coin_type, e))?;
+
+        Ok((
+            utils::sui_from_mist(price, pyth_decimals as usize),
+            coin.decimals as u32,
+        ))
+    }
+
     /// Retrieves the next initialized tick for a given pool and tick index.
     /// If `zero_to_one` is true, the price goes down, so it will find the next lower tick.
     /// If `zero_to_one` is false, the price goes up, so it will find the next higher tick.
@@ -465,9 +808,833 @@ impl PoolService {
         }
     }
 
+    /// Retrieves up to `n` next initialized ticks for a given pool and tick index,
+    /// in traversal order: descending if `zero_to_one` is true, ascending otherwise.
+    /// This avoids a query per tick when walking the tick map.
+    ///
+    pub async fn find_next_n_initialized_ticks(
+        &self,
+        pool_id: &str,
+        tick_index: i32,
+        zero_to_one: bool,
+        n: usize,
+    ) -> Result<Vec<PoolTick>> {
+        if n == 0 {
+            return Ok(vec![]);
+        }
+
+        let next_ticks = if zero_to_one {
+            self.pool_tick_repo
+                .find_lower_ticks_for_address(pool_id, tick_index, n as i64)?
+        } else {
+            self.pool_tick_repo
+                .find_higher_ticks_for_address(pool_id, tick_index, n as i64)?
+        };
+
+        Ok(next_ticks)
+    }
+
+    /// Retrieves the initialized ticks around a tick index, in ascending order:
+    /// up to `n` ticks at or below `tick_index` and up to `n` ticks above it.
+    ///
+    pub async fn find_ticks_around(
+        &self,
+        pool_id: &str,
+        tick_index: i32,
+        n: usize,
+    ) -> Result<Vec<PoolTick>> {
+        // the lower ticks are strictly below the given index, start one above to include it
+        let mut ticks = self
+            .find_next_n_initialized_ticks(pool_id, tick_index.saturating_add(1), true, n)
+            .await?;
+        ticks.reverse();
+
+        ticks.extend(
+            self.find_next_n_initialized_ticks(pool_id, tick_index, false, n)
+                .await?,
+        );
+
+        Ok(ticks)
+    }
+
+    /// Simulates a swap of `amount_in` on a CLMM pool from the stored tick data.
+    /// Starting at the current sqrt price and liquidity of the pool,
+    /// the swap walks the initialized ticks in the swap direction, crossing each of them,
+    /// until the input, net of the pool fee, is consumed or the known ticks run out.
+    /// The ticks are fetched in batches and walked by `TickWalk`.
+    ///
+    pub async fn simulate_swap(
+        &self,
+        pool_id: &str,
+        amount_in: Decimal,
+        zero_to_one: bool,
+    ) -> Result<crate::types::SwapQuote> {
+        let pool = self
+            .pool_repo
+            .find_by_address(pool_id)
+            .map_err(|e| anyhow!("Failed to find pool {}: {}", pool_id, e))?;
+
+        let current_tick_index = pool
+            .current_tick_index
+            .ok_or_else(|| anyhow!("Pool {} has no current tick index", pool_id))?;
+        let current_sqrt_price = pool
+            .current_sqrt_price
+            .as_deref()
+            .ok_or_else(|| anyhow!("Pool {} has no current sqrt price", pool_id))?;
+        let liquidity = pool
+            .liquidity
+            .as_deref()
+            .ok_or_else(|| anyhow!("Pool {} has no liquidity", pool_id))?;
+
+        let sqrt_price = utils::tick_math::sqrt_price_from_q64(
+            Decimal::from_str(current_sqrt_price)
+                .map_err(|e| anyhow!("Invalid sqrt price of pool {}: {}", pool_id, e))?,
+        );
+        let liquidity = Decimal::from_str(liquidity)
+            .map_err(|e| anyhow!("Invalid liquidity of pool {}: {}", pool_id, e))?;
+        let fee_rate = Decimal::from(pool.fee_rate.unwrap_or(0))
+            / Decimal::from(constant::FEE_RATE_DENOMINATOR);
+
+        let mut walk = TickWalk::new(sqrt_price, liquidity, fee_rate, amount_in, zero_to_one)?;
+
+        // the current tick is crossed first when the price goes down,
+        // lower ticks are searched strictly below the given index
+        let mut tick_index = if zero_to_one {
+            current_tick_index.saturating_add(1)
+        } else {
+            current_tick_index
+        };
+
+        while !walk.is_done() {
+            let ticks = self
+                .find_next_n_initialized_ticks(
+                    pool_id,
+                    tick_index,
+                    zero_to_one,
+                    constant::SWAP_SIMULATION_TICK_BATCH,
+                )
+                .await?;
+            walk.cross_ticks(&ticks)?;
+
+            match ticks.last() {
+                Some(last) if ticks.len() == constant::SWAP_SIMULATION_TICK_BATCH => {
+                    tick_index = last.tick_index;
+                }
+                _ => break,
+            }
+        }
+
+        if !walk.remaining.is_zero() {
+            debug!(
+                "Swap simulation on pool {} ran out of ticks, {} of the input not consumed",
+                pool_id, walk.remaining
+            );
+        }
+
+        walk.quote()
+    }
+
+    /// Retrieves the pools of the given addresses in a single query,
+    /// keyed by address. Unknown addresses are absent from the result.
+    ///
+    pub async fn find_pools_by_addresses(
+        &self,
+        pool_ids: &[&str],
+    ) -> Result<HashMap<String, db::models::pool::Pool>> {
+        let pools = self
+            .pool_repo
+            .find_by_addresses(pool_ids)
+            .map_err(|e| anyhow!("Failed to find pools: {}", e))?;
+
+        Ok(pools
+            .into_iter()
+            .map(|pool| (pool.address.clone(), pool))
+            .collect())
+    }
+
+    /// Retrieves all known pools containing both coin types, in any order,
+    /// grouped by exchange.
+    ///
+    pub async fn find_pools_for_pair(
+        &self,
+        coin_a: &str,
+        coin_b: &str,
+    ) -> Result<HashMap<String, Vec<db::models::pool::Pool>>> {
+        let pools = self
+            .pool_repo
+            .find_by_coins_containing(coin_a, coin_b)
+            .map_err(|e| anyhow!("Failed to find pools for {} and {}: {}", coin_a, coin_b, e))?;
+
+        let mut pools_by_exchange: HashMap<String, Vec<db::models::pool::Pool>> = HashMap::new();
+        for pool in pools {
+            pools_by_exchange
+                .entry(pool.exchange.clone())
+                .or_default()
+                .push(pool);
+        }
+
+        Ok(pools_by_exchange)
+    }
+
+    /// Deletes the ticks of a pool which are further than `window`
+    /// tick indexes from its current tick.
+    /// Returns the number of deleted ticks.
+    ///
+    pub async fn prune_pool_ticks(&self, pool_id: &str, window: i32) -> Result<usize> {
+        let pool = self
+            .pool_repo
+            .find_by_address(pool_id)
+            .map_err(|e| anyhow!("Failed to find pool {}: {}", pool_id, e))?;
+
+        let current_tick_index = pool
+            .current_tick_index
+            .ok_or_else(|| anyhow!("Pool {} has no current tick index", pool_id))?;
+
+        let deleted = self
+            .pool_tick_repo
+            .delete_ticks_outside_range(
+                pool_id,
+                current_tick_index.saturating_sub(window),
+                current_tick_index.saturating_add(window),
+            )
+            .map_err(|e| anyhow!("Failed to prune ticks of pool {}: {}", pool_id, e))?;
+
+        Ok(deleted)
+    }
+
+    /// Prunes the ticks of all pools having a current tick index.
+    /// Returns the total number of deleted ticks.
+    ///
+    pub async fn prune_all_pool_ticks(&self, window: i32) -> Result<usize> {
+        let pools = self
+            .pool_repo
+            .find_all()
+            .map_err(|e| anyhow!("Failed to find pools: {}", e))?;
+
+        let mut total_deleted = 0;
+        for pool in pools.iter().filter(|p| p.current_tick_index.is_some()) {
+            match self.prune_pool_ticks(&pool.address, window).await {
+                Ok(deleted) => total_deleted += deleted,
+                Err(e) => error!("{}", e),
+            }
+        }
+
+        Ok(total_deleted)
+    }
+
+    /// Re-fetches the on-chain data of every stored pool of the exchange
+    /// with its DEX service and saves it, e.g. to rebuild stale pool state after downtime.
+    /// Pools are walked page by page, a pool failing to refresh is logged and skipped.
+    /// Returns the number of refreshed pools.
+    ///
+    pub async fn refresh_all_pools(
+        &self,
+        exchange: &str,
+        dex_service: Arc<dyn dex::DEXService + Send + Sync>,
+    ) -> Result<usize> {
+        let total = self
+            .pool_repo
+            .count_by_exchange(exchange)
+            .map_err(|e| anyhow!("Failed to count pools of {}: {}", exchange, e))?;
+
+        info!("Refreshing {} pools of {}", total, exchange);
+
+        let mut refreshed = 0;
+        let mut offset = 0;
+        while offset < total {
+            let pools = self
+                .pool_repo
+                .find_by_exchange_paged(exchange, offset, constant::POOL_REFRESH_PAGE_SIZE)
+                .map_err(|e| anyhow!("Failed to find pools of {}: {}", exchange, e))?;
+
+            if pools.is_empty() {
+                break;
+            }
+            offset += pools.len() as i64;
+
+            for pool in pools {
+                let pool_data = match dex_service.get_pool_data(&pool.address).await {
+                    Ok(pool_data) => pool_data,
+                    Err(e) => {
+                        error!(
+                            "Failed to fetch pool {} of {}: {}",
+                            pool.address, exchange, e
+                        );
+                        continue;
+                    }
+                };
+
+                match self.save_pool_to_db(pool_data).await {
+                    Ok(_) => refreshed += 1,
+                    Err(e) => error!(
+                        "Failed to save pool {} of {}: {}",
+                        pool.address, exchange, e
+                    ),
+                }
+            }
+        }
+
+        info!("Refreshed {}/{} pools of {}", refreshed, total, exchange);
+
+        Ok(refreshed)
+    }
+
+    /// Fetches the pool from chain through the DEX service
+    /// and diffs it against the stored pool, to detect missed events.
+    /// Returns the on-chain pool as well, so callers can save it without fetching it again.
+    ///
+    pub async fn verify_pool_against_chain(
+        &self,
+        pool_id: &str,
+        dex_service: Arc<dyn dex::DEXService + Send + Sync>,
+    ) -> Result<(crate::types::PoolDiff, crate::types::Pool)> {
+        let stored = self
+            .pool_repo
+            .find_by_address(pool_id)
+            .map_err(|e| anyhow!("Failed to find pool: {}", e))?;
+
+        let onchain = dex_service
+            .get_pool_data(pool_id)
+            .await
+            .map_err(|e| anyhow!("Failed to fetch pool {} from chain: {}", pool_id, e))?;
+
+        let diff = crate::types::PoolDiff::compare(&stored, &onchain);
+        if !diff.is_empty() {
+            warn!("Pool {} drifted from chain: {:?}", pool_id, diff.fields);
+        }
+
+        Ok((diff, onchain))
+    }
+
     pub async fn find_coin_by_type(&self, coin_type: &str) -> Result<models::coin::Coin> {
         self.coin_repo
-            .find_by_coin_type(coin_type)
+            .find_by_coin_type(&utils::canonicalize_coin_type(coin_type))
             .map_err(|e| anyhow!("Failed to find coin {}: {}", coin_type, e))
     }
 }
+
+/// CLMM swap walking the initialized ticks in the swap direction,
+/// from a sqrt price without the Q64 scaling and the liquidity at that price.
+/// `fee_rate` is the fraction of the input taken as fee.
+/// Ticks without a stored liquidity net are crossed without changing the liquidity.
+///
+struct TickWalk {
+    zero_to_one: bool,
+    fee_rate: Decimal,
+    amount_in: Decimal,
+    sqrt_price: Decimal,
+    liquidity: Decimal,
+    remaining: Decimal,
+    consumed: Decimal,
+    amount_out: Decimal,
+}
+
+impl TickWalk {
+    fn new(
+        sqrt_price: Decimal,
+        liquidity: Decimal,
+        fee_rate: Decimal,
+        amount_in: Decimal,
+        zero_to_one: bool,
+    ) -> Result<Self> {
+        Ok(TickWalk {
+            zero_to_one,
+            fee_rate,
+            amount_in,
+            sqrt_price,
+            liquidity,
+            remaining: utils::net_value_given_fee_rate(amount_in, fee_rate)?,
+            consumed: Decimal::ZERO,
+            amount_out: Decimal::ZERO,
+        })
+    }
+
+    /// The input, net of the fee, is consumed or no liquidity is left.
+    ///
+    fn is_done(&self) -> bool {
+        self.remaining.is_zero() || self.liquidity <= Decimal::ZERO
+    }
+
+    /// Swaps up to each of the ticks, ordered in the swap direction, and crosses them,
+    /// until the input is consumed before the next tick.
+    ///
+    fn cross_ticks(&mut self, ticks: &[PoolTick]) -> Result<()> {
+        for tick in ticks {
+            if self.is_done() {
+                break;
+            }
+
+            let tick_sqrt_price = utils::tick_math::tick_to_sqrt_price(tick.tick_index)?;
+            let target_sqrt_price = utils::tick_math::target_sqrt_price_given_amount_in(
+                self.sqrt_price,
+                self.remaining,
+                self.liquidity,
+                self.zero_to_one,
+            )?;
+
+            let reaches_tick = if self.zero_to_one {
+                target_sqrt_price <= tick_sqrt_price
+            } else {
+                target_sqrt_price >= tick_sqrt_price
+            };
+
+            // the remaining input is consumed before the next tick
+            if !reaches_tick {
+                self.amount_out += utils::tick_math::amount_out_given_target_sqrt_price(
+                    self.sqrt_price,
+                    target_sqrt_price,
+                    self.liquidity,
+                    self.zero_to_one,
+                )?;
+                self.consumed += self.remaining;
+                self.remaining = Decimal::ZERO;
+                self.sqrt_price = target_sqrt_price;
+                break;
+            }
+
+            let (delta_x, delta_y) = utils::tick_math::delta_amount_from_sqrt_price(
+                self.sqrt_price,
+                tick_sqrt_price,
+                self.liquidity,
+            )?;
+            let step_in = if self.zero_to_one { delta_x } else { delta_y };
+
+            self.amount_out += utils::tick_math::amount_out_given_target_sqrt_price(
+                self.sqrt_price,
+                tick_sqrt_price,
+                self.liquidity,
+                self.zero_to_one,
+            )?;
+            self.consumed += step_in;
+            self.remaining = (self.remaining - step_in).max(Decimal::ZERO);
+            self.sqrt_price = tick_sqrt_price;
+
+            // cross the tick
+            if let Some(liquidity_net) = tick.liquidity_net.as_deref() {
+                let liquidity_net = utils::tick_math::liquidity_net_from_bits(liquidity_net)?;
+                if self.zero_to_one {
+                    self.liquidity -= liquidity_net;
+                } else {
+                    self.liquidity += liquidity_net;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Quote of the swap so far, only the consumed part of the input is charged
+    /// when the ticks ran out.
+    ///
+    fn quote(self) -> Result<crate::types::SwapQuote> {
+        let amount_in = if self.remaining.is_zero() {
+            self.amount_in
+        } else {
+            utils::gross_value_given_fee_rate(self.consumed, self.fee_rate)?
+        };
+
+        Ok(crate::types::SwapQuote {
+            amount_in,
+            amount_out: self.amount_out,
+            fee_amount: amount_in - self.consumed,
+            end_sqrt_price: self.sqrt_price,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::db_service::mock::MockDb;
+
+    fn tick(tick_index: i32, liquidity_net: i128) -> PoolTick {
+        PoolTick {
+            id: 0,
+            address: "0xpool".to_string(),
+            tick_index,
+            liquidity_net: Some(utils::tick_math::liquidity_net_to_bits(liquidity_net)),
+            liquidity_gross: None,
+            created_at: None,
+            updated_at: None,
+        }
+    }
+
+    fn sqrt_price(tick_index: i32) -> Decimal {
+        utils::tick_math::tick_to_sqrt_price(tick_index).unwrap()
+    }
+
+    /// Quote of a swap from a price of 1 over the given ticks.
+    fn quote(
+        liquidity: i64,
+        fee_rate: Decimal,
+        amount_in: i64,
+        zero_to_one: bool,
+        ticks: &[PoolTick],
+    ) -> crate::types::SwapQuote {
+        let mut walk = TickWalk::new(
+            Decimal::ONE,
+            Decimal::from(liquidity),
+            fee_rate,
+            Decimal::from(amount_in),
+            zero_to_one,
+        )
+        .unwrap();
+        walk.cross_ticks(ticks).unwrap();
+
+        walk.quote().unwrap()
+    }
+
+    /// Virtual reserves (in, out) of `liquidity` at `sqrt_price`, as in x·y=k.
+    fn reserves(liquidity: i64, sqrt_price: Decimal, zero_to_one: bool) -> (Decimal, Decimal) {
+        let liquidity = Decimal::from(liquidity);
+        let (x, y) = (liquidity / sqrt_price, liquidity * sqrt_price);
+
+        if zero_to_one {
+            (x, y)
+        } else {
+            (y, x)
+        }
+    }
+
+    /// Input and output of the swap of `liquidity` between two sqrt prices.
+    fn range_swap(
+        liquidity: i64,
+        from: Decimal,
+        to: Decimal,
+        zero_to_one: bool,
+    ) -> (Decimal, Decimal) {
+        let (in_from, out_from) = reserves(liquidity, from, zero_to_one);
+        let (in_to, out_to) = reserves(liquidity, to, zero_to_one);
+
+        (in_to - in_from, out_from - out_to)
+    }
+
+    fn assert_close(actual: Decimal, expected: Decimal) {
+        let tolerance = expected.abs() * Decimal::new(1, 9);
+        assert!(
+            (actual - expected).abs() <= tolerance,
+            "{} is not close to {}",
+            actual,
+            expected
+        );
+    }
+
+    fn between(value: Decimal, a: Decimal, b: Decimal) -> bool {
+        value > a.min(b) && value < a.max(b)
+    }
+
+    /// Positions of 500_000 over [-10, 10] and over [-1000, 1000] at a price of 1,
+    /// ticks ordered in the swap direction.
+    fn nested_ranges(zero_to_one: bool) -> (Vec<PoolTick>, i32, i32) {
+        if zero_to_one {
+            (vec![tick(-10, 500_000), tick(-1000, 500_000)], -10, -1000)
+        } else {
+            (vec![tick(10, -500_000), tick(1000, -500_000)], 10, 1000)
+        }
+    }
+
+    #[test]
+    fn swap_within_a_tick_range_matches_constant_product() {
+        for zero_to_one in [true, false] {
+            let ticks = if zero_to_one {
+                vec![tick(-1000, 1_000_000)]
+            } else {
+                vec![tick(1000, -1_000_000)]
+            };
+
+            let quote = quote(1_000_000, Decimal::new(3, 3), 1_000, zero_to_one, &ticks);
+
+            // both virtual reserves are the liquidity at a price of 1
+            let (reserve_in, reserve_out) = reserves(1_000_000, Decimal::ONE, zero_to_one);
+            let expected =
+                utils::constant_product_amount_out(reserve_in, reserve_out, Decimal::from(997))
+                    .unwrap();
+            assert_eq!(quote.amount_in, Decimal::from(1_000));
+            assert_eq!(quote.fee_amount, Decimal::from(3));
+            assert_close(quote.amount_out, expected);
+
+            let (end_reserve_in, _) = reserves(1_000_000, quote.end_sqrt_price, zero_to_one);
+            assert_close(end_reserve_in, reserve_in + Decimal::from(997));
+        }
+    }
+
+    #[test]
+    fn swap_stops_when_the_input_is_consumed() {
+        for zero_to_one in [true, false] {
+            let (ticks, inner, outer) = nested_ranges(zero_to_one);
+
+            // consumed before the first tick
+            let quote_before = quote(1_000_000, Decimal::ZERO, 100, zero_to_one, &ticks);
+            assert_eq!(quote_before.amount_in, Decimal::from(100));
+            assert!(between(
+                quote_before.end_sqrt_price,
+                Decimal::ONE,
+                sqrt_price(inner)
+            ));
+
+            // crosses the first tick, consumed before the second one
+            let quote_after = quote(1_000_000, Decimal::ZERO, 10_000, zero_to_one, &ticks);
+            assert_eq!(quote_after.amount_in, Decimal::from(10_000));
+            assert!(between(
+                quote_after.end_sqrt_price,
+                sqrt_price(inner),
+                sqrt_price(outer)
+            ));
+
+            let (step_in, step_out) =
+                range_swap(1_000_000, Decimal::ONE, sqrt_price(inner), zero_to_one);
+            let (reserve_in, reserve_out) = reserves(500_000, sqrt_price(inner), zero_to_one);
+            let expected = step_out
+                + utils::constant_product_amount_out(
+                    reserve_in,
+                    reserve_out,
+                    Decimal::from(10_000) - step_in,
+                )
+                .unwrap();
+            assert_close(quote_after.amount_out, expected);
+        }
+    }
+
+    #[test]
+    fn swap_stops_when_the_ticks_run_out() {
+        let fee_rate = Decimal::new(3, 3);
+
+        for zero_to_one in [true, false] {
+            let (ticks, inner, outer) = nested_ranges(zero_to_one);
+
+            let quote = quote(1_000_000, fee_rate, 1_000_000, zero_to_one, &ticks);
+
+            let (inner_in, inner_out) =
+                range_swap(1_000_000, Decimal::ONE, sqrt_price(inner), zero_to_one);
+            let (outer_in, outer_out) =
+                range_swap(500_000, sqrt_price(inner), sqrt_price(outer), zero_to_one);
+            let consumed = inner_in + outer_in;
+
+            assert_eq!(quote.end_sqrt_price, sqrt_price(outer));
+            assert!(quote.amount_in < Decimal::from(1_000_000));
+            assert_close(quote.amount_in, consumed / (Decimal::ONE - fee_rate));
+            assert_close(quote.fee_amount, quote.amount_in * fee_rate);
+            assert_close(quote.amount_out, inner_out + outer_out);
+        }
+    }
+
+    fn liquidity_net(db: &MockDb, tick_index: i32) -> Decimal {
+        let pool_tick = db
+            .find_by_address_and_tick_index("0xpool", tick_index)
+            .unwrap();
+
+        utils::tick_math::liquidity_net_from_bits(&pool_tick.liquidity_net.unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn liquidity_deltas_accumulate_net_liquidity() {
+        let db = Arc::new(MockDb::default());
+        let service = db.pool_service(crate::config::test_config());
+
+        // add 1000 over [-10, 10], add 500 over [0, 10], remove 1000 over [-10, 10]
+        for (tick_lower, tick_upper, liquidity_delta) in
+            [(-10, 10, 1000), (0, 10, 500), (-10, 10, -1000)]
+        {
+            service
+                .apply_liquidity_delta_to_ticks("0xpool", tick_lower, tick_upper, liquidity_delta)
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(liquidity_net(&db, -10), Decimal::ZERO);
+        assert_eq!(liquidity_net(&db, 0), Decimal::from(500));
+        assert_eq!(liquidity_net(&db, 10), Decimal::from(-500));
+
+        let upper = db.find_by_address_and_tick_index("0xpool", 10).unwrap();
+        assert_eq!(upper.liquidity_gross.as_deref(), Some("500"));
+    }
+
+    #[tokio::test]
+    async fn liquidity_delta_rejects_an_empty_range() {
+        let db = Arc::new(MockDb::default());
+        let service = db.pool_service(crate::config::test_config());
+
+        let result = service
+            .apply_liquidity_delta_to_ticks("0xpool", 10, 10, 1000)
+            .await;
+
+        assert!(result.is_err());
+        assert!(db.pool_ticks.lock().unwrap().is_empty());
+    }
+
+    /// Database with the SUI and USDC coins, USDC saved after SUI.
+    fn db_with_coins() -> Arc<MockDb> {
+        let db = Arc::new(MockDb::default());
+        for (id, coin_type) in [(1, "0x2::sui::SUI"), (2, "0xdba34672::usdc::USDC")] {
+            db.coins
+                .lock()
+                .unwrap()
+                .push(MockDb::coin(id, &utils::canonicalize_coin_type(coin_type)));
+        }
+
+        db
+    }
+
+    #[tokio::test]
+    async fn pool_coins_keep_the_pool_order() {
+        let db = db_with_coins();
+        db.pools.lock().unwrap().push(MockDb::pool(
+            1,
+            "cetus",
+            "0xpool",
+            "0xdba34672::usdc::USDC,0x2::sui::SUI",
+        ));
+        let service = db.pool_service(crate::config::test_config());
+
+        let (pool, coins) = service.find_pool_from_db("0xpool", None).await.unwrap();
+
+        assert_eq!(pool.address, "0xpool");
+        assert_eq!(coins.iter().map(|c| c.id).collect::<Vec<_>>(), vec![2, 1]);
+    }
+
+    #[tokio::test]
+    async fn pool_with_a_missing_coin_is_an_error() {
+        let db = db_with_coins();
+        db.pools.lock().unwrap().push(MockDb::pool(
+            1,
+            "cetus",
+            "0xpool",
+            "0x2::sui::SUI,0xcafe::missing::MISSING",
+        ));
+        let service = db.pool_service(crate::config::test_config());
+
+        let result = service.find_pool_from_db("0xpool", None).await;
+
+        assert!(result.is_err());
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("Not all coins found for pool 1"));
+    }
+
+    #[tokio::test]
+    async fn missing_pool_is_an_error() {
+        let db = db_with_coins();
+        let service = db.pool_service(crate::config::test_config());
+
+        assert!(service.find_pool_from_db("0xpool", None).await.is_err());
+    }
+
+    /// Database with the ticks of `0xpool` at the indexes, and a tick of another pool.
+    fn db_with_ticks(tick_indexes: &[i32]) -> Arc<MockDb> {
+        let db = Arc::new(MockDb::default());
+        {
+            let mut pool_ticks = db.pool_ticks.lock().unwrap();
+            pool_ticks.extend(tick_indexes.iter().map(|&tick_index| tick(tick_index, 1)));
+            pool_ticks.push(PoolTick {
+                address: "0xother".to_string(),
+                ..tick(5, 1)
+            });
+        }
+
+        db
+    }
+
+    fn tick_indexes(ticks: &[PoolTick]) -> Vec<i32> {
+        ticks.iter().map(|t| t.tick_index).collect()
+    }
+
+    #[tokio::test]
+    async fn next_ticks_are_in_traversal_order() {
+        let db = db_with_ticks(&[10, -20, 0, 20, -10]);
+        let service = db.pool_service(crate::config::test_config());
+
+        let lower = service
+            .find_next_n_initialized_ticks("0xpool", 0, true, 2)
+            .await
+            .unwrap();
+        let higher = service
+            .find_next_n_initialized_ticks("0xpool", 0, false, 2)
+            .await
+            .unwrap();
+
+        // the tick at the index itself is excluded both ways
+        assert_eq!(tick_indexes(&lower), vec![-10, -20]);
+        assert_eq!(tick_indexes(&higher), vec![10, 20]);
+        assert!(service
+            .find_next_n_initialized_ticks("0xpool", 0, false, 0)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn next_ticks_page_without_overlap() {
+        let db = db_with_ticks(&[10, -20, 0, 20, -10]);
+        let service = db.pool_service(crate::config::test_config());
+
+        let mut walked = vec![];
+        let mut tick_index = -30;
+        loop {
+            let page = service
+                .find_next_n_initialized_ticks("0xpool", tick_index, false, 2)
+                .await
+                .unwrap();
+            let Some(last) = page.last() else {
+                break;
+            };
+            tick_index = last.tick_index;
+            walked.extend(tick_indexes(&page));
+        }
+
+        assert_eq!(walked, vec![-20, -10, 0, 10, 20]);
+    }
+
+    #[tokio::test]
+    async fn ticks_around_include_the_tick_index() {
+        let db = db_with_ticks(&[10, -20, 0, 20, -10]);
+        let service = db.pool_service(crate::config::test_config());
+
+        let on_tick = service.find_ticks_around("0xpool", 0, 2).await.unwrap();
+        let between_ticks = service.find_ticks_around("0xpool", 5, 1).await.unwrap();
+
+        assert_eq!(tick_indexes(&on_tick), vec![-10, 0, 10, 20]);
+        assert_eq!(tick_indexes(&between_ticks), vec![0, 10]);
+    }
+
+    #[tokio::test]
+    async fn pruning_keeps_the_ticks_within_the_window() {
+        let db = db_with_ticks(&[-20, -10, 0, 10, 20, 30]);
+        db.pools.lock().unwrap().push(Pool {
+            current_tick_index: Some(5),
+            ..MockDb::pool(1, "cetus", "0xpool", "0x2::sui::SUI,0x2::sui::SUI")
+        });
+        let service = db.pool_service(crate::config::test_config());
+
+        let deleted = service.prune_pool_ticks("0xpool", 15).await.unwrap();
+
+        // the window [-10, 20] is inclusive, the tick of the other pool is kept
+        assert_eq!(deleted, 2);
+        let mut kept = db
+            .pool_ticks
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|t| t.address == "0xpool")
+            .map(|t| t.tick_index)
+            .collect::<Vec<_>>();
+        kept.sort();
+        assert_eq!(kept, vec![-10, 0, 10, 20]);
+        assert_eq!(db.pool_ticks.lock().unwrap().len(), 5);
+    }
+
+    #[tokio::test]
+    async fn pruning_a_pool_without_current_tick_fails() {
+        let db = db_with_ticks(&[-20, 20]);
+        db.pools.lock().unwrap().push(MockDb::pool(
+            1,
+            "cetus",
+            "0xpool",
+            "0x2::sui::SUI,0x2::sui::SUI",
+        ));
+        let service = db.pool_service(crate::config::test_config());
+
+        assert!(service.prune_pool_ticks("0xpool", 15).await.is_err());
+        assert_eq!(db.pool_ticks.lock().unwrap().len(), 3);
+    }
+}
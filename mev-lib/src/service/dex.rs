@@ -3,15 +3,145 @@ pub mod bluefin;
 pub mod bluemove;
 pub mod cetus;
 pub mod flowx;
+pub mod kriya;
 pub mod momentum;
 pub mod obric;
 pub mod turbos;
 
+use crate::{constant, utils};
+
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
+use futures::stream::{self, StreamExt};
+use std::sync::Arc;
+use tokio::time::Duration;
 
 #[async_trait]
 pub trait DEXService {
     /// Fetches the pool data from the Sui client using the provided pool ID.
     async fn get_pool_data(&self, pool_id: &str) -> Result<crate::types::Pool>;
+
+    /// Fetches the data of several pools, up to `DEX_POOLS_FETCH_CONCURRENCY` at a time.
+    /// Returns a result per pool, in the order of `pool_ids`,
+    /// so a failing pool doesn't fail the others.
+    async fn get_pools_data(&self, pool_ids: Vec<String>) -> Vec<Result<crate::types::Pool>> {
+        stream::iter(pool_ids.iter())
+            .map(|pool_id| self.get_pool_data(pool_id))
+            .buffered(constant::DEX_POOLS_FETCH_CONCURRENCY)
+            .collect()
+            .await
+    }
+}
+
+/// DEX service wrapper which retries fetching the pool data
+/// when the Sui RPC returns a transient error.
+///
+pub struct RetryDEXService {
+    inner: Arc<dyn DEXService + Send + Sync>,
+    max_retries: u32,
+    base_delay: Duration,
+}
+
+impl RetryDEXService {
+    pub fn new(
+        inner: Arc<dyn DEXService + Send + Sync>,
+        max_retries: u32,
+        base_delay: Duration,
+    ) -> Self {
+        RetryDEXService {
+            inner,
+            max_retries,
+            base_delay,
+        }
+    }
+}
+
+#[async_trait]
+impl DEXService for RetryDEXService {
+    async fn get_pool_data(&self, pool_id: &str) -> Result<crate::types::Pool> {
+        utils::retry_with_backoff(
+            || self.inner.get_pool_data(pool_id),
+            self.max_retries,
+            self.base_delay,
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::db_service::mock::MockDEXService;
+
+    fn pool(pool_id: &str) -> crate::types::Pool {
+        crate::types::Pool {
+            exchange: "cetus".to_string(),
+            pool_id: pool_id.to_string(),
+            pool_type: None,
+            coins: vec![],
+            coin_amounts: None,
+            weights: None,
+            tick_spacing: None,
+            current_tick_index: None,
+            current_sqrt_price: None,
+            liquidity: None,
+            fee_rate: None,
+            is_pause: None,
+            fees_swap_in: None,
+            fees_swap_out: None,
+        }
+    }
+
+    /// Pool ID of each result, or the error.
+    fn outcomes(results: Vec<Result<crate::types::Pool>>) -> Vec<Result<String, String>> {
+        results
+            .into_iter()
+            .map(|result| result.map(|p| p.pool_id).map_err(|e| e.to_string()))
+            .collect()
+    }
+
+    fn pool_ids(ids: &[&str]) -> Vec<String> {
+        ids.iter().map(|id| id.to_string()).collect()
+    }
+
+    #[tokio::test]
+    async fn failing_pool_does_not_fail_the_batch() {
+        let service = MockDEXService {
+            pools: vec![pool("0xa"), pool("0xc")],
+        };
+
+        let results = service
+            .get_pools_data(pool_ids(&["0xa", "0xb", "0xc"]))
+            .await;
+
+        assert_eq!(
+            outcomes(results),
+            vec![
+                Ok("0xa".to_string()),
+                Err("Pool 0xb not found".to_string()),
+                Ok("0xc".to_string()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn batch_keeps_the_order_beyond_the_concurrency() {
+        let ids = (0..constant::DEX_POOLS_FETCH_CONCURRENCY * 2 + 1)
+            .map(|i| format!("0x{}", i))
+            .collect::<Vec<_>>();
+        let service = RetryDEXService::new(
+            Arc::new(MockDEXService {
+                pools: ids.iter().rev().map(|id| pool(id)).collect(),
+            }),
+            0,
+            Duration::from_millis(1),
+        );
+
+        let results = service.get_pools_data(ids.clone()).await;
+
+        assert_eq!(
+            outcomes(results),
+            ids.into_iter().map(Ok).collect::<Vec<_>>()
+        );
+    }
 }
@@ -2,7 +2,7 @@ use crate::{
     constant,
     service::{self, dex::DEXService},
     types::ObjectIDWrapper,
-    utils::{self, ptb::PTBHelper},
+    utils::{self, ptb::PTBHelper, rpc_pool::RpcPool},
 };
 use db::repositories::{CoinRepository, PoolRepository};
 
@@ -13,7 +13,6 @@ use serde::{Deserialize, Serialize};
 use serde_with::{serde_as, DisplayFromStr};
 use std::sync::Arc;
 use sui_sdk::rpc_types::{Coin, SuiData, SuiMoveValue, SuiObjectDataOptions};
-use sui_sdk::SuiClient;
 use sui_types::base_types::{ObjectID, SuiAddress};
 use tokio::time::{Duration, Instant};
 use tracing::{debug, error, info, instrument, trace, warn};
@@ -34,7 +33,7 @@ struct AftermathPool {
 
 pub struct AftermathService {
     exchange: String,
-    client: Arc<SuiClient>,
+    rpc_pool: Arc<RpcPool>,
     pool_repo: Arc<dyn PoolRepository + Send + Sync>,
     coin_repo: Arc<dyn CoinRepository + Send + Sync>,
     ptb_helper: Arc<PTBHelper>,
@@ -42,14 +41,14 @@ pub struct AftermathService {
 
 impl AftermathService {
     pub fn new(
-        client: Arc<SuiClient>,
+        rpc_pool: Arc<RpcPool>,
         pool_repo: Arc<dyn PoolRepository + Send + Sync>,
         coin_repo: Arc<dyn CoinRepository + Send + Sync>,
         ptb_helper: Arc<PTBHelper>,
     ) -> Self {
         AftermathService {
             exchange: constant::AFTERMATH_EXCHANGE.to_string(),
-            client,
+            rpc_pool,
             pool_repo,
             coin_repo,
             ptb_helper,
@@ -68,9 +67,16 @@ impl DEXService for AftermathService {
         let pool_id = ObjectID::from_str(pool_id)?;
 
         let pool_obj = self
-            .client
-            .read_api()
-            .get_object_with_options(pool_id, object_data_options)
+            .rpc_pool
+            .call(|client| {
+                let object_data_options = object_data_options.clone();
+                async move {
+                    client
+                        .read_api()
+                        .get_object_with_options(pool_id, object_data_options)
+                        .await
+                }
+            })
             .await?;
 
         let pool_data = pool_obj.data.ok_or(anyhow!(
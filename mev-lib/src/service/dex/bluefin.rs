@@ -3,7 +3,7 @@ use crate::{
     constant,
     service::dex::DEXService,
     types::ObjectIDWrapper,
-    utils::{self, ptb::PTBHelper, tick_math},
+    utils::{self, ptb::PTBHelper, rpc_pool::RpcPool, tick_math},
 };
 
 use db::repositories::{CoinRepository, PoolRepository};
@@ -15,10 +15,7 @@ use rust_decimal::Decimal;
 use serde::{de, Deserialize, Serialize};
 use serde_with::{serde_as, DisplayFromStr};
 use std::{str::FromStr, sync::Arc};
-use sui_sdk::{
-    rpc_types::{Coin, SuiData, SuiMoveValue, SuiObjectDataOptions},
-    SuiClient,
-};
+use sui_sdk::rpc_types::{Coin, SuiData, SuiMoveValue, SuiObjectDataOptions};
 use sui_types::{
     base_types::{ObjectID, SequenceNumber, SuiAddress},
     event::Event,
@@ -71,7 +68,7 @@ where
 
 pub struct BluefinService {
     exchange: String,
-    client: Arc<SuiClient>,
+    rpc_pool: Arc<RpcPool>,
     pool_repo: Arc<dyn PoolRepository + Send + Sync>,
     coin_repo: Arc<dyn CoinRepository + Send + Sync>,
     ptb_helper: Arc<PTBHelper>,
@@ -79,14 +76,14 @@ pub struct BluefinService {
 
 impl BluefinService {
     pub fn new(
-        client: Arc<SuiClient>,
+        rpc_pool: Arc<RpcPool>,
         pool_repo: Arc<dyn PoolRepository + Send + Sync>,
         coin_repo: Arc<dyn CoinRepository + Send + Sync>,
         ptb_helper: Arc<PTBHelper>,
     ) -> Self {
         BluefinService {
             exchange: constant::BLUEFIN_EXCHANGE.to_string(),
-            client,
+            rpc_pool,
             pool_repo,
             coin_repo,
             ptb_helper,
@@ -105,9 +102,16 @@ impl DEXService for BluefinService {
         let pool_id = ObjectID::from_str(pool_id)?;
 
         let pool_obj = self
-            .client
-            .read_api()
-            .get_object_with_options(pool_id, object_data_options)
+            .rpc_pool
+            .call(|client| {
+                let object_data_options = object_data_options.clone();
+                async move {
+                    client
+                        .read_api()
+                        .get_object_with_options(pool_id, object_data_options)
+                        .await
+                }
+            })
             .await?;
 
         let pool_data = pool_obj.data.ok_or(anyhow!(
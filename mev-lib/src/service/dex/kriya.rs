@@ -0,0 +1,199 @@
+use crate::{
+    constant,
+    service::dex::DEXService,
+    types::ObjectIDWrapper,
+    utils::{self, ptb::PTBHelper, rpc_pool::RpcPool},
+};
+use db::repositories::{CoinRepository, PoolRepository};
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_with::{serde_as, DisplayFromStr};
+use std::{str::FromStr, sync::Arc};
+use sui_types::base_types::ObjectID;
+use tracing::{debug, error, info, instrument, trace, warn};
+
+#[serde_as]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct KriyaPool {
+    id: ObjectIDWrapper,
+    token_x: String,
+    token_y: String,
+    #[serde_as(as = "DisplayFromStr")]
+    lp_fee_percent: u64,
+    #[serde_as(as = "DisplayFromStr")]
+    protocol_fee_percent: u64,
+    is_stable: bool,
+    is_swap_enabled: bool,
+}
+
+pub struct KriyaService {
+    exchange: String,
+    rpc_pool: Arc<RpcPool>,
+    pool_repo: Arc<dyn PoolRepository + Send + Sync>,
+    coin_repo: Arc<dyn CoinRepository + Send + Sync>,
+    ptb_helper: Arc<PTBHelper>,
+}
+
+impl KriyaService {
+    pub fn new(
+        rpc_pool: Arc<RpcPool>,
+        pool_repo: Arc<dyn PoolRepository + Send + Sync>,
+        coin_repo: Arc<dyn CoinRepository + Send + Sync>,
+        ptb_helper: Arc<PTBHelper>,
+    ) -> Self {
+        KriyaService {
+            exchange: constant::KRIYA_EXCHANGE.to_string(),
+            rpc_pool,
+            pool_repo,
+            coin_repo,
+            ptb_helper,
+        }
+    }
+}
+
+#[async_trait]
+impl DEXService for KriyaService {
+    /// Fetches the pool data from the Sui client using the provided pool ID.
+    /// Returns a `Pool` struct containing the pool information.
+    /// The function retrieves the pool type, coin types, and other relevant fields.
+    async fn get_pool_data(&self, pool_id: &str) -> Result<crate::types::Pool> {
+        let object_data_options = sui_sdk::rpc_types::SuiObjectDataOptions::full_content();
+
+        let pool_id = ObjectID::from_str(pool_id)?;
+
+        let pool_obj = self
+            .rpc_pool
+            .call(|client| {
+                let object_data_options = object_data_options.clone();
+                async move {
+                    client
+                        .read_api()
+                        .get_object_with_options(pool_id, object_data_options)
+                        .await
+                }
+            })
+            .await?;
+
+        let pool_data = pool_obj.data.ok_or(anyhow!(
+            "Failed to get object data for pool ID: {}",
+            pool_id
+        ))?;
+
+        // pool coins
+        let pool_type = pool_data
+            .type_
+            .ok_or(anyhow!(
+                "Failed to get object type for pool ID: {}",
+                pool_id
+            ))?
+            .to_string();
+
+        let coin_types = utils::get_coin_types_from_pool_type(&pool_type, &self.exchange)?;
+        let coins: Vec<crate::types::Coin> =
+            self.ptb_helper.fetch_coins_metadata(coin_types).await?;
+
+        // pool fields
+
+        let pool_fields = pool_data
+            .content
+            .ok_or_else(|| anyhow!("Missing object content"))?
+            .try_into_move()
+            .ok_or_else(|| anyhow!("Invalid move object"))?
+            .fields;
+
+        let pool_data =
+            serde_json::from_value::<KriyaPool>(pool_fields.to_json_value()).map_err(|e| {
+                error!("Failed to deserialize pool fields: {}", e);
+                e
+            })?;
+        info!("KriyaPool deserialized: {:?}", pool_data);
+
+        self.format_onchain_pool(&pool_data, coins)
+    }
+}
+
+impl KriyaService {
+    fn format_onchain_pool(
+        &self,
+        pool: &KriyaPool,
+        coins: Vec<crate::types::Coin>,
+    ) -> Result<crate::types::Pool> {
+        let coin_amounts = vec![pool.token_x.clone(), pool.token_y.clone()];
+        let fee_rate = Some(Self::fee_rate(pool)?);
+        let pool_type = if pool.is_stable {
+            "stable"
+        } else {
+            "uncorrelated"
+        };
+
+        Ok(crate::types::Pool {
+            exchange: self.exchange.clone(),
+            pool_id: pool.id.id.to_string(),
+            pool_type: Some(pool_type.to_string()),
+            coins,
+            coin_amounts: Some(coin_amounts),
+            weights: None,
+            tick_spacing: None,
+            current_tick_index: None,
+            current_sqrt_price: None,
+            liquidity: None,
+            fee_rate,
+            is_pause: Some(!pool.is_swap_enabled),
+            fees_swap_in: None,
+            fees_swap_out: None,
+        })
+    }
+
+    /// Total fee of the pool in millionths,
+    /// the spot_dex module charges both the LP and the protocol fee on the amount in.
+    ///
+    fn fee_rate(pool: &KriyaPool) -> Result<i32> {
+        let fee_percent = pool.lp_fee_percent + pool.protocol_fee_percent;
+        let fee_rate = fee_percent * constant::FEE_RATE_DENOMINATOR / constant::KRIYA_FEE_SCALING;
+
+        i32::try_from(fee_rate).map_err(|_| {
+            anyhow!(
+                "Kriya pool {} fee rate {} out of range",
+                pool.id.id,
+                fee_rate
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool(lp_fee_percent: &str, protocol_fee_percent: &str) -> KriyaPool {
+        serde_json::from_value(serde_json::json!({
+            "id": { "id": "0x5af4976b871fa1813362f352fa4cada3883a96191bb7212db1bd5d13685ae305" },
+            "token_x": "1000000000",
+            "token_y": "2000000",
+            "lp_fee_percent": lp_fee_percent,
+            "protocol_fee_percent": protocol_fee_percent,
+            "is_stable": false,
+            "is_swap_enabled": true,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn fee_rate_sums_lp_and_protocol_fees_in_millionths() {
+        // 0.25% LP fee and 0.05% protocol fee
+        let fee_rate = KriyaService::fee_rate(&pool("2500", "500")).unwrap();
+
+        assert_eq!(fee_rate, 3000);
+        assert_eq!(
+            fee_rate as f64 / constant::FEE_RATE_DENOMINATOR as f64,
+            0.003
+        );
+    }
+
+    #[test]
+    fn fee_rate_out_of_range_is_an_error() {
+        assert!(KriyaService::fee_rate(&pool("4294967296", "0")).is_err());
+    }
+}
@@ -2,7 +2,7 @@ use crate::{
     constant,
     service::dex::DEXService,
     types::ObjectIDWrapper,
-    utils::{self, ptb::PTBHelper, tick_math},
+    utils::{self, ptb::PTBHelper, rpc_pool::RpcPool, tick_math},
 };
 use db::repositories::{CoinRepository, PoolRepository};
 
@@ -13,10 +13,7 @@ use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use serde_with::{serde_as, DisplayFromStr};
 use std::{str::FromStr, sync::Arc};
-use sui_sdk::{
-    rpc_types::{Coin, SuiData, SuiObjectDataOptions},
-    SuiClient,
-};
+use sui_sdk::rpc_types::{Coin, SuiData, SuiObjectDataOptions};
 use sui_types::base_types::{ObjectID, SuiAddress};
 use tokio::time::{Duration, Instant};
 use tracing::{debug, error, info, instrument, trace, warn};
@@ -38,7 +35,7 @@ struct MomentumPool {
 
 pub struct MomentumService {
     exchange: String,
-    client: Arc<SuiClient>,
+    rpc_pool: Arc<RpcPool>,
     pool_repo: Arc<dyn PoolRepository + Send + Sync>,
     coin_repo: Arc<dyn CoinRepository + Send + Sync>,
     ptb_helper: Arc<PTBHelper>,
@@ -46,14 +43,14 @@ pub struct MomentumService {
 
 impl MomentumService {
     pub fn new(
-        client: Arc<SuiClient>,
+        rpc_pool: Arc<RpcPool>,
         pool_repo: Arc<dyn PoolRepository + Send + Sync>,
         coin_repo: Arc<dyn CoinRepository + Send + Sync>,
         ptb_helper: Arc<PTBHelper>,
     ) -> Self {
         MomentumService {
             exchange: constant::MOMENTUM_EXCHANGE.to_string(),
-            client,
+            rpc_pool,
             pool_repo,
             coin_repo,
             ptb_helper,
@@ -72,9 +69,16 @@ impl DEXService for MomentumService {
         let pool_id = ObjectID::from_str(pool_id)?;
 
         let pool_obj = self
-            .client
-            .read_api()
-            .get_object_with_options(pool_id, object_data_options)
+            .rpc_pool
+            .call(|client| {
+                let object_data_options = object_data_options.clone();
+                async move {
+                    client
+                        .read_api()
+                        .get_object_with_options(pool_id, object_data_options)
+                        .await
+                }
+            })
             .await?;
 
         let pool_data = pool_obj.data.ok_or(anyhow!(
@@ -41,6 +41,27 @@ pub trait LendingService {
         asset_id: Option<u8>,
     ) -> Result<crate::types::UserBorrow>;
 
+    /// Fetch the risk parameters of all lending markets of the platform.
+    ///
+    async fn fetch_lending_markets(&self) -> Result<Vec<crate::types::LendingMarket>> {
+        Err(anyhow!(
+            "Fetching lending markets is not supported for this platform"
+        ))
+    }
+
+    /// Compute the borrower health factor from the on-chain portfolio,
+    /// the lending market risk parameters and the latest coin prices.
+    ///
+    async fn compute_health_factor(
+        &self,
+        borrower: String,
+        obligation_id: Option<String>,
+    ) -> Result<crate::types::CalcHFResult> {
+        Err(anyhow!(
+            "Health factor computation is not supported for this platform"
+        ))
+    }
+
     async fn lookup_borrower_hf_onchain(&self, borrower: String) -> Result<()> {
         Err(anyhow!(
             "Health factor fetching is not supported for this platform"
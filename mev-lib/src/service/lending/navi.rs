@@ -3,7 +3,7 @@ use crate::{
     constant,
     service::{db_service, lending::LendingService},
     types::U256,
-    utils::{self, ptb::PTBHelper},
+    utils::{self, ptb::PTBHelper, rpc_pool::RpcPool},
 };
 use bigdecimal::BigDecimal;
 use db::repositories::CoinRepository;
@@ -18,7 +18,6 @@ use std::{
     collections::HashSet, fmt::Display, fs::File, io::Write, path::Path, str::FromStr, sync::Arc,
 };
 use sui_json_rpc_types::{SuiObjectDataOptions, SuiParsedData};
-use sui_sdk::SuiClient;
 use sui_types::{
     base_types::{ObjectID, SequenceNumber, SuiAddress},
     event::Event,
@@ -48,7 +47,7 @@ struct MarketConfig {
 pub struct NaviService {
     platform: String,
     config: Arc<NaviConfig>,
-    client: Arc<SuiClient>,
+    rpc_pool: Arc<RpcPool>,
     coin_repo: Arc<dyn CoinRepository + Send + Sync>,
     db_service: Arc<db_service::lending::LendingService>,
     ptb_helper: Arc<PTBHelper>,
@@ -57,7 +56,7 @@ pub struct NaviService {
 impl NaviService {
     pub fn new(
         config: Arc<NaviConfig>,
-        client: Arc<SuiClient>,
+        rpc_pool: Arc<RpcPool>,
         coin_repo: Arc<dyn CoinRepository + Send + Sync>,
         db_service: Arc<db_service::lending::LendingService>,
         ptb_helper: Arc<PTBHelper>,
@@ -65,7 +64,7 @@ impl NaviService {
         NaviService {
             platform: constant::NAVI_LENDING.to_string(),
             config,
-            client,
+            rpc_pool,
             coin_repo,
             db_service,
             ptb_helper,
@@ -110,9 +109,8 @@ impl LendingService for NaviService {
         let tx = TransactionKind::ProgrammableTransaction(builder);
 
         let response = self
-            .client
-            .read_api()
-            .dev_inspect_transaction_block(SuiAddress::default(), tx, None, None, None)
+            .ptb_helper
+            .dev_inspect(SuiAddress::default(), tx)
             .await?;
 
         let values = response.results.ok_or(anyhow!(
@@ -248,9 +246,8 @@ impl NaviService {
         let tx = TransactionKind::ProgrammableTransaction(builder);
 
         let response = self
-            .client
-            .read_api()
-            .dev_inspect_transaction_block(SuiAddress::default(), tx, None, None, None)
+            .ptb_helper
+            .dev_inspect(SuiAddress::default(), tx)
             .await?;
 
         let results = response.results.ok_or(anyhow!(
@@ -3,7 +3,7 @@ use crate::{
     constant,
     service::{db_service, lending::LendingService},
     types::{FixedPoint32, FixedPoint32Json, ObjectIDWrapper, TypeName},
-    utils::{self, ptb::PTBHelper},
+    utils::{self, ptb::PTBHelper, rpc_pool::RpcPool},
 };
 
 use anyhow::{anyhow, Result};
@@ -15,10 +15,7 @@ use serde::{Deserialize, Serialize};
 use serde_json::json;
 use serde_with::{serde_as, DisplayFromStr};
 use std::{collections::HashSet, path::Path, str::FromStr, sync::Arc};
-use sui_sdk::{
-    rpc_types::{Coin, SuiData, SuiMoveValue, SuiObjectData, SuiObjectDataOptions},
-    SuiClient,
-};
+use sui_sdk::rpc_types::{Coin, SuiData, SuiMoveValue, SuiObjectData, SuiObjectDataOptions};
 use sui_types::{
     base_types::{ObjectID, SequenceNumber, SuiAddress},
     dynamic_field::DynamicFieldName,
@@ -171,7 +168,7 @@ struct LiquidationFactor {
 pub struct ScallopService {
     platform: String,
     config: Arc<ScallopConfig>,
-    client: Arc<SuiClient>,
+    rpc_pool: Arc<RpcPool>,
     db_pool_service: Arc<db_service::pool::PoolService>,
     db_lending_service: Arc<db_service::lending::LendingService>,
     ptb_helper: Arc<PTBHelper>,
@@ -180,7 +177,7 @@ pub struct ScallopService {
 impl ScallopService {
     pub fn new(
         config: Arc<ScallopConfig>,
-        client: Arc<SuiClient>,
+        rpc_pool: Arc<RpcPool>,
         db_pool_service: Arc<db_service::pool::PoolService>,
         db_lending_service: Arc<db_service::lending::LendingService>,
         ptb_helper: Arc<PTBHelper>,
@@ -188,7 +185,7 @@ impl ScallopService {
         ScallopService {
             platform: constant::SCALLOP_LENDING.to_string(),
             config,
-            client,
+            rpc_pool,
             db_pool_service,
             db_lending_service,
             ptb_helper,
@@ -270,6 +267,58 @@ impl LendingService for ScallopService {
         })
     }
 
+    /// Fetches the collateral risk parameters from the Scallop API.
+    ///
+    async fn fetch_lending_markets(&self) -> Result<Vec<crate::types::LendingMarket>> {
+        let url = format!("{}/market", self.config.api_endpoint);
+
+        let response = reqwest::get(&url)
+            .await
+            .map_err(|e| anyhow!("Failed to fetch Scallop markets: {}", e))?
+            .json::<ApiCollateralResponse>()
+            .await
+            .map_err(|e| anyhow!("Failed to parse Scallop markets: {}", e))?;
+
+        response
+            .collaterals
+            .into_iter()
+            .map(|collateral| {
+                Ok(crate::types::LendingMarket {
+                    platform: self.platform.clone(),
+                    coin_type: utils::format_type_name(&collateral.coin_type, true),
+                    ltv: Decimal::from_f64(collateral.collateral_factor).ok_or_else(|| {
+                        anyhow!("Invalid collateral factor for {}", collateral.coin_type)
+                    })?,
+                    liquidation_threshold: Decimal::from_f64(collateral.liquidation_factor)
+                        .ok_or_else(|| {
+                            anyhow!("Invalid liquidation factor for {}", collateral.coin_type)
+                        })?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()
+    }
+
+    /// Computes the borrower health factor using the Scallop collateral risk parameters.
+    ///
+    async fn compute_health_factor(
+        &self,
+        borrower: String,
+        obligation_id: Option<String>,
+    ) -> Result<crate::types::CalcHFResult> {
+        let (user_deposits, user_borrows) = self
+            .fetch_borrower_portfolio(borrower.clone(), obligation_id)
+            .await?;
+        let markets = self.fetch_lending_markets().await?;
+
+        let result =
+            self.db_lending_service
+                .calc_health_factor(&markets, &user_deposits, &user_borrows)?;
+
+        info!("Health factor of borrower {}: {:?}", borrower, result);
+
+        Ok(result)
+    }
+
     async fn find_obligation_id_from_address(&self, borrower: &str) -> Result<String> {
         let obligation_keys = self
             .ptb_helper
@@ -340,7 +389,7 @@ impl ScallopService {
                         .await
                 }
             })
-            .buffered(10)
+            .buffered(self.config.asset_fetch_concurrency)
             .collect::<Vec<_>>()
             .await
             .into_iter()
@@ -355,7 +404,7 @@ impl ScallopService {
                         .await
                 }
             })
-            .buffered(10)
+            .buffered(self.config.asset_fetch_concurrency)
             .collect::<Vec<_>>()
             .await
             .into_iter()
@@ -421,9 +470,8 @@ impl ScallopService {
         let builder = ptb.finish();
         let tx = TransactionKind::ProgrammableTransaction(builder);
         let response = self
-            .client
-            .read_api()
-            .dev_inspect_transaction_block(SuiAddress::default(), tx, None, None, None)
+            .ptb_helper
+            .dev_inspect(SuiAddress::default(), tx)
             .await?;
         let results = response.results.ok_or(anyhow!(
             "Failed to get return values from dev_inspect_transaction_block"
@@ -488,9 +536,8 @@ impl ScallopService {
         let builder = ptb.finish();
         let tx = TransactionKind::ProgrammableTransaction(builder);
         let response = self
-            .client
-            .read_api()
-            .dev_inspect_transaction_block(SuiAddress::default(), tx, None, None, None)
+            .ptb_helper
+            .dev_inspect(SuiAddress::default(), tx)
             .await?;
         let results = response.results.ok_or(anyhow!(
             "Failed to get return values from dev_inspect_transaction_block"
@@ -3,7 +3,7 @@ use crate::{
     constant, indexer,
     service::{db_service, lending::LendingService},
     types::{ObjectIDWrapper, OnchainDecimal, PythPriceIdentifier, TypeName},
-    utils::{self, ptb::PTBHelper},
+    utils::{self, ptb::PTBHelper, rpc_pool::RpcPool},
 };
 use db::models;
 
@@ -15,7 +15,6 @@ use serde::{Deserialize, Serialize};
 use serde_with::{serde_as, DisplayFromStr};
 use std::{path::Path, str::FromStr, sync::Arc};
 use sui_sdk::rpc_types::{Coin, SuiData, SuiMoveValue, SuiObjectDataOptions};
-use sui_sdk::SuiClient;
 use sui_types::base_types::{ObjectID, SuiAddress};
 use tokio::time::{Duration, Instant};
 use tracing::{debug, error, info, instrument, trace, warn};
@@ -102,7 +101,7 @@ pub struct ObligationOwnerCap {
 pub struct SuilendService {
     platform: String,
     config: Arc<SuilendConfig>,
-    client: Arc<SuiClient>,
+    rpc_pool: Arc<RpcPool>,
     db_lending_service: Arc<db_service::lending::LendingService>,
     ptb_helper: Arc<PTBHelper>,
 }
@@ -110,14 +109,14 @@ pub struct SuilendService {
 impl SuilendService {
     pub fn new(
         config: Arc<SuilendConfig>,
-        client: Arc<SuiClient>,
+        rpc_pool: Arc<RpcPool>,
         db_lending_service: Arc<db_service::lending::LendingService>,
         ptb_helper: Arc<PTBHelper>,
     ) -> Self {
         SuilendService {
             platform: constant::SUILEND_LENDING.to_string(),
             config,
-            client,
+            rpc_pool,
             db_lending_service,
             ptb_helper,
         }
@@ -311,9 +310,13 @@ impl SuilendService {
             .map_err(|e| anyhow!("Invalid obligation ID: {}", e))?;
 
         let obligation_data_resp = self
-            .client
-            .read_api()
-            .get_object_with_options(obligation_id, SuiObjectDataOptions::full_content())
+            .rpc_pool
+            .call(|client| async move {
+                client
+                    .read_api()
+                    .get_object_with_options(obligation_id, SuiObjectDataOptions::full_content())
+                    .await
+            })
             .await?;
 
         let obligation_data = obligation_data_resp.data.ok_or_else(|| {
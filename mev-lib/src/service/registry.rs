@@ -2,7 +2,7 @@ use crate::{
     config::Config,
     constant,
     service::{db_service, dex, lending},
-    utils::ptb::PTBHelper,
+    utils::{ptb::PTBHelper, rpc_pool::RpcPool},
 };
 use db::{
     models,
@@ -14,7 +14,6 @@ use async_trait::async_trait;
 use rust_decimal::{prelude::*, Decimal};
 use serde::{Deserialize, Serialize};
 use std::{collections::HashMap, fs::File, io::Write, path::Path, sync::Arc};
-use sui_sdk::SuiClient;
 use sui_types::coin;
 use tokio::time::{Duration, Instant};
 use toml;
@@ -46,7 +45,7 @@ pub struct ServiceRegistry {
 impl ServiceRegistry {
     pub fn new(
         config: Arc<Config>,
-        client: Arc<SuiClient>,
+        rpc_pool: Arc<RpcPool>,
         coin_repo: Arc<dyn CoinRepository + Send + Sync>,
         pool_repo: Arc<dyn PoolRepository + Send + Sync>,
         db_pool_service: Arc<db_service::pool::PoolService>,
@@ -58,7 +57,7 @@ impl ServiceRegistry {
 
         // Initialize DEX services
         let cetus_service = Arc::new(dex::cetus::CetusService::new(
-            Arc::clone(&client),
+            Arc::clone(&rpc_pool),
             Arc::clone(&pool_repo),
             Arc::clone(&coin_repo),
             Arc::clone(&ptb_helper),
@@ -70,7 +69,7 @@ impl ServiceRegistry {
         );
 
         let aftermath_service = Arc::new(dex::aftermath::AftermathService::new(
-            Arc::clone(&client),
+            Arc::clone(&rpc_pool),
             Arc::clone(&pool_repo),
             Arc::clone(&coin_repo),
             Arc::clone(&ptb_helper),
@@ -82,7 +81,7 @@ impl ServiceRegistry {
         );
 
         let momentum_service = Arc::new(dex::momentum::MomentumService::new(
-            Arc::clone(&client),
+            Arc::clone(&rpc_pool),
             Arc::clone(&pool_repo),
             Arc::clone(&coin_repo),
             Arc::clone(&ptb_helper),
@@ -94,7 +93,7 @@ impl ServiceRegistry {
         );
 
         let obric_service = Arc::new(dex::obric::ObricService::new(
-            Arc::clone(&client),
+            Arc::clone(&rpc_pool),
             Arc::clone(&pool_repo),
             Arc::clone(&coin_repo),
             Arc::clone(&ptb_helper),
@@ -106,7 +105,7 @@ impl ServiceRegistry {
         );
 
         let bluefin_service = Arc::new(dex::bluefin::BluefinService::new(
-            Arc::clone(&client),
+            Arc::clone(&rpc_pool),
             Arc::clone(&pool_repo),
             Arc::clone(&coin_repo),
             Arc::clone(&ptb_helper),
@@ -118,7 +117,7 @@ impl ServiceRegistry {
         );
 
         let bluemove_service = Arc::new(dex::bluemove::BluemoveService::new(
-            Arc::clone(&client),
+            Arc::clone(&rpc_pool),
             Arc::clone(&pool_repo),
             Arc::clone(&coin_repo),
             Arc::clone(&ptb_helper),
@@ -130,7 +129,7 @@ impl ServiceRegistry {
         );
 
         let turbos_service = Arc::new(dex::turbos::TurbosService::new(
-            Arc::clone(&client),
+            Arc::clone(&rpc_pool),
             Arc::clone(&pool_repo),
             Arc::clone(&coin_repo),
             Arc::clone(&ptb_helper),
@@ -142,7 +141,7 @@ impl ServiceRegistry {
         );
 
         let flowx_service = Arc::new(dex::flowx::FlowXService::new(
-            Arc::clone(&client),
+            Arc::clone(&rpc_pool),
             Arc::clone(&pool_repo),
             Arc::clone(&coin_repo),
             Arc::clone(&ptb_helper),
@@ -153,6 +152,31 @@ impl ServiceRegistry {
             Arc::clone(&flowx_service) as Arc<dyn dex::DEXService + Send + Sync>,
         );
 
+        let kriya_service = Arc::new(dex::kriya::KriyaService::new(
+            Arc::clone(&rpc_pool),
+            Arc::clone(&pool_repo),
+            Arc::clone(&coin_repo),
+            Arc::clone(&ptb_helper),
+        ));
+
+        dexes.insert(
+            constant::KRIYA_EXCHANGE.to_string(),
+            Arc::clone(&kriya_service) as Arc<dyn dex::DEXService + Send + Sync>,
+        );
+
+        // Retry fetching pool data on transient RPC errors
+        let dexes = dexes
+            .into_iter()
+            .map(|(name, service)| {
+                let service = Arc::new(dex::RetryDEXService::new(
+                    service,
+                    config.indexer.rpc_max_retries,
+                    Duration::from_millis(constant::RPC_RETRY_BASE_DELAY_MS),
+                )) as Arc<dyn dex::DEXService + Send + Sync>;
+                (name, service)
+            })
+            .collect::<HashMap<_, _>>();
+
         // Initialize Lending services
         let navi_config = Arc::new(config.navi.clone());
         let suilend_config = Arc::new(config.suilend.clone());
@@ -160,7 +184,7 @@ impl ServiceRegistry {
 
         let navi_service = Arc::new(lending::navi::NaviService::new(
             Arc::clone(&navi_config),
-            Arc::clone(&client),
+            Arc::clone(&rpc_pool),
             Arc::clone(&coin_repo),
             Arc::clone(&db_lending_service),
             Arc::clone(&ptb_helper),
@@ -173,7 +197,7 @@ impl ServiceRegistry {
 
         let suilend_service = Arc::new(lending::suilend::SuilendService::new(
             Arc::clone(&suilend_config),
-            Arc::clone(&client),
+            Arc::clone(&rpc_pool),
             Arc::clone(&db_lending_service),
             Arc::clone(&ptb_helper),
         ));
@@ -185,7 +209,7 @@ impl ServiceRegistry {
 
         let scallop_service = Arc::new(lending::scallop::ScallopService::new(
             Arc::clone(&scallop_config),
-            Arc::clone(&client),
+            Arc::clone(&rpc_pool),
             Arc::clone(&db_pool_service),
             Arc::clone(&db_lending_service),
             Arc::clone(&ptb_helper),
@@ -224,4 +248,16 @@ impl ServiceRegistry {
             .cloned()
             .ok_or_else(|| anyhow!("Lending service not found: {}", name))
     }
+
+    /// Rebuilds the borrower portfolio from the on-chain data of its platform.
+    ///
+    pub async fn resync_borrower(&self, platform: &str, borrower: &str) -> Result<()> {
+        let lending_service = self.get_lending_service(platform)?;
+
+        self.db_lending_service
+            .resync_borrower(lending_service.as_ref(), platform, borrower)
+            .await?;
+
+        Ok(())
+    }
 }
@@ -3,7 +3,7 @@ use db::{
     repositories::MetricRepository,
 };
 
-use rust_decimal::Decimal;
+use rust_decimal::{prelude::*, Decimal};
 use serde::{Deserialize, Serialize};
 use serde_with::{serde_as, DisplayFromStr};
 use std::fmt::{self, Display, Formatter};
@@ -40,6 +40,87 @@ pub struct Pool {
     pub fees_swap_out: Option<Vec<String>>,
 }
 
+/// A pool field whose stored value differs from the onchain one.
+/// `delta` is onchain minus stored, when both values are numbers.
+///
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PoolFieldDiff {
+    pub field: String,
+    pub stored: Option<String>,
+    pub onchain: Option<String>,
+    pub delta: Option<Decimal>,
+}
+
+/// Drift between a stored pool and its onchain state,
+/// only the fields that differ are listed.
+///
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PoolDiff {
+    pub pool_id: String,
+    pub fields: Vec<PoolFieldDiff>,
+}
+
+impl PoolDiff {
+    /// Compares liquidity, sqrt_price, tick_index and fee_rate
+    /// of the stored pool against the onchain pool.
+    ///
+    pub fn compare(stored: &models::pool::Pool, onchain: &Pool) -> Self {
+        let candidates = [
+            (
+                "liquidity",
+                stored.liquidity.clone(),
+                onchain.liquidity.clone(),
+            ),
+            (
+                "sqrt_price",
+                stored.current_sqrt_price.clone(),
+                onchain.current_sqrt_price.clone(),
+            ),
+            (
+                "tick_index",
+                stored.current_tick_index.map(|t| t.to_string()),
+                onchain.current_tick_index.map(|t| t.to_string()),
+            ),
+            (
+                "fee_rate",
+                stored.fee_rate.map(|f| f.to_string()),
+                onchain.fee_rate.map(|f| f.to_string()),
+            ),
+        ];
+
+        let fields = candidates
+            .into_iter()
+            .filter(|(_, stored, onchain)| stored != onchain)
+            .map(|(field, stored, onchain)| {
+                // values too large for a Decimal, e.g. some sqrt prices, have no delta
+                let delta = match (stored.as_deref(), onchain.as_deref()) {
+                    (Some(s), Some(o)) => match (Decimal::from_str(s), Decimal::from_str(o)) {
+                        (Ok(s), Ok(o)) => o.checked_sub(s),
+                        _ => None,
+                    },
+                    _ => None,
+                };
+
+                PoolFieldDiff {
+                    field: field.to_string(),
+                    stored,
+                    onchain,
+                    delta,
+                }
+            })
+            .collect();
+
+        PoolDiff {
+            pool_id: onchain.pool_id.clone(),
+            fields,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FlashloanPool {
     pub exchange: String,
@@ -52,10 +133,41 @@ pub struct PythPrice {
     pub spot_price: String,
     pub ema_price: String,
     pub decimals: u8,
+    // publish time in seconds, as reported by Pyth on-chain and by Hermes
     pub latest_updated_timestamp: u64,
     pub vaa: Option<String>,
 }
 
+impl PythPrice {
+    /// Whether the price was published at most `max_age_secs` before `now_secs`.
+    ///
+    pub fn is_fresh(&self, max_age_secs: u64, now_secs: u64) -> bool {
+        Self::is_timestamp_fresh(self.latest_updated_timestamp, max_age_secs, now_secs)
+    }
+
+    /// Same as `is_fresh` given the publish time in seconds,
+    /// publish times in the future count as fresh.
+    ///
+    pub fn is_timestamp_fresh(publish_time_secs: u64, max_age_secs: u64, now_secs: u64) -> bool {
+        now_secs.saturating_sub(publish_time_secs) <= max_age_secs
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SupraPrice {
+    pub pair_id: u32,
+    pub price: String,
+    pub decimals: u16,
+    pub latest_updated_timestamp_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SwitchboardPrice {
+    pub aggregator_id: String,
+    pub price: String,
+    pub latest_updated_timestamp_ms: u64,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Borrower {
     pub platform: String,
@@ -64,6 +176,69 @@ pub struct Borrower {
     pub status: i32,
 }
 
+/// Status of a borrower, stored as its `constant::*_STATUS` value.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BorrowerStatus {
+    Pending,
+    Ready,
+    Liquidated,
+    Failed,
+    Abnormal,
+}
+
+impl BorrowerStatus {
+    /// Whether a borrower in this status can be moved to `next`.
+    /// Keeping the same status is always allowed,
+    /// a liquidated borrower can only become ready again, after a resync.
+    ///
+    pub fn can_transition_to(&self, next: BorrowerStatus) -> bool {
+        use BorrowerStatus::*;
+
+        match (self, next) {
+            (current, next) if *current == next => true,
+            (Pending, Ready | Failed | Abnormal) => true,
+            (Ready, Pending | Liquidated | Failed | Abnormal) => true,
+            (Liquidated, Ready) => true,
+            (Failed | Abnormal, Pending | Ready) => true,
+            _ => false,
+        }
+    }
+}
+
+impl TryFrom<i32> for BorrowerStatus {
+    type Error = anyhow::Error;
+
+    fn try_from(status: i32) -> Result<Self, Self::Error> {
+        match status {
+            crate::constant::PENDING_STATUS => Ok(BorrowerStatus::Pending),
+            crate::constant::READY_STATUS => Ok(BorrowerStatus::Ready),
+            crate::constant::SUCCEED_STATUS => Ok(BorrowerStatus::Liquidated),
+            crate::constant::FAILED_STATUS => Ok(BorrowerStatus::Failed),
+            crate::constant::ABNORMAL_STATUS => Ok(BorrowerStatus::Abnormal),
+            _ => Err(anyhow::anyhow!("Unknown borrower status: {}", status)),
+        }
+    }
+}
+
+impl From<BorrowerStatus> for i32 {
+    fn from(status: BorrowerStatus) -> Self {
+        match status {
+            BorrowerStatus::Pending => crate::constant::PENDING_STATUS,
+            BorrowerStatus::Ready => crate::constant::READY_STATUS,
+            BorrowerStatus::Liquidated => crate::constant::SUCCEED_STATUS,
+            BorrowerStatus::Failed => crate::constant::FAILED_STATUS,
+            BorrowerStatus::Abnormal => crate::constant::ABNORMAL_STATUS,
+        }
+    }
+}
+
+impl Display for BorrowerStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BorrowerAsset {
     pub coin_type: String,
@@ -106,6 +281,66 @@ pub struct UserDeposit {
     pub amount: String,
 }
 
+/// Risk parameters of a lending market for a single coin.
+///
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LendingMarket {
+    pub platform: String,
+    pub coin_type: String,
+    pub ltv: Decimal,
+    pub liquidation_threshold: Decimal,
+}
+
+/// Health factor of a borrower with the USD values it is derived from.
+/// The health factor is `Decimal::MAX` when the borrower has no debt.
+///
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CalcHFResult {
+    pub health_factor: Decimal,
+    pub total_collateral_usd: Decimal,
+    pub total_debt_usd: Decimal,
+}
+
+/// A deposit or borrow of an exported borrower portfolio, with its coin info.
+/// `usd_value` is `None` when the coin has no price yet.
+///
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PortfolioPosition {
+    pub coin_type: String,
+    pub amount: String,
+    pub decimals: i32,
+    pub price_pyth: Option<String>,
+    pub pyth_decimals: Option<i32>,
+    pub usd_value: Option<Decimal>,
+}
+
+/// Full portfolio of a borrower as stored in the database, e.g. for support tickets.
+/// Totals only count the positions with a USD value.
+///
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BorrowerPortfolio {
+    pub platform: String,
+    pub borrower: String,
+    pub obligation_id: Option<String>,
+    pub status: i32,
+    pub deposits: Vec<PortfolioPosition>,
+    pub borrows: Vec<PortfolioPosition>,
+    pub total_collateral_usd: Decimal,
+    pub total_debt_usd: Decimal,
+}
+
+/// Estimated result of a CLMM swap simulated from the stored tick data.
+/// `amount_in` is the input actually consumed, fee included,
+/// which is less than the requested amount when the known ticks run out.
+///
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SwapQuote {
+    pub amount_in: Decimal,
+    pub amount_out: Decimal,
+    pub fee_amount: Decimal,
+    pub end_sqrt_price: Decimal,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Metric {
     pub latest_seq_number: i32,
@@ -135,6 +370,22 @@ impl From<Metric> for db::models::metric::NewMetric {
     }
 }
 
+impl From<db::models::metric::Metric> for Metric {
+    fn from(metric: db::models::metric::Metric) -> Self {
+        Metric {
+            latest_seq_number: metric.latest_seq_number,
+            total_checkpoints: metric.total_checkpoints,
+            total_processed_checkpoints: metric.total_processed_checkpoints,
+            max_processing_time: metric.max_processing_time,
+            min_processing_time: metric.min_processing_time,
+            avg_processing_time: metric.avg_processing_time,
+            max_lagging: metric.max_lagging,
+            min_lagging: metric.min_lagging,
+            avg_lagging: metric.avg_lagging,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct PythPriceIdentifier {
     pub bytes: Vec<u8>,
@@ -168,6 +419,87 @@ impl FromStr for U256 {
     }
 }
 
+/// Amount in the smallest unit of a coin, e.g. MIST for SUI.
+/// Used where on-chain amounts are expected, so a human readable amount can't be passed by mistake.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct MistAmount(pub u64);
+
+impl MistAmount {
+    pub fn value(&self) -> u64 {
+        self.0
+    }
+}
+
+impl From<u64> for MistAmount {
+    fn from(value: u64) -> Self {
+        MistAmount(value)
+    }
+}
+
+impl Display for MistAmount {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Amount of a coin in its smallest unit with the decimals of the coin,
+/// the human readable amount being `mist / 10^decimals`.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CoinAmount {
+    pub mist: u64,
+    pub decimals: u8,
+}
+
+impl CoinAmount {
+    pub fn new(mist: u64, decimals: u8) -> Self {
+        CoinAmount { mist, decimals }
+    }
+
+    /// Human readable amount, exact as long as the decimals fit the Decimal scale.
+    ///
+    pub fn to_decimal(&self) -> anyhow::Result<Decimal> {
+        Decimal::try_from_i128_with_scale(self.mist as i128, self.decimals as u32)
+            .map_err(|e| anyhow::anyhow!("Failed to convert {} to Decimal: {}", self.mist, e))
+    }
+
+    /// Converts a human readable amount, digits beyond the coin decimals are truncated.
+    ///
+    pub fn from_decimal(amount: Decimal, decimals: u8) -> anyhow::Result<Self> {
+        if amount.is_sign_negative() {
+            return Err(anyhow::anyhow!("Negative coin amount: {}", amount));
+        }
+
+        let mist = Decimal::from(10)
+            .checked_powu(decimals as u64)
+            .and_then(|scale| amount.checked_mul(scale))
+            .and_then(|mist| mist.trunc().to_u64())
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Coin amount {} with {} decimals out of range",
+                    amount,
+                    decimals
+                )
+            })?;
+
+        Ok(CoinAmount { mist, decimals })
+    }
+
+    pub fn mist_amount(&self) -> MistAmount {
+        MistAmount(self.mist)
+    }
+}
+
+impl Display for CoinAmount {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.to_decimal() {
+            Ok(amount) => write!(f, "{}", amount),
+            Err(_) => write!(f, "{} (1e-{})", self.mist, self.decimals),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct TypeName {
     pub name: String,
@@ -234,3 +566,322 @@ impl I128 {
 pub struct ObjectIDWrapper {
     pub id: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn borrower_status_round_trips_through_its_stored_value() {
+        use BorrowerStatus::*;
+
+        for status in [Pending, Ready, Liquidated, Failed, Abnormal] {
+            assert_eq!(BorrowerStatus::try_from(i32::from(status)).unwrap(), status);
+        }
+        assert_eq!(i32::from(Liquidated), crate::constant::SUCCEED_STATUS);
+        assert!(BorrowerStatus::try_from(-1).is_err());
+    }
+
+    #[test]
+    fn borrower_status_valid_transitions() {
+        use BorrowerStatus::*;
+
+        for (current, next) in [
+            (Pending, Pending),
+            (Pending, Ready),
+            (Ready, Liquidated),
+            (Ready, Pending),
+            (Liquidated, Ready),
+            (Liquidated, Liquidated),
+            (Failed, Ready),
+            (Abnormal, Pending),
+        ] {
+            assert!(current.can_transition_to(next), "{} -> {}", current, next);
+        }
+    }
+
+    #[test]
+    fn borrower_status_rejected_transitions() {
+        use BorrowerStatus::*;
+
+        for (current, next) in [
+            (Pending, Liquidated),
+            (Liquidated, Pending),
+            (Liquidated, Failed),
+            (Liquidated, Abnormal),
+            (Failed, Liquidated),
+            (Abnormal, Liquidated),
+        ] {
+            assert!(!current.can_transition_to(next), "{} -> {}", current, next);
+        }
+    }
+
+    #[test]
+    fn coin_amount_to_decimal_at_various_decimals() {
+        let amount = |mist, decimals| CoinAmount::new(mist, decimals).to_decimal().unwrap();
+
+        assert_eq!(amount(1_500_000_000, 9), Decimal::new(15, 1));
+        assert_eq!(amount(1_500_000, 6), Decimal::new(15, 1));
+        assert_eq!(amount(15, 0), Decimal::from(15));
+        assert_eq!(amount(1, 18), Decimal::new(1, 18));
+        assert_eq!(
+            amount(u64::MAX, 18),
+            Decimal::from_i128_with_scale(u64::MAX as i128, 18)
+        );
+        assert!(CoinAmount::new(1, 29).to_decimal().is_err());
+    }
+
+    #[test]
+    fn coin_amount_from_decimal_truncates_extra_digits() {
+        let mist = |amount: &str, decimals| {
+            CoinAmount::from_decimal(Decimal::from_str(amount).unwrap(), decimals)
+                .unwrap()
+                .mist
+        };
+
+        assert_eq!(mist("1.5", 9), 1_500_000_000);
+        assert_eq!(mist("1.5", 6), 1_500_000);
+        assert_eq!(mist("1.9999999", 6), 1_999_999);
+        assert_eq!(mist("0.0000009", 6), 0);
+        assert_eq!(mist("1.9", 0), 1);
+        assert_eq!(mist("0.000000000000000001", 18), 1);
+    }
+
+    #[test]
+    fn coin_amount_from_decimal_rejects_invalid_amounts() {
+        assert!(CoinAmount::from_decimal(Decimal::new(-1, 0), 9).is_err());
+        // beyond u64::MAX in the smallest unit
+        assert!(CoinAmount::from_decimal(Decimal::from(20_000_000_000u64), 9).is_err());
+    }
+
+    #[test]
+    fn coin_amount_round_trips_through_decimal() {
+        for (mist, decimals) in [(0, 9), (1, 9), (123_456_789, 6), (u64::MAX, 9), (42, 18)] {
+            let amount = CoinAmount::new(mist, decimals);
+
+            assert_eq!(
+                CoinAmount::from_decimal(amount.to_decimal().unwrap(), decimals).unwrap(),
+                amount
+            );
+        }
+    }
+
+    #[test]
+    fn amounts_display() {
+        assert_eq!(MistAmount(1_500_000_000).to_string(), "1500000000");
+        assert_eq!(CoinAmount::new(1_500_000_000, 9).to_string(), "1.500000000");
+        assert_eq!(CoinAmount::new(1, 29).to_string(), "1 (1e-29)");
+        assert_eq!(
+            CoinAmount::new(1_500_000_000, 9).mist_amount(),
+            MistAmount(1_500_000_000)
+        );
+    }
+
+    #[test]
+    fn metric_row_serializes_to_json() {
+        let row = models::metric::Metric {
+            id: 12,
+            latest_seq_number: 150_000_000,
+            total_checkpoints: 2_000,
+            total_processed_checkpoints: 1_800,
+            max_processing_time: 950.0,
+            min_processing_time: 12.0,
+            avg_processing_time: 180.5,
+            max_lagging: 4_000.0,
+            min_lagging: 250.0,
+            avg_lagging: 600.25,
+            created_at: None,
+            updated_at: None,
+        };
+
+        let json = serde_json::to_value(Metric::from(row)).unwrap();
+
+        // the row ID and timestamps are not part of the export
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "latest_seq_number": 150_000_000,
+                "total_checkpoints": 2_000,
+                "total_processed_checkpoints": 1_800,
+                "max_processing_time": 950.0,
+                "min_processing_time": 12.0,
+                "avg_processing_time": 180.5,
+                "max_lagging": 4_000.0,
+                "min_lagging": 250.0,
+                "avg_lagging": 600.25,
+            })
+        );
+    }
+
+    #[test]
+    fn borrower_portfolio_serializes_to_json() {
+        let position =
+            |coin_type: &str, amount: &str, usd_value: Option<Decimal>| PortfolioPosition {
+                coin_type: coin_type.to_string(),
+                amount: amount.to_string(),
+                decimals: 9,
+                price_pyth: usd_value.map(|_| "350000000".to_string()),
+                pyth_decimals: usd_value.map(|_| 8),
+                usd_value,
+            };
+        let portfolio = BorrowerPortfolio {
+            platform: "navi".to_string(),
+            borrower: "0x1".to_string(),
+            obligation_id: None,
+            status: 1,
+            deposits: vec![
+                position("0x2::sui::SUI", "2000000000", Some(Decimal::from(7))),
+                position("0xunpriced", "1000000000", None),
+            ],
+            borrows: vec![position(
+                "0x2::sui::SUI",
+                "1000000000",
+                Some(Decimal::new(35, 1)),
+            )],
+            total_collateral_usd: Decimal::from(7),
+            total_debt_usd: Decimal::new(35, 1),
+        };
+
+        let json = serde_json::to_value(&portfolio).unwrap();
+
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "platform": "navi",
+                "borrower": "0x1",
+                "obligation_id": null,
+                "status": 1,
+                "deposits": [
+                    {
+                        "coin_type": "0x2::sui::SUI",
+                        "amount": "2000000000",
+                        "decimals": 9,
+                        "price_pyth": "350000000",
+                        "pyth_decimals": 8,
+                        "usd_value": "7",
+                    },
+                    {
+                        "coin_type": "0xunpriced",
+                        "amount": "1000000000",
+                        "decimals": 9,
+                        "price_pyth": null,
+                        "pyth_decimals": null,
+                        "usd_value": null,
+                    },
+                ],
+                "borrows": [
+                    {
+                        "coin_type": "0x2::sui::SUI",
+                        "amount": "1000000000",
+                        "decimals": 9,
+                        "price_pyth": "350000000",
+                        "pyth_decimals": 8,
+                        "usd_value": "3.5",
+                    },
+                ],
+                "total_collateral_usd": "7",
+                "total_debt_usd": "3.5",
+            })
+        );
+    }
+
+    fn stored_pool() -> models::pool::Pool {
+        models::pool::Pool {
+            id: 1,
+            exchange: "cetus".to_string(),
+            address: "0xpool".to_string(),
+            liquidity: Some("1000".to_string()),
+            current_sqrt_price: Some("18446744073709551616".to_string()),
+            tick_spacing: Some(60),
+            fee_rate: Some(2_500),
+            is_pause: Some(false),
+            created_at: None,
+            updated_at: None,
+            coins: "0x2::sui::SUI,0xusdc".to_string(),
+            coin_amounts: None,
+            weights: None,
+            fees_swap_in: None,
+            fees_swap_out: None,
+            current_tick_index: Some(-10),
+            pool_type: None,
+        }
+    }
+
+    /// Onchain state equal to `stored_pool`.
+    fn onchain_pool() -> Pool {
+        Pool {
+            exchange: "cetus".to_string(),
+            pool_id: "0xpool".to_string(),
+            pool_type: None,
+            coins: vec![],
+            coin_amounts: None,
+            weights: None,
+            tick_spacing: Some(60),
+            current_tick_index: Some(-10),
+            current_sqrt_price: Some("18446744073709551616".to_string()),
+            liquidity: Some("1000".to_string()),
+            fee_rate: Some(2_500),
+            is_pause: Some(false),
+            fees_swap_in: None,
+            fees_swap_out: None,
+        }
+    }
+
+    #[test]
+    fn pool_diff_of_a_pool_in_sync_is_empty() {
+        let diff = PoolDiff::compare(&stored_pool(), &onchain_pool());
+
+        assert_eq!(diff.pool_id, "0xpool");
+        assert!(diff.is_empty());
+    }
+
+    #[test]
+    fn pool_diff_lists_the_drifted_fields_with_their_delta() {
+        let onchain = Pool {
+            liquidity: Some("1500".to_string()),
+            current_tick_index: Some(-25),
+            ..onchain_pool()
+        };
+
+        let diff = PoolDiff::compare(&stored_pool(), &onchain);
+
+        let fields = diff
+            .fields
+            .iter()
+            .map(|f| (f.field.as_str(), f.delta))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            fields,
+            vec![
+                ("liquidity", Some(Decimal::from(500))),
+                ("tick_index", Some(Decimal::from(-15))),
+            ]
+        );
+        assert_eq!(diff.fields[0].stored.as_deref(), Some("1000"));
+        assert_eq!(diff.fields[0].onchain.as_deref(), Some("1500"));
+    }
+
+    #[test]
+    fn pool_diff_has_no_delta_without_two_numbers() {
+        let stored = models::pool::Pool {
+            fee_rate: None,
+            ..stored_pool()
+        };
+        let onchain = Pool {
+            // beyond the 96 bits of a Decimal
+            current_sqrt_price: Some("79228162514264337593543950336000".to_string()),
+            ..onchain_pool()
+        };
+
+        let diff = PoolDiff::compare(&stored, &onchain);
+
+        let fields = diff
+            .fields
+            .iter()
+            .map(|f| (f.field.as_str(), f.delta))
+            .collect::<Vec<_>>();
+        assert_eq!(fields, vec![("sqrt_price", None), ("fee_rate", None)]);
+        assert_eq!(diff.fields[1].stored, None);
+        assert_eq!(diff.fields[1].onchain.as_deref(), Some("2500"));
+    }
+}
@@ -1,7 +1,11 @@
 pub mod ptb;
+pub mod rate_limiter;
+pub mod rpc_pool;
+pub mod sui_client;
 pub mod tick_math;
+pub mod ttl_cache;
 
-use crate::constant;
+use crate::{constant, utils::rpc_pool::RpcPool};
 use db::repositories::{CoinRepository, PoolRepository};
 
 use anyhow::{anyhow, Result};
@@ -10,7 +14,7 @@ use bigdecimal::BigDecimal;
 use chrono::{DateTime, NaiveDateTime, Utc};
 use futures::stream::{self, StreamExt};
 use regex::Regex;
-use rust_decimal::Decimal;
+use rust_decimal::{prelude::ToPrimitive, Decimal, MathematicalOps, RoundingStrategy};
 use serde::{Deserialize, Serialize};
 use std::{
     collections::HashMap,
@@ -19,19 +23,18 @@ use std::{
     sync::Arc,
     time::{SystemTime, UNIX_EPOCH},
 };
-use sui_sdk::{
-    rpc_types::{Coin, SuiData, SuiObjectDataOptions},
-    SuiClient,
-};
+use sui_sdk::rpc_types::{Coin, SuiData, SuiObjectDataOptions};
 use sui_types::{
     base_types::{ObjectID, SuiAddress},
     crypto::{EncodeDecodeBase64, SuiKeyPair},
+    digests::TransactionDigest,
     event::Event,
     sui_system_state::sui_system_state_inner_v1::SystemParametersV1,
     transaction::{Argument, ObjectArg},
 };
 use tokio::time::{Duration, Instant};
-use tracing::{debug, error, info, trace, warn, Level};
+use tracing::{debug, error, info, trace, warn, Level, Subscriber};
+use tracing_subscriber::{fmt, layer::SubscriberExt, EnvFilter};
 use twox_hash::XxHash64;
 
 pub fn load_keypair_from_priv_key(priv_key: &str) -> Result<SuiKeyPair> {
@@ -78,7 +81,7 @@ pub fn get_coin_types_from_pool_type(pool_type: &str, exchange: &str) -> Result<
     }
 
     match exchange {
-        "cetus" | "obric" | "bluefin" | "momentum" | "flowx" | "bluemove" => Ok(coins),
+        "cetus" | "obric" | "bluefin" | "momentum" | "flowx" | "bluemove" | "kriya" => Ok(coins),
         "turbos" => Ok(vec![coins[0].clone(), coins[1].clone()]),
         _ => Err(anyhow!("Upsupported exchange {}", exchange)),
     }
@@ -143,15 +146,54 @@ pub fn convert_log_level_to_tracing_level(log_level: &str) -> Level {
     }
 }
 
+/// Build the tracing subscriber of the binaries,
+/// logging as JSON lines if `log_format` is "json", as plain text otherwise.
+/// `RUST_LOG` directives still apply on top of `log_level`.
+///
+pub fn build_subscriber(log_level: &str, log_format: &str) -> Box<dyn Subscriber + Send + Sync> {
+    let log_level = convert_log_level_to_tracing_level(log_level);
+    let filter = EnvFilter::from_default_env().add_directive(log_level.into());
+
+    match log_format.to_lowercase().as_str() {
+        "json" => Box::new(
+            tracing_subscriber::registry()
+                .with(fmt::layer().json())
+                .with(filter),
+        ),
+        _ => Box::new(
+            tracing_subscriber::registry()
+                .with(fmt::layer())
+                .with(filter),
+        ),
+    }
+}
+
 pub fn convert_number_vec_to_hex_string(numbers: &[u8]) -> String {
     let hex_string: String = numbers.iter().map(|num| format!("{:02x}", num)).collect();
 
     format!("0x{}", hex_string)
 }
 
+/// Converts a Unix timestamp in seconds, e.g. a Pyth publish time.
+/// Use `timestamp_ms_to_naive_datetime` for timestamps in milliseconds.
+/// A timestamp out of the supported range gives the Unix epoch.
+///
 pub fn timestamp_to_naive_datetime(timestamp: u64) -> NaiveDateTime {
-    // Unix timestamp is typically in seconds
-    NaiveDateTime::from_timestamp_opt(timestamp as i64, 0).unwrap_or_default()
+    i64::try_from(timestamp)
+        .ok()
+        .and_then(|timestamp| NaiveDateTime::from_timestamp_opt(timestamp, 0))
+        .unwrap_or_default()
+}
+
+/// Converts a Unix timestamp in milliseconds, e.g. a Supra or Switchboard update time,
+/// keeping the milliseconds.
+/// A timestamp out of the supported range gives the Unix epoch.
+///
+pub fn timestamp_ms_to_naive_datetime(timestamp_ms: u64) -> NaiveDateTime {
+    i64::try_from(timestamp_ms)
+        .ok()
+        .and_then(NaiveDateTime::from_timestamp_millis)
+        .unwrap_or_default()
 }
 
 /// Format a coin type to standard format `0x<package>::<module>::<name>`
@@ -202,6 +244,33 @@ pub fn format_type_name(full_type_name: &str, with_prefix: bool) -> String {
     }
 }
 
+/// Canonical form of a coin type, with or without the `0x` prefix and padding:
+/// `0x2::sui::SUI` for SUI, `0x<64 hex package>::<module>::<name>` otherwise.
+/// The types nested in generic arguments are canonicalized the same way and the whitespace is removed,
+/// e.g. `0xa::lp::LP<0x2::sui::SUI, 0xb::usdc::USDC>` becomes `0x0..0a::lp::LP<0x2::sui::SUI,0x0..0b::usdc::USDC>`.
+/// Coin types are written to and queried from the database in this form.
+///
+pub fn canonicalize_coin_type(coin_type: &str) -> String {
+    let re = Regex::new(r"\b(?:0x)?([0-9a-fA-F]+)(::\w+::\w+)").unwrap();
+    let coin_type: String = coin_type.split_whitespace().collect();
+
+    re.replace_all(&coin_type, |captures: &regex::Captures| {
+        let address = captures[1].trim_start_matches('0');
+        let module_and_name = &captures[2];
+
+        if address == "2" && module_and_name == "::sui::SUI" {
+            constant::SUI_COIN.to_string()
+        } else {
+            format!(
+                "0x{:0>64}{}",
+                &address[..address.len().min(64)],
+                module_and_name
+            )
+        }
+    })
+    .into_owned()
+}
+
 pub fn format_pyth_feed_id(feed_id: &str, with_prefix: bool) -> String {
     let re = Regex::new(r"^(0x)([0-9a-fA-F]+)").unwrap();
 
@@ -254,6 +323,26 @@ pub fn mist_from_sui(sui: Decimal, decimals: usize) -> Decimal {
     sui * factor
 }
 
+/// Like `mist_from_sui`, rounded to an integer amount of mist with the given strategy.
+///
+pub fn mist_from_sui_rounded(sui: Decimal, decimals: usize, rounding: RoundingStrategy) -> Decimal {
+    mist_from_sui(sui, decimals).round_dp_with_strategy(0, rounding)
+}
+
+/// Converts a human readable amount to integer mist with an explicit rounding,
+/// e.g. `ToZero` (floor) for amounts spent and `AwayFromZero` (ceil) for amounts required.
+/// Fails on negative amounts or amounts not fitting a u64.
+///
+pub fn to_u64_mist(sui: Decimal, decimals: usize, rounding: RoundingStrategy) -> Result<u64> {
+    if sui.is_sign_negative() {
+        return Err(anyhow!("Negative amount: {}", sui));
+    }
+
+    mist_from_sui_rounded(sui, decimals, rounding)
+        .to_u64()
+        .ok_or_else(|| anyhow!("Amount {} with {} decimals out of u64 range", sui, decimals))
+}
+
 pub fn generate_borrower_id(platform: &str, address: &str) -> u64 {
     let mut hasher = XxHash64::default();
     platform.hash(&mut hasher);
@@ -268,6 +357,114 @@ pub fn generate_market_id(platform: &str, coin_type: &str) -> u64 {
     hasher.finish()
 }
 
+/// Key of an event whose contents can't be decoded, from the hash of its BCS contents.
+/// Such events are still processed, but not deduplicated by pool.
+///
+pub fn undecoded_event_key(event: &Event) -> String {
+    format!("undecoded_{}", event_contents_hash(event))
+}
+
+/// Hex hash of the BCS contents of an event,
+/// which tells apart events of the same type and pool.
+///
+pub fn event_contents_hash(event: &Event) -> String {
+    let mut hasher = XxHash64::default();
+    event.contents.hash(&mut hasher);
+    format!("{:x}", hasher.finish())
+}
+
+/// Extracts the pool ID of a raw event whose BCS layout doesn't match the expected struct,
+/// e.g. after a contract upgrade changed the event fields.
+/// The tx events are fetched from RPC and `pool_field` is read from the parsed JSON
+/// of the events with the same type.
+/// Fails if these events don't point to exactly one pool.
+///
+pub async fn extract_pool_id_fallback(
+    rpc_pool: &RpcPool,
+    tx_digest: &str,
+    event: &Event,
+    pool_field: &str,
+) -> Result<String> {
+    let digest = TransactionDigest::from_str(tx_digest)
+        .map_err(|e| anyhow!("Failed to parse transaction digest {}: {}", tx_digest, e))?;
+
+    let tx_events = rpc_pool
+        .call(|client| async move { client.event_api().get_events(digest).await })
+        .await
+        .map_err(|e| anyhow!("Failed to fetch events of tx {}: {}", tx_digest, e))?;
+
+    let pool_ids = tx_events
+        .iter()
+        .filter(|e| e.type_ == event.type_)
+        .filter_map(|e| e.parsed_json.get(pool_field).and_then(|v| v.as_str()))
+        .map(|pool_id| pool_id.to_string())
+        .collect::<std::collections::BTreeSet<_>>();
+
+    match pool_ids.len() {
+        1 => Ok(pool_ids.into_iter().next().unwrap_or_default()),
+        0 => Err(anyhow!(
+            "No {} field found in {} events of tx {}",
+            pool_field,
+            event.type_,
+            tx_digest
+        )),
+        _ => Err(anyhow!(
+            "Ambiguous {} events in tx {}: pools {:?}",
+            event.type_,
+            tx_digest,
+            pool_ids
+        )),
+    }
+}
+
+/// Checks that an amount stored as a string is a non-negative number, e.g. "1000" or "12.5",
+/// so a malformed amount fails where it is saved rather than where it is parsed.
+/// Digits only, amounts beyond the Decimal range are accepted.
+///
+pub fn validate_amount_str(amount: &str) -> Result<()> {
+    if amount.starts_with('-') {
+        return Err(anyhow!("Invalid amount {}: must not be negative", amount));
+    }
+
+    let is_digits = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_digit());
+    let (integer, fraction) = amount.split_once('.').unwrap_or((amount, "0"));
+    if !is_digits(integer) || !is_digits(fraction) {
+        return Err(anyhow!("Invalid amount {}: not a number", amount));
+    }
+
+    Ok(())
+}
+
+/// Amount of SUI to split from the gas coin for a swap input,
+/// keeping `gas_budget` plus `min_gas_reserve` on the gas coin.
+/// Fails if the split and the other SUI coins can't cover `amount_in`.
+///
+pub fn gas_coin_split_amount(
+    gas_balance: u64,
+    gas_budget: u64,
+    min_gas_reserve: u64,
+    amount_in: u64,
+    other_coins_balance: u128,
+) -> Result<u64> {
+    let split_amount = gas_balance
+        .saturating_sub(gas_budget)
+        .saturating_sub(min_gas_reserve)
+        .min(amount_in);
+
+    if split_amount as u128 + other_coins_balance < amount_in as u128 {
+        return Err(anyhow!(
+            "Insufficient SUI for input {} with gas budget {} and reserve {}: gas coin {}, other coins {}",
+            amount_in,
+            gas_budget,
+            min_gas_reserve,
+            gas_balance,
+            other_coins_balance
+        ));
+    }
+
+    Ok(split_amount)
+}
+
 pub fn net_value_given_fee_rate(gross_value: Decimal, fee_rate: Decimal) -> Result<Decimal> {
     if fee_rate >= Decimal::ONE || fee_rate < Decimal::ZERO {
         return Err(anyhow!("Invalid fee rate: must be between 0 and 1"));
@@ -295,6 +492,122 @@ pub fn threshold_value_given_slippage(
     Ok(expected_value * (Decimal::ONE - slippage))
 }
 
+/// Amount out of a weighted (Balancer-style) pool swap,
+/// `amount_in` being already net of the swap fee:
+/// amount_out = balance_out * (1 - (balance_in / (balance_in + amount_in))^(weight_in / weight_out))
+///
+pub fn weighted_amount_out(
+    balance_in: Decimal,
+    weight_in: Decimal,
+    balance_out: Decimal,
+    weight_out: Decimal,
+    amount_in: Decimal,
+) -> Result<Decimal> {
+    if balance_in <= Decimal::ZERO || balance_out <= Decimal::ZERO {
+        return Err(anyhow!("Invalid pool balances: must be positive"));
+    }
+    if weight_in <= Decimal::ZERO || weight_out <= Decimal::ZERO {
+        return Err(anyhow!("Invalid pool weights: must be positive"));
+    }
+    if amount_in < Decimal::ZERO {
+        return Err(anyhow!("Invalid amount in: must not be negative"));
+    }
+
+    let base = balance_in / (balance_in + amount_in);
+    let exponent = weight_in / weight_out;
+
+    let amount_out = balance_out
+        * (Decimal::ONE
+            - base
+                .checked_powd(exponent)
+                .ok_or_else(|| anyhow!("Failed to calculate weighted pool ratio"))?);
+
+    Ok(amount_out.max(Decimal::ZERO))
+}
+
+/// Amount out of a constant-product (x * y = k) pool swap,
+/// `amount_in` being already net of the swap fee:
+/// amount_out = reserve_out * amount_in / (reserve_in + amount_in)
+///
+pub fn constant_product_amount_out(
+    reserve_in: Decimal,
+    reserve_out: Decimal,
+    amount_in: Decimal,
+) -> Result<Decimal> {
+    if reserve_in <= Decimal::ZERO || reserve_out <= Decimal::ZERO {
+        return Err(anyhow!("Invalid pool reserves: must be positive"));
+    }
+    if amount_in < Decimal::ZERO {
+        return Err(anyhow!("Invalid amount in: must not be negative"));
+    }
+
+    Ok(reserve_out * amount_in / (reserve_in + amount_in))
+}
+
+/// Amount out of an oracle-priced pool swap, e.g. Obric,
+/// `amount_in` being already net of the swap fee:
+/// the USD value of `amount_in` at `price_in`, converted to the coin out at `price_out`.
+/// Amounts are in the smallest unit of their coin, prices in USD per whole coin.
+/// Fails if the amount out exceeds `reserve_out`, the pool can't cover it.
+///
+pub fn oracle_priced_amount_out(
+    amount_in: Decimal,
+    price_in: Decimal,
+    decimals_in: u32,
+    price_out: Decimal,
+    decimals_out: u32,
+    reserve_out: Decimal,
+) -> Result<Decimal> {
+    if price_in <= Decimal::ZERO || price_out <= Decimal::ZERO {
+        return Err(anyhow!("Invalid oracle prices: must be positive"));
+    }
+    if amount_in < Decimal::ZERO {
+        return Err(anyhow!("Invalid amount in: must not be negative"));
+    }
+
+    let value_usd = sui_from_mist(amount_in, decimals_in as usize) * price_in;
+    let amount_out = mist_from_sui(value_usd / price_out, decimals_out as usize).floor();
+
+    if amount_out > reserve_out {
+        return Err(anyhow!(
+            "Amount out {} exceeds the pool reserve {}",
+            amount_out,
+            reserve_out
+        ));
+    }
+
+    Ok(amount_out)
+}
+
+/// Pairs the comma-separated `coins` and `coin_amounts` of a pool
+/// into (coin_type, reserve) tuples, in the pool coin order.
+/// Coin types are canonicalized, see `canonicalize_coin_type`.
+///
+pub fn parse_pool_reserves(coins: &str, coin_amounts: &str) -> Result<Vec<(String, Decimal)>> {
+    let coins = coins.split(',').collect::<Vec<_>>();
+    let amounts = coin_amounts
+        .split(',')
+        .map(|s| {
+            Decimal::from_str(s.trim())
+                .map_err(|e| anyhow!("Failed to parse coin amount {}: {}", s, e))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    if amounts.len() != coins.len() {
+        return Err(anyhow!(
+            "Coin amounts length {} does not match coins length {}",
+            amounts.len(),
+            coins.len()
+        ));
+    }
+
+    Ok(coins
+        .into_iter()
+        .map(canonicalize_coin_type)
+        .zip(amounts)
+        .collect())
+}
+
 pub fn deserialize_tick_index<'de, D>(deserializer: D) -> Result<u32, D::Error>
 where
     D: serde::Deserializer<'de>,
@@ -313,6 +626,63 @@ where
     Err(serde::de::Error::custom("bits not found"))
 }
 
+/// Returns true if the error is caused by the network or the RPC transport,
+/// such errors are transient and worth retrying.
+/// Parsing and validation errors are not retried.
+///
+pub fn is_network_error(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        if let Some(e) = cause.downcast_ref::<sui_sdk::error::Error>() {
+            return matches!(e, sui_sdk::error::Error::RpcError(_));
+        }
+        if let Some(e) = cause.downcast_ref::<reqwest::Error>() {
+            return e.is_timeout() || e.is_connect() || e.is_request();
+        }
+        cause.is::<std::io::Error>() || cause.is::<tokio::time::error::Elapsed>()
+    })
+}
+
+/// Runs the future returned by `future_fn`, retrying on network errors
+/// with an exponential backoff starting from `base_delay`.
+/// Other errors are returned immediately.
+///
+pub async fn retry_with_backoff<F, Fut, T>(
+    mut future_fn: F,
+    max_retries: u32,
+    base_delay: Duration,
+) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = Result<T>>,
+{
+    let mut attempt = 0;
+
+    loop {
+        match future_fn().await {
+            Ok(value) => return Ok(value),
+            Err(e) if attempt < max_retries && is_network_error(&e) => {
+                let delay = base_delay * 2u32.saturating_pow(attempt);
+                attempt += 1;
+                warn!(
+                    "Network error, retry {}/{} in {:?}: {}",
+                    attempt, max_retries, delay, e
+                );
+                tokio::time::sleep(delay).await;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Delay before the given reconnect attempt, doubling from `base_delay`
+/// on each attempt and capped at `max_delay`.
+///
+pub fn backoff_delay(base_delay: Duration, max_delay: Duration, attempt: u32) -> Duration {
+    base_delay
+        .checked_mul(2u32.saturating_pow(attempt))
+        .map_or(max_delay, |delay| delay.min(max_delay))
+}
+
 /// Returns the current timestamp in milliseconds since the Unix epoch.
 pub fn get_current_timestamp_ms() -> u64 {
     let now = SystemTime::now()
@@ -328,6 +698,22 @@ pub fn get_current_timestamp_secs() -> u64 {
     now.as_secs()
 }
 
+/// Whether a checkpoint is past the range scanned in dev mode,
+/// which is `start_checkpoint_number` and the `count` checkpoints after it.
+///
+pub fn is_beyond_dev_mode_range(seq_number: u64, start_checkpoint_number: u64, count: u64) -> bool {
+    seq_number > start_checkpoint_number.saturating_add(count)
+}
+
+/// Whether an event seen at `seq_number` was already processed
+/// at `last_seen_seq_number` within the last `window` checkpoints.
+/// Checkpoints are processed concurrently,
+/// so an older checkpoint than the last seen one is also within the window.
+///
+pub fn is_within_dedup_window(seq_number: u64, last_seen_seq_number: u64, window: u64) -> bool {
+    seq_number <= last_seen_seq_number.saturating_add(window)
+}
+
 pub fn lagging_timestamp_ms(latest_timestamp_ms: u64) -> u64 {
     if latest_timestamp_ms == 0 {
         return 0;
@@ -389,3 +775,530 @@ pub fn format_coin_type_onchain(coin_type: &str) -> Result<String> {
         Err(anyhow!("Invalid coin type format: {}", coin_type))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CETUS_PADDED: &str =
+        "0x06864a6f921804860930db6ddbe2e16acdf8504495ea7481637a1c8b9a8fe54b::cetus::CETUS";
+    const CETUS_SHORT: &str =
+        "0x6864a6f921804860930db6ddbe2e16acdf8504495ea7481637a1c8b9a8fe54b::cetus::CETUS";
+
+    #[test]
+    fn timestamp_ms_keeps_the_milliseconds() {
+        let datetime = timestamp_ms_to_naive_datetime(1_700_000_000_123);
+
+        assert_eq!(datetime.and_utc().timestamp(), 1_700_000_000);
+        assert_eq!(datetime.and_utc().timestamp_subsec_millis(), 123);
+    }
+
+    #[test]
+    fn timestamp_in_seconds_and_milliseconds_give_the_same_datetime() {
+        assert_eq!(
+            timestamp_to_naive_datetime(1_700_000_000),
+            timestamp_ms_to_naive_datetime(1_700_000_000_000)
+        );
+        // a timestamp in seconds read as milliseconds is in January 1970
+        assert_eq!(
+            timestamp_ms_to_naive_datetime(1_700_000_000)
+                .and_utc()
+                .timestamp(),
+            1_700_000
+        );
+    }
+
+    #[test]
+    fn timestamp_out_of_range_is_the_epoch() {
+        let epoch = NaiveDateTime::default();
+
+        assert_eq!(timestamp_ms_to_naive_datetime(u64::MAX), epoch);
+        assert_eq!(timestamp_ms_to_naive_datetime(i64::MAX as u64), epoch);
+        assert_eq!(timestamp_to_naive_datetime(u64::MAX), epoch);
+        assert_eq!(timestamp_to_naive_datetime(i64::MAX as u64), epoch);
+    }
+
+    #[test]
+    fn canonicalize_coin_type_sui() {
+        let padded = format!("0x{}2::sui::SUI", "0".repeat(63));
+
+        assert_eq!(canonicalize_coin_type("0x2::sui::SUI"), constant::SUI_COIN);
+        assert_eq!(canonicalize_coin_type("2::sui::SUI"), constant::SUI_COIN);
+        assert_eq!(canonicalize_coin_type(&padded), constant::SUI_COIN);
+        assert_eq!(canonicalize_coin_type(&padded[2..]), constant::SUI_COIN);
+    }
+
+    #[test]
+    fn canonicalize_coin_type_regular_coin() {
+        assert_eq!(canonicalize_coin_type(CETUS_PADDED), CETUS_PADDED);
+        assert_eq!(canonicalize_coin_type(CETUS_SHORT), CETUS_PADDED);
+        assert_eq!(canonicalize_coin_type(&CETUS_SHORT[2..]), CETUS_PADDED);
+        assert_eq!(canonicalize_coin_type(&CETUS_PADDED[2..]), CETUS_PADDED);
+    }
+
+    #[test]
+    fn canonicalize_coin_type_nested_generics() {
+        let lp = format!(
+            "{}::lp::LP<0x2::sui::SUI, {}>",
+            &CETUS_SHORT[..5],
+            CETUS_SHORT
+        );
+
+        assert_eq!(
+            canonicalize_coin_type(&lp),
+            format!(
+                "0x{:0>64}::lp::LP<0x2::sui::SUI,{}>",
+                &CETUS_SHORT[2..5],
+                CETUS_PADDED
+            )
+        );
+        assert_eq!(
+            canonicalize_coin_type(&format!(
+                "0x2::coin::Coin<0x2::coin::Coin<{}>>",
+                CETUS_SHORT
+            )),
+            format!(
+                "0x{:0>64}::coin::Coin<0x{:0>64}::coin::Coin<{}>>",
+                2, 2, CETUS_PADDED
+            )
+        );
+    }
+
+    #[test]
+    fn canonicalize_coin_type_is_idempotent() {
+        let lp = format!("0xa::lp::LP<2::sui::SUI, {}>", CETUS_SHORT);
+        let canonical = canonicalize_coin_type(&lp);
+
+        assert_eq!(canonicalize_coin_type(&canonical), canonical);
+    }
+
+    #[test]
+    fn parse_pool_reserves_canonicalizes_coins() {
+        let reserves =
+            parse_pool_reserves(&format!("0x2::sui::SUI,{}", CETUS_SHORT), "100,200").unwrap();
+
+        assert_eq!(
+            reserves,
+            vec![
+                (constant::SUI_COIN.to_string(), Decimal::from(100)),
+                (CETUS_PADDED.to_string(), Decimal::from(200)),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_pool_reserves_trims_amounts() {
+        let reserves = parse_pool_reserves("0x2::sui::SUI,0xusdc", " 100.5 , 200").unwrap();
+
+        assert_eq!(reserves[0].1, Decimal::new(1005, 1));
+        assert_eq!(reserves[1].1, Decimal::from(200));
+    }
+
+    #[test]
+    fn parse_pool_reserves_rejects_mismatched_lengths() {
+        assert!(parse_pool_reserves("0x2::sui::SUI,0xusdc", "100").is_err());
+        assert!(parse_pool_reserves("0x2::sui::SUI", "100,200").is_err());
+    }
+
+    #[test]
+    fn parse_pool_reserves_rejects_malformed_amounts() {
+        assert!(parse_pool_reserves("0x2::sui::SUI,0xusdc", "100,abc").is_err());
+        assert!(parse_pool_reserves("0x2::sui::SUI,0xusdc", "100,").is_err());
+    }
+
+    #[test]
+    fn constant_product_amount_out_keeps_the_product() {
+        let amount_out = constant_product_amount_out(
+            Decimal::from(2_000),
+            Decimal::from(5_000),
+            Decimal::from(1_000),
+        )
+        .unwrap();
+
+        assert_close(
+            amount_out,
+            Decimal::from_str("1666.6666666666666666666666667").unwrap(),
+        );
+        assert_close(
+            Decimal::from(3_000) * (Decimal::from(5_000) - amount_out),
+            Decimal::from(2_000) * Decimal::from(5_000),
+        );
+    }
+
+    #[test]
+    fn constant_product_amount_out_net_of_fee() {
+        // 0.3% fee rate, stored in millionths
+        let fee_rate = Decimal::from(3_000) / Decimal::from(constant::FEE_RATE_DENOMINATOR);
+        let amount_in = net_value_given_fee_rate(Decimal::from(100), fee_rate).unwrap();
+
+        let amount_out =
+            constant_product_amount_out(Decimal::from(1_000), Decimal::from(1_000), amount_in)
+                .unwrap();
+
+        assert_close(
+            amount_out,
+            Decimal::from_str("90.661089388014913158").unwrap(),
+        );
+    }
+
+    #[test]
+    fn constant_product_amount_out_rejects_invalid_inputs() {
+        let (one, zero) = (Decimal::ONE, Decimal::ZERO);
+
+        assert!(constant_product_amount_out(zero, one, one).is_err());
+        assert!(constant_product_amount_out(one, zero, one).is_err());
+        assert!(constant_product_amount_out(one, one, -one).is_err());
+        assert_eq!(constant_product_amount_out(one, one, zero).unwrap(), zero);
+    }
+
+    #[test]
+    fn oracle_priced_amount_out_converts_between_decimals() {
+        // 2 SUI (9 decimals) at $3.5 is $7, 7 USDC (6 decimals) at $1
+        let amount_out = oracle_priced_amount_out(
+            Decimal::from(2_000_000_000u64),
+            Decimal::from_str("3.5").unwrap(),
+            9,
+            Decimal::ONE,
+            6,
+            Decimal::from(1_000_000_000),
+        )
+        .unwrap();
+        assert_eq!(amount_out, Decimal::from(7_000_000));
+
+        // 7 USDC back to SUI, rounded down to the mist
+        let amount_out = oracle_priced_amount_out(
+            Decimal::from(7_000_000),
+            Decimal::ONE,
+            6,
+            Decimal::from(3),
+            9,
+            Decimal::from(10_000_000_000u64),
+        )
+        .unwrap();
+        assert_eq!(amount_out, Decimal::from(2_333_333_333u64));
+    }
+
+    #[test]
+    fn oracle_priced_amount_out_net_of_fee() {
+        // 0.25% fee rate, stored in millionths
+        let fee_rate = Decimal::from(2_500) / Decimal::from(constant::FEE_RATE_DENOMINATOR);
+        let amount_in = net_value_given_fee_rate(Decimal::from(1_000_000), fee_rate).unwrap();
+
+        let amount_out = oracle_priced_amount_out(
+            amount_in,
+            Decimal::ONE,
+            6,
+            Decimal::ONE,
+            6,
+            Decimal::from(1_000_000),
+        )
+        .unwrap();
+
+        assert_eq!(amount_out, Decimal::from(997_500));
+    }
+
+    #[test]
+    fn oracle_priced_amount_out_rejects_amounts_over_the_reserve() {
+        let quote = |reserve_out: i64| {
+            oracle_priced_amount_out(
+                Decimal::from(1_000_000),
+                Decimal::from(2),
+                6,
+                Decimal::ONE,
+                6,
+                Decimal::from(reserve_out),
+            )
+        };
+
+        assert_eq!(quote(2_000_000).unwrap(), Decimal::from(2_000_000));
+        assert!(quote(1_999_999).is_err());
+    }
+
+    #[test]
+    fn oracle_priced_amount_out_rejects_invalid_prices() {
+        let quote = |price_in: Decimal, price_out: Decimal| {
+            oracle_priced_amount_out(
+                Decimal::from(1_000),
+                price_in,
+                6,
+                price_out,
+                6,
+                Decimal::from(1_000_000),
+            )
+        };
+
+        assert!(quote(Decimal::ZERO, Decimal::ONE).is_err());
+        assert!(quote(Decimal::ONE, Decimal::ZERO).is_err());
+        assert!(quote(-Decimal::ONE, Decimal::ONE).is_err());
+        assert!(quote(Decimal::ONE, -Decimal::ONE).is_err());
+        assert!(oracle_priced_amount_out(
+            -Decimal::ONE,
+            Decimal::ONE,
+            6,
+            Decimal::ONE,
+            6,
+            Decimal::ONE
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn validate_amount_str_accepts_non_negative_numbers() {
+        for amount in [
+            "0",
+            "1000000000",
+            "0.5",
+            "12.000",
+            "340282366920938463463374607431768211456",
+        ] {
+            assert!(validate_amount_str(amount).is_ok(), "{}", amount);
+        }
+    }
+
+    #[test]
+    fn validate_amount_str_rejects_negative_amounts() {
+        for amount in ["-1", "-0.5", "-0"] {
+            let err = validate_amount_str(amount).unwrap_err();
+
+            assert!(
+                err.to_string().contains("must not be negative"),
+                "{}",
+                amount
+            );
+        }
+    }
+
+    #[test]
+    fn validate_amount_str_rejects_non_numeric_amounts() {
+        for amount in [
+            "", "abc", "1e9", "1.", ".5", "1.2.3", " 1", "+1", "0x10", "1,000",
+        ] {
+            let err = validate_amount_str(amount).unwrap_err();
+
+            assert!(err.to_string().contains("not a number"), "{:?}", amount);
+        }
+    }
+
+    #[test]
+    fn gas_coin_split_amount_keeps_the_budget_and_the_reserve() {
+        // 10 SUI gas coin, 0.1 SUI budget and 0.5 SUI reserve, 2 SUI in
+        let split_amount =
+            gas_coin_split_amount(10_000_000_000, 100_000_000, 500_000_000, 2_000_000_000, 0)
+                .unwrap();
+        assert_eq!(split_amount, 2_000_000_000);
+
+        // only 9.4 SUI can be split, the other coins cover the rest
+        let split_amount = gas_coin_split_amount(
+            10_000_000_000,
+            100_000_000,
+            500_000_000,
+            12_000_000_000,
+            2_600_000_000,
+        )
+        .unwrap();
+        assert_eq!(split_amount, 9_400_000_000);
+    }
+
+    #[test]
+    fn gas_coin_split_amount_without_reserve_splits_down_to_the_budget() {
+        let split_amount =
+            gas_coin_split_amount(1_000_000_000, 100_000_000, 0, 900_000_000, 0).unwrap();
+
+        assert_eq!(split_amount, 900_000_000);
+    }
+
+    #[test]
+    fn gas_coin_split_amount_of_insufficient_balance_is_an_error() {
+        // the reserve leaves 0.4 SUI to split for a 0.5 SUI input
+        let err = gas_coin_split_amount(1_000_000_000, 100_000_000, 500_000_000, 500_000_000, 0)
+            .unwrap_err();
+        assert!(err.to_string().contains("Insufficient SUI"));
+
+        // the gas coin below the budget leaves nothing to split
+        assert!(gas_coin_split_amount(50_000_000, 100_000_000, 0, 1, 0).is_err());
+        assert_eq!(
+            gas_coin_split_amount(50_000_000, 100_000_000, 0, 1, 1).unwrap(),
+            0
+        );
+    }
+
+    fn dec(amount: &str) -> Decimal {
+        Decimal::from_str(amount).unwrap()
+    }
+
+    #[test]
+    fn to_u64_mist_floors_and_ceils_below_one_mist() {
+        let floor = |sui: &str, decimals| to_u64_mist(dec(sui), decimals, RoundingStrategy::ToZero);
+        let ceil =
+            |sui: &str, decimals| to_u64_mist(dec(sui), decimals, RoundingStrategy::AwayFromZero);
+
+        assert_eq!(floor("1.0000000001", 9).unwrap(), 1_000_000_000);
+        assert_eq!(ceil("1.0000000001", 9).unwrap(), 1_000_000_001);
+        assert_eq!(floor("0.0000000001", 9).unwrap(), 0);
+        assert_eq!(ceil("0.0000000001", 9).unwrap(), 1);
+        assert_eq!(floor("2.4999995", 6).unwrap(), 2_499_999);
+        assert_eq!(ceil("2.4999995", 6).unwrap(), 2_500_000);
+    }
+
+    #[test]
+    fn to_u64_mist_keeps_whole_mist_amounts() {
+        for rounding in [RoundingStrategy::ToZero, RoundingStrategy::AwayFromZero] {
+            assert_eq!(to_u64_mist(dec("1.5"), 9, rounding).unwrap(), 1_500_000_000);
+            assert_eq!(to_u64_mist(dec("0"), 9, rounding).unwrap(), 0);
+            assert_eq!(to_u64_mist(dec("7"), 0, rounding).unwrap(), 7);
+        }
+    }
+
+    #[test]
+    fn to_u64_mist_at_the_u64_boundary() {
+        assert_eq!(
+            to_u64_mist(
+                dec("18446744073.709551615"),
+                9,
+                RoundingStrategy::AwayFromZero
+            )
+            .unwrap(),
+            u64::MAX
+        );
+        // a fraction of a mist above u64::MAX only fits when floored
+        assert_eq!(
+            to_u64_mist(dec("18446744073.7095516151"), 9, RoundingStrategy::ToZero).unwrap(),
+            u64::MAX
+        );
+        assert!(to_u64_mist(
+            dec("18446744073.7095516151"),
+            9,
+            RoundingStrategy::AwayFromZero
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn to_u64_mist_rejects_negative_amounts() {
+        assert!(to_u64_mist(dec("-0.000000001"), 9, RoundingStrategy::ToZero).is_err());
+    }
+
+    #[test]
+    fn mist_from_sui_rounded_has_no_fractional_tail() {
+        let mist = mist_from_sui_rounded(dec("0.1234567891"), 9, RoundingStrategy::ToZero);
+
+        assert_eq!(mist, Decimal::from(123_456_789));
+        assert_eq!(mist.to_string(), "123456789");
+    }
+
+    #[test]
+    fn dev_mode_range_is_the_start_and_the_count_after_it() {
+        assert!(!is_beyond_dev_mode_range(100, 100, 20));
+        assert!(!is_beyond_dev_mode_range(120, 100, 20));
+        assert!(is_beyond_dev_mode_range(121, 100, 20));
+    }
+
+    #[test]
+    fn dev_mode_range_of_zero_is_only_the_start() {
+        assert!(!is_beyond_dev_mode_range(100, 100, 0));
+        assert!(is_beyond_dev_mode_range(101, 100, 0));
+    }
+
+    #[test]
+    fn dev_mode_range_does_not_overflow() {
+        assert!(!is_beyond_dev_mode_range(u64::MAX, u64::MAX - 1, u64::MAX));
+    }
+
+    fn assert_close(actual: Decimal, expected: Decimal) {
+        assert!(
+            ((actual - expected) / expected).abs() < Decimal::new(1, 6),
+            "{} != {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn weighted_amount_out_of_equal_weights_is_constant_product() {
+        let amount_out = weighted_amount_out(
+            Decimal::from(1_000),
+            Decimal::new(5, 1),
+            Decimal::from(1_000),
+            Decimal::new(5, 1),
+            Decimal::from(100),
+        )
+        .unwrap();
+
+        assert_close(
+            amount_out,
+            Decimal::from_str("90.909090909090909090").unwrap(),
+        );
+        assert_close(
+            amount_out,
+            constant_product_amount_out(
+                Decimal::from(1_000),
+                Decimal::from(1_000),
+                Decimal::from(100),
+            )
+            .unwrap(),
+        );
+    }
+
+    #[test]
+    fn weighted_amount_out_of_a_20_80_pool() {
+        // 2000 * (1 - (1000 / 1100)^(0.2 / 0.8))
+        let amount_out = weighted_amount_out(
+            Decimal::from(1_000),
+            Decimal::new(2, 1),
+            Decimal::from(2_000),
+            Decimal::new(8, 1),
+            Decimal::from(100),
+        )
+        .unwrap();
+
+        assert_close(
+            amount_out,
+            Decimal::from_str("47.091820647378910214").unwrap(),
+        );
+    }
+
+    #[test]
+    fn weighted_amount_out_of_an_80_20_pool_net_of_fee() {
+        // 500 * (1 - (2000 / (2000 + 100 * 0.997))^(0.8 / 0.2))
+        let amount_in = net_value_given_fee_rate(Decimal::from(100), Decimal::new(3, 3)).unwrap();
+
+        let amount_out = weighted_amount_out(
+            Decimal::from(2_000),
+            Decimal::new(8, 1),
+            Decimal::from(500),
+            Decimal::new(2, 1),
+            amount_in,
+        )
+        .unwrap();
+
+        assert_close(
+            amount_out,
+            Decimal::from_str("88.413620780894856368").unwrap(),
+        );
+    }
+
+    #[test]
+    fn weighted_amount_out_of_nothing_is_nothing() {
+        let amount_out = weighted_amount_out(
+            Decimal::from(1_000),
+            Decimal::new(5, 1),
+            Decimal::from(1_000),
+            Decimal::new(5, 1),
+            Decimal::ZERO,
+        )
+        .unwrap();
+
+        assert_eq!(amount_out, Decimal::ZERO);
+    }
+
+    #[test]
+    fn weighted_amount_out_rejects_invalid_pools() {
+        let (one, zero) = (Decimal::ONE, Decimal::ZERO);
+
+        assert!(weighted_amount_out(zero, one, one, one, one).is_err());
+        assert!(weighted_amount_out(one, one, zero, one, one).is_err());
+        assert!(weighted_amount_out(one, zero, one, one, one).is_err());
+        assert!(weighted_amount_out(one, one, one, zero, one).is_err());
+        assert!(weighted_amount_out(one, one, one, one, -one).is_err());
+    }
+}
@@ -1,17 +1,26 @@
-use crate::{constant, service::db_service};
+use crate::{
+    config::Config,
+    constant,
+    service::db_service,
+    types::MistAmount,
+    utils::{self, rpc_pool::RpcPool},
+};
 use db::repositories::{CoinRepository, PoolRepository};
 
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use fastcrypto::{ed25519::Ed25519KeyPair, hash::HashFunction};
-use rust_decimal::{prelude::*, Decimal};
+use futures::stream::{self, StreamExt};
 use shared_crypto::intent::{Intent, IntentMessage};
 use std::{
+    collections::HashMap,
     hash::{Hash, Hasher},
     str::FromStr,
     sync::Arc,
 };
-use sui_json_rpc_types::SuiTransactionBlockResponse;
+use sui_json_rpc_types::{
+    DevInspectResults, SuiTransactionBlockEffectsAPI, SuiTransactionBlockResponse,
+};
 use sui_sdk::{
     rpc_types::{
         self, SuiExecutionStatus, SuiObjectData, SuiObjectDataFilter, SuiObjectDataOptions,
@@ -22,11 +31,12 @@ use sui_sdk::{
         programmable_transaction_builder::ProgrammableTransactionBuilder,
         quorum_driver_types::ExecuteTransactionRequestType,
         signature::{self, GenericSignature},
-        transaction::{self, Argument, Command, ObjectArg, Transaction, TransactionData},
+        transaction::{
+            self, Argument, Command, ObjectArg, Transaction, TransactionData, TransactionKind,
+        },
         type_input::TypeInput,
         Identifier, TypeTag,
     },
-    SuiClient,
 };
 use sui_types::{
     base_types::{ObjectID, SequenceNumber, SuiAddress},
@@ -40,19 +50,22 @@ use tokio::time::{Duration, Instant};
 use tracing::{debug, error, info, trace, warn, Level};
 
 pub struct PTBHelper {
-    pub client: Arc<SuiClient>,
+    pub config: Arc<Config>,
+    pub rpc_pool: Arc<RpcPool>,
     pub db_pool_service: Arc<db_service::pool::PoolService>,
     pub db_lending_service: Arc<db_service::lending::LendingService>,
 }
 
 impl PTBHelper {
     pub fn new(
-        client: Arc<SuiClient>,
+        config: Arc<Config>,
+        rpc_pool: Arc<RpcPool>,
         db_pool_service: Arc<db_service::pool::PoolService>,
         db_lending_service: Arc<db_service::lending::LendingService>,
     ) -> Self {
         PTBHelper {
-            client,
+            config,
+            rpc_pool,
             db_pool_service,
             db_lending_service,
         }
@@ -64,7 +77,7 @@ impl PTBHelper {
     pub async fn find_gas_coin_for_ptb(&self, sender: &str) -> Result<rpc_types::Coin> {
         let sender_address = SuiAddress::from_str(sender)?;
         let sui_coins = self
-            .get_all_coins_by_address_and_type(&sender_address, constant::SUI_COIN)
+            .get_all_coins_by_address_and_type(&sender_address, constant::SUI_COIN, None, None)
             .await?;
 
         if sui_coins.is_empty() {
@@ -86,17 +99,17 @@ impl PTBHelper {
         &self,
         address: &SuiAddress,
         coin_type: &str,
-        amount: Decimal,
+        amount: MistAmount,
     ) -> Result<Vec<rpc_types::Coin>> {
         let coins = self
-            .get_all_coins_by_address_and_type(address, coin_type)
+            .get_all_coins_by_address_and_type(address, coin_type, Some(1), None)
             .await?;
 
         let mut results = Vec::new();
-        let mut total_amount = Decimal::ZERO;
+        let mut total_amount = 0u128;
 
         for coin in coins {
-            if total_amount >= amount {
+            if total_amount >= amount.value() as u128 {
                 break;
             }
 
@@ -105,10 +118,10 @@ impl PTBHelper {
             }
 
             results.push(coin.clone());
-            total_amount += Decimal::from(coin.balance);
+            total_amount += coin.balance as u128;
         }
 
-        if total_amount < amount {
+        if total_amount < amount.value() as u128 {
             return Err(anyhow!(
                 "Insufficient balance. Required: {}, Available: {}",
                 amount,
@@ -119,36 +132,66 @@ impl PTBHelper {
         Ok(results)
     }
 
+    /// Fetch the coin objects of the given type owned by an address, page by page.
+    /// Coins with a balance below `min_balance` are dropped as each page arrives,
+    /// and at most `max_pages` pages are fetched when a bound is given.
+    /// The RPC has no balance filter, so the floor only bounds memory, not requests.
+    ///
     pub async fn get_all_coins_by_address_and_type(
         &self,
         address: &SuiAddress,
         coin_type: &str,
+        min_balance: Option<u64>,
+        max_pages: Option<usize>,
     ) -> Result<Vec<rpc_types::Coin>> {
         let mut results = Vec::new();
         let count = 50;
         let mut next_cursor = None;
+        let mut pages = 0;
+        let min_balance = min_balance.unwrap_or(0);
 
         loop {
             let coins = self
-                .client
-                .coin_read_api()
-                .get_coins(
-                    *address,
-                    Some(coin_type.to_string()),
-                    next_cursor,
-                    Some(count),
-                )
+                .rpc_pool
+                .call(|client| {
+                    let next_cursor = next_cursor.clone();
+                    async move {
+                        client
+                            .coin_read_api()
+                            .get_coins(
+                                *address,
+                                Some(coin_type.to_string()),
+                                next_cursor,
+                                Some(count),
+                            )
+                            .await
+                    }
+                })
                 .await?;
+            pages += 1;
 
-            results.extend(coins.data);
+            results.extend(
+                coins
+                    .data
+                    .into_iter()
+                    .filter(|coin| coin.balance >= min_balance),
+            );
 
-            if coins.has_next_page {
-                // If there are more pages, continue fetching
-                next_cursor = coins.next_cursor.clone();
-            } else {
+            if !coins.has_next_page {
                 // No more pages, break the loop
                 break;
             }
+
+            if max_pages.is_some_and(|max_pages| pages >= max_pages) {
+                warn!(
+                    "Stopped fetching {} coins of {} after {} pages",
+                    coin_type, address, pages
+                );
+                break;
+            }
+
+            // If there are more pages, continue fetching
+            next_cursor = coins.next_cursor.clone();
         }
 
         Ok(results)
@@ -163,7 +206,11 @@ impl PTBHelper {
     /// Build a Shared object for PTB
     pub async fn build_shared_obj_arg(&self, object_id: &str, mutable: bool) -> Result<ObjectArg> {
         match self.db_lending_service.find_shared_object_by_id(object_id) {
-            Ok(shared_object) => {
+            Ok(shared_object)
+                if !self
+                    .db_lending_service
+                    .is_shared_object_cache_expired(&shared_object) =>
+            {
                 info!(
                     "Found shared object {} in database, use the cached version",
                     object_id
@@ -178,9 +225,9 @@ impl PTBHelper {
                     mutable,
                 })
             }
-            Err(e) => {
+            _ => {
                 info!(
-                    "Shared object {} is not found in database, fetching it from Sui",
+                    "Shared object {} is not found in database or expired, fetching it from Sui",
                     object_id
                 );
 
@@ -190,9 +237,16 @@ impl PTBHelper {
                 let sui_object_id = ObjectID::from_hex_literal(object_id)?;
 
                 let obj_response = self
-                    .client
-                    .read_api()
-                    .get_object_with_options(sui_object_id, object_data_options.clone())
+                    .rpc_pool
+                    .call(|client| {
+                        let object_data_options = object_data_options.clone();
+                        async move {
+                            client
+                                .read_api()
+                                .get_object_with_options(sui_object_id, object_data_options)
+                                .await
+                        }
+                    })
                     .await?;
 
                 let obj_data = obj_response
@@ -325,7 +379,7 @@ impl PTBHelper {
         ptb: &mut ProgrammableTransactionBuilder,
         sender: &str,
         coin_type: &str,
-        amount_in: u64,
+        amount_in: MistAmount,
         gas_budget: u64,
         gas_coin: rpc_types::Coin,
         command_index: u16,
@@ -333,14 +387,11 @@ impl PTBHelper {
         let sender_address = SuiAddress::from_str(sender)?;
         let coin_in_sui = coin_type == constant::SUI_COIN;
         let mut command_index = command_index;
+        let amount_in_mist = amount_in;
+        let amount_in = amount_in.value();
 
         let coins_in = self
-            .get_coins_for_amount(
-                &sender_address,
-                coin_type,
-                Decimal::from_u64(amount_in)
-                    .ok_or_else(|| anyhow!("Failed to convert amount_in to Decimal"))?,
-            )
+            .get_coins_for_amount(&sender_address, coin_type, amount_in_mist)
             .await?;
 
         if coins_in.is_empty() {
@@ -352,18 +403,24 @@ impl PTBHelper {
         }
 
         let coin_input_arg = if coin_in_sui {
-            // split input coin from the gas coin
+            // split input coin from the gas coin, keeping the gas budget and reserve on it
+            let other_coins_balance = coins_in
+                .iter()
+                .filter(|coin| coin.coin_object_id != gas_coin.coin_object_id)
+                .map(|coin| coin.balance as u128)
+                .sum::<u128>();
 
-            let mut split_amount = gas_coin.balance as i64 - gas_budget as i64;
-            if split_amount <= 0 {
-                split_amount = 0;
-            } else if split_amount > amount_in as i64 {
-                split_amount = amount_in as i64;
-            }
+            let split_amount = utils::gas_coin_split_amount(
+                gas_coin.balance,
+                gas_budget,
+                self.config.liquidation.min_gas_reserve,
+                amount_in,
+                other_coins_balance,
+            )?;
 
             info!("Split amount: {}", split_amount);
 
-            let split_amount_arg = ptb.pure::<u64>(split_amount as u64)?;
+            let split_amount_arg = ptb.pure::<u64>(split_amount)?;
 
             ptb.command(Command::SplitCoins(
                 Argument::GasCoin,
@@ -435,6 +492,10 @@ impl PTBHelper {
         Ok((coin_input_arg, command_index))
     }
 
+    /// Finds the objects of the given type owned by the address,
+    /// walking all pages of `liquidation.owned_objects_page_size` objects,
+    /// up to `liquidation.owned_objects_max_count` objects if set.
+    ///
     pub async fn find_owned_objects_given_owner_address_and_type(
         &self,
         owner_address: SuiAddress,
@@ -454,13 +515,41 @@ impl PTBHelper {
             options: Some(object_data_options),
         };
 
-        let objects_response = self
-            .client
-            .read_api()
-            .get_owned_objects(owner_address, Some(query), None, None)
-            .await?;
+        let page_size = self.config.liquidation.owned_objects_page_size;
+        let max_count = self.config.liquidation.owned_objects_max_count;
+
+        let mut objects = vec![];
+        let mut cursor = None;
+        loop {
+            let objects_response = self
+                .rpc_pool
+                .call(|client| {
+                    let query = query.clone();
+                    async move {
+                        client
+                            .read_api()
+                            .get_owned_objects(owner_address, Some(query), cursor, Some(page_size))
+                            .await
+                    }
+                })
+                .await?;
+
+            objects.extend(objects_response.data.into_iter().filter_map(|obj| obj.data));
+
+            if let Some(max_count) = max_count {
+                if objects.len() >= max_count {
+                    objects.truncate(max_count);
+                    break;
+                }
+            }
 
-        if objects_response.data.is_empty() {
+            if !objects_response.has_next_page || objects_response.next_cursor.is_none() {
+                break;
+            }
+            cursor = objects_response.next_cursor;
+        }
+
+        if objects.is_empty() {
             return Err(anyhow!(
                 "No objects found for owner address {} and type {}",
                 owner_address,
@@ -468,35 +557,45 @@ impl PTBHelper {
             ));
         }
 
-        let objects = objects_response
-            .data
-            .into_iter()
-            .filter_map(|obj| obj.data)
-            .collect::<Vec<_>>();
-
         Ok(objects)
     }
 
     /// Fetches the coin metadata for a list of coin types.
-    /// This is executed in parallel to improve performance.
+    /// This is executed in parallel, up to `indexer.coin_metadata_fetch_concurrency` coins at a time,
+    /// each coin being looked up in the database first.
+    /// The returned coins follow the order of the given coin types.
     ///
     pub async fn fetch_coins_metadata(
         &self,
         coin_types: Vec<String>,
     ) -> Result<Vec<crate::types::Coin>> {
-        let mut coin_results = Vec::new();
-
-        for coin_type in coin_types.iter() {
-            let coin = self.get_coin_from_type(coin_type).await?;
-            coin_results.push(coin);
-        }
+        let coin_by_type = stream::iter(coin_types.iter())
+            .map(|coin_type| async move {
+                self.get_coin_from_type(coin_type)
+                    .await
+                    .map(|coin| (coin_type.clone(), coin))
+            })
+            .buffer_unordered(self.config.indexer.coin_metadata_fetch_concurrency.max(1))
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect::<Result<HashMap<_, _>>>()?;
 
-        Ok(coin_results)
+        coin_types
+            .iter()
+            .map(|coin_type| {
+                coin_by_type
+                    .get(coin_type)
+                    .cloned()
+                    .ok_or_else(|| anyhow!("Missing coin metadata for type: {}", coin_type))
+            })
+            .collect()
     }
 
     /// Fetches the coin metadata for a given coin type.
     /// Firstly it checks the local database for the coin metadata.
-    /// If not found, it fetches the metadata from the Sui client and stores it in the database.
+    /// If not found, it uses the configured `known_coins` metadata, cached to the database,
+    /// otherwise it fetches the metadata from the Sui client.
     pub async fn get_coin_from_type(&self, coin_type: &str) -> Result<crate::types::Coin> {
         match self.db_pool_service.find_coin_by_type(coin_type).await {
             Ok(coin) => Ok(crate::types::Coin {
@@ -508,7 +607,9 @@ impl PTBHelper {
                 pyth_info_object_id: coin.pyth_info_object_id,
             }),
             Err(_) => {
-                if coin_type == constant::SUI_COIN {
+                let canonical_type = utils::canonicalize_coin_type(coin_type);
+
+                if canonical_type == constant::SUI_COIN {
                     return Ok(crate::types::Coin {
                         coin_type: constant::SUI_COIN.to_string(),
                         decimals: 9,
@@ -519,17 +620,44 @@ impl PTBHelper {
                     });
                 }
 
+                if let Some(known_coin) = self
+                    .config
+                    .known_coins
+                    .iter()
+                    .find(|c| utils::canonicalize_coin_type(&c.coin_type) == canonical_type)
+                {
+                    let coin = crate::types::Coin {
+                        coin_type: canonical_type,
+                        decimals: known_coin.decimals,
+                        name: known_coin.name.clone(),
+                        symbol: known_coin.symbol.clone(),
+                        pyth_feed_id: None,
+                        pyth_info_object_id: None,
+                    };
+
+                    // cache it so the next lookups hit the database
+                    if let Err(e) = self.db_pool_service.save_coin_to_db(coin.clone()).await {
+                        warn!("Failed to cache known coin {}: {}", coin_type, e);
+                    }
+
+                    return Ok(coin);
+                }
+
                 let metadata = self
-                    .client
-                    .coin_read_api()
-                    .get_coin_metadata(coin_type.to_string())
+                    .rpc_pool
+                    .call(|client| async move {
+                        client
+                            .coin_read_api()
+                            .get_coin_metadata(coin_type.to_string())
+                            .await
+                    })
                     .await?
                     .ok_or_else(|| {
                         anyhow!("Failed to get coin metadata for type: {}", coin_type)
                     })?;
 
                 Ok(crate::types::Coin {
-                    coin_type: coin_type.to_string(),
+                    coin_type: canonical_type,
                     decimals: metadata.decimals,
                     name: Some(metadata.name),
                     symbol: Some(metadata.symbol),
@@ -540,6 +668,98 @@ impl PTBHelper {
         }
     }
 
+    /// Dev-inspects a transaction, failing after `rpc_timeout_ms`
+    /// so a hung RPC doesn't stall the caller, e.g. a checkpoint worker.
+    /// The timeout error is a network error, as told by `utils::is_network_error`.
+    ///
+    pub async fn dev_inspect(
+        &self,
+        sender: SuiAddress,
+        tx: TransactionKind,
+    ) -> Result<DevInspectResults> {
+        let timeout_ms = self.config.rpc_timeout_ms;
+
+        // the timeout applies to each endpoint, so a hung endpoint fails over to the next one
+        self.rpc_pool
+            .call(|client| {
+                let tx = tx.clone();
+                async move {
+                    tokio::time::timeout(
+                        Duration::from_millis(timeout_ms),
+                        client
+                            .read_api()
+                            .dev_inspect_transaction_block(sender, tx, None, None, None),
+                    )
+                    .await
+                    .map_err(|e| {
+                        anyhow::Error::from(e).context(format!(
+                            "dev_inspect_transaction_block timed out after {}ms",
+                            timeout_ms
+                        ))
+                    })?
+                    .map_err(anyhow::Error::from)
+                }
+            })
+            .await
+    }
+
+    /// Estimates the gas budget of a transaction by dev-inspecting it,
+    /// the computation and storage costs are scaled by the configured safety multiplier.
+    /// Storage rebates are ignored since the budget must cover the costs upfront.
+    ///
+    pub async fn estimate_gas_budget(
+        &self,
+        builder: &ProgrammableTransaction,
+        sender: SuiAddress,
+    ) -> Result<u64> {
+        let tx = TransactionKind::ProgrammableTransaction(builder.clone());
+
+        let response = self.dev_inspect(sender, tx).await?;
+
+        if let Some(error) = response.error {
+            return Err(anyhow!("Failed to dev inspect transaction: {}", error));
+        }
+
+        let gas_cost_summary = response.effects.gas_cost_summary();
+        let gas_used = gas_cost_summary.computation_cost + gas_cost_summary.storage_cost;
+
+        let gas_budget = (gas_used as u128
+            * self.config.liquidation.gas_budget_multiplier_bps as u128
+            / 10_000) as u64;
+
+        debug!(
+            "Estimated gas budget {} from gas used {}",
+            gas_budget, gas_used
+        );
+
+        Ok(gas_budget)
+    }
+
+    /// Signs and submits a transaction with a gas budget
+    /// estimated by `estimate_gas_budget`.
+    ///
+    pub async fn sign_and_send_tx_auto_gas(
+        &self,
+        builder: ProgrammableTransaction,
+        sender: Arc<SuiKeyPair>,
+        gas_coin: sui_json_rpc_types::Coin,
+        gas_price: u64,
+        use_shio_endpoint: bool,
+    ) -> Result<SuiTransactionBlockResponse> {
+        let sender_address = SuiAddress::from(&sender.public());
+        let gas_budget = self.estimate_gas_budget(&builder, sender_address).await?;
+
+        self.sign_and_send_tx(
+            builder,
+            sender,
+            gas_coin,
+            gas_budget,
+            gas_price,
+            use_shio_endpoint,
+        )
+        .await
+    }
+
     pub async fn sign_and_send_tx(
         &self,
         builder: ProgrammableTransaction,
@@ -567,18 +787,27 @@ impl PTBHelper {
 
         let signature = sender.sign(&digest);
 
-        // submit tx
+        let tx = transaction::Transaction::from_generic_sig_data(
+            intent_msg.value,
+            vec![signature::GenericSignature::Signature(signature)],
+        );
+
+        // submit tx, resubmitting the same signed tx to the next endpoint is idempotent
         let tx_response = self
-            .client
-            .quorum_driver_api()
-            .execute_transaction_block(
-                transaction::Transaction::from_generic_sig_data(
-                    intent_msg.value,
-                    vec![signature::GenericSignature::Signature(signature)],
-                ),
-                SuiTransactionBlockResponseOptions::new(),
-                None,
-            )
+            .rpc_pool
+            .call(|client| {
+                let tx = tx.clone();
+                async move {
+                    client
+                        .quorum_driver_api()
+                        .execute_transaction_block(
+                            tx,
+                            SuiTransactionBlockResponseOptions::new(),
+                            None,
+                        )
+                        .await
+                }
+            })
             .await?;
 
         Ok(tx_response)
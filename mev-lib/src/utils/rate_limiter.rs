@@ -0,0 +1,168 @@
+use std::{
+    future::Future,
+    sync::{Arc, Mutex},
+};
+use tokio::{
+    sync::{OwnedSemaphorePermit, Semaphore},
+    time::{sleep, Duration, Instant},
+};
+use tracing::error;
+
+/// Token bucket holding up to one second of calls, refilled continuously.
+///
+struct TokenBucket {
+    qps: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    /// Takes a token, or returns how long to wait for the next one.
+    ///
+    fn try_take(&mut self) -> Option<Duration> {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.qps).min(self.qps);
+        self.last_refill = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            return None;
+        }
+
+        Some(Duration::from_secs_f64((1.0 - self.tokens) / self.qps))
+    }
+}
+
+/// Limits the RPC calls shared by all services to respect the provider quotas,
+/// both the number of calls in flight and the calls per second.
+/// Each limit is disabled when not configured.
+///
+pub struct RpcRateLimiter {
+    concurrency: Option<Arc<Semaphore>>,
+    bucket: Option<Mutex<TokenBucket>>,
+}
+
+impl RpcRateLimiter {
+    pub fn new(max_concurrent: Option<usize>, qps: Option<u32>) -> Self {
+        RpcRateLimiter {
+            concurrency: max_concurrent.map(|max| Arc::new(Semaphore::new(max.max(1)))),
+            bucket: qps.filter(|qps| *qps > 0).map(|qps| {
+                Mutex::new(TokenBucket {
+                    qps: qps as f64,
+                    tokens: qps as f64,
+                    last_refill: Instant::now(),
+                })
+            }),
+        }
+    }
+
+    /// Waits until a call is allowed.
+    /// The returned permit must be held until the call completes,
+    /// calls beyond the limits are delayed, never rejected.
+    ///
+    pub async fn acquire(&self) -> Option<OwnedSemaphorePermit> {
+        // wait for a slot first, so no token is spent while queued
+        let permit = match &self.concurrency {
+            Some(semaphore) => Arc::clone(semaphore).acquire_owned().await.ok(),
+            None => None,
+        };
+
+        if let Some(bucket) = &self.bucket {
+            loop {
+                let wait = match bucket.lock() {
+                    Ok(mut bucket) => bucket.try_take(),
+                    Err(e) => {
+                        error!("Failed to lock RPC rate limiter: {}", e);
+                        None
+                    }
+                };
+
+                match wait {
+                    Some(wait) => sleep(wait).await,
+                    None => break,
+                }
+            }
+        }
+
+        permit
+    }
+
+    /// Runs the RPC call once allowed, holding the permit only for the call itself
+    /// so nested calls cannot starve each other.
+    ///
+    pub async fn run<F: Future>(&self, call: F) -> F::Output {
+        let _permit = self.acquire().await;
+        call.await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[tokio::test]
+    async fn burst_beyond_qps_is_delayed() {
+        let limiter = RpcRateLimiter::new(None, Some(20));
+        let start = Instant::now();
+
+        // the bucket starts full, one second of calls goes through at once
+        for _ in 0..20 {
+            limiter.run(async {}).await;
+        }
+        assert!(start.elapsed() < Duration::from_millis(50));
+
+        // the 5 calls beyond it wait for the refill, 50ms each
+        for _ in 0..5 {
+            limiter.run(async {}).await;
+        }
+        assert!(start.elapsed() >= Duration::from_millis(200));
+    }
+
+    #[tokio::test]
+    async fn calls_beyond_max_concurrent_are_delayed() {
+        let limiter = Arc::new(RpcRateLimiter::new(Some(2), None));
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let max_in_flight = Arc::new(AtomicUsize::new(0));
+        let start = Instant::now();
+
+        let tasks: Vec<_> = (0..4)
+            .map(|_| {
+                let limiter = Arc::clone(&limiter);
+                let in_flight = Arc::clone(&in_flight);
+                let max_in_flight = Arc::clone(&max_in_flight);
+
+                tokio::spawn(async move {
+                    limiter
+                        .run(async {
+                            let current = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                            max_in_flight.fetch_max(current, Ordering::SeqCst);
+                            sleep(Duration::from_millis(50)).await;
+                            in_flight.fetch_sub(1, Ordering::SeqCst);
+                        })
+                        .await
+                })
+            })
+            .collect();
+
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        assert_eq!(max_in_flight.load(Ordering::SeqCst), 2);
+        assert!(start.elapsed() >= Duration::from_millis(100));
+    }
+
+    #[tokio::test]
+    async fn unconfigured_limits_do_not_delay() {
+        let limiter = RpcRateLimiter::new(None, None);
+        let start = Instant::now();
+
+        for _ in 0..100 {
+            assert!(limiter.acquire().await.is_none());
+        }
+
+        assert!(start.elapsed() < Duration::from_millis(50));
+    }
+}
@@ -0,0 +1,206 @@
+use crate::utils::{self, rate_limiter::RpcRateLimiter, sui_client::ResilientClient};
+
+use anyhow::{anyhow, Result};
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+use sui_sdk::SuiClient;
+use tokio::time::Duration;
+use tracing::{error, warn};
+
+/// Sui clients of several RPC endpoints, in failover order.
+/// Calls go to the current healthy client
+/// and move on to the next one, round-robin, on network errors.
+/// Each client is rebuilt on network errors before the pool gives up on its endpoint.
+/// Every call goes through the limiter shared by the whole process.
+/// Generic over the client so the failover can be tested without an endpoint.
+///
+pub struct RpcPool<C = SuiClient> {
+    clients: Vec<ResilientClient<C>>,
+    current: AtomicUsize,
+    limiter: Arc<RpcRateLimiter>,
+}
+
+impl RpcPool<SuiClient> {
+    /// Builds a client per RPC URL, each reconnecting up to `max_retries` times
+    /// on network errors with an exponential backoff from `base_delay`.
+    /// Unreachable endpoints are skipped, it fails only if none can be reached.
+    ///
+    pub async fn new(
+        rpc_urls: &[String],
+        max_retries: u32,
+        base_delay: Duration,
+        limiter: Arc<RpcRateLimiter>,
+    ) -> Result<Self> {
+        let mut clients = vec![];
+        let mut last_error = None;
+
+        for rpc_url in rpc_urls {
+            match ResilientClient::connect(rpc_url, max_retries, base_delay).await {
+                Ok(client) => clients.push(client),
+                Err(e) => {
+                    error!("Failed to build Sui client for {}: {}", rpc_url, e);
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        // keep the Sui error so callers can tell a network error
+        if clients.is_empty() {
+            return Err(match last_error {
+                Some(e) => anyhow::Error::from(e).context("None of the RPC URLs is reachable"),
+                None => anyhow!("No RPC URL configured"),
+            });
+        }
+
+        Ok(Self::from_clients(clients, limiter))
+    }
+}
+
+impl<C> RpcPool<C> {
+    fn from_clients(clients: Vec<ResilientClient<C>>, limiter: Arc<RpcRateLimiter>) -> Self {
+        RpcPool {
+            clients,
+            current: AtomicUsize::new(0),
+            limiter,
+        }
+    }
+
+    /// Returns the current healthy client,
+    /// prefer `call` which fails over to the next client on network errors.
+    ///
+    pub async fn client(&self) -> Arc<C> {
+        self.clients[self.current.load(Ordering::Relaxed) % self.clients.len()]
+            .client()
+            .await
+    }
+
+    /// RPC URL of the current healthy client.
+    ///
+    pub fn rpc_url(&self) -> &str {
+        self.clients[self.current.load(Ordering::Relaxed) % self.clients.len()].rpc_url()
+    }
+
+    /// Runs the call against the current client.
+    /// On network errors the client is rebuilt and the call retried with a backoff,
+    /// see `ResilientClient::call`, then the next client becomes the current one.
+    /// Each client is tried once that way.
+    /// Every attempt waits for the rate limiter, the backoff sleeps hold no permit.
+    /// Other errors are returned immediately.
+    ///
+    pub async fn call<F, Fut, T, E>(&self, call_fn: F) -> Result<T>
+    where
+        F: Fn(Arc<C>) -> Fut,
+        Fut: std::future::Future<Output = std::result::Result<T, E>>,
+        E: Into<anyhow::Error>,
+    {
+        let start = self.current.load(Ordering::Relaxed);
+        let mut last_error = None;
+
+        for offset in 0..self.clients.len() {
+            let index = (start + offset) % self.clients.len();
+            let client = &self.clients[index];
+
+            match client.call(|c| self.limiter.run(call_fn(c))).await {
+                Ok(value) => {
+                    self.current.store(index, Ordering::Relaxed);
+                    return Ok(value);
+                }
+                Err(e) if utils::is_network_error(&e) => {
+                    warn!(
+                        "Sui RPC {} failed, trying the next one: {}",
+                        client.rpc_url(),
+                        e
+                    );
+                    last_error = Some(e);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| anyhow!("No Sui client available")))
+    }
+}
+
+/// Pool without any client, for the tests of the callers never reaching the RPC.
+///
+#[cfg(test)]
+impl<C> RpcPool<C> {
+    pub(crate) fn without_clients() -> Self {
+        Self::from_clients(vec![], Arc::new(RpcRateLimiter::new(None, None)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::future::FutureExt;
+
+    /// Client of an endpoint which is either down or answering its name.
+    struct MockClient {
+        name: &'static str,
+        up: bool,
+    }
+
+    impl MockClient {
+        async fn name(&self) -> Result<&'static str> {
+            if self.up {
+                Ok(self.name)
+            } else {
+                Err(std::io::Error::new(std::io::ErrorKind::ConnectionRefused, "down").into())
+            }
+        }
+    }
+
+    async fn pool(endpoints: &[(&'static str, bool)]) -> RpcPool<MockClient> {
+        let mut clients = vec![];
+        for &(name, up) in endpoints {
+            let client = ResilientClient::with_connect(
+                name,
+                Box::new(move || async move { Ok(MockClient { name, up }) }.boxed()),
+                0,
+                Duration::ZERO,
+            )
+            .await
+            .unwrap();
+            clients.push(client);
+        }
+
+        RpcPool::from_clients(clients, Arc::new(RpcRateLimiter::new(None, None)))
+    }
+
+    #[tokio::test]
+    async fn call_skips_failing_client() {
+        let pool = pool(&[("first", false), ("second", true)]).await;
+
+        let name = pool.call(|client| async move { client.name().await }).await;
+
+        assert_eq!(name.unwrap(), "second");
+        assert_eq!(pool.rpc_url(), "second");
+    }
+
+    #[tokio::test]
+    async fn call_fails_when_all_clients_fail() {
+        let pool = pool(&[("first", false), ("second", false)]).await;
+
+        let result = pool.call(|client| async move { client.name().await }).await;
+
+        assert!(result.is_err_and(|e| utils::is_network_error(&e)));
+        assert_eq!(pool.rpc_url(), "first");
+    }
+
+    #[tokio::test]
+    async fn call_does_not_fail_over_on_other_errors() {
+        let pool = pool(&[("first", true), ("second", true)]).await;
+
+        let result: Result<()> = pool
+            .call(|client| async move { Err(anyhow!("invalid response from {}", client.name)) })
+            .await;
+
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "invalid response from first"
+        );
+    }
+}
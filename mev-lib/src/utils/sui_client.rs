@@ -0,0 +1,270 @@
+use crate::utils;
+
+use anyhow::Result;
+use futures::future::{BoxFuture, FutureExt};
+use std::sync::Arc;
+use sui_sdk::{SuiClient, SuiClientBuilder};
+use tokio::{
+    sync::RwLock,
+    time::{sleep, Duration},
+};
+use tracing::{error, warn};
+
+/// Builds a new client for the endpoint, called again on every reconnect.
+pub type Connect<C> = Box<dyn Fn() -> BoxFuture<'static, Result<C>> + Send + Sync>;
+
+/// Client which is rebuilt when the RPC endpoint fails with a network error,
+/// so an endpoint going down does not leave the process with a dead client.
+/// Generic over the client so the reconnects can be tested without an endpoint.
+///
+pub struct ResilientClient<C> {
+    rpc_url: String,
+    connect: Connect<C>,
+    max_retries: u32,
+    base_delay: Duration,
+    client: RwLock<Arc<C>>,
+}
+
+/// Sui client rebuilt the same way it was built at startup, from its RPC and websocket URLs.
+pub type ResilientSuiClient = ResilientClient<SuiClient>;
+
+impl ResilientClient<SuiClient> {
+    /// Builds the client, retrying with an exponential backoff
+    /// if the RPC endpoint is not reachable yet.
+    ///
+    pub async fn new(
+        rpc_url: &str,
+        ws_url: Option<&str>,
+        max_retries: u32,
+        base_delay: Duration,
+    ) -> Result<Self> {
+        let connect = Self::sui_connect(rpc_url, ws_url);
+        let client = utils::retry_with_backoff(&connect, max_retries, base_delay).await?;
+
+        Ok(Self::from_client(
+            rpc_url,
+            connect,
+            client,
+            max_retries,
+            base_delay,
+        ))
+    }
+
+    /// Builds the client with a single attempt,
+    /// e.g. for a pool which skips the unreachable endpoints.
+    ///
+    pub async fn connect(rpc_url: &str, max_retries: u32, base_delay: Duration) -> Result<Self> {
+        Self::with_connect(
+            rpc_url,
+            Self::sui_connect(rpc_url, None),
+            max_retries,
+            base_delay,
+        )
+        .await
+    }
+
+    fn sui_connect(rpc_url: &str, ws_url: Option<&str>) -> Connect<SuiClient> {
+        let rpc_url = rpc_url.to_string();
+        let ws_url = ws_url.map(|url| url.to_string());
+
+        Box::new(move || {
+            let rpc_url = rpc_url.clone();
+            let ws_url = ws_url.clone();
+
+            async move {
+                let mut builder = SuiClientBuilder::default();
+                if let Some(ws_url) = ws_url {
+                    builder = builder.ws_url(ws_url);
+                }
+
+                Ok(builder.build(rpc_url).await?)
+            }
+            .boxed()
+        })
+    }
+}
+
+impl<C> ResilientClient<C> {
+    /// Builds the client with `connect`, with a single attempt.
+    ///
+    pub async fn with_connect(
+        rpc_url: &str,
+        connect: Connect<C>,
+        max_retries: u32,
+        base_delay: Duration,
+    ) -> Result<Self> {
+        let client = connect().await?;
+
+        Ok(Self::from_client(
+            rpc_url,
+            connect,
+            client,
+            max_retries,
+            base_delay,
+        ))
+    }
+
+    fn from_client(
+        rpc_url: &str,
+        connect: Connect<C>,
+        client: C,
+        max_retries: u32,
+        base_delay: Duration,
+    ) -> Self {
+        ResilientClient {
+            rpc_url: rpc_url.to_string(),
+            connect,
+            max_retries,
+            base_delay,
+            client: RwLock::new(Arc::new(client)),
+        }
+    }
+
+    /// RPC URL the client is built for.
+    ///
+    pub fn rpc_url(&self) -> &str {
+        &self.rpc_url
+    }
+
+    /// Returns the current client,
+    /// callers should not keep it across reconnects.
+    ///
+    pub async fn client(&self) -> Arc<C> {
+        Arc::clone(&*self.client.read().await)
+    }
+
+    /// Rebuilds the client after `failed_client` returned a network error.
+    /// The client is left untouched if another caller already replaced it.
+    ///
+    pub async fn reconnect(&self, failed_client: &Arc<C>) -> Result<()> {
+        let mut client = self.client.write().await;
+        if !Arc::ptr_eq(&client, failed_client) {
+            return Ok(());
+        }
+
+        *client = Arc::new((self.connect)().await?);
+        warn!("Sui client reconnected to {}", self.rpc_url);
+
+        Ok(())
+    }
+
+    /// Runs the call against the current client.
+    /// On network errors the client is rebuilt and the call retried
+    /// up to `max_retries` times with an exponential backoff.
+    /// Other errors are returned immediately.
+    ///
+    pub async fn call<F, Fut, T, E>(&self, call_fn: F) -> Result<T>
+    where
+        F: Fn(Arc<C>) -> Fut,
+        Fut: std::future::Future<Output = std::result::Result<T, E>>,
+        E: Into<anyhow::Error>,
+    {
+        let mut attempt = 0;
+
+        loop {
+            let client = self.client().await;
+
+            match call_fn(Arc::clone(&client)).await.map_err(Into::into) {
+                Ok(value) => return Ok(value),
+                Err(e) if attempt < self.max_retries && utils::is_network_error(&e) => {
+                    let delay = self.base_delay * 2u32.saturating_pow(attempt);
+                    attempt += 1;
+                    warn!(
+                        "Sui RPC {} network error, reconnect {}/{} in {:?}: {}",
+                        self.rpc_url, attempt, self.max_retries, delay, e
+                    );
+                    sleep(delay).await;
+
+                    if let Err(e) = self.reconnect(&client).await {
+                        error!("Failed to reconnect Sui client: {}", e);
+                    }
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Transport whose first connection is dropped by the endpoint,
+    /// the connections made after it are served.
+    struct MockTransport {
+        connection: usize,
+    }
+
+    impl MockTransport {
+        async fn ping(&self) -> Result<usize> {
+            if self.connection == 0 {
+                Err(std::io::Error::new(std::io::ErrorKind::ConnectionReset, "dropped").into())
+            } else {
+                Ok(self.connection)
+            }
+        }
+    }
+
+    async fn client(max_retries: u32) -> (ResilientClient<MockTransport>, Arc<AtomicUsize>) {
+        let connections = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&connections);
+
+        let client = ResilientClient::with_connect(
+            "mock",
+            Box::new(move || {
+                let connection = counter.fetch_add(1, Ordering::SeqCst);
+                async move { Ok(MockTransport { connection }) }.boxed()
+            }),
+            max_retries,
+            Duration::ZERO,
+        )
+        .await
+        .unwrap();
+
+        (client, connections)
+    }
+
+    #[tokio::test]
+    async fn call_succeeds_after_reconnect() {
+        let (client, connections) = client(2).await;
+
+        let connection = client
+            .call(|transport| async move { transport.ping().await })
+            .await;
+
+        assert_eq!(connection.unwrap(), 1);
+        assert_eq!(connections.load(Ordering::SeqCst), 2);
+
+        // the rebuilt client is kept for the next calls
+        let connection = client
+            .call(|transport| async move { transport.ping().await })
+            .await;
+        assert_eq!(connection.unwrap(), 1);
+        assert_eq!(connections.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn call_fails_without_retries() {
+        let (client, connections) = client(0).await;
+
+        let result = client
+            .call(|transport| async move { transport.ping().await })
+            .await;
+
+        assert!(result.is_err_and(|e| utils::is_network_error(&e)));
+        assert_eq!(connections.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn reconnect_keeps_client_replaced_by_another_caller() {
+        let (client, connections) = client(0).await;
+        let failed = client.client().await;
+
+        client.reconnect(&failed).await.unwrap();
+        client.reconnect(&failed).await.unwrap();
+
+        assert_eq!(connections.load(Ordering::SeqCst), 2);
+        assert_eq!(client.client().await.connection, 1);
+    }
+}
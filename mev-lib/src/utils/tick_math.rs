@@ -20,12 +20,19 @@ pub fn as_i32(tick: &str) -> Result<i32> {
     i32_from_u32(tick_u32)
 }
 
+/// Tick index from its on-chain bits, the two's complement of the signed tick.
+/// Fails on ticks out of the tick bounds, which are not valid tick bits.
+///
 pub fn i32_from_u32(tick: u32) -> Result<i32> {
-    if sign_u32(tick) == 0 {
-        Ok(tick as i32)
-    } else {
-        Ok(-(u32_neg(tick - 1)? as i32))
+    // a plain cast reinterprets the two's complement,
+    // negating the bits overflowed on i32::MIN
+    let tick_i32 = tick as i32;
+
+    if tick_i32 < -tick_bound() || tick_i32 > tick_bound() {
+        return Err(anyhow!("Tick {} out of bounds: {}", tick_i32, tick_bound()));
     }
+
+    Ok(tick_i32)
 }
 
 pub fn sqrt_price_from_tick(tick: Decimal) -> Result<Decimal> {
@@ -44,6 +51,46 @@ pub fn sqrt_price_from_tick(tick: Decimal) -> Result<Decimal> {
     Ok(sqrt_price)
 }
 
+/// Sqrt price of a tick, without the Q64 scaling.
+/// Formula: sqrt_price = 1.0001^(tick / 2), so that price = sqrt_price^2 = 1.0001^tick
+/// as in `utils::convert_q64_to_decimal_price`.
+///
+pub fn tick_to_sqrt_price(tick: i32) -> Result<Decimal> {
+    sqrt_price_from_tick(Decimal::from(tick))
+}
+
+/// Tick of a sqrt price, without the Q64 scaling.
+/// Returns the greatest tick whose sqrt price is lower than or equal to `sqrt_price`.
+/// Formula: tick = floor(2 * ln(sqrt_price) / ln(1.0001)),
+/// the f64 estimate is then corrected against `tick_to_sqrt_price`.
+///
+pub fn sqrt_price_to_tick(sqrt_price: Decimal) -> Result<i32> {
+    if sqrt_price <= Decimal::ZERO {
+        return Err(anyhow!("Sqrt price must be positive: {}", sqrt_price));
+    }
+    if sqrt_price < tick_to_sqrt_price(-tick_bound())?
+        || sqrt_price > tick_to_sqrt_price(tick_bound())?
+    {
+        return Err(anyhow!("Sqrt price {} out of tick bounds", sqrt_price));
+    }
+
+    let sqrt_price_f64 = sqrt_price
+        .to_f64()
+        .ok_or_else(|| anyhow!("sqrt_price to f64 failed"))?;
+    let estimate = (2.0 * sqrt_price_f64.ln() / 1.0001_f64.ln()).floor() as i32;
+    let mut tick = estimate.clamp(-tick_bound(), tick_bound());
+
+    // the f64 estimate may be off by one around tick boundaries
+    while tick > -tick_bound() && tick_to_sqrt_price(tick)? > sqrt_price {
+        tick -= 1;
+    }
+    while tick < tick_bound() && tick_to_sqrt_price(tick + 1)? <= sqrt_price {
+        tick += 1;
+    }
+
+    Ok(tick)
+}
+
 pub fn slippage_from_sqrt_price(
     current_sqrt_price: Decimal,
     target_sqrt_price: Decimal,
@@ -77,6 +124,25 @@ pub fn target_sqrt_price_by_slippage(
         .ok_or_else(|| anyhow!("Failed to calculate target sqrt price"))
 }
 
+/// Signed liquidity net of a tick from the bits of its Move `I128`,
+/// as stored in the pool ticks table.
+///
+pub fn liquidity_net_from_bits(bits: &str) -> Result<Decimal> {
+    let bits = bits
+        .parse::<u128>()
+        .map_err(|e| anyhow!("Invalid liquidity net {}: {}", bits, e))?;
+
+    Decimal::from_i128(bits as i128)
+        .ok_or_else(|| anyhow!("Liquidity net out of range: {}", bits as i128))
+}
+
+/// Bits of the Move `I128` of a signed liquidity net,
+/// as stored in the pool ticks table.
+///
+pub fn liquidity_net_to_bits(liquidity_net: i128) -> String {
+    (liquidity_net as u128).to_string()
+}
+
 pub fn delta_amount_from_sqrt_price(
     current_sqrt_price: Decimal,
     target_sqrt_price: Decimal,
@@ -146,6 +212,66 @@ pub fn amount_out_given_target_sqrt_price(
     Ok(-amount_out)
 }
 
+/// Price impact of a swap in basis points, from its start and end sqrt prices.
+///
+pub fn price_impact_bps_from_sqrt_price(
+    start_sqrt_price: Decimal,
+    end_sqrt_price: Decimal,
+) -> Result<Decimal> {
+    Ok(slippage_from_sqrt_price(start_sqrt_price, end_sqrt_price)? * Decimal::from(10_000))
+}
+
+/// Largest amount in whose price impact stays within `max_price_impact_bps`,
+/// assuming the swap does not cross an initialized tick.
+///
+pub fn max_amount_in_given_price_impact(
+    current_sqrt_price: Decimal,
+    liquidity: Decimal,
+    max_price_impact_bps: u64,
+    zero_to_one: bool,
+) -> Result<Decimal> {
+    let max_slippage = Decimal::from(max_price_impact_bps) / Decimal::from(10_000);
+    let target_sqrt_price =
+        target_sqrt_price_by_slippage(current_sqrt_price, max_slippage, zero_to_one)?;
+
+    let (delta_x, delta_y) =
+        delta_amount_from_sqrt_price(current_sqrt_price, target_sqrt_price, liquidity)?;
+
+    // x goes into the pool when price goes down, y when price goes up
+    if zero_to_one {
+        Ok(delta_x)
+    } else {
+        Ok(delta_y)
+    }
+}
+
+/// Checks the price impact of swapping `amount_in` against `max_price_impact_bps`.
+/// Returns the amount in to use: `amount_in` itself when the impact is acceptable,
+/// otherwise the largest amount within the limit.
+///
+pub fn amount_in_within_price_impact(
+    current_sqrt_price: Decimal,
+    amount_in: Decimal,
+    liquidity: Decimal,
+    max_price_impact_bps: u64,
+    zero_to_one: bool,
+) -> Result<Decimal> {
+    let target_sqrt_price =
+        target_sqrt_price_given_amount_in(current_sqrt_price, amount_in, liquidity, zero_to_one)?;
+    let price_impact_bps = price_impact_bps_from_sqrt_price(current_sqrt_price, target_sqrt_price)?;
+
+    if price_impact_bps <= Decimal::from(max_price_impact_bps) {
+        return Ok(amount_in);
+    }
+
+    max_amount_in_given_price_impact(
+        current_sqrt_price,
+        liquidity,
+        max_price_impact_bps,
+        zero_to_one,
+    )
+}
+
 // 0 is positive, 1 is negative
 pub fn sign(tick: &str) -> Result<u8> {
     let tick_u32 = as_u32(tick)?;
@@ -170,3 +296,203 @@ pub fn u32_neg(tick: u32) -> Result<u32> {
 pub fn tick_bound() -> i32 {
     443636
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn price_impact_bps(amount_in: Decimal, zero_to_one: bool) -> Decimal {
+        let target_sqrt_price = target_sqrt_price_given_amount_in(
+            Decimal::ONE,
+            amount_in,
+            Decimal::from(1_000_000),
+            zero_to_one,
+        )
+        .unwrap();
+
+        price_impact_bps_from_sqrt_price(Decimal::ONE, target_sqrt_price).unwrap()
+    }
+
+    #[test]
+    fn amount_in_within_price_impact_keeps_small_swaps() {
+        for zero_to_one in [true, false] {
+            let amount_in = amount_in_within_price_impact(
+                Decimal::ONE,
+                Decimal::from(1_000),
+                Decimal::from(1_000_000),
+                100,
+                zero_to_one,
+            )
+            .unwrap();
+
+            // ~20 bps of impact
+            assert_eq!(amount_in, Decimal::from(1_000));
+        }
+    }
+
+    #[test]
+    fn amount_in_within_price_impact_shrinks_large_swaps() {
+        for zero_to_one in [true, false] {
+            assert!(price_impact_bps(Decimal::from(100_000), zero_to_one) > Decimal::from(100));
+
+            let amount_in = amount_in_within_price_impact(
+                Decimal::ONE,
+                Decimal::from(100_000),
+                Decimal::from(1_000_000),
+                100,
+                zero_to_one,
+            )
+            .unwrap();
+
+            // the largest amount within the limit, its impact is the limit itself
+            assert!(amount_in < Decimal::from(100_000));
+            let impact = price_impact_bps(amount_in, zero_to_one);
+            assert!(
+                (impact - Decimal::from(100)).abs() < Decimal::new(1, 2),
+                "impact {} bps",
+                impact
+            );
+        }
+    }
+
+    #[test]
+    fn price_impact_bps_from_sqrt_price_of_a_doubled_price() {
+        let sqrt_2 = Decimal::TWO.sqrt().unwrap();
+
+        let impact = price_impact_bps_from_sqrt_price(Decimal::ONE, sqrt_2).unwrap();
+
+        assert!((impact - Decimal::from(10_000)).abs() < Decimal::new(1, 6));
+    }
+
+    /// Ticks across the whole tick range, both bounds and around zero included.
+    fn ticks() -> impl Iterator<Item = i32> {
+        (-tick_bound()..=tick_bound()).step_by(997).chain([
+            -tick_bound() + 1,
+            -1,
+            0,
+            1,
+            tick_bound() - 1,
+            tick_bound(),
+        ])
+    }
+
+    #[test]
+    fn tick_to_sqrt_price_of_reference_ticks() {
+        assert_eq!(tick_to_sqrt_price(0).unwrap(), Decimal::ONE);
+
+        // price = 1.0001^tick
+        for (tick, price) in [
+            (2, 1.0001),
+            (-2, 1.0 / 1.0001),
+            (20_000, 7.388_317_279_514_934),
+        ] {
+            let sqrt_price = tick_to_sqrt_price(tick).unwrap().to_f64().unwrap();
+
+            assert!(
+                ((sqrt_price * sqrt_price) / price - 1.0).abs() < 1e-12,
+                "tick {} price {}",
+                tick,
+                sqrt_price * sqrt_price
+            );
+        }
+    }
+
+    #[test]
+    fn tick_to_sqrt_price_is_increasing() {
+        let mut previous = Decimal::ZERO;
+
+        for tick in (-tick_bound()..=tick_bound()).step_by(997) {
+            let sqrt_price = tick_to_sqrt_price(tick).unwrap();
+
+            assert!(sqrt_price > previous, "tick {}", tick);
+            previous = sqrt_price;
+        }
+    }
+
+    #[test]
+    fn sqrt_price_to_tick_round_trips_over_tick_range() {
+        for tick in ticks() {
+            let sqrt_price = tick_to_sqrt_price(tick).unwrap();
+
+            assert_eq!(sqrt_price_to_tick(sqrt_price).unwrap(), tick);
+        }
+    }
+
+    #[test]
+    fn sqrt_price_to_tick_rounds_down_between_ticks() {
+        for tick in ticks().filter(|tick| *tick < tick_bound()) {
+            let lower = tick_to_sqrt_price(tick).unwrap();
+            let upper = tick_to_sqrt_price(tick + 1).unwrap();
+            let between = (lower + upper) / Decimal::TWO;
+
+            assert_eq!(sqrt_price_to_tick(between).unwrap(), tick);
+        }
+    }
+
+    #[test]
+    fn tick_to_sqrt_price_out_of_bounds_is_an_error() {
+        assert!(tick_to_sqrt_price(tick_bound() + 1).is_err());
+        assert!(tick_to_sqrt_price(-tick_bound() - 1).is_err());
+        assert!(tick_to_sqrt_price(i32::MAX).is_err());
+        assert!(tick_to_sqrt_price(i32::MIN).is_err());
+    }
+
+    #[test]
+    fn sqrt_price_to_tick_out_of_bounds_is_an_error() {
+        let min_sqrt_price = tick_to_sqrt_price(-tick_bound()).unwrap();
+        let max_sqrt_price = tick_to_sqrt_price(tick_bound()).unwrap();
+
+        assert!(sqrt_price_to_tick(Decimal::ZERO).is_err());
+        assert!(sqrt_price_to_tick(-Decimal::ONE).is_err());
+        assert!(sqrt_price_to_tick(min_sqrt_price * Decimal::new(9, 1)).is_err());
+        assert!(sqrt_price_to_tick(max_sqrt_price * Decimal::TWO).is_err());
+    }
+
+    #[test]
+    fn i32_from_u32_maps_twos_complement_over_tick_range() {
+        for tick in ticks() {
+            assert_eq!(i32_from_u32(tick as u32).unwrap(), tick, "tick {}", tick);
+        }
+    }
+
+    #[test]
+    fn i32_from_u32_of_bounds_and_negatives() {
+        assert_eq!(i32_from_u32(0).unwrap(), 0);
+        assert_eq!(i32_from_u32(u32::MAX).unwrap(), -1);
+        assert_eq!(i32_from_u32(443636).unwrap(), tick_bound());
+        assert_eq!(i32_from_u32(4294523660).unwrap(), -tick_bound());
+    }
+
+    #[test]
+    fn i32_from_u32_out_of_bounds_is_an_error() {
+        assert!(i32_from_u32(tick_bound() as u32 + 1).is_err());
+        assert!(i32_from_u32((-tick_bound() - 1) as u32).is_err());
+        // the bits of i32::MIN and i32::MAX
+        assert!(i32_from_u32(1 << 31).is_err());
+        assert!(i32_from_u32(u32::MAX >> 1).is_err());
+    }
+
+    #[test]
+    fn as_i32_parses_negative_tick_bits() {
+        assert_eq!(as_i32("4294967286").unwrap(), -10);
+        assert!(as_i32("-10").is_err());
+        assert!(as_i32("4294967296").is_err());
+    }
+
+    #[test]
+    fn liquidity_net_bits_round_trip() {
+        for liquidity_net in [0, 500, -500, i64::MAX as i128, i64::MIN as i128] {
+            let bits = liquidity_net_to_bits(liquidity_net);
+
+            assert_eq!(
+                liquidity_net_from_bits(&bits).unwrap(),
+                Decimal::from_i128(liquidity_net).unwrap()
+            );
+        }
+
+        assert_eq!(
+            liquidity_net_to_bits(-1),
+            "340282366920938463463374607431768211455"
+        );
+    }
+}
@@ -0,0 +1,91 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+use tracing::error;
+
+/// In-memory cache whose entries expire `ttl` after being inserted,
+/// for lookups repeated within a short time, e.g. by the events of a checkpoint.
+/// Expired entries are dropped on insert.
+///
+pub struct TtlCache<V> {
+    ttl: Duration,
+    entries: Mutex<HashMap<String, (V, Instant)>>,
+}
+
+impl<V: Clone> TtlCache<V> {
+    pub fn new(ttl: Duration) -> Self {
+        TtlCache {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the cached value if it has not expired yet.
+    ///
+    pub fn get(&self, key: &str) -> Option<V> {
+        match self.entries.lock() {
+            Ok(entries) => entries
+                .get(key)
+                .filter(|(_, inserted_at)| inserted_at.elapsed() < self.ttl)
+                .map(|(value, _)| value.clone()),
+            Err(e) => {
+                error!("Failed to lock TTL cache: {}", e);
+                None
+            }
+        }
+    }
+
+    pub fn insert(&self, key: &str, value: V) {
+        match self.entries.lock() {
+            Ok(mut entries) => {
+                entries.retain(|_, (_, inserted_at)| inserted_at.elapsed() < self.ttl);
+                entries.insert(key.to_string(), (value, Instant::now()));
+            }
+            Err(e) => error!("Failed to lock TTL cache: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_returns_value_until_it_expires() {
+        let cache = TtlCache::new(Duration::from_millis(50));
+        cache.insert("0x1", "0xobligation".to_string());
+
+        assert_eq!(cache.get("0x1"), Some("0xobligation".to_string()));
+        assert_eq!(cache.get("0x2"), None);
+
+        std::thread::sleep(Duration::from_millis(60));
+        assert_eq!(cache.get("0x1"), None);
+    }
+
+    #[test]
+    fn insert_drops_expired_entries() {
+        let cache = TtlCache::new(Duration::from_millis(50));
+        cache.insert("0x1", 1);
+
+        std::thread::sleep(Duration::from_millis(60));
+        cache.insert("0x2", 2);
+
+        let entries = cache.entries.lock().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert!(entries.contains_key("0x2"));
+    }
+
+    #[test]
+    fn insert_refreshes_the_entry() {
+        let cache = TtlCache::new(Duration::from_millis(50));
+        cache.insert("0x1", 1);
+
+        std::thread::sleep(Duration::from_millis(30));
+        cache.insert("0x1", 2);
+        std::thread::sleep(Duration::from_millis(30));
+
+        assert_eq!(cache.get("0x1"), Some(2));
+    }
+}
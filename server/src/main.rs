@@ -1,5 +1,6 @@
 use mev_lib::{
     config::Config,
+    constant,
     indexer::{
         self,
         onchain_indexer::{self, OnchainIndexer},
@@ -11,55 +12,53 @@ use mev_lib::{
         registry::ServiceRegistry,
     },
     types::Borrower,
-    utils::{self, ptb::PTBHelper},
+    utils::{
+        self, ptb::PTBHelper, rate_limiter::RpcRateLimiter, rpc_pool::RpcPool,
+        sui_client::ResilientSuiClient,
+    },
 };
 
 use db::repositories::{
-    borrower::BorrowerRepositoryImpl, coin::CoinRepositoryImpl, metric::MetricRepositoryImpl,
+    borrower::BorrowerRepositoryImpl, coin::CoinRepositoryImpl,
+    failed_event::FailedEventRepositoryImpl, metric::MetricRepositoryImpl,
     pool::PoolRepositoryImpl, pool_tick::PoolTickRepositoryImpl,
-    shared_object::SharedObjectRepositoryImpl, user_borrow::UserBorrowRepositoryImpl,
-    user_deposit::UserDepositRepositoryImpl, BorrowerRepository, CoinRepository, MetricRepository,
-    PoolRepository, PoolTickRepository, SharedObjectRepository, UserBorrowRepository,
+    shared_object::SharedObjectRepositoryImpl, swap_event::SwapEventRepositoryImpl,
+    user_borrow::UserBorrowRepositoryImpl, user_deposit::UserDepositRepositoryImpl,
+    BorrowerRepository, CoinRepository, FailedEventRepository, MetricRepository, PoolRepository,
+    PoolTickRepository, SharedObjectRepository, SwapEventRepository, UserBorrowRepository,
     UserDepositRepository,
 };
-use db::{establish_connection_pool, run_migrations};
+use db::{establish_connection_pool_with_retry, run_migrations};
 
 use anyhow::Result;
+use axum::{extract::State, http::StatusCode, routing::get, Router};
 use futures::future;
-use std::sync::{
-    atomic::{AtomicBool, AtomicU64, Ordering},
-    Arc,
-};
+use std::sync::{atomic::AtomicU64, Arc};
 use sui_data_ingestion_core::setup_single_workflow;
-use sui_sdk::SuiClientBuilder;
 use tokio::{
     self,
     sync::{mpsc, RwLock},
     time::{sleep, Duration},
 };
 use tracing::{debug, error, info, instrument, trace, warn};
-use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+use tracing_subscriber::util::SubscriberInitExt;
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let config = Arc::new(Config::load_toml()?);
 
-    let log_level = utils::convert_log_level_to_tracing_level(&config.log_level);
-    let filter = EnvFilter::from_default_env().add_directive(log_level.into());
-
-    tracing_subscriber::registry()
-        .with(fmt::layer())
-        .with(filter)
-        .try_init()?;
+    utils::build_subscriber(&config.log_level, &config.log_format).try_init()?;
 
     warn!("Starting server...");
 
     // connect database
 
-    let db_conn = establish_connection_pool(
+    let db_conn = establish_connection_pool_with_retry(
         &config.database.database_url,
         config.database.db_connection_pool_max_size,
         config.database.db_connection_pool_idle_size,
+        config.database.db_connection_max_retries,
+        Duration::from_millis(config.database.db_connection_retry_delay_ms),
     )?;
     warn!("Connected to database {}", &config.database.database_url);
 
@@ -92,25 +91,48 @@ async fn main() -> Result<()> {
     let shared_object_repo: Arc<dyn SharedObjectRepository + Send + Sync> =
         Arc::new(SharedObjectRepositoryImpl::new(db_conn.clone()));
 
+    let failed_event_repo: Arc<dyn FailedEventRepository + Send + Sync> =
+        Arc::new(FailedEventRepositoryImpl::new(db_conn.clone()));
+    let swap_event_repo: Arc<dyn SwapEventRepository + Send + Sync> =
+        Arc::new(SwapEventRepositoryImpl::new(db_conn.clone()));
+
     // initialize sui client
     let network_config = config.networks.get(&config.run_mode).unwrap();
 
-    let sui_client = Arc::new(
-        SuiClientBuilder::default()
-            .build(network_config.rpc_url.clone())
-            .await?,
+    let rpc_urls = network_config.all_rpc_urls();
+    // one limiter shared by every RPC call of the process
+    let rpc_limiter = Arc::new(RpcRateLimiter::new(
+        config.rpc_max_concurrent,
+        config.rpc_qps,
+    ));
+    let rpc_pool = Arc::new(
+        utils::retry_with_backoff(
+            || {
+                RpcPool::new(
+                    &rpc_urls,
+                    config.indexer.rpc_max_retries,
+                    Duration::from_millis(constant::RPC_RETRY_BASE_DELAY_MS),
+                    Arc::clone(&rpc_limiter),
+                )
+            },
+            config.indexer.rpc_max_retries,
+            Duration::from_millis(constant::RPC_RETRY_BASE_DELAY_MS),
+        )
+        .await?,
     );
     warn!(
         "Sui client initialized with RPC URL: {}",
-        network_config.rpc_url
+        rpc_pool.rpc_url()
     );
 
     // services
     let db_pool_service = Arc::new(PoolService::new(
         Arc::clone(&config),
+        Arc::clone(&rpc_pool),
         Arc::clone(&pool_repo),
         Arc::clone(&coin_repo),
         Arc::clone(&pool_tick_repo),
+        Arc::clone(&swap_event_repo),
     ));
 
     let db_lending_service = Arc::new(LendingService::new(
@@ -121,17 +143,19 @@ async fn main() -> Result<()> {
         Arc::clone(&borrower_repo),
         Arc::clone(&metric_repo),
         Arc::clone(&shared_object_repo),
+        Arc::clone(&failed_event_repo),
     ));
 
     let ptb_helper = Arc::new(PTBHelper::new(
-        Arc::clone(&sui_client),
+        Arc::clone(&config),
+        Arc::clone(&rpc_pool),
         Arc::clone(&db_pool_service),
         Arc::clone(&db_lending_service),
     ));
 
     let service_registry = Arc::new(ServiceRegistry::new(
         Arc::clone(&config),
-        Arc::clone(&sui_client),
+        Arc::clone(&rpc_pool),
         Arc::clone(&coin_repo),
         Arc::clone(&pool_repo),
         Arc::clone(&db_pool_service),
@@ -141,7 +165,7 @@ async fn main() -> Result<()> {
 
     let event_processor_registry = Arc::new(EventProcessorRegistry::new(
         Arc::clone(&config),
-        Arc::clone(&sui_client),
+        Arc::clone(&rpc_pool),
         Arc::clone(&pool_repo),
         Arc::clone(&coin_repo),
         Arc::clone(&db_pool_service),
@@ -154,16 +178,126 @@ async fn main() -> Result<()> {
 
     let onchain_indexer = OnchainIndexer::new(
         Arc::clone(&config),
-        Arc::clone(&sui_client),
+        Arc::clone(&rpc_pool),
         Arc::clone(&db_pool_service),
         Arc::clone(&db_lending_service),
         Arc::clone(&service_registry),
         Arc::clone(&event_processor_registry),
         Arc::clone(&latest_timestamp_ms),
+        None,
+    );
+    let onchain_indexer = if config.indexer.transactional_checkpoints {
+        onchain_indexer.with_checkpoint_transactions(db_conn.clone())
+    } else {
+        onchain_indexer
+    };
+
+    warn!(
+        "Startup summary:\n{}\nstart checkpoint: {}\nevent concurrency: {}",
+        config.summary(),
+        onchain_indexer.start_seq_number,
+        onchain_indexer.event_concurrency(),
     );
 
+    // Task for serving Prometheus metrics
+    if let Some(metrics_listen_address) = config.indexer.metrics_listen_address.clone() {
+        let listener = tokio::net::TcpListener::bind(&metrics_listen_address).await?;
+        let healthz_threshold_ms = config.indexer.healthz_threshold_ms();
+        let app = Router::new()
+            .route("/metrics", get(metrics_handler))
+            .route(
+                "/healthz",
+                get(
+                    move |State(onchain_indexer): State<OnchainIndexer>| async move {
+                        healthz_handler(&onchain_indexer, healthz_threshold_ms)
+                    },
+                ),
+            )
+            .with_state(onchain_indexer.clone());
+
+        warn!(
+            "Serving metrics at http://{}/metrics and health at /healthz",
+            metrics_listen_address
+        );
+
+        tokio::spawn(async move {
+            if let Err(e) = axum::serve(listener, app).await {
+                error!("Metrics server failed: {:?}", e);
+            }
+        });
+    }
+
+    // Task for pruning pool ticks far from the current price
+    if let Some(pruning) = config.pool_tick_pruning.clone().filter(|p| p.enabled) {
+        let db_pool_service = Arc::clone(&db_pool_service);
+
+        tokio::spawn(async move {
+            let mut interval =
+                tokio::time::interval(Duration::from_secs(pruning.interval_secs.max(1)));
+            loop {
+                interval.tick().await;
+                match db_pool_service.prune_all_pool_ticks(pruning.window).await {
+                    Ok(deleted) => warn!("Pruned {} pool ticks", deleted),
+                    Err(e) => error!("Failed to prune pool ticks: {}", e),
+                }
+            }
+        });
+    }
+
+    // Task for persisting metrics independently of the checkpoint count
+    let metrics_flush = config
+        .indexer
+        .metrics_flush_interval_secs
+        .filter(|_| config.onchain_indexer_enabled)
+        .map(|interval_secs| {
+            let onchain_indexer = onchain_indexer.clone();
+            let (shutdown_sender, shutdown_receiver) = tokio::sync::oneshot::channel();
+
+            let task = tokio::spawn(async move {
+                onchain_indexer
+                    .run_metrics_flush(Duration::from_secs(interval_secs.max(1)), shutdown_receiver)
+                    .await
+            });
+
+            (task, shutdown_sender)
+        });
+
     // Task for starting Onchain indexer
-    let (onchain_task, exit_sender) = if config.onchain_indexer_enabled {
+    let use_event_subscription =
+        config.onchain_indexer_enabled && config.indexer.use_event_subscription;
+    let (onchain_task, exit_sender) = if use_event_subscription {
+        if network_config.ws_url.is_none() {
+            return Err(anyhow::anyhow!(
+                "ws_url of network {} is required for event subscription",
+                config.run_mode
+            ));
+        }
+
+        let (exit_sender, _exit_receiver) = tokio::sync::oneshot::channel();
+        let event_filters = onchain_indexer.event_subscription_filters()?;
+        let onchain_subscriber = onchain_indexer.clone();
+        let subscriber_sui_client = Arc::new(
+            ResilientSuiClient::new(
+                rpc_pool.rpc_url(),
+                network_config.ws_url.as_deref(),
+                config.indexer.rpc_max_retries,
+                Duration::from_millis(constant::RPC_RETRY_BASE_DELAY_MS),
+            )
+            .await?,
+        );
+
+        (
+            tokio::spawn(async move {
+                if let Err(e) = onchain_subscriber
+                    .run_event_subscription(subscriber_sui_client, event_filters)
+                    .await
+                {
+                    error!("Event subscription failed: {:?}", e);
+                }
+            }),
+            exit_sender,
+        )
+    } else if config.onchain_indexer_enabled {
         // start the onchain indexer
         // term sender MUST be kept in process lifecycle
         // and can be used to gracefully terminate the indexer
@@ -176,10 +310,10 @@ async fn main() -> Result<()> {
         // ```
 
         let (onchain_indexing, exit_sender) = setup_single_workflow(
-            onchain_indexer,
+            onchain_indexer.clone(),
             network_config.remote_store_url.clone(),
             config.indexer.start_checkpoint_number, /* initial checkpoint number */
-            config.indexer.indexer_worker_count,    /* concurrency */
+            config.indexer.worker_count(),          /* concurrency */
             None,                                   /* extra reader options */
         )
         .await?;
@@ -214,5 +348,40 @@ async fn main() -> Result<()> {
         }
     }
 
+    // stop the periodic flush first, so it doesn't race the final one
+    if let Some((task, shutdown_sender)) = metrics_flush {
+        let _ = shutdown_sender.send(());
+        if let Err(e) = task.await {
+            error!("Metrics flush task failed: {}", e);
+        }
+    }
+
+    // persist metrics accumulated since the last checkpoint boundary
+    if config.onchain_indexer_enabled {
+        if let Err(e) = onchain_indexer.flush_metrics() {
+            error!("Failed to flush metrics on shutdown: {}", e);
+        }
+    }
+
     Ok(())
 }
+
+async fn metrics_handler(State(onchain_indexer): State<OnchainIndexer>) -> String {
+    onchain_indexer.render_prometheus_metrics()
+}
+
+/// Liveness of the indexer from the lag of the latest processed checkpoint.
+/// Healthy until the first checkpoint is processed.
+///
+fn healthz_handler(onchain_indexer: &OnchainIndexer, threshold_ms: u64) -> (StatusCode, String) {
+    let lagging_ms = onchain_indexer.latest_lagging_ms();
+
+    if lagging_ms < threshold_ms {
+        (StatusCode::OK, format!("ok, lagging {}ms", lagging_ms))
+    } else {
+        (
+            StatusCode::SERVICE_UNAVAILABLE,
+            format!("lagging {}ms, threshold {}ms", lagging_ms, threshold_ms),
+        )
+    }
+}